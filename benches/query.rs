@@ -0,0 +1,74 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmark of building queries.
+//!
+//! Run with `cargo bench --bench query`, no cloud is required.
+
+extern crate openstack;
+
+use std::time::{Duration, Instant};
+
+const ITERATIONS: usize = 100_000;
+
+fn report(name: &str, elapsed: Duration) {
+    let nanos = elapsed.as_secs() * 1_000_000_000 +
+        elapsed.subsec_nanos() as u64;
+    println!("{}: {} ns/iter", name, nanos / ITERATIONS as u64);
+}
+
+#[cfg(feature = "compute")]
+fn bench_server_query(os: &openstack::Cloud) {
+    use openstack::compute::{ServerSortKey, ServerStatus};
+    use openstack::Sort;
+
+    let started = Instant::now();
+    let queries = (0..ITERATIONS).map(|i| {
+        os.find_servers()
+            .with_name(format!("server-{}", i))
+            .with_status(ServerStatus::Active)
+            .with_availability_zone("nova")
+            .sort_by(Sort::Asc(ServerSortKey::CreatedAt))
+            .with_limit(50)
+    }).collect::<Vec<_>>();
+    report("server_query", started.elapsed());
+    assert_eq!(queries.len(), ITERATIONS);
+}
+
+#[cfg(not(feature = "compute"))]
+fn bench_server_query(_os: &openstack::Cloud) {}
+
+#[cfg(feature = "network")]
+fn bench_port_query_tags(os: &openstack::Cloud) {
+    let started = Instant::now();
+    let queries = (0..ITERATIONS).map(|i| {
+        os.find_ports()
+            .with_tags(vec!["env-prod", "team-infra", "managed"])
+            .with_name(format!("port-{}", i))
+            .with_limit(50)
+    }).collect::<Vec<_>>();
+    report("port_query_tags", started.elapsed());
+    assert_eq!(queries.len(), ITERATIONS);
+}
+
+#[cfg(not(feature = "network"))]
+fn bench_port_query_tags(_os: &openstack::Cloud) {}
+
+fn main() {
+    let auth = openstack::auth::NoAuth::new("http://127.0.0.1/")
+        .expect("Invalid URL");
+    let os = openstack::Cloud::new(auth);
+    bench_server_query(&os);
+    bench_port_query_tags(&os);
+}
@@ -31,7 +31,8 @@ fn main() {
     println!("ID = {}, Name = {}, VCPUs = {}, RAM = {} MiB, DISK = {} GiB",
              flavor.id(), flavor.name(),
              flavor.vcpu_count(), flavor.ram_size(), flavor.root_size());
-    println!("Extra Specs = {:?}", flavor.extra_specs());
+    println!("Extra Specs = {:?}",
+             flavor.extra_specs().expect("Cannot get extra specs"));
 }
 
 #[cfg(not(feature = "compute"))]
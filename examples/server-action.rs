@@ -39,9 +39,29 @@ fn main() {
         "reboot" => server.reboot(openstack::compute::RebootType::Soft)
             .expect("Cannot reboot the server")
             .wait().expect("Failed to reach ACTIVE"),
+        "hard-reboot" => server.reboot(openstack::compute::RebootType::Hard)
+            .expect("Cannot reboot the server")
+            .wait().expect("Failed to reach ACTIVE"),
+        "confirm-resize" => server.confirm_resize()
+            .expect("Cannot confirm resize of the server")
+            .wait().expect("Failed to reach ACTIVE"),
+        "revert-resize" => server.revert_resize()
+            .expect("Cannot revert resize of the server")
+            .wait().expect("Failed to reach ACTIVE"),
+        "rescue" => server.rescue().expect("Cannot rescue the server")
+            .wait().expect("Failed to reach RESCUE"),
+        "unrescue" => server.unrescue().expect("Cannot unrescue the server")
+            .wait().expect("Failed to reach ACTIVE"),
+        "lock" => server.lock().expect("Cannot lock the server")
+            .wait().expect("Failed to refresh the server"),
+        "unlock" => server.unlock().expect("Cannot unlock the server")
+            .wait().expect("Failed to refresh the server"),
         "delete" => server.delete().expect("Cannot delete the server")
             .wait().expect("Failed to delete the server"),
-        _ => panic!("Unknown action, supported are 'start' and 'stop'")
+        _ => panic!("Unknown action, supported are 'start', 'stop', \
+                     'reboot', 'hard-reboot', 'confirm-resize', \
+                     'revert-resize', 'rescue', 'unrescue', 'lock', \
+                     'unlock' and 'delete'")
     }
 }
 
@@ -15,10 +15,11 @@
 //! Base code for authentication.
 
 use std::fmt::Debug;
+use std::time::Duration;
 
 use reqwest::{Method, Url};
 
-use super::super::Result;
+use super::super::{Error, ErrorKind, Result};
 use super::super::session::RequestBuilder;
 
 
@@ -46,8 +47,35 @@ pub trait AuthMethod: BoxedClone + Debug {
     /// Create an authenticated request.
     fn request(&self, method: Method, url: Url) -> Result<RequestBuilder>;
 
+    /// Service types available in the service catalog.
+    ///
+    /// The default implementation returns an empty list, which is suitable
+    /// for authentication methods without a catalog.
+    fn service_types(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
     /// Refresh the authentication (renew the token, etc).
     fn refresh(&mut self) -> Result<()>;
+
+    /// Revoke the authentication (invalidate the token, etc).
+    ///
+    /// The default implementation does nothing, which is suitable for
+    /// authentication methods without tokens.
+    fn revoke(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Set the timeout for HTTP requests made with this authentication.
+    ///
+    /// The default implementation fails with `InvalidConfig`, which is
+    /// suitable for authentication methods that cannot change their HTTP
+    /// client.
+    fn set_timeout(&mut self, _timeout: Duration) -> Result<()> {
+        Err(Error::new(ErrorKind::InvalidConfig,
+                       "This authentication method does not support \
+                        changing the timeout"))
+    }
 }
 
 
@@ -0,0 +1,297 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Settings of the underlying HTTP client.
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use reqwest::{Certificate, Client, Identity as TlsIdentity, Proxy, Url};
+
+use super::super::{Error, ErrorKind, Result};
+
+
+const PEM_CERTIFICATE_END: &'static str = "-----END CERTIFICATE-----";
+
+
+/// HTTP proxy settings.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// Proxy for plain HTTP requests.
+    pub http: Option<Url>,
+    /// Proxy for HTTPS requests.
+    pub https: Option<Url>,
+    /// Hosts and domains to access directly.
+    ///
+    /// Entries match the host itself and all its subdomains, a leading dot
+    /// is ignored. A single `*` disables the proxy for all hosts.
+    pub no_proxy: Vec<String>,
+}
+
+/// Settings for building an HTTP client.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ClientConfig {
+    pub timeout: Option<Duration>,
+    pub ca_certificates: Vec<PathBuf>,
+    pub client_identity: Option<(PathBuf, String)>,
+    pub insecure: bool,
+    pub proxy: Option<ProxyConfig>,
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    let mut result = Vec::new();
+    let _ = File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut result))
+        .map_err(|e| Error::new(ErrorKind::InvalidConfig,
+                                format!("Cannot read {:?}: {}", path, e)))?;
+    Ok(result)
+}
+
+/// Split a PEM bundle into separate certificates.
+fn split_pem_bundle(bundle: &str) -> Vec<String> {
+    bundle.split(PEM_CERTIFICATE_END)
+        .filter(|item| ! item.trim().is_empty())
+        .map(|item| format!("{}{}\n", item.trim_left(), PEM_CERTIFICATE_END))
+        .collect()
+}
+
+/// Get a variable that can be set in upper or lower case.
+fn env_any_case(name: &str) -> Option<String> {
+    env::var(name).or_else(|_| env::var(name.to_lowercase())).ok()
+        .and_then(|value| if value.is_empty() { None } else { Some(value) })
+}
+
+fn env_proxy_url(name: &str) -> Option<Url> {
+    let value = env_any_case(name)?;
+    match Url::parse(&value) {
+        Ok(url) => Some(url),
+        Err(e) => {
+            warn!("Ignoring invalid proxy {} in {}: {}", value, name, e);
+            None
+        }
+    }
+}
+
+impl ProxyConfig {
+    /// Read proxy settings from the standard environment variables.
+    ///
+    /// Uses `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` (or their lower case
+    /// variants). Returns `None` if no proxy is configured.
+    pub fn from_env() -> Option<ProxyConfig> {
+        let http = env_proxy_url("HTTP_PROXY");
+        let https = env_proxy_url("HTTPS_PROXY");
+        if http.is_none() && https.is_none() {
+            return None;
+        }
+
+        let no_proxy = env_any_case("NO_PROXY").map(|value| {
+            value.split(',').map(|item| item.trim())
+                .filter(|item| ! item.is_empty())
+                .map(String::from).collect()
+        }).unwrap_or_else(Vec::new);
+
+        Some(ProxyConfig {
+            http: http,
+            https: https,
+            no_proxy: no_proxy,
+        })
+    }
+
+    /// Whether the host has to be accessed without a proxy.
+    pub fn is_excluded(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.no_proxy.iter().any(|item| {
+            let item = item.trim_left_matches('.').to_lowercase();
+            item == "*" || host == item ||
+                host.ends_with(&format!(".{}", item))
+        })
+    }
+
+    /// Proxy to use for the URL, if any.
+    pub fn proxy_for(&self, url: &Url) -> Option<Url> {
+        match url.host_str() {
+            Some(host) if self.is_excluded(host) => None,
+            _ => match url.scheme() {
+                "http" => self.http.clone(),
+                "https" => self.https.clone(),
+                _ => None
+            }
+        }
+    }
+}
+
+/// Whether an environment variable is set to a true value.
+fn env_flag(name: &str) -> bool {
+    match env::var(name) {
+        Ok(value) => match value.to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => true,
+            _ => false
+        },
+        Err(..) => false
+    }
+}
+
+impl ClientConfig {
+    /// Default settings with the proxy taken from the environment.
+    pub fn with_env_proxy() -> ClientConfig {
+        ClientConfig {
+            proxy: ProxyConfig::from_env(),
+            .. ClientConfig::default()
+        }
+    }
+
+    /// Whether the default HTTP client can be used.
+    pub fn is_default(&self) -> bool {
+        self.timeout.is_none() && self.ca_certificates.is_empty() &&
+            self.client_identity.is_none() && ! self.insecure &&
+            self.proxy.is_none()
+    }
+
+    /// Update the settings from the standard environment variables.
+    ///
+    /// Supports `OS_CACERT`, `OS_CERT` (with `OS_CERT_PASSWORD`), `OS_KEY`
+    /// and `OS_INSECURE`.
+    pub fn update_from_env(&mut self) -> Result<()> {
+        if let Ok(cacert) = env::var("OS_CACERT") {
+            self.ca_certificates.push(PathBuf::from(cacert));
+        }
+
+        if let Ok(cert) = env::var("OS_CERT") {
+            if env::var("OS_KEY").is_ok() {
+                return Err(Error::new(
+                    ErrorKind::InvalidConfig,
+                    "Separate client keys (OS_KEY) are not supported, \
+                     provide a PKCS #12 bundle in OS_CERT instead"));
+            }
+            let password = env::var("OS_CERT_PASSWORD").unwrap_or_default();
+            self.client_identity = Some((PathBuf::from(cert), password));
+        }
+
+        if env_flag("OS_INSECURE") {
+            self.insecure = true;
+        }
+
+        Ok(())
+    }
+
+    /// Build an HTTP client with these settings.
+    ///
+    /// Fails with `InvalidConfig` if the insecure mode is requested without
+    /// CA certificates, since it cannot make untrusted certificates work.
+    pub fn build(&self) -> Result<Client> {
+        if self.insecure && self.ca_certificates.is_empty() {
+            // NOTE: reqwest 0.8 cannot skip validation of the certificate
+            // chain, only the check that the certificate matches the host.
+            return Err(Error::new(
+                ErrorKind::InvalidConfig,
+                "Insecure mode only disables TLS host name verification, \
+                 certificates are still validated: provide the CA that \
+                 issued them (OS_CACERT or cacert in clouds.yaml)"));
+        }
+
+        let mut builder = Client::builder();
+
+        if let Some(timeout) = self.timeout {
+            let _ = builder.timeout(timeout);
+        }
+
+        for path in &self.ca_certificates {
+            let bundle = String::from_utf8(read_file(path)?).map_err(|_| {
+                Error::new(ErrorKind::InvalidConfig,
+                           format!("CA bundle {:?} is not valid PEM", path))
+            })?;
+            for pem in split_pem_bundle(&bundle) {
+                let _ = builder.add_root_certificate(
+                    Certificate::from_pem(pem.as_bytes())?);
+            }
+        }
+
+        if let Some((ref path, ref password)) = self.client_identity {
+            let der = read_file(path)?;
+            let _ = builder.identity(TlsIdentity::from_pkcs12_der(&der,
+                                                                  password)?);
+        }
+
+        if let Some(ref proxy) = self.proxy {
+            let proxy = proxy.clone();
+            let _ = builder.proxy(Proxy::custom(move |url| proxy.proxy_for(url)));
+        }
+
+        if self.insecure {
+            warn!("TLS hostname verification is disabled");
+            let _ = builder.danger_disable_hostname_verification();
+        }
+
+        builder.build().map_err(From::from)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use reqwest::Url;
+
+    use super::super::super::ErrorKind;
+    use super::{split_pem_bundle, ClientConfig, ProxyConfig};
+
+    #[test]
+    fn test_insecure_requires_ca_certificates() {
+        let config = ClientConfig {
+            insecure: true,
+            .. ClientConfig::default()
+        };
+        let err = config.build().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidConfig);
+        assert!(err.to_string().contains("host name verification"));
+    }
+
+    #[test]
+    fn test_proxy_for() {
+        let proxy = ProxyConfig {
+            http: Some(Url::parse("http://proxy:3128").unwrap()),
+            https: Some(Url::parse("http://sproxy:3128").unwrap()),
+            no_proxy: vec![String::from("localhost"),
+                           String::from(".internal.example.com")],
+        };
+        let get = |url: &str| proxy.proxy_for(&Url::parse(url).unwrap())
+            .map(|url| url.to_string());
+        assert_eq!(get("http://cloud.example.com/identity"),
+                   Some(String::from("http://proxy:3128/")));
+        assert_eq!(get("https://cloud.example.com/identity"),
+                   Some(String::from("http://sproxy:3128/")));
+        assert_eq!(get("http://localhost:5000/"), None);
+        assert_eq!(get("https://keystone.internal.example.com/"), None);
+        assert_eq!(get("https://internal.example.com/"), None);
+        assert_eq!(get("https://notinternal.example.com/"),
+                   Some(String::from("http://sproxy:3128/")));
+    }
+
+    #[test]
+    fn test_split_pem_bundle() {
+        let bundle = "-----BEGIN CERTIFICATE-----\nAAAA\n\
+                      -----END CERTIFICATE-----\n\
+                      -----BEGIN CERTIFICATE-----\nBBBB\n\
+                      -----END CERTIFICATE-----\n";
+        let certs = split_pem_bundle(bundle);
+        assert_eq!(certs, vec![
+            String::from("-----BEGIN CERTIFICATE-----\nAAAA\n\
+                          -----END CERTIFICATE-----\n"),
+            String::from("-----BEGIN CERTIFICATE-----\nBBBB\n\
+                          -----END CERTIFICATE-----\n"),
+        ]);
+    }
+}
@@ -39,7 +39,15 @@ struct Auth {
 struct Cloud {
     auth: Auth,
     #[serde(default)]
+    cacert: Option<String>,
+    #[serde(default)]
+    cert: Option<String>,
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(default)]
     region_name: Option<String>,
+    #[serde(default)]
+    verify: Option<bool>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -96,13 +104,30 @@ pub fn from_config<S: AsRef<str>>(cloud_name: S) -> Result<Identity> {
         .ok_or_else(|| Error::new(ErrorKind::InvalidConfig,
                                   format!("No such cloud: {}", name)))?;
 
+    if cloud.key.is_some() {
+        return Err(Error::new(ErrorKind::InvalidConfig,
+                              "Separate client keys are not supported, \
+                               provide a PKCS #12 bundle in cert instead"));
+    }
+
     let auth = cloud.auth;
-    Ok(if let Some(region) = cloud.region_name {
+    let mut id = if let Some(region) = cloud.region_name {
         Identity::new_with_region(&auth.auth_url, region)
     } else {
         Identity::new(&auth.auth_url)
     }?.with_user(auth.username, auth.password,
                  auth.user_domain_name.unwrap_or(String::from("Default")))
     .with_project_scope(auth.project_name,
-                        auth.project_domain_name.unwrap_or(String::from("Default"))))
+                        auth.project_domain_name.unwrap_or(String::from("Default")));
+
+    if let Some(cacert) = cloud.cacert {
+        id = id.with_ca_certificate(cacert);
+    }
+    if let Some(cert) = cloud.cert {
+        id = id.with_client_certificate(cert, "");
+    }
+    if cloud.verify == Some(false) {
+        id = id.with_insecure(true);
+    }
+    Ok(id)
 }
@@ -17,6 +17,9 @@
 use std::collections::hash_map::DefaultHasher;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::rc::Rc;
+use std::time;
 
 use chrono::{Duration, Local};
 use reqwest::{Client, IntoUrl, Method, Response, StatusCode, Url, UrlError};
@@ -25,17 +28,25 @@ use reqwest::header::{ContentType, Headers};
 use super::super::{Error, ErrorKind, Result};
 use super::super::identity::{catalog, protocol};
 use super::super::session::RequestBuilder;
+use super::super::telemetry;
 use super::super::utils::ValueCache;
 use super::AuthMethod;
+use super::client::{ClientConfig, ProxyConfig};
 
 
 const MISSING_USER: &'static str = "User information required";
 const MISSING_SCOPE: &'static str = "Unscoped tokens are not supported now";
 const MISSING_SUBJECT_HEADER: &'static str =
     "Missing X-Subject-Token header";
-// Required validity time in minutes. Here we refresh the token if it expires
-// in 10 minutes or less.
+const CUSTOM_CLIENT_CONFIG: &'static str =
+    "HTTP client settings cannot be applied to a client passed to \
+     Identity::new_with_client";
+// Default required validity time in minutes. Here we refresh the token if it
+// expires in 10 minutes or less.
 const TOKEN_MIN_VALIDITY: i64 = 10;
+// Maximum token renewal margin in hours. Tokens rarely live longer, so a
+// longer margin would only mean renewing the token before every request.
+const MAX_RENEWAL_MARGIN: i64 = 24;
 
 
 /// Plain authentication token without additional details.
@@ -62,12 +73,22 @@ pub struct Identity {
     auth_url: Url,
     region: Option<String>,
     password_identity: Option<protocol::PasswordIdentity>,
-    project_scope: Option<protocol::ProjectScope>
+    project_scope: Option<protocol::ProjectScope>,
+    renewal_margin: Duration,
+    client_config: ClientConfig,
+    custom_client: bool
 }
 
 /// Password authentication using Identity API V3.
 ///
 /// Has to be created via [Identity object](struct.Identity.html) methods.
+///
+/// The token is cached and renewed shortly before it expires. Clones share
+/// the same cached token.
+///
+/// The renewal happens before the first request issued within the renewal
+/// margin (10 minutes by default), so no request is sent with a token that
+/// is about to expire.
 #[derive(Clone, Debug)]
 pub struct PasswordAuth {
     client: Client,
@@ -75,7 +96,21 @@ pub struct PasswordAuth {
     region: Option<String>,
     body: protocol::ProjectScopedAuthRoot,
     token_endpoint: String,
-    cached_token: ValueCache<Token>
+    cached_token: Rc<ValueCache<Token>>,
+    renewal_margin: Duration,
+    client_config: Option<ClientConfig>
+}
+
+fn convert_margin(margin: time::Duration) -> Duration {
+    let max = Duration::hours(MAX_RENEWAL_MARGIN);
+    match Duration::from_std(margin) {
+        Ok(value) if value <= max => value,
+        _ => {
+            warn!("Token renewal margin {:?} is too long, using {} hours",
+                  margin, MAX_RENEWAL_MARGIN);
+            max
+        }
+    }
 }
 
 impl Identity {
@@ -87,7 +122,10 @@ impl Identity {
     /// Create a password authentication against the given Identity service.
     pub fn new<U>(auth_url: U) -> ::std::result::Result<Identity, UrlError>
             where U: IntoUrl  {
-        Identity::new_with_client(auth_url, Client::new())
+        let mut id = Identity::new_with_client(auth_url, Client::new())?;
+        id.client_config = ClientConfig::with_env_proxy();
+        id.custom_client = false;
+        Ok(id)
     }
 
     /// Create a password authentication against the given Identity service.
@@ -99,10 +137,17 @@ impl Identity {
             region: Some(region),
             password_identity: None,
             project_scope: None,
+            renewal_margin: Duration::minutes(TOKEN_MIN_VALIDITY),
+            client_config: ClientConfig::with_env_proxy(),
+            custom_client: false,
         })
     }
 
     /// Create a password authentication against the given Identity service.
+    ///
+    /// The client is used as it is, so HTTP client settings like
+    /// `with_timeout`, `with_ca_certificate` or `with_proxy` cannot be used
+    /// with it: `create` fails with `InvalidConfig` if any of them is set.
     pub fn new_with_client<U>(auth_url: U, client: Client)
             -> ::std::result::Result<Identity, UrlError> where U: IntoUrl  {
         Ok(Identity {
@@ -111,6 +156,9 @@ impl Identity {
             region: None,
             password_identity: None,
             project_scope: None,
+            renewal_margin: Duration::minutes(TOKEN_MIN_VALIDITY),
+            client_config: ClientConfig::default(),
+            custom_client: true,
         })
     }
 
@@ -136,6 +184,90 @@ impl Identity {
         }
     }
 
+    /// Renew the token when it expires in less than this time.
+    ///
+    /// The default is 10 minutes. Increase it for long-running requests that
+    /// must not fail because of a token expiring while they are in flight.
+    /// Margins longer than 24 hours are lowered to 24 hours.
+    pub fn with_token_renewal_margin(self, margin: time::Duration) -> Identity {
+        Identity {
+            renewal_margin: convert_margin(margin),
+            .. self
+        }
+    }
+
+    /// Abort HTTP requests that take longer than this time.
+    ///
+    /// The timeout covers connecting as well as reading the response. The
+    /// default is 30 seconds. Cannot be used with `new_with_client`.
+    pub fn with_timeout(mut self, timeout: time::Duration) -> Identity {
+        self.client_config.timeout = Some(timeout);
+        self
+    }
+
+    /// Trust certificates from the given PEM bundle in addition to the
+    /// system ones.
+    ///
+    /// The file is read in `create`. Cannot be used with `new_with_client`.
+    pub fn with_ca_certificate<P: AsRef<Path>>(mut self, path: P) -> Identity {
+        self.client_config.ca_certificates.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Authenticate with a client certificate from a PKCS #12 bundle.
+    ///
+    /// The HTTP client does not support separate PEM certificates and keys,
+    /// convert them with e.g. `openssl pkcs12 -export`. The file is read in
+    /// `create`. Cannot be used with `new_with_client`.
+    pub fn with_client_certificate<P, S>(mut self, path: P, password: S)
+            -> Identity where P: AsRef<Path>, S: Into<String> {
+        self.client_config.client_identity =
+            Some((path.as_ref().to_path_buf(), password.into()));
+        self
+    }
+
+    /// Disable verification of TLS host names.
+    ///
+    /// The HTTP client cannot disable validation of certificates: they are
+    /// still required to chain to a CA added with `with_ca_certificate`, but
+    /// they are accepted for any host name. Without such a CA `create` fails
+    /// with `InvalidConfig`. This is dangerous and should only be used for
+    /// testing. Cannot be used with `new_with_client`.
+    pub fn with_insecure(mut self, insecure: bool) -> Identity {
+        self.client_config.insecure = insecure;
+        self
+    }
+
+    /// Send requests through the given HTTP proxy.
+    ///
+    /// `Identity::new` and `Identity::new_with_region` pick up the proxy
+    /// from the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment
+    /// variables, this call overrides it. Cannot be used with
+    /// `new_with_client`.
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Identity {
+        self.client_config.proxy = Some(proxy);
+        self
+    }
+
+    /// Do not use an HTTP proxy, even if one is set in the environment.
+    pub fn without_proxy(mut self) -> Identity {
+        self.client_config.proxy = None;
+        self
+    }
+
+    /// Apply TLS settings from the standard environment variables.
+    ///
+    /// `OS_CACERT` is a PEM bundle of CA certificates to trust, `OS_CERT`
+    /// is a PKCS #12 client certificate bundle (protected by
+    /// `OS_CERT_PASSWORD`, if needed), and `OS_INSECURE` disables host name
+    /// verification only and requires `OS_CACERT` (see `with_insecure`).
+    /// Separate PEM keys in `OS_KEY` are not supported and result in an
+    /// `InvalidConfig` error.
+    pub fn with_tls_from_env(mut self) -> Result<Identity> {
+        self.client_config.update_from_env()?;
+        Ok(self)
+    }
+
     /// Create an authentication method based on provided information.
     pub fn create(self) -> Result<PasswordAuth> {
         // TODO: support more authentication methods (at least a token)
@@ -152,8 +284,24 @@ impl Identity {
                 return Err(Error::new(ErrorKind::InvalidInput, MISSING_SCOPE))
         };
 
-        Ok(PasswordAuth::new(self.auth_url, self.region, password_identity,
-                             project_scope, self.client))
+        let (client, client_config) = if self.custom_client {
+            if ! self.client_config.is_default() {
+                return Err(Error::new(ErrorKind::InvalidConfig,
+                                      CUSTOM_CLIENT_CONFIG));
+            }
+            (self.client, None)
+        } else if self.client_config.is_default() {
+            (self.client, Some(self.client_config))
+        } else {
+            (self.client_config.build()?, Some(self.client_config))
+        };
+
+        let mut auth = PasswordAuth::new(self.auth_url, self.region,
+                                         password_identity, project_scope,
+                                         client);
+        auth.renewal_margin = self.renewal_margin;
+        auth.client_config = client_config;
+        Ok(auth)
     }
 }
 
@@ -189,10 +337,25 @@ impl PasswordAuth {
             region: region,
             body: body,
             token_endpoint: token_endpoint,
-            cached_token: ValueCache::new(None)
+            cached_token: Rc::new(ValueCache::new(None)),
+            renewal_margin: Duration::minutes(TOKEN_MIN_VALIDITY),
+            client_config: None
         }
     }
 
+    /// Time before the token expiration when it gets renewed.
+    pub fn token_renewal_margin(&self) -> time::Duration {
+        self.renewal_margin.to_std()
+            .unwrap_or_else(|_| time::Duration::new(0, 0))
+    }
+
+    /// Renew the token when it expires in less than this time.
+    ///
+    /// Margins longer than 24 hours are lowered to 24 hours.
+    pub fn set_token_renewal_margin(&mut self, margin: time::Duration) {
+        self.renewal_margin = convert_margin(margin);
+    }
+
     fn token_from_response(&self, mut resp: Response) -> Result<Token> {
         let token_value = match resp.status() {
             StatusCode::Ok | StatusCode::Created => {
@@ -245,11 +408,12 @@ impl PasswordAuth {
             let validity_time_left = val.body.expires_at.clone()
                 .signed_duration_since(Local::now());
             trace!("Token is valid for {:?}", validity_time_left);
-            return validity_time_left > Duration::minutes(TOKEN_MIN_VALIDITY);
+            return validity_time_left > self.renewal_margin;
         }, || {
             debug!("Requesting a token for user {} from {}",
                    self.body.auth.identity.password.user.name,
                    self.token_endpoint);
+            telemetry::record_auth_refresh();
             let resp = self.client.post(&self.token_endpoint).json(&self.body)
                 .header(ContentType::json()).send()?.error_for_status()?;
             self.token_from_response(resp)
@@ -284,6 +448,15 @@ impl AuthMethod for PasswordAuth {
         Ok(RequestBuilder::new(builder))
     }
 
+    /// Service types available in the service catalog.
+    fn service_types(&self) -> Result<Vec<String>> {
+        let mut result = self.get_catalog()?.into_iter()
+            .map(|record| record.service_type).collect::<Vec<_>>();
+        result.sort();
+        result.dedup();
+        Ok(result)
+    }
+
     /// Get a URL for the requested service.
     fn get_endpoint(&self, service_type: String,
                     endpoint_interface: Option<String>) -> Result<Url> {
@@ -308,17 +481,66 @@ impl AuthMethod for PasswordAuth {
     }
 
     fn refresh(&mut self) -> Result<()> {
-        self.cached_token = ValueCache::new(None);
+        self.cached_token.clear();
         self.refresh_token()
     }
+
+    /// Set the timeout for HTTP requests, replacing the HTTP client.
+    ///
+    /// Fails for a client passed to `Identity::new_with_client`.
+    fn set_timeout(&mut self, timeout: time::Duration) -> Result<()> {
+        let client = match self.client_config {
+            Some(ref mut config) => {
+                config.timeout = Some(timeout);
+                config.build()?
+            },
+            None => return Err(Error::new(ErrorKind::InvalidConfig,
+                                          CUSTOM_CLIENT_CONFIG))
+        };
+        self.client = client;
+        Ok(())
+    }
+
+    /// Revoke the cached token.
+    ///
+    /// The token is dropped from the cache even if the revocation fails,
+    /// the next request will authenticate again.
+    fn revoke(&mut self) -> Result<()> {
+        let token = match self.cached_token.extract(|t| t.value.clone()) {
+            Some(token) => token,
+            None => return Ok(())
+        };
+        self.cached_token.clear();
+
+        debug!("Revoking the token of user {} at {}",
+               self.body.auth.identity.password.user.name,
+               self.token_endpoint);
+        let mut headers = Headers::new();
+        headers.set_raw("x-auth-token", token.clone());
+        headers.set_raw("x-subject-token", token);
+        let resp = self.client.delete(&self.token_endpoint).headers(headers)
+            .send()?;
+        match resp.status() {
+            // The token is already invalid.
+            StatusCode::NotFound => Ok(()),
+            _ => {
+                let _ = resp.error_for_status()?;
+                Ok(())
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 pub mod test {
     #![allow(unused_results)]
 
+    use std::time::Duration;
+
+    use reqwest::Client;
+
     use super::super::AuthMethod;
-    use super::Identity;
+    use super::{convert_margin, Identity};
 
     #[test]
     fn test_identity_new() {
@@ -330,6 +552,28 @@ pub mod test {
         assert_eq!(e.path(), "/");
     }
 
+    #[test]
+    fn test_identity_custom_client_config() {
+        let id = Identity::new_with_client("http://127.0.0.1:8080/identity",
+                                           Client::new()).unwrap()
+            .with_user("user", "pa$$w0rd", "example.com")
+            .with_project_scope("cool project", "example.com");
+        let mut auth = id.clone().create().unwrap();
+        assert!(auth.set_timeout(Duration::from_secs(5)).is_err());
+        assert!(id.with_timeout(Duration::from_secs(5)).create().is_err());
+    }
+
+    #[test]
+    fn test_identity_set_timeout() {
+        let mut auth = Identity::new("http://127.0.0.1:8080/identity").unwrap()
+            .with_user("user", "pa$$w0rd", "example.com")
+            .with_project_scope("cool project", "example.com")
+            .create().unwrap();
+        auth.set_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(auth.client_config.unwrap().timeout,
+                   Some(Duration::from_secs(5)));
+    }
+
     #[test]
     fn test_identity_new_invalid() {
         Identity::new("http://127.0.0.1 8080/").err().unwrap();
@@ -357,6 +601,32 @@ pub mod test {
         assert_eq!(id.region(), None);
     }
 
+    #[test]
+    fn test_identity_create_renewal_margin() {
+        let id = Identity::new("http://127.0.0.1:8080/identity").unwrap()
+            .with_user("user", "pa$$w0rd", "example.com")
+            .with_project_scope("cool project", "example.com")
+            .create().unwrap();
+        assert_eq!(id.token_renewal_margin(), Duration::from_secs(600));
+
+        let id = Identity::new("http://127.0.0.1:8080/identity").unwrap()
+            .with_user("user", "pa$$w0rd", "example.com")
+            .with_project_scope("cool project", "example.com")
+            .with_token_renewal_margin(Duration::from_secs(1800))
+            .create().unwrap();
+        assert_eq!(id.token_renewal_margin(), Duration::from_secs(1800));
+    }
+
+    #[test]
+    fn test_convert_margin_clamps() {
+        let day = Duration::from_secs(24 * 3600);
+        assert_eq!(convert_margin(Duration::from_secs(3600)).to_std().unwrap(),
+                   Duration::from_secs(3600));
+        assert_eq!(convert_margin(day * 2).to_std().unwrap(), day);
+        assert_eq!(convert_margin(Duration::new(u64::max_value(), 0))
+                   .to_std().unwrap(), day);
+    }
+
     #[test]
     fn test_identity_create_no_scope() {
         Identity::new("http://127.0.0.1:8080/identity").unwrap()
@@ -74,11 +74,13 @@
 //! * Only Identity API v3 is supported and planned for support.
 
 mod base;
+mod client;
 mod config;
 mod identity;
 mod simple;
 
 pub use self::base::{AuthMethod, BoxedClone};
+pub use self::client::ProxyConfig;
 pub use self::config::from_config;
 pub use self::simple::NoAuth;
 pub use self::identity::{Identity, PasswordAuth};
@@ -102,9 +104,17 @@ fn _get_env(name: &str) -> Result<String> {
 
 
 /// Create an authentication method from environment variables.
+///
+/// TLS settings are taken from `OS_CACERT`, `OS_CERT`, `OS_CERT_PASSWORD`
+/// and `OS_INSECURE` (see `Identity::with_tls_from_env`). The HTTP client
+/// limits them, violations fail with `InvalidConfig`:
+///
+/// * client certificates have to be PKCS #12 bundles, `OS_KEY` is rejected;
+/// * `OS_INSECURE` only disables host name verification and is rejected
+///   without `OS_CACERT`.
 pub fn from_env() -> Result<PasswordAuth> {
     if let Ok(cloud_name) = env::var("OS_CLOUD") {
-        from_config(cloud_name).and_then(Identity::create)
+        from_config(cloud_name)?.with_tls_from_env()?.create()
     } else {
         let auth_url = _get_env("OS_AUTH_URL")?;
         let id = Identity::new(&auth_url).map_err(|_| {
@@ -123,6 +133,7 @@ pub fn from_env() -> Result<PasswordAuth> {
 
         id.with_user(user_name, password, user_domain)
             .with_project_scope(project_name, project_domain)
+            .with_tls_from_env()?
             .create()
     }
 }
@@ -14,11 +14,14 @@
 
 //! Simple authentication methods.
 
+use std::time::Duration;
+
 use reqwest::{Client, IntoUrl, Method, Url, UrlError};
 
 use super::super::Result;
 use super::super::session::RequestBuilder;
 use super::AuthMethod;
+use super::client::ClientConfig;
 
 /// Authentication method that provides no authentication.
 ///
@@ -57,6 +60,16 @@ impl AuthMethod for NoAuth {
     }
 
     fn refresh(&mut self) -> Result<()> { Ok(()) }
+
+    /// Set the timeout for HTTP requests, replacing the HTTP client.
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        let config = ClientConfig {
+            timeout: Some(timeout),
+            .. ClientConfig::default()
+        };
+        self.client = config.build()?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -14,29 +14,115 @@
 
 //! Cloud API.
 
+use std::collections::BTreeMap;
+#[cfg(feature = "compute")]
+use std::net::IpAddr;
 use std::rc::Rc;
+use std::time::Duration;
+
+#[cfg(feature = "network")]
+use ipnet;
+use reqwest::{Method, Url};
 
-use super::Result;
+use super::{Error, Result};
+#[allow(unused_imports)]
+use super::ErrorKind;
 use super::auth::{self, AuthMethod};
+use super::common::NetworkRef;
 #[allow(unused_imports)]
-use super::common::{FlavorRef, NetworkRef};
+use super::common::{ApiVersion, ApiVersionRequest, FlavorRef, PortRef,
+                    ProjectRef};
 #[cfg(feature = "compute")]
-use super::compute::{Flavor, FlavorQuery, FlavorSummary, KeyPair, KeyPairQuery,
-                     NewKeyPair, NewServer, Server, ServerQuery, ServerSummary};
+use super::compute::{self, AvailabilityZone, ComputeQuotas,
+                     ComputeQuotasUpdate, Flavor, FlavorQuery, FlavorSummary,
+                     KeyPair, KeyPairQuery, NewKeyPair, NewServer,
+                     NewServerGroup, Server, ServerGroup, ServerGroupPolicy,
+                     ServerLookupOptions, ServerQuery, ServerSummary};
+#[cfg(feature = "dns")]
+use super::dns::{NewZone, Zone, ZoneQuery};
+#[cfg(feature = "identity")]
+use super::identity::{Project, ProjectQuery};
 #[cfg(feature = "image")]
-use super::image::{Image, ImageQuery};
+use super::image::{Image, ImageMemberStatus, ImageQuery, NewImage, Task,
+                   TaskQuery};
 #[cfg(feature = "network")]
-use super::network::{Network, NetworkQuery, NewPort, Port, PortQuery,
-                     Subnet, SubnetQuery};
-use super::session::Session;
+use super::network::{self, AddressGroup, AddressGroupQuery,
+                     ConntrackHelper, Network, NetworkQuery,
+                     NetworkQuotaDetails, NetworkQuotas, NetworkQuotasUpdate,
+                     NewAddressGroup, NewConntrackHelper, NewNetwork, NewPort,
+                     NewSecurityGroup, NewSubnet, NewTapFlow, NewTapService, NewTrunk, Port,
+                     PortQuery,
+                     QosDirection, QosMinimumBandwidthRule,
+                     QosMinimumPacketRateRule, QosPacketRateLimitRule,
+                     QosRuleType, Router,
+                     RouterInterface, RouterQuery, SecurityGroup,
+                     SecurityGroupQuery, SecurityGroupRule,
+                     SecurityGroupRuleQuery, ServiceFlavor,
+                     Subnet, SubnetQuery, TapFlow,
+                     TapFlowDirection, TapFlowQuery, TapService,
+                     TapServiceQuery, Trunk, TrunkQuery};
+#[cfg(feature = "object-storage")]
+use super::object_storage::{Account, Object};
+use super::session::{RequestBuilder, ResponseCache, RetryPolicy,
+                     ServiceVersion, Session};
+#[cfg(feature = "compute")]
+use super::utils::ResultExt;
+#[cfg(any(feature = "compute", feature = "network"))]
+use super::workflows::{self, BulkDeletionWaiter};
+
+
+/// Defaults applied to new resources created through a `Cloud`.
+///
+/// Every field is only used when the corresponding value is not provided
+/// explicitly on the creation builder.
+#[derive(Clone, Debug, Default)]
+pub struct Defaults {
+    /// Availability zone for new servers.
+    pub availability_zone: Option<String>,
+    /// Key pair name for new servers.
+    pub key_name: Option<String>,
+    /// Network to attach new servers to when no NICs are requested.
+    pub network: Option<NetworkRef>,
+    /// Security groups for new servers and ports.
+    ///
+    /// Servers accept names or IDs, while ports require IDs.
+    pub security_groups: Vec<String>,
+}
+
+/// Maximum number of IDs to fetch in one request.
+#[allow(dead_code)]
+const IDS_PER_REQUEST: usize = 50;
+
+/// Quotas of a project across services.
+///
+/// Quotas of a service are `None` if the service is not in the catalog.
+#[derive(Clone, Debug)]
+pub struct Quotas {
+    /// Compute quotas.
+    #[cfg(feature = "compute")]
+    pub compute: Option<ComputeQuotas>,
+    /// Network quotas.
+    #[cfg(feature = "network")]
+    pub network: Option<NetworkQuotas>,
+}
 
+/// Convert a missing endpoint into `None`.
+#[allow(dead_code)]
+fn if_service_exists<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ref err) if err.kind() == ErrorKind::EndpointNotFound => Ok(None),
+        Err(err) => Err(err)
+    }
+}
 
 /// OpenStack cloud API.
 ///
 /// Provides high-level API for working with OpenStack clouds.
 #[derive(Debug, Clone)]
 pub struct Cloud {
-    session: Rc<Session>
+    session: Rc<Session>,
+    defaults: Defaults,
 }
 
 impl Cloud {
@@ -59,12 +145,29 @@ impl Cloud {
     /// [from_env](#method.from_env).
     pub fn new<Auth: AuthMethod + 'static>(auth_method: Auth) -> Cloud {
         Cloud {
-            session: Rc::new(Session::new(auth_method))
+            session: Rc::new(Session::new(auth_method)),
+            defaults: Defaults::default(),
         }
     }
 
     /// Create a new cloud object from environment variables.
     ///
+    /// The endpoint interface is taken from `OS_INTERFACE` (or the legacy
+    /// `OS_ENDPOINT_TYPE`), the default is `public`. It can be set for one
+    /// service via `OS_<TYPE>_INTERFACE`, e.g. `OS_COMPUTE_INTERFACE`.
+    /// `OS_<TYPE>_ENDPOINT_OVERRIDE`, e.g. `OS_NETWORK_ENDPOINT_OVERRIDE`,
+    /// replaces the catalog endpoint of a service.
+    ///
+    /// The `OS_<TYPE>_API_VERSION` variables, e.g. `OS_COMPUTE_API_VERSION`,
+    /// select the API version to use by default for a service. They are
+    /// negotiated on the first request to the service: versions above the
+    /// ones supported by this crate are lowered, unsupported versions are
+    /// ignored with a warning. No requests are made by this call.
+    ///
+    /// See [auth::from_env](auth/fn.from_env.html) for the authentication
+    /// and TLS variables, including the limits of `OS_KEY` and
+    /// `OS_INSECURE`.
+    ///
     /// # Example
     ///
     /// ```rust,no_run
@@ -74,13 +177,22 @@ impl Cloud {
     /// # fn main() { cloud_from_env().unwrap(); }
     /// ```
     pub fn from_env() -> Result<Cloud> {
+        let mut session = Session::new(auth::from_env()?);
+        session.set_endpoint_interface_from_env();
+        session.set_endpoint_overrides_from_env()?;
+        session.set_api_versions_from_env();
         Ok(Cloud {
-            session: Rc::new(Session::new(auth::from_env()?))
+            session: Rc::new(session),
+            defaults: Defaults::default(),
         })
     }
 
     /// Convert this cloud into one using the given endpoint interface.
     ///
+    /// The interface (`public`, `internal` or `admin`) is used for all
+    /// services, unless set for a service via
+    /// [with_service_endpoint_interface](#method.with_service_endpoint_interface).
+    ///
     /// # Example
     ///
     /// ```rust,no_run
@@ -97,149 +209,162 @@ impl Cloud {
         self
     }
 
-    /// Refresh this `Cloud` object (renew token, refetch service catalog, etc).
-    pub fn refresh(&mut self) -> Result<()> {
-        Rc::make_mut(&mut self.session).auth_method_mut().refresh()
-    }
-
-    /// Build a query against flavor list.
-    ///
-    /// The returned object is a builder that should be used to construct
-    /// the query.
-    #[cfg(feature = "compute")]
-    pub fn find_flavors(&self) -> FlavorQuery {
-        FlavorQuery::new(self.session.clone())
-    }
-
-    /// Build a query against image list.
-    ///
-    /// The returned object is a builder that should be used to construct
-    /// the query.
-    #[cfg(feature = "image")]
-    pub fn find_images(&self) -> ImageQuery {
-        ImageQuery::new(self.session.clone())
-    }
-
-    /// Build a query against key pairs list.
+    /// Convert this cloud into one using the given endpoint interface for
+    /// a service.
     ///
-    /// The returned object is a builder that should be used to construct
-    /// the query.
-    #[cfg(feature = "compute")]
-    pub fn find_keypairs(&self) -> KeyPairQuery {
-        KeyPairQuery::new(self.session.clone())
-    }
-
-    /// Build a query against network list.
+    /// # Example
     ///
-    /// The returned object is a builder that should be used to construct
-    /// the query.
-    #[cfg(feature = "network")]
-    pub fn find_networks(&self) -> NetworkQuery {
-        NetworkQuery::new(self.session.clone())
-    }
-
-    /// Build a query against port list.
+    /// ```rust,no_run
+    /// fn cloud_from_env() -> openstack::Result<openstack::Cloud> {
+    ///     openstack::Cloud::from_env()
+    ///         .map(|os| os.with_service_endpoint_interface("compute",
+    ///                                                      "internal"))
+    /// }
     ///
-    /// The returned object is a builder that should be used to construct
-    /// the query.
-    #[cfg(feature = "network")]
-    pub fn find_ports(&self) -> PortQuery {
-        PortQuery::new(self.session.clone())
+    /// # fn main() { cloud_from_env().unwrap(); }
+    /// ```
+    pub fn with_service_endpoint_interface<S1, S2>(mut self, service_type: S1,
+                                                   endpoint_interface: S2)
+            -> Cloud where S1: Into<String>, S2: Into<String> {
+        Rc::make_mut(&mut self.session)
+            .set_service_endpoint_interface(service_type, endpoint_interface);
+        self
     }
 
-    /// Build a query against server list.
+    /// Convert this cloud into one using the given endpoint for a service.
     ///
-    /// The returned object is a builder that should be used to construct
-    /// the query.
+    /// The endpoint is used instead of the one from the service catalog,
+    /// e.g. when the catalog only contains URLs that are not reachable.
+    /// With [from_env](#method.from_env) the same can be achieved by setting
+    /// the `OS_<TYPE>_ENDPOINT_OVERRIDE` environment variable, e.g.
+    /// `OS_NETWORK_ENDPOINT_OVERRIDE`.
     ///
     /// # Example
     ///
-    /// Sorting servers by `access_ip_v4` and getting first 5 results:
-    ///
     /// ```rust,no_run
-    /// use openstack;
+    /// fn cloud_from_env() -> openstack::Result<openstack::Cloud> {
+    ///     openstack::Cloud::from_env()?
+    ///         .with_endpoint_override("network", "https://neutron.example.com")
+    /// }
     ///
-    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let sorting = openstack::compute::ServerSortKey::AccessIpv4;
-    /// let server_list = os.find_servers()
-    ///     .sort_by(openstack::Sort::Asc(sorting)).with_limit(5)
-    ///     .all().expect("Unable to fetch servers");
+    /// # fn main() { cloud_from_env().unwrap(); }
     /// ```
-    #[cfg(feature = "compute")]
-    pub fn find_servers(&self) -> ServerQuery {
-        ServerQuery::new(self.session.clone())
+    pub fn with_endpoint_override<S>(mut self, service_type: S, endpoint: &str)
+            -> Result<Cloud> where S: Into<String> {
+        let endpoint = Url::parse(endpoint).map_err(|err| {
+            Error::new(ErrorKind::InvalidInput,
+                       format!("Invalid endpoint URL {}: {}", endpoint, err))
+        })?;
+        Rc::make_mut(&mut self.session).set_endpoint_override(service_type,
+                                                              endpoint);
+        Ok(self)
     }
 
-    /// Build a query against subnet list.
+    /// Negotiate the compute API version to use by default.
     ///
-    /// The returned object is a builder that should be used to construct
-    /// the query.
-    #[cfg(feature = "network")]
-    pub fn find_subnets(&self) -> SubnetQuery {
-        SubnetQuery::new(self.session.clone())
-    }
-
-    /// Find a flavor by its name or ID.
+    /// Features that need a newer version still use it. Returns the
+    /// resulting version, `None` means that no version is requested.
     ///
     /// # Example
     ///
     /// ```rust,no_run
     /// use openstack;
+    /// use openstack::common::{ApiVersion, ApiVersionRequest};
     ///
-    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server = os.get_flavor("m1.medium").expect("Unable to get a flavor");
+    /// let mut os = openstack::Cloud::from_env()
+    ///     .expect("Unable to authenticate");
+    /// let version = os.set_compute_api_version(
+    ///         ApiVersionRequest::Exact(ApiVersion(2, 26)))
+    ///     .expect("Compute API version 2.26 is not supported");
     /// ```
     #[cfg(feature = "compute")]
-    pub fn get_flavor<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Flavor> {
-        Flavor::load(self.session.clone(), id_or_name)
+    pub fn set_compute_api_version(&mut self, request: ApiVersionRequest)
+            -> Result<Option<ApiVersion>> {
+        Rc::make_mut(&mut self.session)
+            .set_api_version::<compute::ServiceType>(request)
     }
 
-    /// Find an image by its name or ID.
+    /// Convert this cloud into one using the given retry policy.
+    ///
+    /// The policy applies to idempotent requests that fail with HTTP 429
+    /// or 503.
     ///
     /// # Example
     ///
     /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
     /// use openstack;
+    /// use openstack::session::RetryPolicy;
     ///
-    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server = os.get_image("centos7").expect("Unable to get a image");
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate")
+    ///     .with_retry_policy(RetryPolicy::new(5)
+    ///                        .with_max_delay(Duration::from_secs(60)));
     /// ```
-    #[cfg(feature = "image")]
-    pub fn get_image<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Image> {
-        Image::new(self.session.clone(), id_or_name)
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Cloud {
+        Rc::make_mut(&mut self.session).set_retry_policy(policy);
+        self
     }
 
-    /// Find a key pair by its name or ID.
+    /// Convert this cloud into one using the given timeout for HTTP requests.
+    ///
+    /// The timeout covers connecting as well as reading the response, the
+    /// default is 30 seconds. Waiters have their own timeouts, see their
+    /// `with_timeout` and `with_poll_interval` calls.
+    ///
+    /// Fails with `InvalidConfig` if the authentication method cannot change
+    /// its HTTP client, e.g. when created with `Identity::new_with_client`.
     ///
     /// # Example
     ///
     /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
     /// use openstack;
     ///
-    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server = os.get_keypair("default").expect("Unable to get a key pair");
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate")
+    ///     .with_timeout(Duration::from_secs(120))
+    ///     .expect("Unable to set the timeout");
     /// ```
-    #[cfg(feature = "compute")]
-    pub fn get_keypair<Id: AsRef<str>>(&self, name: Id) -> Result<KeyPair> {
-        KeyPair::new(self.session.clone(), name)
+    pub fn with_timeout(mut self, timeout: Duration) -> Result<Cloud> {
+        Rc::make_mut(&mut self.session).set_timeout(timeout)?;
+        Ok(self)
     }
 
-    /// Find an network by its name or ID.
+    /// Convert this cloud into one caching GET responses for the given time.
+    ///
+    /// Cached responses are dropped on successful changes to the same
+    /// resources made through this `Cloud`, use `response_cache` to
+    /// invalidate them manually or to subscribe to invalidation events.
     ///
     /// # Example
     ///
     /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
     /// use openstack;
     ///
-    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server = os.get_network("centos7").expect("Unable to get a network");
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate")
+    ///     .cached(Duration::from_secs(30));
+    /// os.response_cache().unwrap().subscribe(|url| {
+    ///     println!("{} changed", url);
+    /// });
     /// ```
-    #[cfg(feature = "network")]
-    pub fn get_network<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Network> {
-        Network::new(self.session.clone(), id_or_name)
+    pub fn cached(mut self, ttl: Duration) -> Cloud {
+        Rc::make_mut(&mut self.session)
+            .set_response_cache(Some(ResponseCache::new(ttl)));
+        self
     }
 
-    /// Find an port by its name or ID.
+    /// Cache of GET responses, if enabled with `cached`.
+    pub fn response_cache(&self) -> Option<&ResponseCache> {
+        self.session.response_cache()
+    }
+
+    /// Convert this cloud into one using the given defaults for new resources.
+    ///
+    /// The session is shared, so a cloned `Cloud` can be given different
+    /// defaults without re-authenticating.
     ///
     /// # Example
     ///
@@ -247,51 +372,67 @@ impl Cloud {
     /// use openstack;
     ///
     /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server = os.get_port("4d9c1710-fa02-49f9-8218-291024ef4140")
-    ///     .expect("Unable to get a port");
+    /// let staging = os.clone().with_defaults(openstack::Defaults {
+    ///     availability_zone: Some("staging".to_string()),
+    ///     key_name: Some("deploy".to_string()),
+    ///     network: Some("private".into()),
+    ///     security_groups: vec!["default".to_string()],
+    /// });
+    /// let server = staging.new_server("app-1", "m1.small")
+    ///     .with_image("centos7")
+    ///     .create().expect("Unable to start server creation");
     /// ```
-    #[cfg(feature = "network")]
-    pub fn get_port<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Port> {
-        Port::load(self.session.clone(), id_or_name)
+    pub fn with_defaults(mut self, defaults: Defaults) -> Cloud {
+        self.defaults = defaults;
+        self
     }
 
-    /// Find a server by its name or ID.
-    ///
-    /// # Example
-    ///
-    /// ```rust,no_run
-    /// use openstack;
+    /// Defaults used for new resources.
+    pub fn defaults(&self) -> &Defaults {
+        &self.defaults
+    }
+
+    /// Refresh this `Cloud` object (renew token, refetch service catalog, etc).
     ///
-    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server = os.get_server("8a1c355b-2e1e-440a-8aa8-f272df72bc32")
-    ///     .expect("Unable to get a server");
-    /// ```
-    #[cfg(feature = "compute")]
-    pub fn get_server<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Server> {
-        Server::load(self.session.clone(), id_or_name)
+    /// Cached service information, including discovered API versions, is
+    /// dropped as well.
+    pub fn refresh(&mut self) -> Result<()> {
+        let session = Rc::make_mut(&mut self.session);
+        session.clear_service_info();
+        session.auth_method_mut().refresh()
     }
 
-    /// Find an subnet by its name or ID.
+    /// Revoke the authentication token of this `Cloud` object.
+    ///
+    /// Use it to explicitly log out instead of leaving a valid token behind.
+    /// The revoked token is never used again, including by clones of this
+    /// object; further requests authenticate again. Cached responses are
+    /// dropped as well.
     ///
     /// # Example
     ///
     /// ```rust,no_run
     /// use openstack;
     ///
-    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server = os.get_subnet("private-subnet")
-    ///     .expect("Unable to get a subnet");
+    /// let mut os = openstack::Cloud::from_env()
+    ///     .expect("Unable to authenticate");
+    /// // ... use the cloud ...
+    /// os.revoke_token().expect("Unable to revoke the token");
     /// ```
-    #[cfg(feature = "network")]
-    pub fn get_subnet<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Subnet> {
-        Subnet::load(self.session.clone(), id_or_name)
+    pub fn revoke_token(&mut self) -> Result<()> {
+        if let Some(cache) = self.session.response_cache() {
+            cache.clear();
+        }
+        Rc::make_mut(&mut self.session).auth_method_mut().revoke()
     }
 
-    /// List all flavors.
+    /// Discover API versions of all services in the catalog.
     ///
-    /// This call can yield a lot of results, use the
-    /// [find_flavors](#method.find_flavors) call to limit the number of
-    /// flavors to receive.
+    /// Returns a map from a service type to the versions it supports,
+    /// including the microversion ranges. Useful to verify compatibility
+    /// before upgrading a cloud or this crate. Services that fail version
+    /// discovery (e.g. because they do not support it) are reported with
+    /// the error.
     ///
     /// # Example
     ///
@@ -299,33 +440,57 @@ impl Cloud {
     /// use openstack;
     ///
     /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server_list = os.list_flavors().expect("Unable to fetch flavors");
+    /// let matrix = os.probe_all_versions().expect("Unable to read catalog");
+    /// for (service_type, versions) in matrix {
+    ///     match versions {
+    ///         Ok(versions) => for version in versions {
+    ///             println!("{}: {} {} ({:?} to {:?})", service_type,
+    ///                      version.id, version.status,
+    ///                      version.minimum_version, version.current_version);
+    ///         },
+    ///         Err(err) => println!("{}: {}", service_type, err)
+    ///     }
+    /// }
     /// ```
-    #[cfg(feature = "compute")]
-    pub fn list_flavors(&self) -> Result<Vec<FlavorSummary>> {
-        self.find_flavors().all()
+    pub fn probe_all_versions(&self)
+            -> Result<BTreeMap<String, Result<Vec<ServiceVersion>>>> {
+        self.session.probe_all_versions()
     }
 
-    /// List all images.
+    /// Make an authenticated HTTP request to any service from the catalog.
     ///
-    /// This call can yield a lot of results, use the
-    /// [find_images](#method.find_images) call to limit the number of
-    /// images to receive.
+    /// Use this for APIs that are not covered by this crate yet. The path is
+    /// appended to the catalog endpoint of the given service type as is,
+    /// including the API version if the endpoint does not contain it.
     ///
     /// # Example
     ///
     /// ```rust,no_run
-    /// use openstack;
+    /// extern crate openstack;
+    /// extern crate reqwest;
+    /// extern crate serde_json;
     ///
+    /// use reqwest::Method;
+    ///
+    /// # fn main() {
     /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server_list = os.list_images().expect("Unable to fetch images");
+    /// let volumes: serde_json::Value = os
+    ///     .raw_request("volumev3", Method::Get, &["volumes"])
+    ///     .expect("Unable to build a request")
+    ///     .query(&[("limit", "10")])
+    ///     .receive_json().expect("Unable to list volumes");
+    /// # }
     /// ```
-    #[cfg(feature = "image")]
-    pub fn list_images(&self) -> Result<Vec<Image>> {
-        self.find_images().all()
+    pub fn raw_request<S: AsRef<str>>(&self, service_type: S, method: Method,
+                                      path: &[&str])
+            -> Result<RequestBuilder> {
+        self.session.raw_request(service_type, method, path)
     }
 
-    /// List all key pairs.
+    /// Delete all key pairs with names starting with the given prefix.
+    ///
+    /// Returns the number of deleted key pairs. Key pairs that disappear
+    /// while this call is running are ignored.
     ///
     /// # Example
     ///
@@ -333,18 +498,34 @@ impl Cloud {
     /// use openstack;
     ///
     /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let result = os.list_keypairs().expect("Unable to fetch key pairs");
+    /// let count = os.delete_keypairs_matching("ci-")
+    ///     .expect("Unable to delete key pairs");
     /// ```
     #[cfg(feature = "compute")]
-    pub fn list_keypairs(&self) -> Result<Vec<KeyPair>> {
-        self.find_keypairs().all()
+    pub fn delete_keypairs_matching<S: AsRef<str>>(&self, prefix: S)
+            -> Result<usize> {
+        let prefix = prefix.as_ref();
+        let mut count = 0;
+        for keypair in self.list_keypairs()? {
+            if ! keypair.name().starts_with(prefix) {
+                continue;
+            }
+
+            let name = keypair.name().clone();
+            if keypair.delete().not_found_as_none()?.is_some() {
+                count += 1;
+            } else {
+                debug!("Key pair {} was already deleted", name);
+            }
+        }
+        Ok(count)
     }
 
-    /// List all networks.
+    /// Delete all ports matching the query.
     ///
-    /// This call can yield a lot of results, use the
-    /// [find_networks](#method.find_networks) call to limit the number of
-    /// networks to receive.
+    /// Deletions are requested for all ports at once, the returned waiter
+    /// waits for all of them. Failures of individual ports are collected in
+    /// its report; only a failure to list the ports is returned as an error.
     ///
     /// # Example
     ///
@@ -352,18 +533,27 @@ impl Cloud {
     /// use openstack;
     ///
     /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server_list = os.list_networks().expect("Unable to fetch networks");
+    /// let report = os.delete_ports(os.find_ports().with_name("ci-port"))
+    ///     .expect("Unable to list ports")
+    ///     .wait_report();
+    /// if ! report.is_success() {
+    ///     println!("Failed to delete {:?}", report.failed);
+    /// }
     /// ```
     #[cfg(feature = "network")]
-    pub fn list_networks(&self) -> Result<Vec<Network>> {
-        self.find_networks().all()
+    pub fn delete_ports(&self, query: PortQuery)
+            -> Result<BulkDeletionWaiter<Port>> {
+        let ports = query.all()?;
+        debug!("Deleting {} ports", ports.len());
+        Ok(workflows::start_delete_all(ports, Port::delete))
     }
 
-    /// List all ports.
+    /// Delete all servers matching the query.
     ///
-    /// This call can yield a lot of results, use the
-    /// [find_ports](#method.find_ports) call to limit the number of
-    /// ports to receive.
+    /// Deletions are requested for all servers at once, the returned waiter
+    /// waits for all of them. Failures of individual servers are collected
+    /// in its report; only a failure to list the servers is returned as
+    /// an error.
     ///
     /// # Example
     ///
@@ -371,18 +561,34 @@ impl Cloud {
     /// use openstack;
     ///
     /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server_list = os.list_ports().expect("Unable to fetch ports");
+    /// let token = openstack::common::CancellationToken::new();
+    /// let report = os.delete_servers(os.find_servers().with_name("^ci-"))
+    ///     .expect("Unable to list servers")
+    ///     .with_cancellation(token)
+    ///     .wait_report();
+    /// println!("Deleted {:?}, failed {:?}", report.succeeded, report.failed);
     /// ```
+    #[cfg(feature = "compute")]
+    pub fn delete_servers(&self, query: ServerQuery)
+            -> Result<BulkDeletionWaiter<Server>> {
+        let servers = query.detailed()?;
+        debug!("Deleting {} servers", servers.len());
+        Ok(workflows::start_delete_all(servers, Server::delete))
+    }
+
+    /// Build a query against address group list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
     #[cfg(feature = "network")]
-    pub fn list_ports(&self) -> Result<Vec<Port>> {
-        self.find_ports().all()
+    pub fn find_address_groups(&self) -> AddressGroupQuery {
+        AddressGroupQuery::new(self.session.clone())
     }
 
-    /// List all servers.
+    /// Find the external network, e.g. to allocate floating IPs from.
     ///
-    /// This call can yield a lot of results, use the
-    /// [find_servers](#method.find_servers) call to limit the number of
-    /// servers to receive.
+    /// If several external networks exist, the one marked as default is
+    /// returned. Fails with `TooManyItems` if there is no single default.
     ///
     /// # Example
     ///
@@ -390,18 +596,37 @@ impl Cloud {
     /// use openstack;
     ///
     /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server_list = os.list_servers().expect("Unable to fetch servers");
+    /// let network = os.find_external_network()
+    ///     .expect("Unable to find an external network");
+    /// let subnets = network.subnets().expect("Unable to fetch subnets");
     /// ```
+    #[cfg(feature = "network")]
+    pub fn find_external_network(&self) -> Result<Network> {
+        network::find_external_network(self.session.clone())
+    }
+
+    /// Build a query against flavor list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
     #[cfg(feature = "compute")]
-    pub fn list_servers(&self) -> Result<Vec<ServerSummary>> {
-        self.find_servers().all()
+    pub fn find_flavors(&self) -> FlavorQuery {
+        FlavorQuery::new(self.session.clone())
     }
 
-    /// List all subnets.
+    /// Build a query against image list.
     ///
-    /// This call can yield a lot of results, use the
-    /// [find_subnets](#method.find_subnets) call to limit the number of
-    /// subnets to receive.
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "image")]
+    pub fn find_images(&self) -> ImageQuery {
+        ImageQuery::new(self.session.clone())
+    }
+
+    /// Build a query against images shared with the current project.
+    ///
+    /// Only images with the given member status are returned, e.g.
+    /// `ImageMemberStatus::Pending` for images awaiting acceptance.
     ///
     /// # Example
     ///
@@ -409,39 +634,1661 @@ impl Cloud {
     /// use openstack;
     ///
     /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
-    /// let server_list = os.list_subnets().expect("Unable to fetch subnets");
+    /// let pending = os.find_shared_images(
+    ///         openstack::image::ImageMemberStatus::Pending)
+    ///     .all().expect("Unable to list shared images");
     /// ```
-    #[cfg(feature = "network")]
-    pub fn list_subnets(&self) -> Result<Vec<Subnet>> {
-        self.find_subnets().all()
+    #[cfg(feature = "image")]
+    pub fn find_shared_images(&self, status: ImageMemberStatus) -> ImageQuery {
+        self.find_images().with_shared_status(status)
     }
 
-    /// Prepare a new key pair for creation.
+    /// Build a query against image task list.
     ///
-    /// This call returns a `NewKeyPair` object, which is a builder to populate
-    /// key pair fields.
-    #[cfg(feature = "compute")]
-    pub fn new_keypair<S>(&self, name: S) -> NewKeyPair where S: Into<String> {
-        NewKeyPair::new(self.session.clone(), name.into())
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "image")]
+    pub fn find_image_tasks(&self) -> TaskQuery {
+        TaskQuery::new(self.session.clone())
+    }
+
+    /// Build a query against key pairs list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "compute")]
+    pub fn find_keypairs(&self) -> KeyPairQuery {
+        KeyPairQuery::new(self.session.clone())
+    }
+
+    /// Build a query against network list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "network")]
+    pub fn find_networks(&self) -> NetworkQuery {
+        NetworkQuery::new(self.session.clone())
+    }
+
+    /// Build a query against port list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "network")]
+    pub fn find_ports(&self) -> PortQuery {
+        PortQuery::new(self.session.clone())
+    }
+
+    /// Build a query against router list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "network")]
+    pub fn find_routers(&self) -> RouterQuery {
+        RouterQuery::new(self.session.clone())
+    }
+
+    /// Build a query against project list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    ///
+    /// # Example
+    ///
+    /// Listing direct children of a project:
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let children = os.find_projects()
+    ///     .with_parent("4d9c1710fa0249f98218291024ef4140")
+    ///     .all().expect("Unable to fetch projects");
+    /// ```
+    #[cfg(feature = "identity")]
+    pub fn find_projects(&self) -> ProjectQuery {
+        ProjectQuery::new(self.session.clone())
+    }
+
+    /// Build a query against security group rule list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    ///
+    /// # Example
+    ///
+    /// Finding rules that open SSH to the whole world:
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    /// use openstack::network::RuleDirection;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let rules = os.find_security_group_rules()
+    ///     .with_direction(RuleDirection::Ingress)
+    ///     .with_protocol("tcp")
+    ///     .with_port_range(22, 22)
+    ///     .with_remote_ip_prefix("0.0.0.0/0".parse().unwrap())
+    ///     .all().expect("Unable to fetch security group rules");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn find_security_group_rules(&self) -> SecurityGroupRuleQuery {
+        SecurityGroupRuleQuery::new(self.session.clone())
+    }
+
+    /// Build a query against security group list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let groups = os.find_security_groups()
+    ///     .with_tags(vec!["managed"])
+    ///     .all().expect("Unable to fetch security groups");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn find_security_groups(&self) -> SecurityGroupQuery {
+        SecurityGroupQuery::new(self.session.clone())
+    }
+
+    /// Build a query against server list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    ///
+    /// # Example
+    ///
+    /// Sorting servers by `access_ip_v4` and getting first 5 results:
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let sorting = openstack::compute::ServerSortKey::AccessIpv4;
+    /// let server_list = os.find_servers()
+    ///     .sort_by(openstack::Sort::Asc(sorting)).with_limit(5)
+    ///     .all().expect("Unable to fetch servers");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn find_servers(&self) -> ServerQuery {
+        ServerQuery::new(self.session.clone())
+    }
+
+    /// Build a query against subnet list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "network")]
+    pub fn find_subnets(&self) -> SubnetQuery {
+        SubnetQuery::new(self.session.clone())
+    }
+
+    /// Build a query against tap flow list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query. Requires the `taas` Network API extension.
+    #[cfg(feature = "network")]
+    pub fn find_tap_flows(&self) -> TapFlowQuery {
+        TapFlowQuery::new(self.session.clone())
+    }
+
+    /// Build a query against tap service list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query. Requires the `taas` Network API extension.
+    #[cfg(feature = "network")]
+    pub fn find_tap_services(&self) -> TapServiceQuery {
+        TapServiceQuery::new(self.session.clone())
+    }
+
+    /// Build a query against trunk list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query. Requires the `trunk` Network API extension.
+    #[cfg(feature = "network")]
+    pub fn find_trunks(&self) -> TrunkQuery {
+        TrunkQuery::new(self.session.clone())
+    }
+
+    /// Build a query against DNS zone list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "dns")]
+    pub fn find_zones(&self) -> ZoneQuery {
+        ZoneQuery::new(self.session.clone())
+    }
+
+    /// Get information about the current object storage account.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let account = os.get_account().expect("Unable to get the account");
+    /// println!("{} bytes used of {:?}", account.bytes_used(),
+    ///          account.quota_bytes());
+    /// ```
+    #[cfg(feature = "object-storage")]
+    pub fn get_account(&self) -> Result<Account> {
+        Account::load(self.session.clone())
+    }
+
+    /// Find an address group by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let group = os.get_address_group("allowlist")
+    ///     .expect("Unable to get an address group");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_address_group<Id: AsRef<str>>(&self, id_or_name: Id)
+            -> Result<AddressGroup> {
+        AddressGroup::load(self.session.clone(), id_or_name)
+    }
+
+    /// Find a flavor by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server = os.get_flavor("m1.medium").expect("Unable to get a flavor");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn get_flavor<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Flavor> {
+        Flavor::load(self.session.clone(), id_or_name)
+    }
+
+    /// Find an image by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server = os.get_image("centos7").expect("Unable to get a image");
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn get_image<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Image> {
+        Image::new(self.session.clone(), id_or_name)
+    }
+
+    /// Get an image task by its ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    /// use openstack::image::TaskStatus;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let task = os.get_image_task("8a2ea42d-06b5-42c2-a54d-97105420f2bb")
+    ///     .expect("Unable to get a task");
+    /// if task.status() == TaskStatus::Failure {
+    ///     println!("Import failed: {}", task.message());
+    /// }
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn get_image_task<Id: AsRef<str>>(&self, id: Id) -> Result<Task> {
+        Task::load(self.session.clone(), id)
+    }
+
+    /// Find a key pair by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server = os.get_keypair("default").expect("Unable to get a key pair");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn get_keypair<Id: AsRef<str>>(&self, name: Id) -> Result<KeyPair> {
+        KeyPair::new(self.session.clone(), name)
+    }
+
+    /// Find an network by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server = os.get_network("centos7").expect("Unable to get a network");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_network<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Network> {
+        Network::new(self.session.clone(), id_or_name)
+    }
+
+    /// Get network quotas of a project with their usage.
+    ///
+    /// Returns the limit, the number of used and the number of reserved
+    /// items for each resource. Requires the `quota_details` Network API
+    /// extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let details = os.get_network_quota_details("demo")
+    ///     .expect("Unable to get quota details");
+    /// println!("{} of {} ports used", details.ports.used, details.ports.limit);
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_network_quota_details<P>(&self, project: P)
+            -> Result<NetworkQuotaDetails> where P: Into<ProjectRef> {
+        network::get_quota_details(&self.session, project.into())
+    }
+
+    /// Fetch networks with the given IDs.
+    ///
+    /// IDs that do not exist are silently skipped. The IDs are requested in
+    /// batches to keep the URLs short.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let networks = os.get_networks_by_ids(vec![
+    ///         "3e3f3b8c-1c54-4a39-8b16-4ab2c1ef3f5a",
+    ///         "8a4ef8b3-5a5c-4b0e-a4e9-2b9fe6c3a1e7",
+    ///     ])
+    ///     .expect("Unable to get networks");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_networks_by_ids<I, S>(&self, ids: I) -> Result<Vec<Network>>
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        let ids: Vec<String> = ids.into_iter().map(Into::into).collect();
+        let mut result = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(IDS_PER_REQUEST) {
+            result.extend(self.find_networks().with_ids(chunk.iter().cloned())
+                          .all()?);
+        }
+        Ok(result)
+    }
+
+    /// Get information about an object in a container.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let mut object = os.get_object("backups", "db/2018-05-01.tar.gz")
+    ///     .expect("Unable to get the object");
+    /// let copy = object.copy_to("archive", "db/2018-05-01.tar.gz")
+    ///     .expect("Unable to copy the object");
+    /// object.set_metadata("archived-to", "archive")
+    ///     .expect("Unable to update metadata");
+    /// println!("Copied {} bytes", copy.content_length());
+    /// ```
+    #[cfg(feature = "object-storage")]
+    pub fn get_object<C, N>(&self, container: C, name: N) -> Result<Object>
+            where C: AsRef<str>, N: AsRef<str> {
+        Object::load(self.session.clone(), container, name)
+    }
+
+    /// Find an port by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server = os.get_port("4d9c1710-fa02-49f9-8218-291024ef4140")
+    ///     .expect("Unable to get a port");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_port<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Port> {
+        Port::load(self.session.clone(), id_or_name)
+    }
+
+    /// Find a router by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let router = os.get_router("router1").expect("Unable to get a router");
+    /// let interfaces = router.interfaces()
+    ///     .expect("Unable to fetch router interfaces");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_router<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Router> {
+        Router::load(self.session.clone(), id_or_name)
+    }
+
+    /// Fetch ports with the given IDs.
+    ///
+    /// IDs that do not exist are silently skipped. The IDs are requested in
+    /// batches to keep the URLs short.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let ports = os.get_ports_by_ids(vec![
+    ///         "4d9c1710-fa02-49f9-8218-291024ef4140",
+    ///         "c5ec1b6a-2dde-4a53-9c39-0d7e7ee0c1a2",
+    ///     ])
+    ///     .expect("Unable to get ports");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_ports_by_ids<I, S>(&self, ids: I) -> Result<Vec<Port>>
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        let ids: Vec<String> = ids.into_iter().map(Into::into).collect();
+        let mut result = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(IDS_PER_REQUEST) {
+            result.extend(self.find_ports().with_ids(chunk.iter().cloned())
+                          .all()?);
+        }
+        Ok(result)
+    }
+
+    /// Find a project by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let project = os.get_project("demo").expect("Unable to get a project");
+    /// let subtree = project.subtree().expect("Unable to fetch the subtree");
+    /// ```
+    #[cfg(feature = "identity")]
+    pub fn get_project<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Project> {
+        Project::load(self.session.clone(), id_or_name)
+    }
+
+    /// Get quotas of a project across all supported services.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let quotas = os.get_quotas("demo").expect("Unable to get quotas");
+    /// if let Some(compute) = quotas.compute {
+    ///     println!("Up to {} servers", compute.instances);
+    /// }
+    /// ```
+    pub fn get_quotas<P: Into<ProjectRef>>(&self, project: P) -> Result<Quotas> {
+        #[allow(unused_variables)]
+        let project = project.into();
+        Ok(Quotas {
+            #[cfg(feature = "compute")]
+            compute: if_service_exists(
+                compute::get_quotas(&self.session, project.clone()))?,
+            #[cfg(feature = "network")]
+            network: if_service_exists(
+                network::get_quotas(&self.session, project.clone()))?,
+        })
+    }
+
+    /// Find a security group rule by its ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let rule = os.get_security_group_rule("a3e1f4c2-5f3a-4d8e-9c1b-2f6d7e8a9b0c")
+    ///     .expect("Unable to get a security group rule");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_security_group_rule<Id: AsRef<str>>(&self, id: Id)
+            -> Result<SecurityGroupRule> {
+        SecurityGroupRule::load(self.session.clone(), id)
+    }
+
+    /// Find a security group by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let group = os.get_security_group("default")
+    ///     .expect("Unable to get a security group");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_security_group<Id: AsRef<str>>(&self, id_or_name: Id)
+            -> Result<SecurityGroup> {
+        SecurityGroup::load(self.session.clone(), id_or_name)
+    }
+
+    /// Find a server by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server = os.get_server("8a1c355b-2e1e-440a-8aa8-f272df72bc32")
+    ///     .expect("Unable to get a server");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn get_server<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Server> {
+        Server::load(self.session.clone(), id_or_name)
+    }
+
+    /// Find a server by its name or ID, controlling how names are resolved.
+    ///
+    /// The value is tried as an ID first. See `ServerLookupOptions` for the
+    /// name matching and disambiguation rules.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    /// use openstack::compute::{ServerLookupOptions, ServerNameAmbiguity};
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let options = ServerLookupOptions::new()
+    ///     .with_ambiguity(ServerNameAmbiguity::MostRecent);
+    /// let server = os.get_server_with_options("worker", options)
+    ///     .expect("Unable to get a server");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn get_server_with_options<Id: AsRef<str>>(&self, id_or_name: Id,
+                                                   options: ServerLookupOptions)
+            -> Result<Server> {
+        Server::load_with_options(self.session.clone(), id_or_name, options)
+    }
+
+    /// Find a server owning the given fixed or floating IP address.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server = os.get_server_by_ip("172.24.4.10".parse().unwrap())
+    ///     .expect("Unable to find a server");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn get_server_by_ip(&self, ip: IpAddr) -> Result<Server> {
+        Server::load_by_ip(self.session.clone(), ip)
+    }
+
+    /// Get a server group by its ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let group = os.get_server_group("c3c0a4c2-7a4e-4d0f-9a8a-5b3a2f1e9d7b")
+    ///     .expect("Unable to get a server group");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn get_server_group<Id: AsRef<str>>(&self, id: Id)
+            -> Result<ServerGroup> {
+        ServerGroup::load(self.session.clone(), id)
+    }
+
+    /// Find an subnet by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server = os.get_subnet("private-subnet")
+    ///     .expect("Unable to get a subnet");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_subnet<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Subnet> {
+        Subnet::load(self.session.clone(), id_or_name)
+    }
+
+    /// Fetch subnets with the given IDs.
+    ///
+    /// IDs that do not exist are silently skipped. The IDs are requested in
+    /// batches to keep the URLs short.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let subnets = os.get_subnets_by_ids(vec![
+    ///         "0e8c1f11-76e7-4b0a-9a1a-49d5b9e5e2f4",
+    ///         "fb7d2c7b-ae1e-4c8e-8a0b-5a1c76e6bf41",
+    ///     ])
+    ///     .expect("Unable to get subnets");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_subnets_by_ids<I, S>(&self, ids: I) -> Result<Vec<Subnet>>
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        let ids: Vec<String> = ids.into_iter().map(Into::into).collect();
+        let mut result = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(IDS_PER_REQUEST) {
+            result.extend(self.find_subnets().with_ids(chunk.iter().cloned())
+                          .all()?);
+        }
+        Ok(result)
+    }
+
+    /// Find a tap flow by its name or ID.
+    ///
+    /// Requires the `taas` Network API extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let flow = os.get_tap_flow("web-mirror")
+    ///     .expect("Unable to get a tap flow");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_tap_flow<Id: AsRef<str>>(&self, id_or_name: Id)
+            -> Result<TapFlow> {
+        TapFlow::load(self.session.clone(), id_or_name)
+    }
+
+    /// Find a tap service by its name or ID.
+    ///
+    /// Requires the `taas` Network API extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let service = os.get_tap_service("ids")
+    ///     .expect("Unable to get a tap service");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_tap_service<Id: AsRef<str>>(&self, id_or_name: Id)
+            -> Result<TapService> {
+        TapService::load(self.session.clone(), id_or_name)
+    }
+
+    /// Find a trunk by its name or ID.
+    ///
+    /// Requires the `trunk` Network API extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let trunk = os.get_trunk("vm-trunk").expect("Unable to get a trunk");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn get_trunk<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Trunk> {
+        Trunk::load(self.session.clone(), id_or_name)
+    }
+
+    /// Find a DNS zone by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let zone = os.get_zone("example.com.").expect("Unable to get a zone");
+    /// let records = zone.list_recordsets().expect("Unable to get records");
+    /// ```
+    #[cfg(feature = "dns")]
+    pub fn get_zone<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Zone> {
+        Zone::load(self.session.clone(), id_or_name)
+    }
+
+    /// Check whether a key pair with the given name exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// if ! os.keypair_exists("default").expect("Unable to check key pair") {
+    ///     println!("Key pair default does not exist");
+    /// }
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn keypair_exists<Id: AsRef<str>>(&self, name: Id) -> Result<bool> {
+        Ok(self.get_keypair(name).not_found_as_none()?.is_some())
+    }
+
+    /// List all address groups.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_address_groups](#method.find_address_groups) call to limit the
+    /// number of address groups to receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let groups = os.list_address_groups()
+    ///     .expect("Unable to fetch address groups");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_address_groups(&self) -> Result<Vec<AddressGroup>> {
+        self.find_address_groups().all()
+    }
+
+    /// List compute availability zones.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// for zone in os.list_availability_zones().expect("Unable to list zones") {
+    ///     println!("{} available: {}", zone.name, zone.state.available);
+    /// }
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn list_availability_zones(&self) -> Result<Vec<AvailabilityZone>> {
+        compute::list_availability_zones(&self.session, false)
+    }
+
+    /// List compute availability zones with their hosts and services.
+    ///
+    /// This call is admin-only.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let zones = os.list_availability_zones_detail()
+    ///     .expect("Unable to list zones");
+    /// for zone in zones {
+    ///     for (host, service) in zone.unavailable_services() {
+    ///         println!("{} is down on {} in {}", service, host, zone.name);
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn list_availability_zones_detail(&self)
+            -> Result<Vec<AvailabilityZone>> {
+        compute::list_availability_zones(&self.session, true)
+    }
+
+    /// List conntrack helpers of a router.
+    ///
+    /// Requires the `l3-conntrack-helper` Network API extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let helpers = os.list_conntrack_helpers("8ecd6a7b-5e9c-4a0b-8b2f-5f3e1d1c2a4b")
+    ///     .expect("Unable to fetch conntrack helpers");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_conntrack_helpers<S>(&self, router_id: S)
+            -> Result<Vec<ConntrackHelper>> where S: Into<String> {
+        network::list_conntrack_helpers(self.session.clone(), router_id.into())
+    }
+
+    /// List all flavors.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_flavors](#method.find_flavors) call to limit the number of
+    /// flavors to receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server_list = os.list_flavors().expect("Unable to fetch flavors");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn list_flavors(&self) -> Result<Vec<FlavorSummary>> {
+        self.find_flavors().all()
+    }
+
+    /// List all images.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_images](#method.find_images) call to limit the number of
+    /// images to receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server_list = os.list_images().expect("Unable to fetch images");
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn list_images(&self) -> Result<Vec<Image>> {
+        self.find_images().all()
+    }
+
+    /// List all image tasks.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_image_tasks](#method.find_image_tasks) call to limit the number
+    /// of tasks to receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let task_list = os.list_image_tasks().expect("Unable to fetch tasks");
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn list_image_tasks(&self) -> Result<Vec<Task>> {
+        self.find_image_tasks().all()
+    }
+
+    /// List all key pairs.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let result = os.list_keypairs().expect("Unable to fetch key pairs");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn list_keypairs(&self) -> Result<Vec<KeyPair>> {
+        self.find_keypairs().all()
+    }
+
+    /// List all networks.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_networks](#method.find_networks) call to limit the number of
+    /// networks to receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server_list = os.list_networks().expect("Unable to fetch networks");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_networks(&self) -> Result<Vec<Network>> {
+        self.find_networks().all()
+    }
+
+    /// List all ports.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_ports](#method.find_ports) call to limit the number of
+    /// ports to receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server_list = os.list_ports().expect("Unable to fetch ports");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_ports(&self) -> Result<Vec<Port>> {
+        self.find_ports().all()
+    }
+
+    /// List all projects.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let projects = os.list_projects().expect("Unable to fetch projects");
+    /// ```
+    #[cfg(feature = "identity")]
+    pub fn list_projects(&self) -> Result<Vec<Project>> {
+        self.find_projects().all()
+    }
+
+    /// List QoS rule types supported by the Network service backend.
+    ///
+    /// Rules of other types are accepted by the API but not honoured, so
+    /// check this list before relying on e.g. bandwidth guarantees. Rule
+    /// types unknown to this crate are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let supported = os.list_qos_rule_types()
+    ///     .expect("Unable to fetch QoS rule types")
+    ///     .contains(&openstack::network::QosRuleType::MinimumBandwidth);
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_qos_rule_types(&self) -> Result<Vec<QosRuleType>> {
+        network::list_qos_rule_types(&self.session)
+    }
+
+    /// List minimum bandwidth rules of a QoS policy.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// for rule in os.list_qos_minimum_bandwidth_rules(
+    ///         "5e5a0e6a-1c2a-4b6e-9d3c-2f8b1e9a7c41")
+    ///         .expect("Unable to fetch minimum bandwidth rules") {
+    ///     rule.delete().expect("Unable to delete a minimum bandwidth rule");
+    /// }
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_qos_minimum_bandwidth_rules<S: Into<String>>(&self, policy_id: S)
+            -> Result<Vec<QosMinimumBandwidthRule>> {
+        network::list_minimum_bandwidth_rules(self.session.clone(),
+                                              policy_id.into())
+    }
+
+    /// List minimum packet rate rules of a QoS policy.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let rules = os.list_qos_minimum_packet_rate_rules(
+    ///         "5e5a0e6a-1c2a-4b6e-9d3c-2f8b1e9a7c41")
+    ///     .expect("Unable to fetch minimum packet rate rules");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_qos_minimum_packet_rate_rules<S: Into<String>>(
+            &self, policy_id: S) -> Result<Vec<QosMinimumPacketRateRule>> {
+        network::list_minimum_packet_rate_rules(self.session.clone(),
+                                                policy_id.into())
+    }
+
+    /// List packet rate limit rules of a QoS policy.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let rules = os.list_qos_packet_rate_limit_rules(
+    ///         "5e5a0e6a-1c2a-4b6e-9d3c-2f8b1e9a7c41")
+    ///     .expect("Unable to fetch packet rate limit rules");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_qos_packet_rate_limit_rules<S: Into<String>>(
+            &self, policy_id: S) -> Result<Vec<QosPacketRateLimitRule>> {
+        network::list_packet_rate_limit_rules(self.session.clone(),
+                                              policy_id.into())
+    }
+
+    /// List flavors that can be used for routers.
+    ///
+    /// Requires the `flavors` Network API extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let flavors = os.list_router_flavors()
+    ///     .expect("Unable to fetch router flavors");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_router_flavors(&self) -> Result<Vec<ServiceFlavor>> {
+        network::list_router_flavors(&self.session)
+    }
+
+    /// List internal interfaces of a router.
+    ///
+    /// Each interface contains the port connecting the router, the subnets
+    /// it is attached to and the fixed IP addresses of the router on them.
+    /// The gateway port is not included.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let interfaces = os.list_router_interfaces("8ecd6a7b-5e9c-4a0b-8b2f-5f3e1d1c2a4b")
+    ///     .expect("Unable to fetch router interfaces");
+    /// for iface in interfaces {
+    ///     for subnet in iface.subnets() {
+    ///         println!("{} ({})", subnet.cidr(), iface.port().id());
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_router_interfaces<S>(&self, router_id: S)
+            -> Result<Vec<RouterInterface>> where S: Into<String> {
+        network::list_router_interfaces(self.session.clone(), router_id.into())
+    }
+
+    /// List all routers.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_routers](#method.find_routers) call to limit the number of
+    /// routers to receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let routers = os.list_routers().expect("Unable to fetch routers");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_routers(&self) -> Result<Vec<Router>> {
+        self.find_routers().all()
+    }
+
+    /// List all security group rules.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_security_group_rules](#method.find_security_group_rules) call
+    /// to limit the number of rules to receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let rules = os.list_security_group_rules()
+    ///     .expect("Unable to fetch security group rules");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_security_group_rules(&self) -> Result<Vec<SecurityGroupRule>> {
+        self.find_security_group_rules().all()
+    }
+
+    /// List all security groups.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let groups = os.list_security_groups()
+    ///     .expect("Unable to fetch security groups");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_security_groups(&self) -> Result<Vec<SecurityGroup>> {
+        self.find_security_groups().all()
+    }
+
+    /// List server groups of the current project.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// for group in os.list_server_groups().expect("Unable to fetch groups") {
+    ///     println!("{} {:?} {:?}", group.name(), group.policy(),
+    ///              group.members());
+    /// }
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn list_server_groups(&self) -> Result<Vec<ServerGroup>> {
+        compute::list_server_groups(self.session.clone(), false)
+    }
+
+    /// List server groups of all projects (admin-only).
+    #[cfg(feature = "compute")]
+    pub fn list_all_server_groups(&self) -> Result<Vec<ServerGroup>> {
+        compute::list_server_groups(self.session.clone(), true)
+    }
+
+    /// List all servers.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_servers](#method.find_servers) call to limit the number of
+    /// servers to receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server_list = os.list_servers().expect("Unable to fetch servers");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn list_servers(&self) -> Result<Vec<ServerSummary>> {
+        self.find_servers().all()
+    }
+
+    /// List all subnets.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_subnets](#method.find_subnets) call to limit the number of
+    /// subnets to receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let server_list = os.list_subnets().expect("Unable to fetch subnets");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_subnets(&self) -> Result<Vec<Subnet>> {
+        self.find_subnets().all()
+    }
+
+    /// List all tap flows.
+    ///
+    /// Requires the `taas` Network API extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let flows = os.list_tap_flows().expect("Unable to fetch tap flows");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_tap_flows(&self) -> Result<Vec<TapFlow>> {
+        self.find_tap_flows().all()
+    }
+
+    /// List all tap services.
+    ///
+    /// Requires the `taas` Network API extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let services = os.list_tap_services()
+    ///     .expect("Unable to fetch tap services");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_tap_services(&self) -> Result<Vec<TapService>> {
+        self.find_tap_services().all()
+    }
+
+    /// List all trunks.
+    ///
+    /// Requires the `trunk` Network API extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let trunks = os.list_trunks().expect("Unable to fetch trunks");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn list_trunks(&self) -> Result<Vec<Trunk>> {
+        self.find_trunks().all()
+    }
+
+    /// List all DNS zones.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_zones](#method.find_zones) call to limit the number of zones to
+    /// receive.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let zones = os.list_zones().expect("Unable to fetch zones");
+    /// ```
+    #[cfg(feature = "dns")]
+    pub fn list_zones(&self) -> Result<Vec<Zone>> {
+        self.find_zones().all()
+    }
+
+    /// Prepare a new address group for creation.
+    ///
+    /// This call returns a `NewAddressGroup` object, which is a builder to
+    /// populate address group fields.
+    #[cfg(feature = "network")]
+    pub fn new_address_group<S>(&self, name: S) -> NewAddressGroup
+            where S: Into<String> {
+        NewAddressGroup::new(self.session.clone(), name.into())
+    }
+
+    /// Prepare a new conntrack helper of a router for creation.
+    ///
+    /// Enables the netfilter `helper` (e.g. `ftp`) for traffic with the given
+    /// protocol and port. Requires the `l3-conntrack-helper` Network API
+    /// extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let helper = os.new_conntrack_helper("8ecd6a7b-5e9c-4a0b-8b2f-5f3e1d1c2a4b",
+    ///                                      "ftp", "tcp", 21)
+    ///     .create().expect("Unable to create a conntrack helper");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn new_conntrack_helper<R, H, P>(&self, router_id: R, helper: H,
+                                         protocol: P, port: u16)
+            -> NewConntrackHelper
+            where R: Into<String>, H: Into<String>, P: Into<String> {
+        NewConntrackHelper::new(self.session.clone(), router_id.into(),
+                                helper.into(), protocol.into(), port)
+    }
+
+    /// Prepare a new image for creation.
+    ///
+    /// This call returns a `NewImage` object, which is a builder to populate
+    /// image fields.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::fs::File;
+    ///
+    /// use openstack;
+    /// use openstack::image::{ImageContainerFormat, ImageDiskFormat};
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let mut image = os.new_image("cirros")
+    ///     .with_container_format(ImageContainerFormat::Bare)
+    ///     .with_disk_format(ImageDiskFormat::QCOW2)
+    ///     .create().expect("Unable to create an image");
+    /// let data = File::open("cirros.qcow2").expect("Unable to open the file");
+    /// image.upload(data).expect("Unable to upload the image");
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn new_image<S>(&self, name: S) -> NewImage where S: Into<String> {
+        NewImage::new(self.session.clone(), name.into())
+    }
+
+    /// Prepare a new key pair for creation.
+    ///
+    /// This call returns a `NewKeyPair` object, which is a builder to populate
+    /// key pair fields.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let (keypair, private_key) = os.new_keypair("ci")
+    ///     .generate().expect("Unable to generate a key pair");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn new_keypair<S>(&self, name: S) -> NewKeyPair where S: Into<String> {
+        NewKeyPair::new(self.session.clone(), name.into())
+    }
+
+    /// Prepare a new network for creation.
+    ///
+    /// This call returns a `NewNetwork` object, which is a builder to populate
+    /// network fields.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let network = os.new_network().with_name("private")
+    ///     .with_mtu(1450).create().expect("Unable to create a network");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn new_network(&self) -> NewNetwork {
+        NewNetwork::new(self.session.clone())
     }
 
     /// Prepare a new port for creation.
     ///
     /// This call returns a `NewPort` object, which is a builder to populate
-    /// port fields.
+    /// port fields. Security groups from the [defaults](#method.defaults) are
+    /// used unless set explicitly.
     #[cfg(feature = "network")]
     pub fn new_port<N>(&self, network: N) -> NewPort where N: Into<NetworkRef> {
-        NewPort::new(self.session.clone(), network.into())
+        let mut new_port = NewPort::new(self.session.clone(), network.into());
+        new_port.apply_defaults(&self.defaults);
+        new_port
+    }
+
+    /// Create several ports in one request.
+    ///
+    /// Either all ports are created or none of them.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let requests = (0..16).map(|idx| {
+    ///     os.new_port("private").with_name(format!("node-{}", idx))
+    /// }).collect();
+    /// let ports = os.create_ports(requests).expect("Unable to create ports");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn create_ports(&self, ports: Vec<NewPort>) -> Result<Vec<Port>> {
+        NewPort::create_bulk(self.session.clone(), ports)
+    }
+
+    /// Prepare a new security group for creation.
+    ///
+    /// This call returns a `NewSecurityGroup` object, which is a builder to
+    /// populate security group fields.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    /// use openstack::network::{RuleDirection, RuleEtherType, SecurityRule};
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let group = os.new_security_group("web")
+    ///     .with_description("Web servers").create()
+    ///     .expect("Unable to create a security group");
+    /// let rule = SecurityRule::new(RuleDirection::Ingress, RuleEtherType::Ipv4)
+    ///     .with_protocol("tcp").with_port_range(443, 443);
+    /// let _ = group.add_rule(&rule).expect("Unable to add a rule");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn new_security_group<S>(&self, name: S) -> NewSecurityGroup
+            where S: Into<String> {
+        NewSecurityGroup::new(self.session.clone(), name.into())
+    }
+
+    /// Prepare a new subnet for creation.
+    ///
+    /// This call returns a `NewSubnet` object, which is a builder to populate
+    /// subnet fields. The IP version is derived from the CIDR.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let subnet = os.new_subnet("private", "192.168.1.0/24".parse().unwrap())
+    ///     .with_name("private-subnet").create()
+    ///     .expect("Unable to create a subnet");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn new_subnet<N>(&self, network: N, cidr: ipnet::IpNet) -> NewSubnet
+            where N: Into<NetworkRef> {
+        NewSubnet::new(self.session.clone(), network.into(), cidr)
+    }
+
+    /// Prepare a new tap flow for creation.
+    ///
+    /// Traffic of `source_port` in the given direction will be mirrored to
+    /// the tap service (referenced by name or ID). Requires the `taas`
+    /// Network API extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let service = os.new_tap_service("ids-port").with_name("ids")
+    ///     .create().expect("Unable to create a tap service");
+    /// let flow = os.new_tap_flow(service.id().clone(), "web-port",
+    ///                            openstack::network::TapFlowDirection::Both)
+    ///     .create().expect("Unable to create a tap flow");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn new_tap_flow<S, P>(&self, tap_service: S, source_port: P,
+                              direction: TapFlowDirection) -> NewTapFlow
+            where S: Into<String>, P: Into<PortRef> {
+        NewTapFlow::new(self.session.clone(), tap_service.into(),
+                        source_port.into(), direction)
+    }
+
+    /// Prepare a new tap service for creation.
+    ///
+    /// Mirrored traffic will be delivered to the given port. Requires the
+    /// `taas` Network API extension.
+    #[cfg(feature = "network")]
+    pub fn new_tap_service<P>(&self, port: P) -> NewTapService
+            where P: Into<PortRef> {
+        NewTapService::new(self.session.clone(), port.into())
+    }
+
+    /// Prepare a new trunk for creation.
+    ///
+    /// The given port becomes the parent port of the trunk, carrying
+    /// untagged traffic. Requires the `trunk` Network API extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let mut trunk = os.new_trunk("parent-port").with_name("vm-trunk")
+    ///     .create().expect("Unable to create a trunk");
+    /// trunk.add_vlan_subport("vlan-100-port", 100)
+    ///     .expect("Unable to add a subport");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn new_trunk<P>(&self, port: P) -> NewTrunk where P: Into<PortRef> {
+        NewTrunk::new(self.session.clone(), port.into())
+    }
+
+    /// Prepare a new DNS zone for creation.
+    ///
+    /// This call returns a `NewZone` object, which is a builder to populate
+    /// zone fields. The name must be a fully qualified domain name.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// extern crate openstack;
+    /// extern crate waiter;
+    ///
+    /// use waiter::Waiter;
+    ///
+    /// # fn main() {
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let zone = os.new_zone("example.com.")
+    ///     .with_email("hostmaster@example.com")
+    ///     .create().expect("Unable to request zone creation")
+    ///     .wait().expect("Zone did not become active");
+    /// let record = zone.new_recordset("www.example.com.", "A")
+    ///     .with_record("192.0.2.10")
+    ///     .create().expect("Unable to create a recordset");
+    /// # }
+    /// ```
+    #[cfg(feature = "dns")]
+    pub fn new_zone<S>(&self, name: S) -> NewZone where S: Into<String> {
+        NewZone::new(self.session.clone(), name.into())
+    }
+
+    /// Create several subnets in one request.
+    ///
+    /// Either all subnets are created or none of them.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let subnets = os.create_subnets(vec![
+    ///     os.new_subnet("private", "192.168.1.0/24".parse().unwrap()),
+    ///     os.new_subnet("private", "192.168.2.0/24".parse().unwrap()),
+    /// ]).expect("Unable to create subnets");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn create_subnets(&self, subnets: Vec<NewSubnet>) -> Result<Vec<Subnet>> {
+        NewSubnet::create_bulk(self.session.clone(), subnets)
+    }
+
+    /// Add a minimum bandwidth guarantee to a QoS policy.
+    ///
+    /// The guarantee applies to all ports using the policy.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let rule = os.create_qos_minimum_bandwidth_rule(
+    ///         "5e5a0e6a-1c2a-4b6e-9d3c-2f8b1e9a7c41", 100_000,
+    ///         openstack::network::QosDirection::Egress)
+    ///     .expect("Unable to create a minimum bandwidth rule");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn create_qos_minimum_bandwidth_rule<S: Into<String>>(
+            &self, policy_id: S, min_kbps: u64, direction: QosDirection)
+            -> Result<QosMinimumBandwidthRule> {
+        network::create_minimum_bandwidth_rule(self.session.clone(),
+                                               policy_id.into(), min_kbps,
+                                               direction)
+    }
+
+    /// Add a minimum packet rate guarantee to a QoS policy.
+    ///
+    /// The guarantee applies to all ports using the policy.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let rule = os.create_qos_minimum_packet_rate_rule(
+    ///         "5e5a0e6a-1c2a-4b6e-9d3c-2f8b1e9a7c41", 1000,
+    ///         openstack::network::QosDirection::Egress)
+    ///     .expect("Unable to create a minimum packet rate rule");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn create_qos_minimum_packet_rate_rule<S: Into<String>>(
+            &self, policy_id: S, min_kpps: u64, direction: QosDirection)
+            -> Result<QosMinimumPacketRateRule> {
+        network::create_minimum_packet_rate_rule(self.session.clone(),
+                                                 policy_id.into(), min_kpps,
+                                                 direction)
+    }
+
+    /// Add a packet rate limit to a QoS policy.
+    ///
+    /// The burst defaults to 80% of the maximum rate if not provided.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let rule = os.create_qos_packet_rate_limit_rule(
+    ///         "5e5a0e6a-1c2a-4b6e-9d3c-2f8b1e9a7c41", 1000, None,
+    ///         openstack::network::QosDirection::Ingress)
+    ///     .expect("Unable to create a packet rate limit rule");
+    /// ```
+    #[cfg(feature = "network")]
+    pub fn create_qos_packet_rate_limit_rule<S: Into<String>>(
+            &self, policy_id: S, max_kpps: u64, max_burst_kpps: Option<u64>,
+            direction: QosDirection) -> Result<QosPacketRateLimitRule> {
+        network::create_packet_rate_limit_rule(self.session.clone(),
+                                               policy_id.into(), max_kpps,
+                                               max_burst_kpps, direction)
     }
 
     /// Prepare a new server for creation.
     ///
     /// This call returns a `NewServer` object, which is a builder to populate
-    /// server fields.
+    /// server fields. Values from the [defaults](#method.defaults) are used
+    /// unless set explicitly.
     #[cfg(feature = "compute")]
     pub fn new_server<S, F>(&self, name: S, flavor: F) -> NewServer
             where S: Into<String>, F: Into<FlavorRef> {
-        NewServer::new(self.session.clone(), name.into(), flavor.into())
+        let mut new_server = NewServer::new(self.session.clone(), name.into(),
+                                            flavor.into());
+        new_server.apply_defaults(&self.defaults);
+        new_server
+    }
+
+    /// Prepare a new server group for creation.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let group = os.new_server_group(
+    ///         "workers", openstack::compute::ServerGroupPolicy::AntiAffinity)
+    ///     .with_max_server_per_host(2)
+    ///     .create().expect("Unable to create a server group");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn new_server_group<S>(&self, name: S, policy: ServerGroupPolicy)
+            -> NewServerGroup where S: Into<String> {
+        NewServerGroup::new(self.session.clone(), name.into(), policy)
+    }
+
+    /// Update compute quotas of a project (admin-only).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let update = openstack::compute::ComputeQuotasUpdate {
+    ///     cores: Some(64),
+    ///     ram: Some(131072),
+    ///     ..Default::default()
+    /// };
+    /// let quotas = os.update_compute_quotas("demo", update)
+    ///     .expect("Unable to update quotas");
+    /// ```
+    #[cfg(feature = "compute")]
+    pub fn update_compute_quotas<P>(&self, project: P,
+                                    update: ComputeQuotasUpdate)
+            -> Result<ComputeQuotas> where P: Into<ProjectRef> {
+        compute::update_quotas(&self.session, project.into(), update)
+    }
+
+    /// Update network quotas of a project (admin-only).
+    #[cfg(feature = "network")]
+    pub fn update_network_quotas<P>(&self, project: P,
+                                    update: NetworkQuotasUpdate)
+            -> Result<NetworkQuotas> where P: Into<ProjectRef> {
+        network::update_quotas(&self.session, project.into(), update)
     }
 }
 
@@ -449,7 +2296,243 @@ impl Cloud {
 impl From<Session> for Cloud {
     fn from(value: Session) -> Cloud {
         Cloud {
-            session: Rc::new(value)
+            session: Rc::new(value),
+            defaults: Defaults::default(),
         }
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    #[cfg(any(feature = "compute", feature = "network"))]
+    use super::super::utils::test::FakeServer;
+    #[cfg(any(feature = "compute", feature = "network"))]
+    use super::Cloud;
+
+    #[cfg(feature = "compute")]
+    const KEYPAIR: &'static str = r#"{"keypair": {"name": "%s",
+        "fingerprint": "aa:bb", "public_key": "ssh-rsa AAAA"}}"#;
+
+    #[cfg(feature = "compute")]
+    fn compute_server() -> FakeServer {
+        FakeServer::with_version("v2.1", "2.1")
+    }
+
+    #[cfg(feature = "compute")]
+    #[test]
+    fn test_keypair_exists() {
+        let server = compute_server();
+        server.respond("GET", "/os-keypairs/present", 200,
+                       &KEYPAIR.replace("%s", "present"));
+        let cloud = Cloud::from(server.session());
+        assert!(cloud.keypair_exists("present").unwrap());
+        assert!(! cloud.keypair_exists("missing").unwrap());
+    }
+
+    #[cfg(feature = "compute")]
+    #[test]
+    fn test_keypair_exists_error() {
+        let server = compute_server();
+        server.respond("GET", "/os-keypairs/broken", 403,
+                       r#"{"forbidden": {"code": 403, "message": "No"}}"#);
+        let cloud = Cloud::from(server.session());
+        assert!(cloud.keypair_exists("broken").is_err());
+    }
+
+    #[cfg(feature = "compute")]
+    #[test]
+    fn test_delete_keypairs_matching() {
+        let server = compute_server();
+        server.respond("GET", "/os-keypairs", 200, &format!(
+            r#"{{"keypairs": [{}, {}, {}]}}"#,
+            KEYPAIR.replace("%s", "ci-1"), KEYPAIR.replace("%s", "ci-2"),
+            KEYPAIR.replace("%s", "prod")));
+        server.respond("DELETE", "/os-keypairs/ci-1", 202, "");
+        // ci-2 disappears concurrently and results in HTTP 404.
+        let cloud = Cloud::from(server.session());
+        assert_eq!(cloud.delete_keypairs_matching("ci-").unwrap(), 1);
+
+        let deleted = server.requests().into_iter()
+            .filter(|req| req.starts_with("DELETE"))
+            .collect::<Vec<_>>();
+        assert_eq!(deleted, vec![String::from("DELETE /os-keypairs/ci-1"),
+                                 String::from("DELETE /os-keypairs/ci-2")]);
+    }
+
+    #[cfg(feature = "network")]
+    fn port(id: &str) -> String {
+        format!(r#"{{"admin_state_up": true, "id": "{}", "name": "ci-port",
+                    "mac_address": "fa:16:3e:00:00:01",
+                    "network_id": "net", "status": "ACTIVE"}}"#, id)
+    }
+
+    #[cfg(feature = "network")]
+    #[test]
+    fn test_delete_ports() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", "/ports", 200,
+                       &format!(r#"{{"ports": [{}, {}]}}"#,
+                                port("p1"), port("p2")));
+        server.respond("GET", "/ports", 200, r#"{"ports": []}"#);
+        server.respond("DELETE", "/ports/p1", 204, "");
+        server.respond("DELETE", "/ports/p2", 409,
+                       r#"{"NeutronError": {"message": "Port is in use"}}"#);
+        let cloud = Cloud::from(server.session());
+
+        let waiter = cloud.delete_ports(cloud.find_ports().with_name("ci-port"))
+            .unwrap();
+        // Only the deletion of p1 was requested successfully.
+        assert_eq!(waiter.pending(), 1);
+
+        let report = waiter.wait_report();
+        assert_eq!(report.succeeded, vec!["p1"]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "p2");
+        assert!(server.requests().contains(&String::from("GET /ports/p1")));
+    }
+
+    #[cfg(feature = "network")]
+    fn router_port(id: &str, owner: &str, fixed_ips: &str) -> String {
+        format!(r#"{{"admin_state_up": true, "id": "{}", "name": "",
+                    "device_id": "r1", "device_owner": "{}",
+                    "fixed_ips": {}, "mac_address": "fa:16:3e:00:00:01",
+                    "network_id": "net", "status": "ACTIVE"}}"#,
+                id, owner, fixed_ips)
+    }
+
+    #[cfg(feature = "network")]
+    fn subnet(id: &str, cidr: &str, version: u8) -> String {
+        format!(r#"{{"cidr": "{}", "enable_dhcp": true, "id": "{}",
+                    "ip_version": {}, "name": "", "network_id": "net"}}"#,
+                cidr, id, version)
+    }
+
+    #[cfg(feature = "network")]
+    fn router_server() -> FakeServer {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", "/ports", 200, &format!(
+            r#"{{"ports": [{}, {}, {}]}}"#,
+            router_port("p1", "network:router_interface",
+                        r#"[{"subnet_id": "s1", "ip_address": "10.0.0.1"},
+                            {"subnet_id": "s2", "ip_address": "fd00::1"}]"#),
+            router_port("gw", "network:router_gateway",
+                        r#"[{"subnet_id": "ext", "ip_address": "172.24.4.2"}]"#),
+            router_port("p2", "network:router_interface_distributed",
+                        r#"[{"subnet_id": "s1", "ip_address": "10.0.0.2"}]"#)));
+        server.respond("GET", "/ports", 200, r#"{"ports": []}"#);
+        server.respond("GET", "/subnets", 200, &format!(
+            r#"{{"subnets": [{}, {}]}}"#,
+            subnet("s1", "10.0.0.0/24", 4), subnet("s2", "fd00::/64", 6)));
+        server.respond("GET", "/subnets", 200, r#"{"subnets": []}"#);
+        server
+    }
+
+    #[cfg(feature = "network")]
+    fn check_router_interfaces(server: &FakeServer,
+                               interfaces: Vec<super::RouterInterface>) {
+        let summary = interfaces.iter().map(|iface| {
+            (iface.port().id().clone(),
+             iface.subnets().iter().map(|s| s.id().clone()).collect::<Vec<_>>())
+        }).collect::<Vec<_>>();
+        assert_eq!(summary, vec![
+            (String::from("p1"), vec![String::from("s1"), String::from("s2")]),
+            (String::from("p2"), vec![String::from("s1")]),
+        ]);
+        assert_eq!(interfaces[0].fixed_ips().len(), 2);
+
+        // All subnets are fetched with one query instead of one by one.
+        let requests = server.requests();
+        assert!(! requests.iter().any(|req| req.starts_with("GET /subnets/")));
+        let query = requests.iter().find(|req| req.starts_with("GET /subnets?"))
+            .expect("Subnets were not fetched");
+        assert!(query.contains("id=s1"));
+        assert!(query.contains("id=s2"));
+        assert!(! query.contains("id=ext"));
+    }
+
+    #[cfg(feature = "network")]
+    #[test]
+    fn test_list_router_interfaces() {
+        let server = router_server();
+        let cloud = Cloud::from(server.session());
+        let interfaces = cloud.list_router_interfaces("r1").unwrap();
+        check_router_interfaces(&server, interfaces);
+        assert!(server.requests().iter()
+                .any(|req| req.starts_with("GET /ports?") &&
+                     req.contains("device_id=r1")));
+    }
+
+    #[cfg(feature = "network")]
+    #[test]
+    fn test_router_interfaces() {
+        let server = router_server();
+        server.respond("GET", "/routers/r1", 200,
+                       r#"{"router": {"admin_state_up": true, "id": "r1",
+                           "name": "router1", "status": "ACTIVE",
+                           "external_gateway_info": {"network_id": "public"}}}"#);
+        let cloud = Cloud::from(server.session());
+        let router = cloud.get_router("r1").unwrap();
+        assert_eq!(router.name(), &Some(String::from("router1")));
+        assert_eq!(router.external_network_id(), Some(&String::from("public")));
+        let interfaces = router.interfaces().unwrap();
+        check_router_interfaces(&server, interfaces);
+    }
+
+    #[cfg(feature = "network")]
+    #[test]
+    fn test_list_router_interfaces_without_subnets() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", "/ports", 200, r#"{"ports": []}"#);
+        let cloud = Cloud::from(server.session());
+        assert!(cloud.list_router_interfaces("r1").unwrap().is_empty());
+        assert!(! server.requests().iter()
+                .any(|req| req.starts_with("GET /subnets")));
+    }
+
+    #[cfg(feature = "network")]
+    #[test]
+    fn test_router_gateway_update() {
+        let server = FakeServer::with_version("v2.0", "");
+        let router = |snat: bool| format!(
+            r#"{{"router": {{"admin_state_up": true, "id": "r1",
+                "name": "router1", "status": "ACTIVE",
+                "external_gateway_info": {{"network_id": "public",
+                    "enable_snat": {},
+                    "external_fixed_ips": [{{"subnet_id": "ext",
+                        "ip_address": "172.24.4.2"}}]}}}}}}"#, snat);
+        server.respond("GET", "/routers/r1", 200, &router(true));
+        server.respond("PUT", "/routers/r1", 200, &router(false));
+        let cloud = Cloud::from(server.session());
+
+        let mut router = cloud.get_router("r1").unwrap();
+        assert_eq!(router.enable_snat(), Some(true));
+        assert_eq!(router.external_fixed_ips().len(), 1);
+        assert_eq!(router.external_fixed_ips()[0].subnet_id, "ext");
+        assert!(! router.is_dirty());
+
+        router.set_enable_snat(false);
+        assert!(router.is_dirty());
+        router.save().unwrap();
+        assert!(! router.is_dirty());
+        assert_eq!(router.enable_snat(), Some(false));
+        assert_eq!(router.external_fixed_ips().len(), 1);
+        assert!(server.requests().contains(&String::from("PUT /routers/r1")));
+    }
+
+    #[cfg(feature = "network")]
+    #[test]
+    fn test_router_gateway_update_without_gateway() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", "/routers/r1", 200,
+                       r#"{"router": {"admin_state_up": true, "id": "r1",
+                           "status": "ACTIVE", "external_gateway_info": null}}"#);
+        let cloud = Cloud::from(server.session());
+
+        let mut router = cloud.get_router("r1").unwrap().with_enable_snat(false);
+        assert_eq!(router.enable_snat(), None);
+        assert_eq!(router.save().unwrap_err().kind(),
+                   super::super::ErrorKind::InvalidInput);
+        assert!(! server.requests().iter().any(|req| req.starts_with("PUT")));
+    }
+}
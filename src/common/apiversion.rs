@@ -27,6 +27,17 @@ use super::super::{Error, ErrorKind, Result};
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
 pub struct ApiVersion(pub u16, pub u16);
 
+/// A request for an API version to use by default.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ApiVersionRequest {
+    /// The minimum version, i.e. no version is requested explicitly.
+    Minimum,
+    /// The latest version supported by both the server and this crate.
+    Latest,
+    /// Exactly the given version.
+    Exact(ApiVersion),
+}
+
 impl fmt::Display for ApiVersion {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}.{}", self.0, self.1)
@@ -60,6 +71,39 @@ impl FromStr for ApiVersion {
     }
 }
 
+fn is_number(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_digit(10))
+}
+
+impl FromStr for ApiVersionRequest {
+    type Err = Error;
+
+    /// Parse a version request as accepted by OpenStack command line clients.
+    ///
+    /// `latest` (or `X.latest`, e.g. `2.latest`) requests the latest version,
+    /// a major version alone (e.g. `2`) requests the minimum version, `X.Y`
+    /// requests exactly this version.
+    fn from_str(s: &str) -> Result<ApiVersionRequest> {
+        let s = s.trim();
+        let latest = match s.find('.') {
+            Some(pos) => is_number(&s[..pos]) &&
+                s[pos + 1..].eq_ignore_ascii_case("latest"),
+            None => s.eq_ignore_ascii_case("latest")
+        };
+        if latest {
+            Ok(ApiVersionRequest::Latest)
+        } else if is_number(s) {
+            Ok(ApiVersionRequest::Minimum)
+        } else {
+            ApiVersion::from_str(s).map(ApiVersionRequest::Exact).map_err(|_| {
+                Error::new(ErrorKind::InvalidInput,
+                           format!("Invalid API version request {}: expected \
+                                    latest, X or X.Y", s))
+            })
+        }
+    }
+}
+
 impl Serialize for ApiVersion {
     fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
             where S: Serializer {
@@ -96,7 +140,7 @@ pub mod test {
 
     use serde_json;
 
-    use super::ApiVersion;
+    use super::{ApiVersion, ApiVersionRequest};
 
     #[test]
     fn test_apiversion_format() {
@@ -105,6 +149,22 @@ pub mod test {
         assert_eq!(ApiVersion::from_str("2.27").unwrap(), ver);
     }
 
+    #[test]
+    fn test_apiversion_request_from_str() {
+        assert_eq!(ApiVersionRequest::from_str("2.26").unwrap(),
+                   ApiVersionRequest::Exact(ApiVersion(2, 26)));
+        assert_eq!(ApiVersionRequest::from_str("latest").unwrap(),
+                   ApiVersionRequest::Latest);
+        assert_eq!(ApiVersionRequest::from_str("2.latest").unwrap(),
+                   ApiVersionRequest::Latest);
+        assert_eq!(ApiVersionRequest::from_str("2").unwrap(),
+                   ApiVersionRequest::Minimum);
+        assert!(ApiVersionRequest::from_str("").is_err());
+        assert!(ApiVersionRequest::from_str("2.x").is_err());
+        assert!(ApiVersionRequest::from_str("2.latest.1").is_err());
+        assert!(ApiVersionRequest::from_str("x.latest").is_err());
+    }
+
     #[test]
     fn test_apiversion_serde() {
         let ver = ApiVersion(2, 27);
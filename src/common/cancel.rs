@@ -0,0 +1,76 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cancellation of long-running operations.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::super::{Error, ErrorKind, Result};
+
+
+/// A token to cancel long-running operations, e.g. on Ctrl+C.
+///
+/// Clones share the state and can be sent to other threads, e.g. to a signal
+/// handler. Operations check the token between steps, requests that are
+/// already in flight are not interrupted.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new token.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Request cancellation of all operations using this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fail with `Cancelled` if cancellation was requested.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(Error::new(ErrorKind::Cancelled, "Operation was cancelled"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::super::super::ErrorKind;
+    use super::CancellationToken;
+
+    #[test]
+    fn test_cancel_from_thread() {
+        let token = CancellationToken::new();
+        token.check().unwrap();
+        let clone = token.clone();
+        thread::spawn(move || clone.cancel()).join().unwrap();
+        assert!(token.is_cancelled());
+        assert_eq!(token.check().err().unwrap().kind(), ErrorKind::Cancelled);
+    }
+}
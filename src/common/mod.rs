@@ -15,14 +15,17 @@
 //! Types and traits shared by all API parts.
 
 mod apiversion;
+mod cancel;
 pub(crate) mod protocol;
 mod resourceiterator;
 mod types;
 mod waiter;
 
-pub use self::apiversion::ApiVersion;
+pub use self::apiversion::{ApiVersion, ApiVersionRequest};
+pub use self::cancel::CancellationToken;
 pub use self::resourceiterator::ResourceIterator;
 pub use self::types::{FlavorRef, ImageRef, KeyPairRef, ListResources,
                       NetworkRef, PortRef, ProjectRef, Refresh, ResourceId,
-                      SubnetRef, UserRef};
-pub use self::waiter::DeletionWaiter;
+                      ResourceStatus, SubnetRef, UserRef};
+pub use self::waiter::{DeletionWaiter, StatusTransition};
+pub(crate) use self::waiter::StatusTracker;
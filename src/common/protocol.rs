@@ -17,8 +17,9 @@
 #![allow(dead_code)] // various things are unused with --no-default-features
 #![allow(missing_docs)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
 use eui48::MacAddress;
 use reqwest::{Method, Url};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -67,11 +68,28 @@ pub struct Version {
     pub min_version: Option<ApiVersion>
 }
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct VersionValues {
+    pub values: Vec<Version>
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(untagged)]
 pub enum Root {
     Versions { versions: Vec<Version> },
-    Version { version: Version }
+    Version { version: Version },
+    /// Identity-style list of versions.
+    VersionValues { versions: VersionValues }
+}
+
+impl Root {
+    pub fn into_versions(self) -> Vec<Version> {
+        match self {
+            Root::Versions { versions } => versions,
+            Root::Version { version } => vec![version],
+            Root::VersionValues { versions } => versions.values
+        }
+    }
 }
 
 impl Version {
@@ -108,8 +126,9 @@ pub fn fetch_service_info(endpoint: Url, auth: &AuthMethod,
         Ok(mut resp) => {
             let mut info = match resp.json()? {
                 Root::Version { version: ver } => ver.into_service_info(),
-                Root::Versions { versions: vers } => {
-                    match vers.into_iter().find(|x| &x.id == major_version) {
+                root => {
+                    match root.into_versions().into_iter()
+                            .find(|x| &x.id == major_version) {
                         Some(ver) => ver.into_service_info(),
                         None => Err(Error::new_endpoint_not_found(service_type))
                     }
@@ -139,6 +158,21 @@ pub fn fetch_service_info(endpoint: Url, auth: &AuthMethod,
     }
 }
 
+/// Fetch all versions reported by the endpoint or its parents.
+pub fn fetch_versions(endpoint: Url, auth: &AuthMethod, service_type: &str)
+        -> Result<Vec<Version>> {
+    debug!("Fetching {} versions from {}", service_type, endpoint);
+    match auth.request(Method::Get, endpoint.clone())?.send() {
+        Ok(mut resp) => Ok(resp.json::<Root>()?.into_versions()),
+        Err(ref e) if e.kind() == ErrorKind::ResourceNotFound &&
+                ! utils::url::is_root(&endpoint) => {
+            debug!("Got HTTP 404 from {}, trying parent endpoint", endpoint);
+            fetch_versions(utils::url::pop(endpoint, true), auth, service_type)
+        },
+        Err(other) => Err(other)
+    }
+}
+
 /// Deserialize value where empty string equals None.
 pub fn empty_as_none<'de, D, T>(des: D) -> ::std::result::Result<Option<T>, D::Error>
         where D: Deserializer<'de>, T: DeserializeOwned {
@@ -181,6 +215,54 @@ pub fn deser_optional_url<'de, D>(des: D)
     }
 }
 
+/// Parse a timestamp in any of the formats emitted by OpenStack services.
+///
+/// Besides RFC 3339, accepts timestamps with a space instead of `T` and
+/// timestamps without a time zone, which are assumed to be in UTC.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<FixedOffset>> {
+    if let Ok(result) = DateTime::parse_from_rfc3339(value) {
+        return Some(result);
+    }
+
+    if let Ok(result) = DateTime::parse_from_str(value,
+                                                 "%Y-%m-%d %H:%M:%S%.f%:z") {
+        return Some(result);
+    }
+
+    let value = value.trim_right_matches('Z');
+    for format in &["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(DateTime::from_utc(naive, FixedOffset::east(0)));
+        }
+    }
+
+    None
+}
+
+/// Deserialize a timestamp using `parse_timestamp`.
+pub fn deser_timestamp<'de, D>(des: D)
+        -> ::std::result::Result<DateTime<FixedOffset>, D::Error>
+        where D: Deserializer<'de> {
+    let value = String::deserialize(des)?;
+    parse_timestamp(&value).ok_or_else(|| {
+        DeserError::custom(format!("Invalid timestamp {}", value))
+    })
+}
+
+/// Deserialize an optional timestamp using `parse_timestamp`.
+///
+/// An empty string is treated as None.
+pub fn deser_optional_timestamp<'de, D>(des: D)
+        -> ::std::result::Result<Option<DateTime<FixedOffset>>, D::Error>
+        where D: Deserializer<'de> {
+    let value: Option<String> = Deserialize::deserialize(des)?;
+    match value {
+        Some(ref s) if !s.is_empty() => parse_timestamp(s).map(Some)
+            .ok_or_else(|| DeserError::custom(format!("Invalid timestamp {}", s))),
+        _ => Ok(None)
+    }
+}
+
 /// Deserialize a key-value mapping.
 pub fn deser_key_value<'de, D>(des: D)
         -> ::std::result::Result<HashMap<String, String>, D::Error>
@@ -202,3 +284,77 @@ pub fn ser_opt_mac<S>(value: &Option<MacAddress>, serializer: S)
         where S: Serializer {
     value.map(|m| m.to_hex_string()).serialize(serializer)
 }
+
+/// Serialize a mapping with its keys sorted.
+pub fn ser_sorted_map<S, V>(value: &HashMap<String, V>, serializer: S)
+        -> ::std::result::Result<S::Ok, S::Error>
+        where S: Serializer, V: Serialize {
+    value.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Serialize a request body into a stable human-readable JSON.
+///
+/// Fields are emitted in the order of their declaration, mappings are sorted.
+pub fn to_request_json<T: Serialize>(body: &T) -> Result<String> {
+    serde_json::to_string_pretty(body).map_err(|err| {
+        Error::new(ErrorKind::InvalidInput,
+                   format!("Cannot serialize the request: {}", err))
+    })
+}
+
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, Timelike};
+    use serde_json;
+
+    use super::super::ApiVersion;
+    use super::{parse_timestamp, Root};
+
+    #[test]
+    fn test_root_into_versions() {
+        let nova: Root = serde_json::from_str(r#"{"versions": [
+            {"id": "v2.0", "status": "SUPPORTED", "version": "",
+             "min_version": "", "links": [
+                {"rel": "self", "href": "http://cloud/compute/v2/"}]},
+            {"id": "v2.1", "status": "CURRENT", "version": "2.79",
+             "min_version": "2.1", "links": [
+                {"rel": "self", "href": "http://cloud/compute/v2.1/"}]}
+        ]}"#).unwrap();
+        let versions = nova.into_versions();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].version, None);
+        assert_eq!(versions[1].version, Some(ApiVersion(2, 79)));
+
+        let keystone: Root = serde_json::from_str(r#"{"versions": {"values": [
+            {"id": "v3.14", "status": "stable", "links": [
+                {"rel": "self", "href": "http://cloud/identity/v3/"}]}
+        ]}}"#).unwrap();
+        let versions = keystone.into_versions();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].id, "v3.14");
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let expected = DateTime::parse_from_rfc3339("2013-09-23T13:53:12Z")
+            .unwrap();
+        for value in &["2013-09-23T13:53:12Z",
+                       "2013-09-23T13:53:12+00:00",
+                       "2013-09-23T13:53:12",
+                       "2013-09-23 13:53:12",
+                       "2013-09-23 13:53:12+00:00"] {
+            assert_eq!(parse_timestamp(value), Some(expected), "{}", value);
+        }
+
+        let with_micro = parse_timestamp("2013-09-23T13:53:12.774549").unwrap();
+        assert_eq!(with_micro.nanosecond(), 774549000);
+        assert_eq!(with_micro.with_nanosecond(0).unwrap(), expected);
+
+        let with_offset = parse_timestamp("2013-09-23T15:53:12+02:00").unwrap();
+        assert_eq!(with_offset, expected);
+
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+}
@@ -26,6 +26,11 @@ use super::{ListResources, ResourceId};
 
 
 /// Generic implementation of a `FallibleIterator` over resources.
+///
+/// If the query contains `page_reverse=true` (supported by the Network API),
+/// the items are returned in the reverse sorting order, starting from the
+/// end or from the marker. In both directions the last returned item is the
+/// marker to continue the traversal with.
 #[derive(Debug, Clone)]
 pub struct ResourceIterator<T> {
     session: Rc<Session>,
@@ -33,6 +38,10 @@ pub struct ResourceIterator<T> {
     cache: Option<vec::IntoIter<T>>,
     marker: Option<String>,
     can_paginate: Option<bool>,
+    page_reverse: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+    returned: usize,
 }
 
 impl<T> ResourceIterator<T> {
@@ -42,6 +51,9 @@ impl<T> ResourceIterator<T> {
         let can_paginate = query.0.iter().all(|pair| {
             pair.0 != "limit" && pair.0 != "marker"
         });
+        let page_reverse = query.0.iter().any(|pair| {
+            pair.0 == "page_reverse" && pair.1 == "true"
+        });
 
         ResourceIterator {
             session: session,
@@ -52,9 +64,39 @@ impl<T> ResourceIterator<T> {
                 None  // ask the service later
             } else {
                 Some(false)
-            }
+            },
+            page_reverse: page_reverse,
+            page_size: None,
+            max_items: None,
+            returned: 0,
         }
     }
+
+    /// Apply optional page size and maximum number of items.
+    #[allow(dead_code)]  // unused with --no-default-features
+    pub(crate) fn with_limits(mut self, page_size: Option<usize>,
+                              max_items: Option<usize>) -> ResourceIterator<T> {
+        self.page_size = page_size;
+        self.max_items = max_items;
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Automatic pagination is kept. Has no effect if an explicit limit or
+    /// marker is used or if the service does not support pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> ResourceIterator<T> {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after returning this number of items.
+    ///
+    /// When paginating, no more items than necessary are requested.
+    pub fn with_max_items(mut self, max_items: usize) -> ResourceIterator<T> {
+        self.max_items = Some(max_items);
+        self
+    }
 }
 
 impl<T> ResourceIterator<T> where T: ListResources + ResourceId {
@@ -82,6 +124,12 @@ impl<T> FallibleIterator for ResourceIterator<T> where T: ListResources + Resour
     type Error = Error;
 
     fn next(&mut self) -> Result<Option<T>> {
+        let returned = self.returned;
+        let remaining = self.max_items.map(|max| max.saturating_sub(returned));
+        if remaining == Some(0) {
+            return Ok(None);
+        }
+
         if self.can_paginate.is_none() {
             self.can_paginate = Some(T::can_paginate(&self.session)?);
         }
@@ -98,15 +146,26 @@ impl<T> FallibleIterator for ResourceIterator<T> where T: ListResources + Resour
 
                 if self.can_paginate == Some(true) {
                     // can_paginate=true implies no limit was provided
-                    query.push("limit", T::DEFAULT_LIMIT);
+                    let page_size = self.page_size.unwrap_or(T::DEFAULT_LIMIT);
+                    query.push("limit", match remaining {
+                        Some(remaining) if remaining < page_size => remaining,
+                        _ => page_size
+                    });
                     if let Some(marker) = self.marker.take() {
                         query.push_str("marker", marker);
                     }
                 }
 
-                let mut servers_iter = T::list_resources(self.session.clone(),
-                                                         &query.0)?
-                    .into_iter();
+                let mut items = T::list_resources(self.session.clone(),
+                                                  &query.0)?;
+                if self.page_reverse {
+                    // The service returns the page preceding the marker in
+                    // the normal order, so the marker for the next page is
+                    // its first item.
+                    items.reverse();
+                }
+
+                let mut servers_iter = items.into_iter();
                 let maybe_next = servers_iter.next();
                 self.cache = Some(servers_iter);
 
@@ -114,6 +173,7 @@ impl<T> FallibleIterator for ResourceIterator<T> where T: ListResources + Resour
             }
         }.map(|next| {
             self.marker = Some(next.resource_id());
+            self.returned += 1;
             next
         }))
     }
@@ -208,6 +268,46 @@ mod test {
                    vec![Test(0), Test(1), Test(2), Test(3)]);
     }
 
+    #[derive(Debug, PartialEq, Eq)]
+    struct Reverse(u8);
+
+    impl ResourceId for Reverse {
+        fn resource_id(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    impl ListResources for Reverse {
+        const DEFAULT_LIMIT: usize = 2;
+
+        fn list_resources<Q>(_session: Rc<Session>, query: Q) -> Result<Vec<Self>>
+                where Q: ::serde::Serialize + ::std::fmt::Debug {
+            let map = match serde_json::to_value(query).unwrap() {
+                Value::Array(arr) => array_to_map(arr),
+                x => panic!("unexpected query {:?}", x)
+            };
+            assert_eq!(*map.get("page_reverse").unwrap(),
+                       Value::String("true".into()));
+            Ok(match map.get("marker") {
+                Some(&Value::String(ref s)) if s == "2" => vec![Reverse(0), Reverse(1)],
+                Some(&Value::String(ref s)) if s == "0" => Vec::new(),
+                None => vec![Reverse(2), Reverse(3)],
+                Some(ref x) => panic!("unexpected marker {:?}", x)
+            })
+        }
+    }
+
+    #[test]
+    fn test_resource_iterator_page_reverse() {
+        let s = utils::test::new_session(utils::test::URL);
+        let mut query = Query::new();
+        query.push("page_reverse", true);
+        let it: ResourceIterator<Reverse> = ResourceIterator::new(Rc::new(s),
+                                                                  query);
+        assert_eq!(it.collect::<Vec<Reverse>>().unwrap(),
+                   vec![Reverse(3), Reverse(2), Reverse(1), Reverse(0)]);
+    }
+
     #[test]
     fn test_resource_iterator_no_pagination() {
         let s = utils::test::new_session(utils::test::URL);
@@ -216,4 +316,14 @@ mod test {
         assert_eq!(it.collect::<Vec<NoPagination>>().unwrap(),
                    vec![NoPagination(0), NoPagination(1), NoPagination(2)]);
     }
+
+    #[test]
+    fn test_resource_iterator_max_items() {
+        let s = utils::test::new_session(utils::test::URL);
+        let it: ResourceIterator<NoPagination> = ResourceIterator::new(Rc::new(s),
+                                                                       Query::new())
+            .with_max_items(2);
+        assert_eq!(it.collect::<Vec<NoPagination>>().unwrap(),
+                   vec![NoPagination(0), NoPagination(1)]);
+    }
 }
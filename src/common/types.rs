@@ -14,6 +14,7 @@
 
 //! Types and traits shared between services.
 
+use std::fmt::Debug;
 use std::rc::Rc;
 
 use serde::Serialize;
@@ -41,6 +42,30 @@ pub trait Refresh {
     fn refresh(&mut self) -> Result<()>;
 }
 
+/// Trait classifying statuses of resources.
+///
+/// Allows waiting and retrying generically, without matching on the status
+/// enumeration of each resource type.
+pub trait ResourceStatus: Copy + Debug + PartialEq {
+    /// Whether the status indicates a failure.
+    fn is_error(&self) -> bool;
+
+    /// Whether the resource is expected to leave this status by itself.
+    fn is_transitional(&self) -> bool;
+
+    /// Whether the service does not know or did not report the status.
+    fn is_unknown(&self) -> bool {
+        false
+    }
+
+    /// Whether the status only changes on user request.
+    ///
+    /// Error statuses are terminal, unknown statuses are not.
+    fn is_terminal(&self) -> bool {
+        ! self.is_transitional() && ! self.is_unknown()
+    }
+}
+
 /// Trait representing something that has an ID.
 pub trait ResourceId {
     /// Identifier of the current resource.
@@ -14,12 +14,15 @@
 
 //! Waiters.
 
-use std::time::Duration;
+use std::fmt;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use waiter::{Waiter, WaiterCurrentState};
 
 use super::super::{Error, ErrorKind, Result};
-use super::{Refresh, ResourceId};
+use super::super::telemetry;
+use super::{CancellationToken, Refresh, ResourceId};
 
 
 /// Wait for resource deletion.
@@ -28,6 +31,70 @@ pub struct DeletionWaiter<T> {
     inner: T,
     wait_timeout: Duration,
     delay: Duration,
+    started: Instant,
+    cancellation: Option<CancellationToken>,
+    progress: Option<Callback<FnMut(&T)>>,
+}
+
+/// A status change observed while waiting for a resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatusTransition<S> {
+    /// The new status.
+    pub status: S,
+    /// Time passed since the waiting started.
+    pub elapsed: Duration,
+    /// Date and time of the observation.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A boxed user callback.
+pub(crate) struct Callback<F: ?Sized>(Box<F>);
+
+/// Detects status transitions and reports them to an optional callback.
+#[derive(Debug)]
+pub(crate) struct StatusTracker<S> {
+    last: Option<S>,
+    callback: Option<Callback<FnMut(&StatusTransition<S>)>>,
+}
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Callback")
+    }
+}
+
+impl<S: Clone + PartialEq> StatusTracker<S> {
+    #[allow(dead_code)]  // unused with --no-default-features
+    pub(crate) fn new() -> StatusTracker<S> {
+        StatusTracker {
+            last: None,
+            callback: None,
+        }
+    }
+
+    /// Set a callback to call on every transition.
+    #[allow(dead_code)]  // unused with --no-default-features
+    pub(crate) fn set_callback<F>(&mut self, callback: F)
+            where F: FnMut(&StatusTransition<S>) + 'static {
+        self.callback = Some(Callback(Box::new(callback)));
+    }
+
+    /// Record the current status, ignoring it if it has not changed.
+    #[allow(dead_code)]  // unused with --no-default-features
+    pub(crate) fn observe(&mut self, status: S, started: Instant) {
+        if self.last.as_ref() == Some(&status) {
+            return;
+        }
+
+        if let Some(Callback(ref mut callback)) = self.callback {
+            callback(&StatusTransition {
+                status: status.clone(),
+                elapsed: started.elapsed(),
+                timestamp: Utc::now(),
+            });
+        }
+        self.last = Some(status);
+    }
 }
 
 impl<T> DeletionWaiter<T> {
@@ -38,8 +105,41 @@ impl<T> DeletionWaiter<T> {
             inner: inner,
             wait_timeout: wait_timeout,
             delay: delay,
+            started: Instant::now(),
+            cancellation: None,
+            progress: None,
         }
     }
+
+    /// Stop waiting with a `Cancelled` error once the token is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken)
+            -> DeletionWaiter<T> {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Call the given function every time the resource is found still existing.
+    ///
+    /// The function receives the refreshed resource, e.g. to report its
+    /// status while the deletion is in progress.
+    pub fn with_progress<F>(mut self, callback: F) -> DeletionWaiter<T>
+            where F: FnMut(&T) + 'static {
+        self.progress = Some(Callback(Box::new(callback)));
+        self
+    }
+
+    /// Wait at most the given time for the deletion.
+    pub fn with_timeout(mut self, timeout: Duration) -> DeletionWaiter<T> {
+        self.wait_timeout = timeout;
+        self
+    }
+
+    /// Check the resource with the given interval.
+    pub fn with_poll_interval(mut self, interval: Duration)
+            -> DeletionWaiter<T> {
+        self.delay = interval;
+        self
+    }
 }
 
 impl<T> WaiterCurrentState<T> for DeletionWaiter<T> {
@@ -58,27 +158,60 @@ impl<T: ResourceId + Refresh> Waiter<(), Error> for DeletionWaiter<T> {
     }
 
     fn timeout_error(&self) -> Error {
+        telemetry::record_waiter("deletion", self.started, false);
         Error::new(ErrorKind::OperationTimedOut,
                    format!("Timeout waiting for resource {} to be deleted",
                            self.inner.resource_id()))
     }
 
     fn poll(&mut self) -> Result<Option<()>> {
+        if let Some(ref token) = self.cancellation {
+            token.check()?;
+        }
+
         match self.inner.refresh() {
             Ok(..) => {
                 trace!("Still waiting for resource {} to be deleted",
                        self.inner.resource_id());
+                if let Some(Callback(ref mut callback)) = self.progress {
+                    callback(&self.inner);
+                }
                 Ok(None)
             },
             Err(ref e) if e.kind() == ErrorKind::ResourceNotFound => {
                 debug!("Resource {} was deleted", self.inner.resource_id());
+                telemetry::record_waiter("deletion", self.started, true);
                 Ok(Some(()))
             },
             Err(e) => {
                 debug!("Failed to delete resource {} - {}",
                        self.inner.resource_id(), e);
+                telemetry::record_waiter("deletion", self.started, false);
                 Err(e)
             }
         }
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Instant;
+
+    use super::StatusTracker;
+
+    #[test]
+    fn test_status_tracker_skips_repeated_statuses() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen_clone = seen.clone();
+        let mut tracker = StatusTracker::new();
+        tracker.set_callback(move |t| seen_clone.borrow_mut().push(t.status));
+        let started = Instant::now();
+        for status in &[1, 1, 2, 2, 2, 3, 1] {
+            tracker.observe(*status, started);
+        }
+        assert_eq!(*seen.borrow(), vec![1, 2, 3, 1]);
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+
+//! Availability zones via Compute API.
+
+use super::super::Result;
+use super::super::session::Session;
+use super::base::V2API;
+use super::protocol;
+
+
+/// List availability zones.
+///
+/// The detailed listing includes hosts and services and is admin-only.
+pub(crate) fn list_availability_zones(session: &Session, detailed: bool)
+        -> Result<Vec<protocol::AvailabilityZone>> {
+    let mut result = session.list_availability_zones(detailed)?;
+    result.sort_by(|left, right| left.name.cmp(&right.name));
+    Ok(result)
+}
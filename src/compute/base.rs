@@ -18,11 +18,11 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 
 use reqwest::{Method, Url};
-use reqwest::header::Headers;
+use reqwest::header::{Headers, Location};
 use serde::Serialize;
 use serde_json;
 
-use super::super::Result;
+use super::super::{Error, ErrorKind, Result};
 use super::super::auth::AuthMethod;
 use super::super::common::{self, ApiVersion};
 use super::super::common::protocol::Ref;
@@ -32,19 +32,42 @@ use super::protocol;
 
 
 const API_VERSION_KEYPAIR_TYPE: ApiVersion = ApiVersion(2, 2);
+const API_VERSION_REMOTE_CONSOLE: ApiVersion = ApiVersion(2, 6);
+const API_VERSION_KEYPAIR_USER: ApiVersion = ApiVersion(2, 10);
 const API_VERSION_SERVER_DESCRIPTION: ApiVersion = ApiVersion(2, 19);
 const API_VERSION_KEYPAIR_PAGINATION: ApiVersion = ApiVersion(2, 35);
+const API_VERSION_SERVER_IMAGE_ID: ApiVersion = ApiVersion(2, 45);
 const API_VERSION_FLAVOR_DESCRIPTION: ApiVersion = ApiVersion(2, 55);
 const API_VERSION_FLAVOR_EXTRA_SPECS: ApiVersion = ApiVersion(2, 61);
+const API_VERSION_TRUSTED_CERTIFICATES: ApiVersion = ApiVersion(2, 63);
+const API_VERSION_SERVER_GROUP_RULES: ApiVersion = ApiVersion(2, 64);
+const API_VERSION_MAX_SUPPORTED: ApiVersion = ApiVersion(2, 46);
+const API_VERSION_SERVER_DIAGNOSTICS: ApiVersion = ApiVersion(2, 48);
+const API_VERSION_VOLUME_TYPE: ApiVersion = ApiVersion(2, 67);
+const API_VERSION_SERVER_TOPOLOGY: ApiVersion = ApiVersion(2, 78);
 
 
 /// Extensions for Session.
 pub trait V2API {
+    /// Attach an existing port or a new port on a network to a server.
+    fn attach_server_interface<S>(&self, id: S,
+                                  request: protocol::InterfaceAttachmentCreate)
+        -> Result<protocol::InterfaceAttachment> where S: AsRef<str>;
+
     /// Create a key pair.
     fn create_keypair(&self, request: protocol::KeyPairCreate) -> Result<protocol::KeyPair>;
 
     /// Create a server.
-    fn create_server(&self, request: protocol::ServerCreate) -> Result<Ref>;
+    fn create_server(&self, request: protocol::ServerCreateRoot) -> Result<Ref>;
+
+    /// Create a server group.
+    fn create_server_group(&self, request: protocol::ServerGroupCreate)
+        -> Result<protocol::ServerGroup>;
+
+    /// Create an image (snapshot) of a server, returning the image ID.
+    fn create_server_image<S: AsRef<str>>(&self, id: S,
+                                          request: protocol::ServerImageCreate)
+        -> Result<String>;
 
     /// Delete a key pair.
     fn delete_keypair<S: AsRef<str>>(&self, name: S) -> Result<()>;
@@ -52,6 +75,13 @@ pub trait V2API {
     /// Delete a server.
     fn delete_server<S: AsRef<str>>(&self, id: S) -> Result<()>;
 
+    /// Delete a server group.
+    fn delete_server_group<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Detach a port from a server.
+    fn detach_server_interface<S1, S2>(&self, id: S1, port_id: S2) -> Result<()>
+        where S1: AsRef<str>, S2: AsRef<str>;
+
     /// Get a flavor by its ID.
     fn get_extra_specs_by_flavor_id<S: AsRef<str>>(&self, id: S)
         -> Result<HashMap<String, String>>;
@@ -71,6 +101,20 @@ pub trait V2API {
     /// Get a key pair by its nam.e
     fn get_keypair<S: AsRef<str>>(&self, name: S) -> Result<protocol::KeyPair>;
 
+    /// Get compute quotas of a project.
+    fn get_compute_quotas<S: AsRef<str>>(&self, project_id: S)
+        -> Result<protocol::ComputeQuotas>;
+
+    /// Get a remote console of a server.
+    fn get_server_console<S: AsRef<str>>(&self, id: S,
+                                         console_type: protocol::ConsoleType)
+        -> Result<protocol::RemoteConsole>;
+
+    /// Get console output of a server.
+    fn get_server_console_output<S: AsRef<str>>(&self, id: S,
+                                                length: Option<u32>)
+        -> Result<String>;
+
     /// Get a server.
     fn get_server<S: AsRef<str>>(&self, id_or_name: S) -> Result<protocol::Server> {
         let s = id_or_name.as_ref();
@@ -83,6 +127,22 @@ pub trait V2API {
     /// Get a server by its ID.
     fn get_server_by_name<S: AsRef<str>>(&self, id: S) -> Result<protocol::Server>;
 
+    /// Get hypervisor diagnostics of a server.
+    fn get_server_diagnostics<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::ServerDiagnostics>;
+
+    /// Get a server group.
+    fn get_server_group<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::ServerGroup>;
+
+    /// Get NUMA topology of a server.
+    fn get_server_topology<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::ServerTopology>;
+
+    /// List availability zones, optionally with hosts and services.
+    fn list_availability_zones(&self, detailed: bool)
+        -> Result<Vec<protocol::AvailabilityZone>>;
+
     /// List flavors.
     fn list_flavors<Q: Serialize + Debug>(&self, query: &Q)
         -> Result<Vec<common::protocol::IdAndName>>;
@@ -95,6 +155,10 @@ pub trait V2API {
     fn list_keypairs<Q: Serialize + Debug>(&self, query: &Q)
         -> Result<Vec<protocol::KeyPair>>;
 
+    /// List server groups, optionally of all projects (admin-only).
+    fn list_server_groups(&self, all_projects: bool)
+        -> Result<Vec<protocol::ServerGroup>>;
+
     /// List servers.
     fn list_servers<Q: Serialize + Debug>(&self, query: &Q)
         -> Result<Vec<common::protocol::IdAndName>>;
@@ -123,6 +187,61 @@ pub trait V2API {
     fn supports_keypair_pagination(&self) -> Result<bool> {
         self.supports_compute_api_version(API_VERSION_KEYPAIR_PAGINATION)
     }
+
+    /// Update compute quotas of a project.
+    fn update_compute_quotas<S: AsRef<str>>(&self, project_id: S,
+                                            update: protocol::ComputeQuotasUpdate)
+        -> Result<protocol::ComputeQuotas>;
+
+    /// Update a server.
+    fn update_server<S: AsRef<str>>(&self, id: S, update: protocol::ServerUpdate)
+        -> Result<()>;
+
+    /// Add or replace metadata items of a server.
+    fn update_server_metadata<S: AsRef<str>>(&self, id: S,
+                                             metadata: HashMap<String, String>)
+        -> Result<HashMap<String, String>>;
+}
+
+/// Pick the API version required to send a server description.
+fn server_description_version<Api: V2API + ?Sized>(api: &Api,
+                                                   description: &Option<String>)
+        -> Result<Option<ApiVersion>> {
+    if description.is_none() {
+        Ok(None)
+    } else if api.supports_compute_api_version(API_VERSION_SERVER_DESCRIPTION)? {
+        Ok(Some(API_VERSION_SERVER_DESCRIPTION))
+    } else {
+        Err(Error::new(ErrorKind::IncompatibleApiVersion,
+                       "Server description requires compute API version 2.19 \
+                        or newer"))
+    }
+}
+
+/// Pick the API version required to create the server.
+fn server_create_version<Api: V2API + ?Sized>(api: &Api,
+                                              request: &protocol::ServerCreate)
+        -> Result<Option<ApiVersion>> {
+    let mut version = server_description_version(api, &request.description)?;
+    if ! request.trusted_image_certificates.is_empty() {
+        if api.supports_compute_api_version(API_VERSION_TRUSTED_CERTIFICATES)? {
+            version = Some(API_VERSION_TRUSTED_CERTIFICATES);
+        } else {
+            return Err(Error::new(ErrorKind::IncompatibleApiVersion,
+                                  "Trusted image certificates require compute \
+                                   API version 2.63 or newer"));
+        }
+    }
+    if request.block_device_mapping_v2.iter().any(|bd| bd.volume_type.is_some()) {
+        if api.supports_compute_api_version(API_VERSION_VOLUME_TYPE)? {
+            version = Some(API_VERSION_VOLUME_TYPE);
+        } else {
+            return Err(Error::new(ErrorKind::IncompatibleApiVersion,
+                                  "Volume types of block devices require \
+                                   compute API version 2.67 or newer"));
+        }
+    }
+    Ok(version)
 }
 
 /// Service type of Compute API V2.
@@ -141,25 +260,108 @@ fn flavor_api_version<T: V2API>(api: &T) -> Result<Option<ApiVersion>> {
 }
 
 impl V2API for Session {
+    fn attach_server_interface<S>(&self, id: S,
+                                  request: protocol::InterfaceAttachmentCreate)
+            -> Result<protocol::InterfaceAttachment> where S: AsRef<str> {
+        debug!("Attaching an interface {:?} to server {}", request, id.as_ref());
+        let body = protocol::InterfaceAttachmentCreateRoot {
+            interface_attachment: request
+        };
+        let attachment = self.request::<V2>(Method::Post,
+                                            &["servers", id.as_ref(),
+                                              "os-interface"],
+                                            None)?
+            .json(&body)
+            .receive_json::<protocol::InterfaceAttachmentRoot>()?
+            .interface_attachment;
+        debug!("Attached port {} to server {}", attachment.port_id, id.as_ref());
+        Ok(attachment)
+    }
+
     fn create_keypair(&self, request: protocol::KeyPairCreate)
             -> Result<protocol::KeyPair> {
         debug!("Creating a key pair with {:?}", request);
+        let ver = if request.key_type.is_some() {
+            if ! self.supports_compute_api_version(API_VERSION_KEYPAIR_TYPE)? {
+                return Err(Error::new(
+                    ErrorKind::IncompatibleApiVersion,
+                    "Key pair types require compute API version 2.2 or newer"));
+            }
+            Some(API_VERSION_KEYPAIR_TYPE)
+        } else {
+            None
+        };
         let body = protocol::KeyPairCreateRoot { keypair: request };
-        let keypair = self.request::<V2>(Method::Post, &["os-keypairs"], None)?
+        let keypair = self.request::<V2>(Method::Post, &["os-keypairs"], ver)?
             .json(&body).receive_json::<protocol::KeyPairRoot>()?.keypair;
-        debug!("Created key pair {:?}", keypair);
+        // Avoid logging the generated private key.
+        debug!("Created key pair {} with fingerprint {}", keypair.name,
+               keypair.fingerprint);
         Ok(keypair)
     }
 
-    fn create_server(&self, request: protocol::ServerCreate) -> Result<Ref> {
+    fn create_server(&self, request: protocol::ServerCreateRoot) -> Result<Ref> {
         debug!("Creating a server with {:?}", request);
-        let body = protocol::ServerCreateRoot { server: request };
-        let server = self.request::<V2>(Method::Post, &["servers"], None)?
-            .json(&body).receive_json::<protocol::CreatedServerRoot>()?.server;
+        let version = server_create_version(self, &request.server)?;
+        let server = self.request::<V2>(Method::Post, &["servers"], version)?
+            .json(&request).receive_json::<protocol::CreatedServerRoot>()?.server;
         trace!("Requested creation of server {:?}", server);
         Ok(server)
     }
 
+    fn create_server_group(&self, mut request: protocol::ServerGroupCreate)
+            -> Result<protocol::ServerGroup> {
+        debug!("Creating a server group with {:?}", request);
+        let version = if self.supports_compute_api_version(API_VERSION_SERVER_GROUP_RULES)? {
+            Some(API_VERSION_SERVER_GROUP_RULES)
+        } else if request.rules.is_some() {
+            return Err(Error::new(
+                ErrorKind::IncompatibleApiVersion,
+                "Server group rules require compute API version 2.64 or newer"));
+        } else {
+            // Older versions only accept a list of policies.
+            request.policies = request.policy.take().into_iter().collect();
+            None
+        };
+        let body = protocol::ServerGroupCreateRoot { server_group: request };
+        let group = self.request::<V2>(Method::Post, &["os-server-groups"],
+                                       version)?
+            .json(&body).receive_json::<protocol::ServerGroupRoot>()?
+            .server_group;
+        debug!("Created server group {:?}", group);
+        Ok(group)
+    }
+
+    fn create_server_image<S: AsRef<str>>(&self, id: S,
+                                          request: protocol::ServerImageCreate)
+            -> Result<String> {
+        debug!("Creating an image of server {} with {:?}", id.as_ref(), request);
+        let version = self.pick_compute_api_version(&[API_VERSION_SERVER_IMAGE_ID])?;
+        let body = protocol::ServerImageCreateRoot { request: request };
+        let mut builder = self.request::<V2>(Method::Post,
+                                             &["servers", id.as_ref(), "action"],
+                                             version)?;
+        let _ = builder.json(&body);
+        let image_id = if version.is_some() {
+            builder.receive_json::<protocol::CreatedImage>()?.image_id
+        } else {
+            // Older versions only return the image URL in the Location header.
+            let resp = builder.send()?;
+            let location = resp.headers().get::<Location>().ok_or_else(|| {
+                Error::new(ErrorKind::InvalidResponse,
+                           "No Location header in response to createImage")
+            })?;
+            match location.rsplit('/').next() {
+                Some(item) if ! item.is_empty() => String::from(item),
+                _ => return Err(Error::new(
+                    ErrorKind::InvalidResponse,
+                    format!("Invalid image location {}", location)))
+            }
+        };
+        debug!("Requested image {} of server {}", image_id, id.as_ref());
+        Ok(image_id)
+    }
+
     fn delete_keypair<S: AsRef<str>>(&self, name: S) -> Result<()> {
         debug!("Deleting key pair {}", name.as_ref());
         let _ = self.request::<V2>(Method::Delete,
@@ -180,6 +382,29 @@ impl V2API for Session {
         Ok(())
     }
 
+    fn delete_server_group<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting server group {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["os-server-groups", id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Server group {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn detach_server_interface<S1, S2>(&self, id: S1, port_id: S2) -> Result<()>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        debug!("Detaching port {} from server {}", port_id.as_ref(), id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["servers", id.as_ref(), "os-interface",
+                                     port_id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Requested detaching port {} from server {}",
+               port_id.as_ref(), id.as_ref());
+        Ok(())
+    }
+
     fn get_extra_specs_by_flavor_id<S: AsRef<str>>(&self, id: S)
             -> Result<HashMap<String, String>> {
         trace!("Get compute extra specs by ID {}", id.as_ref());
@@ -224,6 +449,59 @@ impl V2API for Session {
         Ok(keypair)
     }
 
+    fn get_compute_quotas<S: AsRef<str>>(&self, project_id: S)
+            -> Result<protocol::ComputeQuotas> {
+        trace!("Get compute quotas of project {}", project_id.as_ref());
+        let quotas = self.request::<V2>(Method::Get,
+                                        &["os-quota-sets", project_id.as_ref()],
+                                        None)?
+           .receive_json::<protocol::ComputeQuotasRoot>()?.quota_set;
+        trace!("Received {:?}", quotas);
+        Ok(quotas)
+    }
+
+    fn get_server_console<S: AsRef<str>>(&self, id: S,
+                                         console_type: protocol::ConsoleType)
+            -> Result<protocol::RemoteConsole> {
+        trace!("Requesting {} console for server {}", console_type, id.as_ref());
+        if ! self.supports_compute_api_version(API_VERSION_REMOTE_CONSOLE)? {
+            return Err(Error::new(
+                ErrorKind::IncompatibleApiVersion,
+                "Remote consoles require compute API version 2.6 or newer"));
+        }
+
+        let body = protocol::RemoteConsoleCreateRoot {
+            remote_console: protocol::RemoteConsoleCreate {
+                protocol: console_type.protocol(),
+                console_type: console_type
+            }
+        };
+        let console = self.request::<V2>(Method::Post,
+                                         &["servers", id.as_ref(),
+                                           "remote-consoles"],
+                                         Some(API_VERSION_REMOTE_CONSOLE))?
+            .json(&body).receive_json::<protocol::RemoteConsoleRoot>()?
+            .remote_console;
+        debug!("Received {} console for server {}", console_type, id.as_ref());
+        Ok(console)
+    }
+
+    fn get_server_console_output<S: AsRef<str>>(&self, id: S,
+                                                length: Option<u32>)
+            -> Result<String> {
+        trace!("Requesting console output for server {}", id.as_ref());
+        let body = protocol::ConsoleOutputRequestRoot {
+            request: protocol::ConsoleOutputRequest { length: length }
+        };
+        let output = self.request::<V2>(Method::Post,
+                                        &["servers", id.as_ref(), "action"],
+                                        None)?
+            .json(&body).receive_json::<protocol::ConsoleOutput>()?.output;
+        debug!("Received {} bytes of console output for server {}",
+               output.len(), id.as_ref());
+        Ok(output)
+    }
+
     fn get_server_by_id<S: AsRef<str>>(&self, id: S) -> Result<protocol::Server> {
         trace!("Get compute server with ID {}", id.as_ref());
         let version = self.pick_compute_api_version(&[API_VERSION_SERVER_DESCRIPTION])?;
@@ -246,6 +524,68 @@ impl V2API for Session {
             .and_then(|item| self.get_server_by_id(item.id))
     }
 
+    fn get_server_diagnostics<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::ServerDiagnostics> {
+        trace!("Requesting diagnostics of server {}", id.as_ref());
+        if ! self.supports_compute_api_version(API_VERSION_SERVER_DIAGNOSTICS)? {
+            return Err(Error::new(
+                ErrorKind::IncompatibleApiVersion,
+                "Server diagnostics require compute API version 2.48 or newer"));
+        }
+
+        let diagnostics = self.request::<V2>(Method::Get,
+                                             &["servers", id.as_ref(),
+                                               "diagnostics"],
+                                             Some(API_VERSION_SERVER_DIAGNOSTICS))?
+            .receive_json::<protocol::ServerDiagnostics>()?;
+        trace!("Received {:?}", diagnostics);
+        Ok(diagnostics)
+    }
+
+    fn get_server_group<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::ServerGroup> {
+        trace!("Get server group {}", id.as_ref());
+        let ver = self.pick_compute_api_version(&[API_VERSION_SERVER_GROUP_RULES])?;
+        let group = self.request::<V2>(Method::Get,
+                                       &["os-server-groups", id.as_ref()],
+                                       ver)?
+           .receive_json::<protocol::ServerGroupRoot>()?.server_group;
+        trace!("Received {:?}", group);
+        Ok(group)
+    }
+
+    fn get_server_topology<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::ServerTopology> {
+        trace!("Requesting NUMA topology of server {}", id.as_ref());
+        if ! self.supports_compute_api_version(API_VERSION_SERVER_TOPOLOGY)? {
+            return Err(Error::new(
+                ErrorKind::IncompatibleApiVersion,
+                "Server topology requires compute API version 2.78 or newer"));
+        }
+
+        let topology = self.request::<V2>(Method::Get,
+                                          &["servers", id.as_ref(), "topology"],
+                                          Some(API_VERSION_SERVER_TOPOLOGY))?
+            .receive_json::<protocol::ServerTopology>()?;
+        trace!("Received {:?}", topology);
+        Ok(topology)
+    }
+
+    fn list_availability_zones(&self, detailed: bool)
+            -> Result<Vec<protocol::AvailabilityZone>> {
+        trace!("Listing availability zones (detailed: {})", detailed);
+        let path: &[&str] = if detailed {
+            &["os-availability-zone", "detail"]
+        } else {
+            &["os-availability-zone"]
+        };
+        let result = self.request::<V2>(Method::Get, path, None)?
+           .receive_json::<protocol::AvailabilityZonesRoot>()?
+           .availabilityZoneInfo;
+        trace!("Received availability zones: {:?}", result);
+        Ok(result)
+    }
+
     fn list_flavors<Q: Serialize + Debug>(&self, query: &Q)
             -> Result<Vec<common::protocol::IdAndName>> {
         trace!("Listing compute flavors with {:?}", query);
@@ -271,6 +611,7 @@ impl V2API for Session {
             -> Result<Vec<protocol::KeyPair>> {
         trace!("Listing compute key pairs with {:?}", query);
         let ver = self.pick_compute_api_version(&[API_VERSION_KEYPAIR_TYPE,
+                                                  API_VERSION_KEYPAIR_USER,
                                                   API_VERSION_KEYPAIR_PAGINATION])?;
         let result = self.request::<V2>(Method::Get, &["os-keypairs"], ver)?
            .query(query).receive_json::<protocol::KeyPairsRoot>()?.keypairs
@@ -279,6 +620,21 @@ impl V2API for Session {
         Ok(result)
     }
 
+    fn list_server_groups(&self, all_projects: bool)
+            -> Result<Vec<protocol::ServerGroup>> {
+        trace!("Listing server groups (all projects: {})", all_projects);
+        let ver = self.pick_compute_api_version(&[API_VERSION_SERVER_GROUP_RULES])?;
+        let mut builder = self.request::<V2>(Method::Get, &["os-server-groups"],
+                                             ver)?;
+        if all_projects {
+            let _ = builder.query(&[("all_projects", "true")]);
+        }
+        let result = builder.receive_json::<protocol::ServerGroupsRoot>()?
+            .server_groups;
+        trace!("Received server groups: {:?}", result);
+        Ok(result)
+    }
+
     fn list_servers<Q: Serialize + Debug>(&self, query: &Q)
             -> Result<Vec<common::protocol::IdAndName>> {
         trace!("Listing compute servers with {:?}", query);
@@ -326,6 +682,49 @@ impl V2API for Session {
         let info = self.get_service_info_ref::<V2>()?;
         Ok(info.supports_api_version(version))
     }
+
+    fn update_compute_quotas<S: AsRef<str>>(&self, project_id: S,
+                                            update: protocol::ComputeQuotasUpdate)
+            -> Result<protocol::ComputeQuotas> {
+        debug!("Updating compute quotas of project {} with {:?}",
+               project_id.as_ref(), update);
+        let body = protocol::ComputeQuotasUpdateRoot { quota_set: update };
+        let quotas = self.request::<V2>(Method::Put,
+                                        &["os-quota-sets", project_id.as_ref()],
+                                        None)?
+            .json(&body).receive_json::<protocol::ComputeQuotasRoot>()?
+            .quota_set;
+        debug!("Updated compute quotas of project {}: {:?}",
+               project_id.as_ref(), quotas);
+        Ok(quotas)
+    }
+
+    fn update_server<S: AsRef<str>>(&self, id: S, update: protocol::ServerUpdate)
+            -> Result<()> {
+        debug!("Updating server {} with {:?}", id.as_ref(), update);
+        let version = server_description_version(self, &update.description)?;
+        let body = protocol::ServerUpdateRoot { server: update };
+        let _ = self.request::<V2>(Method::Put, &["servers", id.as_ref()],
+                                   version)?
+            .json(&body).send()?;
+        debug!("Updated server {}", id.as_ref());
+        Ok(())
+    }
+
+    fn update_server_metadata<S: AsRef<str>>(&self, id: S,
+                                             metadata: HashMap<String, String>)
+            -> Result<HashMap<String, String>> {
+        debug!("Updating metadata of server {} with {:?}", id.as_ref(),
+               metadata);
+        let body = protocol::ServerMetadataRoot { metadata: metadata };
+        let result = self.request::<V2>(Method::Post,
+                                        &["servers", id.as_ref(), "metadata"],
+                                        None)?
+            .json(&body).receive_json::<protocol::ServerMetadataRoot>()?
+            .metadata;
+        debug!("Updated metadata of server {}", id.as_ref());
+        Ok(result)
+    }
 }
 
 
@@ -340,8 +739,14 @@ impl ServiceType for V2 {
 
     fn api_version_headers(version: ApiVersion) -> Option<Headers> {
         let mut hdrs = Headers::new();
-        // TODO: typed header, new-style header support
+        // TODO: typed header
         hdrs.set_raw("x-openstack-nova-api-version", version.to_string());
+        hdrs.set_raw("openstack-api-version", format!("compute {}", version));
         Some(hdrs)
     }
+
+    fn max_api_version() -> Option<ApiVersion> {
+        // Starting with 2.47 servers embed their flavors.
+        Some(API_VERSION_MAX_SUPPORTED)
+    }
 }
@@ -25,7 +25,7 @@ use super::super::{Error, Result};
 use super::super::common::{self, FlavorRef, ListResources, Refresh, ResourceId,
                            ResourceIterator};
 use super::super::session::Session;
-use super::super::utils::Query;
+use super::super::utils::{Query, ValueCache};
 use super::base::V2API;
 use super::protocol;
 
@@ -35,7 +35,7 @@ use super::protocol;
 pub struct Flavor {
     session: Rc<Session>,
     inner: protocol::Flavor,
-    extra_specs: HashMap<String, String>,
+    extra_specs: ValueCache<HashMap<String, String>>,
 }
 
 /// Structure representing a summary of a flavor.
@@ -51,6 +51,8 @@ pub struct FlavorQuery {
     session: Rc<Session>,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
 }
 
 
@@ -58,11 +60,7 @@ impl Flavor {
     /// Create a flavor object.
     pub(crate) fn new(session: Rc<Session>, mut inner: protocol::Flavor)
             -> Result<Flavor> {
-        let extra_specs = match inner.extra_specs.take() {
-            Some(es) => es,
-            None => session.get_extra_specs_by_flavor_id(&inner.id)?
-        };
-
+        let extra_specs = ValueCache::new(inner.extra_specs.take());
         Ok(Flavor {
             session: session,
             inner: inner,
@@ -70,6 +68,12 @@ impl Flavor {
         })
     }
 
+    fn ensure_extra_specs(&self) -> Result<()> {
+        self.extra_specs.ensure_value(|| {
+            self.session.get_extra_specs_by_flavor_id(&self.inner.id)
+        })
+    }
+
     /// Load a Flavor object.
     pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
             -> Result<Flavor> {
@@ -77,16 +81,41 @@ impl Flavor {
         Flavor::new(session, inner)
     }
 
+    transparent_property! {
+        #[doc = "Flavor description (if available)."]
+        description: ref Option<String>
+    }
+
     /// Get ephemeral disk size in GiB.
     ///
     /// Returns `0` when ephemeral disk was not requested.
-    pub fn emphemeral_size(&self) -> u64 {
+    pub fn ephemeral_size(&self) -> u64 {
         self.inner.ephemeral
     }
 
+    /// Get ephemeral disk size in GiB.
+    #[deprecated(since = "0.2.0", note = "Use ephemeral_size")]
+    pub fn emphemeral_size(&self) -> u64 {
+        self.ephemeral_size()
+    }
+
+    /// Get one extra spec of the flavor.
+    ///
+    /// Extra specs are loaded on first access, unless the Compute API returned
+    /// them together with the flavor.
+    pub fn extra_spec<S: AsRef<str>>(&self, key: S) -> Result<Option<String>> {
+        self.ensure_extra_specs()?;
+        Ok(self.extra_specs.extract(|es| es.get(key.as_ref()).cloned())
+           .and_then(|x| x))
+    }
+
     /// Extra specs of the flavor.
-    pub fn extra_specs(&self) -> &HashMap<String, String> {
-        &self.extra_specs
+    ///
+    /// Extra specs are loaded on first access, unless the Compute API returned
+    /// them together with the flavor.
+    pub fn extra_specs(&self) -> Result<HashMap<String, String>> {
+        self.ensure_extra_specs()?;
+        Ok(self.extra_specs.extract(|es| es.clone()).unwrap())
     }
 
     /// Get a reference to flavor unique ID.
@@ -114,6 +143,11 @@ impl Flavor {
         self.inner.disk
     }
 
+    /// Get the RX/TX factor of the network bandwidth.
+    pub fn rxtx_factor(&self) -> f32 {
+        self.inner.rxtx_factor
+    }
+
     /// Get swap size in MiB.
     ///
     /// Returns `0` when swap was not requested.
@@ -130,7 +164,9 @@ impl Flavor {
 impl Refresh for Flavor {
     /// Refresh the flavor.
     fn refresh(&mut self) -> Result<()> {
-        self.inner = self.session.get_flavor(&self.inner.id)?;
+        let mut inner = self.session.get_flavor(&self.inner.id)?;
+        self.extra_specs = ValueCache::new(inner.extra_specs.take());
+        self.inner = inner;
         Ok(())
     }
 }
@@ -158,6 +194,8 @@ impl FlavorQuery {
             session: session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            max_items: None,
         }
     }
 
@@ -179,6 +217,62 @@ impl FlavorQuery {
         self
     }
 
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Filter by flavor visibility.
+    ///
+    /// `Some(true)` (the default) returns only public flavors, `Some(false)`
+    /// returns only private flavors, `None` returns both (admin only).
+    pub fn set_is_public(&mut self, value: Option<bool>) {
+        match value {
+            Some(value) => self.query.push("is_public", value),
+            None => self.query.push_str("is_public", "none")
+        }
+    }
+
+    /// Filter by flavor visibility.
+    ///
+    /// `Some(true)` (the default) returns only public flavors, `Some(false)`
+    /// returns only private flavors, `None` returns both (admin only).
+    pub fn with_is_public(mut self, value: Option<bool>) -> Self {
+        self.set_is_public(value);
+        self
+    }
+
+    /// Filter by minimum root disk size in GiB.
+    pub fn set_minimum_disk(&mut self, value: u64) {
+        self.query.push("minDisk", value);
+    }
+
+    /// Filter by minimum root disk size in GiB.
+    pub fn with_minimum_disk(mut self, value: u64) -> Self {
+        self.set_minimum_disk(value);
+        self
+    }
+
+    /// Filter by minimum RAM size in MiB.
+    pub fn set_minimum_ram(&mut self, value: u64) {
+        self.query.push("minRam", value);
+    }
+
+    /// Filter by minimum RAM size in MiB.
+    pub fn with_minimum_ram(mut self, value: u64) -> Self {
+        self.set_minimum_ram(value);
+        self
+    }
+
     /// Convert this query into an iterator executing the request.
     ///
     /// This iterator yields only `FlavorSummary` objects, containing
@@ -191,6 +285,7 @@ impl FlavorQuery {
     pub fn into_iter(self) -> ResourceIterator<FlavorSummary> {
         debug!("Fetching flavors with {:?}", self.query);
         ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
     }
 
     /// Convert this query into an iterator executing the request.
@@ -205,6 +300,14 @@ impl FlavorQuery {
     pub fn into_iter_detailed(self) -> ResourceIterator<Flavor> {
         debug!("Fetching flavor details with {:?}", self.query);
         ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Convert this query into an iterator fetching `page_size` items per
+    /// request.
+    pub fn into_iter_paginated(self, page_size: usize)
+            -> ResourceIterator<FlavorSummary> {
+        self.with_page_size(page_size).into_iter()
     }
 
     /// Execute this request and return all results.
@@ -300,7 +403,8 @@ impl FlavorRef {
         Ok(if self.verified {
             self.value
         } else {
-            session.get_flavor(&self.value)?.id
+            session.resolve_id("flavor", &self.value,
+                               |s| Ok(session.get_flavor(s)?.id))?
         })
     }
 }
@@ -309,7 +413,7 @@ impl From<Flavor> for protocol::ServerFlavor {
     fn from(value: Flavor) -> protocol::ServerFlavor {
         protocol::ServerFlavor {
             ephemeral_size: value.inner.ephemeral,
-            extra_specs: Some(value.extra_specs),
+            extra_specs: value.extra_specs.extract(|es| es.clone()),
             original_name: value.inner.name,
             ram_size: value.inner.ram,
             root_size: value.inner.disk,
@@ -18,12 +18,13 @@ use std::fmt::Debug;
 use std::io;
 use std::rc::Rc;
 
+use chrono::{DateTime, FixedOffset};
 use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
 use serde::Serialize;
 
 use super::super::{Error, ErrorKind, Result};
-use super::super::common::{KeyPairRef, ListResources, Refresh, ResourceId,
-                           ResourceIterator};
+use super::super::common::{self, KeyPairRef, ListResources, Refresh,
+                           ResourceId, ResourceIterator, UserRef};
 use super::super::session::Session;
 use super::super::utils::Query;
 use super::base::V2API;
@@ -43,12 +44,15 @@ pub struct KeyPairQuery {
     session: Rc<Session>,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
 }
 
 /// A request to create a key pair.
 #[derive(Clone, Debug)]
 pub struct NewKeyPair {
     session: Rc<Session>,
+    key_type: Option<protocol::KeyPairType>,
     name: String,
     public_key: Option<String>,
 }
@@ -70,6 +74,11 @@ impl KeyPair {
         self.session.delete_keypair(&self.inner.name)
     }
 
+    transparent_property! {
+        #[doc = "Creation date and time (if available)."]
+        created_at: Option<DateTime<FixedOffset>>
+    }
+
     transparent_property! {
         #[doc = "Key pair fingerprint."]
         fingerprint: ref String
@@ -84,6 +93,11 @@ impl KeyPair {
         #[doc = "Key pair name."]
         name: ref String
     }
+
+    transparent_property! {
+        #[doc = "Public key contents."]
+        public_key: ref String
+    }
 }
 
 impl Refresh for KeyPair {
@@ -100,6 +114,8 @@ impl KeyPairQuery {
             session: session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            max_items: None,
         }
     }
 
@@ -121,6 +137,28 @@ impl KeyPairQuery {
         self
     }
 
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Filter by user ID (admin-only).
+    ///
+    /// Requires compute API version 2.10 or newer.
+    pub fn with_user<T: Into<UserRef>>(mut self, value: T) -> Self {
+        self.query.push_str("user_id", value.into());
+        self
+    }
+
     /// Convert this query into an iterator executing the request.
     ///
     /// Returns a `FallibleIterator`, which is an iterator with each `next`
@@ -130,6 +168,14 @@ impl KeyPairQuery {
     pub fn into_iter(self) -> ResourceIterator<KeyPair> {
         debug!("Fetching key pairs with {:?}", self.query);
         ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Convert this query into an iterator fetching `page_size` items per
+    /// request.
+    pub fn into_iter_paginated(self, page_size: usize)
+            -> ResourceIterator<KeyPair> {
+        self.with_page_size(page_size).into_iter()
     }
 
     /// Execute this request and return all results.
@@ -161,33 +207,66 @@ impl NewKeyPair {
             -> NewKeyPair {
         NewKeyPair {
             session: session,
+            key_type: None,
             name: name,
             public_key: None,
         }
     }
 
+    fn build_request(&self) -> protocol::KeyPairCreate {
+        protocol::KeyPairCreate {
+            key_type: self.key_type,
+            name: self.name.clone(),
+            public_key: self.public_key.clone()
+        }
+    }
+
+    /// Serialize the request body that `create` or `generate` would send.
+    pub fn to_request_json(&self) -> Result<String> {
+        let body = protocol::KeyPairCreateRoot { keypair: self.build_request() };
+        common::protocol::to_request_json(&body)
+    }
+
     /// Request creation of a key pair.
     ///
     /// This call fails immediately if no public_key is provided.
     pub fn create(self) -> Result<KeyPair> {
-        let request = if let Some(public_key) = self.public_key {
-            protocol::KeyPairCreate {
-                key_type: None,  // TODO
-                name: self.name,
-                public_key: public_key
-            }
-        } else {
+        if self.public_key.is_none() {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Public key contents is required"));
-        };
+        }
 
-        let keypair = self.session.create_keypair(request)?;
+        let mut keypair = self.session.create_keypair(self.build_request())?;
+        keypair.private_key = None;
         Ok(KeyPair {
             session: self.session,
             inner: keypair
         })
     }
 
+    /// Request creation of a key pair with a key generated by the server.
+    ///
+    /// Returns the new key pair and its private key. The private key is not
+    /// stored anywhere, so this is the only chance to save it.
+    ///
+    /// This call fails immediately if a public_key is provided.
+    pub fn generate(self) -> Result<(KeyPair, String)> {
+        if self.public_key.is_some() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "Public key cannot be used with generation"));
+        }
+
+        let mut keypair = self.session.create_keypair(self.build_request())?;
+        let private_key = keypair.private_key.take().ok_or_else(|| {
+            Error::new(ErrorKind::InvalidResponse,
+                       "Private key was not returned by the server")
+        })?;
+        Ok((KeyPair {
+            session: self.session,
+            inner: keypair
+        }, private_key))
+    }
+
     /// Add public key from a reader.
     pub fn from_reader<R>(self, reader: &mut R) -> io::Result<NewKeyPair> where R: io::Read {
         let mut s = String::new();
@@ -205,6 +284,21 @@ impl NewKeyPair {
     pub fn set_string<S>(&mut self, public_key: S) where S: Into<String> {
         self.public_key = Some(public_key.into());
     }
+
+    /// Set the key pair type.
+    ///
+    /// Requires compute API version 2.2 or newer.
+    pub fn set_key_type(&mut self, value: protocol::KeyPairType) {
+        self.key_type = Some(value);
+    }
+
+    /// Set the key pair type.
+    ///
+    /// Requires compute API version 2.2 or newer.
+    pub fn with_key_type(mut self, value: protocol::KeyPairType) -> NewKeyPair {
+        self.set_key_type(value);
+        self
+    }
 }
 
 impl ResourceId for KeyPair {
@@ -14,17 +14,36 @@
 
 //! Compute API implementation bits.
 
+mod availability_zones;
 mod base;
 mod flavors;
 mod keypairs;
 mod protocol;
+mod quotas;
+mod server_groups;
 mod servers;
 
+pub(crate) use self::availability_zones::list_availability_zones;
 pub use self::base::V2 as ServiceType;
 pub use self::flavors::{Flavor, FlavorSummary, FlavorQuery};
 pub use self::keypairs::{KeyPair, KeyPairQuery, NewKeyPair};
-pub use self::protocol::{AddressType, KeyPairType, RebootType, ServerAddress,
-                         ServerFlavor, ServerSortKey, ServerPowerState,
-                         ServerStatus};
-pub use self::servers::{NewServer, Server, ServerCreationWaiter, ServerNIC,
-                        ServerQuery, ServerStatusWaiter, ServerSummary};
+pub use self::protocol::{AddressType, AvailabilityZone,
+                         AvailabilityZoneService, AvailabilityZoneState,
+                         ComputeQuotas, ComputeQuotasUpdate,
+                         ConsoleType, CpuDiagnostics, DiskConfig,
+                         DiskDiagnostics,
+                         KeyPairType, MemoryDiagnostics, NicDiagnostics,
+                         RebootType, ServerAddress, ServerDiagnostics,
+                         ServerFlavor, ServerGroupPolicy, ServerNumaNode,
+                         ServerSortKey, ServerPowerState, ServerStatus,
+                         ServerTopology};
+pub(crate) use self::quotas::{get_quotas, update_quotas};
+pub use self::server_groups::{NewServerGroup, ServerGroup};
+pub(crate) use self::server_groups::list_server_groups;
+pub use self::servers::{BlockDevice, BlockDeviceSource, MetadataPolicy,
+                        NewServer, Server, ServerAction, ServerCreationWaiter,
+                        ServerLookupOptions, ServerNameAmbiguity,
+                        ServerNameMatch, ServerNIC, ServerQuery, ServerRebuild,
+                        ServerStatusWaiter, ServerSummary};
+#[cfg(feature = "network")]
+pub use self::servers::InterfaceWaiter;
@@ -17,10 +17,12 @@
 #![allow(non_snake_case)]
 #![allow(missing_docs)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use chrono::{DateTime, FixedOffset};
+use eui48::MacAddress;
+use serde_json::Value;
 
 use super::super::common;
 
@@ -60,6 +62,16 @@ protocol_enum! {
     }
 }
 
+protocol_enum! {
+    #[doc = "Scheduling policy of a server group."]
+    enum ServerGroupPolicy {
+        Affinity = "affinity",
+        AntiAffinity = "anti-affinity",
+        SoftAffinity = "soft-affinity",
+        SoftAntiAffinity = "soft-anti-affinity"
+    }
+}
+
 protocol_enum! {
     #[doc = "Possible server statuses."]
     enum ServerStatus {
@@ -85,6 +97,14 @@ protocol_enum! {
     }
 }
 
+protocol_enum! {
+    #[doc = "How the root disk of a server is partitioned."]
+    enum DiskConfig {
+        Auto = "AUTO",
+        Manual = "MANUAL"
+    }
+}
+
 protocol_enum! {
     #[doc = "Possible power states."]
     enum ServerPowerState: u8 {
@@ -121,14 +141,26 @@ protocol_enum! {
     }
 }
 
+protocol_enum! {
+    #[doc = "Type of a remote console."]
+    enum ConsoleType {
+        NoVnc = "novnc",
+        XvpVnc = "xvpvnc",
+        RdpHtml5 = "rdp-html5",
+        SpiceHtml5 = "spice-html5",
+        Serial = "serial",
+        WebMks = "webmks"
+    }
+}
+
 /// Address of a server.
 #[derive(Clone, Debug, Deserialize)]
 pub struct ServerAddress {
-    /// IP (v4 of v6) address.
+    /// IP (v4 or v6) address.
     pub addr: IpAddr,
     /// MAC address (if available).
     #[serde(rename = "OS-EXT-IPS-MAC:mac_addr", default)]
-    pub mac_addr: Option<String>,
+    pub mac_addr: Option<MacAddress>,
     /// Address type (if known).
     #[serde(rename = "OS-EXT-IPS:type", default)]
     pub addr_type: Option<AddressType>
@@ -170,29 +202,55 @@ pub struct Server {
     pub addresses: HashMap<String, Vec<ServerAddress>>,
     #[serde(rename = "OS-EXT-AZ:availability_zone")]
     pub availability_zone: String,
-    #[serde(rename = "created")]
+    #[serde(rename = "created",
+            deserialize_with = "common::protocol::deser_timestamp")]
     pub created_at: DateTime<FixedOffset>,
     #[serde(deserialize_with = "common::protocol::empty_as_none", default)]
     pub description: Option<String>,
+    #[serde(rename = "OS-DCF:diskConfig", default)]
+    pub disk_config: Option<DiskConfig>,
     // TODO(dtantsur): flavor in newer versions
     pub flavor: common::protocol::Ref,
     #[serde(deserialize_with = "common::protocol::empty_as_default",
             rename = "config_drive")]
     pub has_config_drive: bool,
     pub id: String,
+    #[serde(rename = "OS-EXT-SRV-ATTR:host", default)]
+    pub host: Option<String>,
+    #[serde(rename = "OS-EXT-SRV-ATTR:hypervisor_hostname", default)]
+    pub hypervisor_hostname: Option<String>,
     #[serde(deserialize_with = "common::protocol::empty_as_none", default)]
     pub image: Option<common::protocol::Ref>,
+    #[serde(rename = "OS-EXT-SRV-ATTR:instance_name", default)]
+    pub instance_name: Option<String>,
     #[serde(rename = "key_name", deserialize_with = "common::protocol::empty_as_none",
             default)]
     pub key_pair_name: Option<String>,
+    #[serde(rename = "OS-SRV-USG:launched_at",
+            deserialize_with = "common::protocol::deser_optional_timestamp",
+            default)]
+    pub launched_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub locked: Option<bool>,
     pub name: String,
     #[serde(default)]
     pub metadata: HashMap<String, String>,
     pub status: ServerStatus,
     #[serde(rename = "OS-EXT-STS:power_state", default)]
     pub power_state: ServerPowerState,
+    #[serde(rename = "OS-EXT-SRV-ATTR:reservation_id", default)]
+    pub reservation_id: Option<String>,
+    #[serde(default)]
+    pub security_groups: Vec<ServerSecurityGroup>,
+    #[serde(rename = "OS-EXT-STS:task_state", default)]
+    pub task_state: Option<String>,
     pub tenant_id: String,
-    #[serde(rename = "updated")]
+    #[serde(rename = "OS-SRV-USG:terminated_at",
+            deserialize_with = "common::protocol::deser_optional_timestamp",
+            default)]
+    pub terminated_at: Option<DateTime<FixedOffset>>,
+    #[serde(rename = "updated",
+            deserialize_with = "common::protocol::deser_timestamp")]
     pub updated_at: DateTime<FixedOffset>,
     pub user_id: String
 }
@@ -220,22 +278,111 @@ pub enum ServerNetwork {
     FixedIp { fixed_ip: Ipv4Addr }
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct BlockDeviceMapping {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boot_index: Option<u16>,
+    pub delete_on_termination: bool,
+    pub destination_type: String,
+    pub source_type: String,
+    pub uuid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume_size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume_type: Option<String>,
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct ServerCreate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub availability_zone: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub block_device_mapping_v2: Vec<BlockDeviceMapping>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_drive: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "OS-DCF:diskConfig",
+            skip_serializing_if = "Option::is_none")]
+    pub disk_config: Option<DiskConfig>,
     pub flavorRef: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub imageRef: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_name: Option<String>,
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    #[serde(skip_serializing_if = "HashMap::is_empty",
+            serialize_with = "common::protocol::ser_sorted_map")]
     pub metadata: HashMap<String, String>,
     pub name: String,
-    pub networks: Vec<ServerNetwork>
+    pub networks: Vec<ServerNetwork>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub security_groups: Vec<ServerSecurityGroup>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trusted_image_certificates: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_data: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServerSecurityGroup {
+    pub name: String
 }
 
 #[derive(Clone, Debug, Serialize)]
 pub struct ServerCreateRoot {
-    pub server: ServerCreate
+    pub server: ServerCreate,
+    #[serde(rename = "os:scheduler_hints",
+            skip_serializing_if = "HashMap::is_empty",
+            serialize_with = "common::protocol::ser_sorted_map")]
+    pub scheduler_hints: HashMap<String, Value>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ServerUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ServerUpdateRoot {
+    pub server: ServerUpdate
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServerMetadataRoot {
+    pub metadata: HashMap<String, String>
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ServerPersonality {
+    pub path: String,
+    pub contents: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ServerResize {
+    #[serde(rename = "OS-DCF:diskConfig",
+            skip_serializing_if = "Option::is_none")]
+    pub disk_config: Option<DiskConfig>,
+    pub flavorRef: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ServerRebuild {
+    #[serde(rename = "OS-DCF:diskConfig",
+            skip_serializing_if = "Option::is_none")]
+    pub disk_config: Option<DiskConfig>,
+    pub imageRef: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub personality: Option<Vec<ServerPersonality>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preserve_ephemeral: Option<bool>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -243,6 +390,291 @@ pub struct CreatedServerRoot {
     pub server: common::protocol::Ref
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct ServerImageCreate {
+    pub name: String,
+    #[serde(skip_serializing_if = "HashMap::is_empty",
+            serialize_with = "common::protocol::ser_sorted_map")]
+    pub metadata: HashMap<String, String>
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ServerImageCreateRoot {
+    #[serde(rename = "createImage")]
+    pub request: ServerImageCreate
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreatedImage {
+    pub image_id: String
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InterfaceFixedIp {
+    pub ip_address: IpAddr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subnet_id: Option<String>
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InterfaceAttachmentCreate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub net_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fixed_ips: Vec<InterfaceFixedIp>
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InterfaceAttachmentCreateRoot {
+    #[serde(rename = "interfaceAttachment")]
+    pub interface_attachment: InterfaceAttachmentCreate
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InterfaceAttachment {
+    #[serde(default)]
+    pub fixed_ips: Vec<InterfaceFixedIp>,
+    #[serde(default)]
+    pub mac_addr: Option<String>,
+    pub net_id: String,
+    pub port_id: String,
+    #[serde(default)]
+    pub port_state: Option<String>
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InterfaceAttachmentRoot {
+    #[serde(rename = "interfaceAttachment")]
+    pub interface_attachment: InterfaceAttachment
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RemoteConsoleCreate {
+    pub protocol: &'static str,
+    #[serde(rename = "type")]
+    pub console_type: ConsoleType
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RemoteConsoleCreateRoot {
+    pub remote_console: RemoteConsoleCreate
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RemoteConsole {
+    pub protocol: String,
+    #[serde(rename = "type")]
+    pub console_type: ConsoleType,
+    pub url: String
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RemoteConsoleRoot {
+    pub remote_console: RemoteConsole
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ServerGroupRules {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_server_per_host: Option<u32>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServerGroup {
+    pub id: String,
+    #[serde(default)]
+    pub members: Vec<String>,
+    pub name: String,
+    #[serde(default)]
+    pub policies: Vec<ServerGroupPolicy>,
+    #[serde(default)]
+    pub policy: Option<ServerGroupPolicy>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub rules: ServerGroupRules,
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServerGroupRoot {
+    pub server_group: ServerGroup
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServerGroupsRoot {
+    pub server_groups: Vec<ServerGroup>
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ServerGroupCreate {
+    pub name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<ServerGroupPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<ServerGroupPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<ServerGroupRules>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ServerGroupCreateRoot {
+    pub server_group: ServerGroupCreate
+}
+
+/// Diagnostics of a virtual CPU.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CpuDiagnostics {
+    /// CPU index.
+    #[serde(default)]
+    pub id: Option<u32>,
+    /// CPU time in nanoseconds.
+    #[serde(default)]
+    pub time: Option<u64>,
+    /// CPU utilisation in percents.
+    #[serde(default)]
+    pub utilisation: Option<u32>,
+}
+
+/// Diagnostics of a virtual disk.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DiskDiagnostics {
+    /// Number of read bytes.
+    #[serde(default)]
+    pub read_bytes: Option<u64>,
+    /// Number of read requests.
+    #[serde(default)]
+    pub read_requests: Option<u64>,
+    /// Number of written bytes.
+    #[serde(default)]
+    pub write_bytes: Option<u64>,
+    /// Number of write requests.
+    #[serde(default)]
+    pub write_requests: Option<u64>,
+    /// Number of disk errors.
+    #[serde(default)]
+    pub errors_count: Option<u64>,
+}
+
+/// Diagnostics of a virtual NIC.
+#[derive(Clone, Debug, Deserialize)]
+pub struct NicDiagnostics {
+    /// MAC address of the NIC.
+    #[serde(default)]
+    pub mac_address: Option<String>,
+    /// Number of received bytes.
+    #[serde(default)]
+    pub rx_octets: Option<u64>,
+    /// Number of received packets.
+    #[serde(default)]
+    pub rx_packets: Option<u64>,
+    /// Number of dropped received packets.
+    #[serde(default)]
+    pub rx_drop: Option<u64>,
+    /// Number of transmitted bytes.
+    #[serde(default)]
+    pub tx_octets: Option<u64>,
+    /// Number of transmitted packets.
+    #[serde(default)]
+    pub tx_packets: Option<u64>,
+    /// Number of dropped transmitted packets.
+    #[serde(default)]
+    pub tx_drop: Option<u64>,
+}
+
+/// Memory diagnostics.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct MemoryDiagnostics {
+    /// Amount of memory in MiB.
+    #[serde(default)]
+    pub maximum: Option<u64>,
+    /// Amount of used memory in MiB.
+    #[serde(default)]
+    pub used: Option<u64>,
+}
+
+/// Hypervisor-level diagnostics of a server (admin-only).
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServerDiagnostics {
+    /// Whether a config drive is attached.
+    #[serde(default)]
+    pub config_drive: bool,
+    /// Details of virtual CPUs.
+    #[serde(default)]
+    pub cpu_details: Vec<CpuDiagnostics>,
+    /// Details of virtual disks.
+    #[serde(default)]
+    pub disk_details: Vec<DiskDiagnostics>,
+    /// Compute driver in use.
+    pub driver: String,
+    /// Hypervisor type.
+    #[serde(default)]
+    pub hypervisor: Option<String>,
+    /// Hypervisor operating system.
+    #[serde(default)]
+    pub hypervisor_os: Option<String>,
+    /// Memory usage.
+    #[serde(default)]
+    pub memory_details: MemoryDiagnostics,
+    /// Details of virtual NICs.
+    #[serde(default)]
+    pub nic_details: Vec<NicDiagnostics>,
+    /// Power state of the server as reported by the hypervisor.
+    pub state: String,
+    /// Uptime in seconds.
+    #[serde(default)]
+    pub uptime: Option<u64>,
+}
+
+/// A NUMA node of a server.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServerNumaNode {
+    /// Mapping of virtual CPUs to host CPUs (admin-only).
+    #[serde(default)]
+    pub cpu_pinning: HashMap<String, u32>,
+    /// Host NUMA node the node is placed on (admin-only).
+    #[serde(default)]
+    pub host_node: Option<u32>,
+    /// Memory of the node in MiB.
+    pub memory_mb: u64,
+    /// Virtual CPU thread siblings.
+    #[serde(default)]
+    pub siblings: Vec<Vec<u32>>,
+    /// Virtual CPUs of the node.
+    #[serde(default)]
+    pub vcpu_set: Vec<u32>,
+}
+
+/// NUMA topology of a server.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServerTopology {
+    /// NUMA nodes.
+    pub nodes: Vec<ServerNumaNode>,
+    /// Page size in KiB (admin-only).
+    #[serde(default)]
+    pub pagesize_kb: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConsoleOutputRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<u32>
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConsoleOutputRequestRoot {
+    #[serde(rename = "os-getConsoleOutput")]
+    pub request: ConsoleOutputRequest
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConsoleOutput {
+    pub output: String
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Flavor {
     #[serde(rename = "OS-FLV-EXT-DATA:ephemeral", default)]
@@ -281,10 +713,15 @@ pub struct FlavorRoot {
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct KeyPair {
+    #[serde(deserialize_with = "common::protocol::deser_optional_timestamp",
+            default)]
+    pub created_at: Option<DateTime<FixedOffset>>,
     pub fingerprint: String,
     #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
     pub key_type: Option<KeyPairType>,
     pub name: String,
+    #[serde(default)]
+    pub private_key: Option<String>,
     pub public_key: String,
 }
 
@@ -293,7 +730,8 @@ pub struct KeyPairCreate {
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub key_type: Option<KeyPairType>,
     pub name: String,
-    pub public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -311,12 +749,180 @@ pub struct KeyPairsRoot {
     pub keypairs: Vec<KeyPairRoot>
 }
 
+/// Compute quotas of a project.
+///
+/// A value of -1 means no limit.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ComputeQuotas {
+    /// Number of virtual CPUs.
+    pub cores: i64,
+    /// Number of servers.
+    pub instances: i64,
+    /// Number of key pairs per user.
+    pub key_pairs: i64,
+    /// Number of metadata items per server.
+    pub metadata_items: i64,
+    /// RAM in MiB.
+    pub ram: i64,
+    /// Number of servers per server group.
+    pub server_group_members: i64,
+    /// Number of server groups.
+    pub server_groups: i64,
+}
+
+/// An update to compute quotas of a project.
+///
+/// Only set fields are changed, use -1 to remove a limit.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ComputeQuotasUpdate {
+    /// Number of virtual CPUs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cores: Option<i64>,
+    /// Number of servers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instances: Option<i64>,
+    /// Number of key pairs per user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_pairs: Option<i64>,
+    /// Number of metadata items per server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_items: Option<i64>,
+    /// RAM in MiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ram: Option<i64>,
+    /// Number of servers per server group.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_group_members: Option<i64>,
+    /// Number of server groups.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_groups: Option<i64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ComputeQuotasRoot {
+    pub quota_set: ComputeQuotas
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ComputeQuotasUpdateRoot {
+    pub quota_set: ComputeQuotasUpdate
+}
+
+/// State of a compute service in an availability zone.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AvailabilityZoneService {
+    /// Whether the service is enabled.
+    pub active: bool,
+    /// Whether the service is up, i.e. reports its state in time.
+    pub available: bool,
+    /// Time of the last state report.
+    #[serde(deserialize_with = "common::protocol::deser_optional_timestamp",
+            default)]
+    pub updated_at: Option<DateTime<FixedOffset>>,
+}
+
+/// State of an availability zone.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AvailabilityZoneState {
+    /// Whether the zone is available.
+    pub available: bool,
+}
+
+/// A compute availability zone.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AvailabilityZone {
+    /// Hosts of the zone with their services by name (admin-only).
+    ///
+    /// Only provided by the detailed listing.
+    #[serde(default)]
+    pub hosts: Option<HashMap<String, HashMap<String, AvailabilityZoneService>>>,
+    /// Name of the zone.
+    #[serde(rename = "zoneName")]
+    pub name: String,
+    /// State of the zone.
+    #[serde(rename = "zoneState")]
+    pub state: AvailabilityZoneState,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AvailabilityZonesRoot {
+    pub availabilityZoneInfo: Vec<AvailabilityZone>
+}
+
+impl ConsoleType {
+    /// Remote console protocol corresponding to this console type.
+    pub fn protocol(&self) -> &'static str {
+        match *self {
+            ConsoleType::NoVnc | ConsoleType::XvpVnc => "vnc",
+            ConsoleType::RdpHtml5 => "rdp",
+            ConsoleType::SpiceHtml5 => "spice",
+            ConsoleType::Serial => "serial",
+            ConsoleType::WebMks => "mks",
+            _ => unreachable!()
+        }
+    }
+}
+
+impl AvailabilityZone {
+    /// Services that are enabled but not up, as pairs (host, service).
+    ///
+    /// Always empty unless the zone comes from the detailed listing.
+    pub fn unavailable_services(&self) -> Vec<(&String, &String)> {
+        let mut result = self.hosts.iter().flat_map(|hosts| hosts.iter())
+            .flat_map(|(host, services)| {
+                services.iter()
+                    .filter(|&(_, service)| service.active && ! service.available)
+                    .map(move |(name, _)| (host, name))
+            }).collect::<Vec<_>>();
+        result.sort();
+        result
+    }
+}
+
+impl ServerAddress {
+    /// IP version of the address (4 or 6).
+    pub fn version(&self) -> u8 {
+        match self.addr {
+            IpAddr::V4(..) => 4,
+            IpAddr::V6(..) => 6
+        }
+    }
+
+    /// Whether the address is a floating IP.
+    pub fn is_floating(&self) -> bool {
+        self.addr_type == Some(AddressType::Floating)
+    }
+}
+
 impl Default for ServerStatus {
     fn default() -> ServerStatus {
         ServerStatus::Unknown
     }
 }
 
+impl common::ResourceStatus for ServerStatus {
+    fn is_error(&self) -> bool {
+        *self == ServerStatus::Error
+    }
+
+    fn is_transitional(&self) -> bool {
+        match *self {
+            ServerStatus::Building | ServerStatus::HardRebooting |
+            ServerStatus::Migrating | ServerStatus::Rebooting |
+            ServerStatus::Resizing | ServerStatus::RevertingResize |
+            ServerStatus::UpdatingPassword => true,
+            _ => false
+        }
+    }
+
+    fn is_unknown(&self) -> bool {
+        match *self {
+            ServerStatus::Unknown | ServerStatus::__Nonexhaustive => true,
+            _ => false
+        }
+    }
+}
+
 impl Default for ServerPowerState {
     fn default() -> ServerPowerState {
         ServerPowerState::NoState
@@ -325,3 +931,181 @@ impl Default for ServerPowerState {
 
 #[inline]
 fn default_flavor_is_public() -> bool { true }
+
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, Timelike};
+    use serde_json;
+
+    use super::super::super::common::ResourceStatus;
+    use super::{AvailabilityZonesRoot, DiskConfig, KeyPairRoot, ServerResize,
+                ServerRoot, ServerStatus};
+
+    // Trimmed down from the Nova API reference.
+    const SERVER: &'static str = r#"{"server": {
+        "OS-DCF:diskConfig": "MANUAL",
+        "OS-EXT-AZ:availability_zone": "nova",
+        "OS-EXT-STS:power_state": 1,
+        "OS-EXT-STS:task_state": null,
+        "OS-SRV-USG:launched_at": "2013-09-23T13:53:12.774549",
+        "OS-SRV-USG:terminated_at": null,
+        "config_drive": "",
+        "created": "2013-09-23T13:53:12Z",
+        "flavor": {"id": "1", "links": [
+            {"href": "http://openstack.example.com/flavors/1",
+             "rel": "bookmark"}]},
+        "id": "9168b536-cd40-4630-b43f-b259807c6e87",
+        "image": "",
+        "key_name": null,
+        "metadata": {"My Server Name": "Apache1"},
+        "name": "new-server-test",
+        "status": "ACTIVE",
+        "tenant_id": "6f70656e737461636b20342065766572",
+        "updated": "2013-09-23T13:53:12Z",
+        "user_id": "fake"
+    }}"#;
+
+    #[test]
+    fn test_server_timestamps() {
+        let server: ServerRoot = serde_json::from_str(SERVER).unwrap();
+        let expected = DateTime::parse_from_rfc3339("2013-09-23T13:53:12Z")
+            .unwrap();
+        assert_eq!(server.server.created_at, expected);
+        assert_eq!(server.server.updated_at, expected);
+        let launched_at = server.server.launched_at.unwrap();
+        assert_eq!(launched_at.with_nanosecond(0).unwrap(), expected);
+        assert_eq!(server.server.terminated_at, None);
+    }
+
+    #[test]
+    fn test_server_disk_config() {
+        let server: ServerRoot = serde_json::from_str(SERVER).unwrap();
+        assert_eq!(server.server.disk_config, Some(DiskConfig::Manual));
+
+        let resize = ServerResize {
+            disk_config: Some(DiskConfig::Auto),
+            flavorRef: String::from("1"),
+        };
+        assert_eq!(serde_json::to_string(&resize).unwrap(),
+                   r#"{"OS-DCF:diskConfig":"AUTO","flavorRef":"1"}"#);
+    }
+
+    #[test]
+    fn test_server_security_groups() {
+        let server: ServerRoot = serde_json::from_str(SERVER).unwrap();
+        assert!(server.server.security_groups.is_empty());
+
+        let payload = SERVER.replace(
+            "\"status\": \"ACTIVE\",",
+            "\"status\": \"ACTIVE\", \"security_groups\": \
+             [{\"name\": \"default\"}, {\"name\": \"web\"}],");
+        let server: ServerRoot = serde_json::from_str(&payload).unwrap();
+        let names = server.server.security_groups.iter()
+            .map(|group| group.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names, vec![String::from("default"), String::from("web")]);
+    }
+
+    #[test]
+    fn test_server_timestamps_invalid() {
+        let payload = SERVER.replace("\"updated\": \"2013-09-23T13:53:12Z\"",
+                                     "\"updated\": \"not a date\"");
+        assert!(serde_json::from_str::<ServerRoot>(&payload).is_err());
+    }
+
+    #[test]
+    fn test_keypair_timestamp() {
+        let keypair: KeyPairRoot = serde_json::from_str(r#"{"keypair": {
+            "created_at": "2014-05-07T12:06:13.681238",
+            "deleted": false,
+            "fingerprint": "1e:2c:9b:56:79:4b:45:77:f9:ca:7a:98:2c:b0:d5:3c",
+            "id": 1,
+            "name": "keypair-test",
+            "public_key": "ssh-rsa AAAA Generated-by-Nova",
+            "type": "ssh",
+            "user_id": "fake"
+        }}"#).unwrap();
+        let created_at = keypair.keypair.created_at.unwrap();
+        assert_eq!(created_at.to_rfc3339(), "2014-05-07T12:06:13.681238+00:00");
+    }
+
+    #[test]
+    fn test_availability_zones_detail() {
+        let root: AvailabilityZonesRoot = serde_json::from_str(r#"{
+            "availabilityZoneInfo": [
+                {
+                    "zoneName": "internal",
+                    "zoneState": {"available": true},
+                    "hosts": {
+                        "controller": {
+                            "nova-scheduler": {
+                                "available": true,
+                                "active": true,
+                                "updated_at": "2018-05-01T12:00:00.000000"
+                            }
+                        }
+                    }
+                },
+                {
+                    "zoneName": "nova",
+                    "zoneState": {"available": true},
+                    "hosts": {
+                        "compute-1": {
+                            "nova-compute": {
+                                "available": false,
+                                "active": true,
+                                "updated_at": null
+                            }
+                        },
+                        "compute-2": {
+                            "nova-compute": {
+                                "available": false,
+                                "active": false,
+                                "updated_at": "2018-05-01T11:00:00.000000"
+                            }
+                        }
+                    }
+                }
+            ]
+        }"#).unwrap();
+        let zones = root.availabilityZoneInfo;
+        assert_eq!(zones.len(), 2);
+        assert!(zones[0].unavailable_services().is_empty());
+        let service = &zones[0].hosts.as_ref().unwrap()["controller"]
+            ["nova-scheduler"];
+        assert_eq!(service.updated_at.unwrap().hour(), 12);
+        assert_eq!(zones[1].unavailable_services(),
+                   vec![(&String::from("compute-1"),
+                         &String::from("nova-compute"))]);
+    }
+
+    #[test]
+    fn test_availability_zones_simple() {
+        let root: AvailabilityZonesRoot = serde_json::from_str(r#"{
+            "availabilityZoneInfo": [
+                {"zoneName": "nova", "zoneState": {"available": true},
+                 "hosts": null}
+            ]
+        }"#).unwrap();
+        let zone = &root.availabilityZoneInfo[0];
+        assert_eq!(zone.name, "nova");
+        assert!(zone.state.available);
+        assert!(zone.hosts.is_none());
+        assert!(zone.unavailable_services().is_empty());
+    }
+
+    #[test]
+    fn test_server_status_classification() {
+        assert!(ServerStatus::Active.is_terminal());
+        assert!(! ServerStatus::Active.is_error());
+        assert!(ServerStatus::Error.is_terminal());
+        assert!(ServerStatus::Error.is_error());
+        assert!(ServerStatus::Building.is_transitional());
+        assert!(! ServerStatus::Building.is_terminal());
+        assert!(ServerStatus::VerifyingResize.is_terminal());
+        assert!(ServerStatus::Unknown.is_unknown());
+        assert!(! ServerStatus::Unknown.is_terminal());
+        assert!(! ServerStatus::Unknown.is_transitional());
+        assert!(! ServerStatus::default().is_terminal());
+    }
+}
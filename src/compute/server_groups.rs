@@ -0,0 +1,197 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Server groups management via Compute API.
+
+use std::rc::Rc;
+
+use super::super::Result;
+use super::super::common::{Refresh, ResourceId};
+use super::super::session::Session;
+use super::base::V2API;
+use super::protocol;
+
+
+/// A server group, constraining placement of its members.
+///
+/// Quotas on the number of server groups and of their members are reported
+/// by `Cloud::get_quotas`.
+#[derive(Clone, Debug)]
+pub struct ServerGroup {
+    session: Rc<Session>,
+    inner: protocol::ServerGroup
+}
+
+/// A request to create a server group.
+#[derive(Clone, Debug)]
+pub struct NewServerGroup {
+    session: Rc<Session>,
+    inner: protocol::ServerGroupCreate
+}
+
+impl ServerGroup {
+    /// Create a server group object.
+    fn new(session: Rc<Session>, inner: protocol::ServerGroup)
+            -> ServerGroup {
+        ServerGroup {
+            session: session,
+            inner: inner
+        }
+    }
+
+    /// Load a server group.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<ServerGroup> {
+        let inner = session.get_server_group(id)?;
+        Ok(ServerGroup::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    /// Maximum number of members per host for the anti-affinity policy.
+    ///
+    /// Requires compute API version 2.64, `None` on older versions and when
+    /// not limited.
+    pub fn max_server_per_host(&self) -> Option<u32> {
+        self.inner.rules.max_server_per_host
+    }
+
+    transparent_property! {
+        #[doc = "IDs of the servers in the group."]
+        members: ref Vec<String>
+    }
+
+    transparent_property! {
+        #[doc = "Server group name."]
+        name: ref String
+    }
+
+    /// Scheduling policy of the group.
+    ///
+    /// Older compute API versions report a list of policies with only one
+    /// item, this call handles both representations.
+    pub fn policy(&self) -> Option<protocol::ServerGroupPolicy> {
+        self.inner.policy.or_else(|| self.inner.policies.first().cloned())
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the group (if available)."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the user owning the group (if available)."]
+        user_id: ref Option<String>
+    }
+
+    /// Delete the server group.
+    ///
+    /// Members of the group are not affected.
+    pub fn delete(self) -> Result<()> {
+        self.session.delete_server_group(&self.inner.id)
+    }
+}
+
+impl Refresh for ServerGroup {
+    /// Refresh the server group.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_server_group(&self.inner.id)?;
+        Ok(())
+    }
+}
+
+impl ResourceId for ServerGroup {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+/// List server groups.
+pub(crate) fn list_server_groups(session: Rc<Session>, all_projects: bool)
+        -> Result<Vec<ServerGroup>> {
+    Ok(session.list_server_groups(all_projects)?.into_iter()
+       .map(|item| ServerGroup::new(session.clone(), item)).collect())
+}
+
+impl NewServerGroup {
+    /// Start creating a server group.
+    pub(crate) fn new(session: Rc<Session>, name: String,
+                      policy: protocol::ServerGroupPolicy) -> NewServerGroup {
+        NewServerGroup {
+            session: session,
+            inner: protocol::ServerGroupCreate {
+                name: name,
+                policies: Vec::new(),
+                policy: Some(policy),
+                rules: None,
+            }
+        }
+    }
+
+    /// Request creation of the server group.
+    pub fn create(self) -> Result<ServerGroup> {
+        let inner = self.session.create_server_group(self.inner)?;
+        Ok(ServerGroup::new(self.session, inner))
+    }
+
+    /// Set the maximum number of members per host.
+    ///
+    /// Only valid with the anti-affinity policy, requires compute API
+    /// version 2.64.
+    pub fn set_max_server_per_host(&mut self, value: u32) {
+        self.inner.rules = Some(protocol::ServerGroupRules {
+            max_server_per_host: Some(value)
+        });
+    }
+
+    /// Set the maximum number of members per host.
+    pub fn with_max_server_per_host(mut self, value: u32) -> NewServerGroup {
+        self.set_max_server_per_host(value);
+        self
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use serde_json;
+
+    use super::super::protocol::{self, ServerGroupPolicy};
+
+    #[test]
+    fn test_policy_formats() {
+        let old: protocol::ServerGroupRoot = serde_json::from_str(r#"{
+            "server_group": {"id": "1", "name": "old",
+                             "policies": ["anti-affinity"],
+                             "members": ["s1", "s2"], "metadata": {}}
+        }"#).unwrap();
+        assert_eq!(old.server_group.policies,
+                   vec![ServerGroupPolicy::AntiAffinity]);
+        assert_eq!(old.server_group.members.len(), 2);
+        assert!(old.server_group.rules.max_server_per_host.is_none());
+
+        let new: protocol::ServerGroupRoot = serde_json::from_str(r#"{
+            "server_group": {"id": "2", "name": "new",
+                             "policy": "anti-affinity",
+                             "rules": {"max_server_per_host": 3},
+                             "members": []}
+        }"#).unwrap();
+        assert_eq!(new.server_group.policy,
+                   Some(ServerGroupPolicy::AntiAffinity));
+        assert_eq!(new.server_group.rules.max_server_per_host, Some(3));
+    }
+}
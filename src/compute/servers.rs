@@ -14,25 +14,34 @@
 
 //! Server management via Compute API.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
+use std::io::Read;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::rc::Rc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use base64;
 use chrono::{DateTime, FixedOffset};
 use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
 use serde::Serialize;
+use serde_json::Value;
 use waiter::{Waiter, WaiterCurrentState};
 
 use super::super::{Error, ErrorKind, Result, Sort};
-use super::super::common::{self, DeletionWaiter, FlavorRef, ImageRef, KeyPairRef,
-                           ListResources, NetworkRef, PortRef, ProjectRef,
-                           Refresh, ResourceId, ResourceIterator, UserRef};
+use super::super::cloud::Defaults;
+use super::super::common::{self, CancellationToken, DeletionWaiter, FlavorRef,
+                           ImageRef, KeyPairRef, ListResources, NetworkRef,
+                           PortRef, ProjectRef, Refresh, ResourceId,
+                           ResourceIterator, StatusTracker, StatusTransition,
+                           UserRef};
 #[cfg(feature = "image")]
 use super::super::image::Image;
+#[cfg(feature = "network")]
+use super::super::network::{NetworkStatus, Port};
 use super::super::session::Session;
-use super::super::utils::Query;
+use super::super::telemetry;
+use super::super::utils::{self, Query};
 use super::base::V2API;
 use super::{protocol, KeyPair};
 
@@ -43,6 +52,8 @@ pub struct ServerQuery {
     session: Rc<Session>,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
 }
 
 /// Structure representing a single server.
@@ -51,6 +62,7 @@ pub struct Server {
     session: Rc<Session>,
     inner: protocol::Server,
     flavor: protocol::ServerFlavor,
+    dirty: HashSet<&'static str>,
 }
 
 /// Structure representing a summary of a single server.
@@ -64,7 +76,23 @@ pub struct ServerSummary {
 #[derive(Debug)]
 pub struct ServerStatusWaiter<'server> {
     server: &'server mut Server,
-    target: protocol::ServerStatus
+    target: protocol::ServerStatus,
+    wait_timeout: Duration,
+    delay: Duration,
+    started: Instant,
+    progress: StatusTracker<protocol::ServerStatus>,
+}
+
+/// Waiter for a port to get attached to or detached from a server.
+#[cfg(feature = "network")]
+#[derive(Debug)]
+pub struct InterfaceWaiter {
+    port: Port,
+    server_id: String,
+    attach: bool,
+    wait_timeout: Duration,
+    delay: Duration,
+    started: Instant
 }
 
 /// A virtual NIC of a new server.
@@ -78,22 +106,151 @@ pub enum ServerNIC {
     WithFixedIp(Ipv4Addr)
 }
 
+/// How to match server names when looking up a server.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ServerNameMatch {
+    /// The name must be exactly equal to the requested one.
+    Exact,
+    /// The requested name is a regular expression (as understood by the
+    /// database of the Compute service).
+    Regex,
+}
+
+/// What to do when several servers match the requested name.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ServerNameAmbiguity {
+    /// Fail with `TooManyItems`, listing the candidates in the message.
+    Fail,
+    /// Pick the most recently created server.
+    MostRecent,
+}
+
+/// Options for looking up a server by its name or ID.
+///
+/// By default names have to match exactly and ambiguous names are an error.
+#[derive(Copy, Clone, Debug)]
+pub struct ServerLookupOptions {
+    name_match: ServerNameMatch,
+    ambiguity: ServerNameAmbiguity,
+}
+
+/// A lifecycle action on a server.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ServerAction {
+    /// Confirm a pending resize or migration.
+    ConfirmResize,
+    /// Create an image (snapshot).
+    CreateImage,
+    /// Delete the server.
+    Delete,
+    /// Lock the server.
+    Lock,
+    /// Migrate the server to another host.
+    Migrate,
+    /// Reboot the server.
+    Reboot,
+    /// Rebuild the server.
+    Rebuild,
+    /// Put the server into the rescue mode.
+    Rescue,
+    /// Resize the server.
+    Resize,
+    /// Revert a pending resize or migration.
+    RevertResize,
+    /// Start the server.
+    Start,
+    /// Stop the server.
+    Stop,
+    /// Unlock the server.
+    Unlock,
+    /// Bring the server out of the rescue mode.
+    Unrescue,
+}
+
+/// How to treat server metadata on rebuild.
+#[derive(Clone, Debug)]
+pub enum MetadataPolicy {
+    /// Keep the current metadata.
+    Preserve,
+    /// Replace the metadata with the given items.
+    Replace(HashMap<String, String>),
+    /// Add the given items to the current metadata, overwriting existing keys.
+    Merge(HashMap<String, String>),
+}
+
+/// A request to rebuild a server.
+#[derive(Debug)]
+pub struct ServerRebuild<'server> {
+    server: &'server mut Server,
+    disk_config: Option<protocol::DiskConfig>,
+    image: ImageRef,
+    metadata: MetadataPolicy,
+    name: Option<String>,
+    personality: Option<Vec<(String, Vec<u8>)>>,
+    preserve_ephemeral: Option<bool>,
+}
+
+/// A source of a block device of a new server.
+#[derive(Clone, Debug)]
+pub enum BlockDeviceSource {
+    /// A new volume created from the given image.
+    Image(ImageRef),
+    /// An existing volume with the given ID.
+    Volume(String),
+    /// A new volume created from the volume snapshot with the given ID.
+    Snapshot(String),
+}
+
+/// A block device of a new server.
+#[derive(Clone, Debug)]
+pub struct BlockDevice {
+    /// Boot index of the device, `None` for devices that are not bootable.
+    pub boot_index: Option<u16>,
+    /// Whether to delete the volume when the server is deleted.
+    pub delete_on_termination: bool,
+    /// Size of the volume to create in GiB (if applicable).
+    pub size: Option<u32>,
+    /// Source of the device.
+    pub source: BlockDeviceSource,
+    /// Volume type of the volume to create (if applicable).
+    ///
+    /// Requires compute API version 2.67 or newer.
+    pub volume_type: Option<String>,
+}
+
 /// A request to create a server.
 #[derive(Debug)]
 pub struct NewServer {
     session: Rc<Session>,
+    availability_zone: Option<String>,
+    block_devices: Vec<BlockDevice>,
+    config_drive: Option<bool>,
+    default_network: Option<NetworkRef>,
+    description: Option<String>,
+    default_security_groups: Vec<String>,
+    disk_config: Option<protocol::DiskConfig>,
     flavor: FlavorRef,
     image: Option<ImageRef>,
     keypair: Option<KeyPairRef>,
+    max_nics: Option<usize>,
     metadata: HashMap<String, String>,
     name: String,
     networks: Vec<ServerNIC>,
+    scheduler_hints: HashMap<String, Value>,
+    security_groups: Vec<String>,
+    trusted_image_certificates: Vec<String>,
+    user_data: Option<Vec<u8>>,
 }
 
 /// Waiter for server to be created.
 #[derive(Debug)]
 pub struct ServerCreationWaiter {
-    server: Server
+    server: Server,
+    wait_timeout: Duration,
+    delay: Duration,
+    started: Instant,
+    cancellation: Option<CancellationToken>,
+    progress: StatusTracker<protocol::ServerStatus>,
 }
 
 
@@ -101,6 +258,7 @@ impl Refresh for Server {
     /// Refresh the server.
     fn refresh(&mut self) -> Result<()> {
         self.inner = self.session.get_server(&self.inner.id)?;
+        self.dirty.clear();
         Ok(())
     }
 }
@@ -110,7 +268,13 @@ impl Server {
     pub(crate) fn new(session: Rc<Session>, inner: protocol::Server)
             -> Result<Server> {
         let flavor = session.get_flavor(&inner.flavor.id)?;
-        Ok(Server {
+        Ok(Server::with_flavor(session, inner, flavor))
+    }
+
+    /// Create a Server object from an already fetched flavor.
+    fn with_flavor(session: Rc<Session>, inner: protocol::Server,
+                   flavor: protocol::Flavor) -> Server {
+        Server {
             session: session,
             inner: inner,
             flavor: protocol::ServerFlavor {
@@ -122,7 +286,36 @@ impl Server {
                 swap_size: flavor.swap,
                 vcpu_count: flavor.vcpus,
             },
-        })
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Session used by this server.
+    #[cfg(all(feature = "image", feature = "network"))]
+    pub(crate) fn session(&self) -> &Rc<Session> {
+        &self.session
+    }
+
+    /// Prepare a new server with the same settings booted from the image.
+    ///
+    /// Copies the name, flavor, description, metadata, key pair,
+    /// availability zone and security groups. Networks are not copied.
+    #[cfg(all(feature = "image", feature = "network"))]
+    pub(crate) fn prepare_replacement(&self, image_id: String) -> NewServer {
+        let mut new_server = NewServer::new(
+            self.session.clone(), self.inner.name.clone(),
+            FlavorRef::new_verified(self.inner.flavor.id.clone()))
+            .with_image(ImageRef::new_verified(image_id))
+            .with_availability_zone(self.inner.availability_zone.clone());
+        new_server.description = self.inner.description.clone();
+        new_server.metadata = self.inner.metadata.clone();
+        if let Some(ref key_pair) = self.inner.key_pair_name {
+            new_server.set_keypair(KeyPairRef::new_verified(key_pair.clone()));
+        }
+        for group in self.security_groups() {
+            new_server.add_security_group(group.clone());
+        }
+        new_server
     }
 
     /// Load a Server object.
@@ -132,6 +325,65 @@ impl Server {
         Server::new(session, inner)
     }
 
+    /// Load a Server object by its ID or name with the given options.
+    pub(crate) fn load_with_options<Id: AsRef<str>>(session: Rc<Session>,
+                                                    id_or_name: Id,
+                                                    options: ServerLookupOptions)
+            -> Result<Server> {
+        let s = id_or_name.as_ref();
+        let inner = match session.get_server_by_id(s) {
+            Err(ref err) if err.kind() == ErrorKind::ResourceNotFound => None,
+            other => Some(other?)
+        };
+        if let Some(inner) = inner {
+            return Server::new(session, inner);
+        }
+
+        // The Compute service treats the name as a regular expression, so
+        // anchor and escape it for exact matches. The results are still
+        // checked, since the regular expression dialect depends on the
+        // database.
+        let name_filter = match options.name_match {
+            ServerNameMatch::Exact => format!("^{}$", regex_escape(s)),
+            ServerNameMatch::Regex => String::from(s)
+        };
+        let mut candidates = ServerQuery::new(session).with_name(name_filter)
+            .into_iter_detailed()
+            .filter(|server| options.name_match == ServerNameMatch::Regex ||
+                    server.name() == s)
+            .collect::<Vec<Server>>()?;
+        match candidates.len() {
+            0 => Err(Error::new(ErrorKind::ResourceNotFound,
+                                format!("Server with name or ID {} not found",
+                                        s))),
+            1 => Ok(candidates.pop().unwrap()),
+            _ if options.ambiguity == ServerNameAmbiguity::MostRecent => {
+                candidates.sort_by_key(|server| server.created_at());
+                debug!("Picking the most recent of {} servers matching {}",
+                       candidates.len(), s);
+                Ok(candidates.pop().unwrap())
+            },
+            _ => {
+                let names = candidates.iter()
+                    .map(|server| format!("{} ({})", server.id(), server.name()))
+                    .collect::<Vec<String>>();
+                Err(Error::new(ErrorKind::TooManyItems,
+                               format!("Several servers match name {}: {}",
+                                       s, names.join(", "))))
+            }
+        }
+    }
+
+    /// Load a Server object owning the given fixed or floating IP.
+    pub(crate) fn load_by_ip(session: Rc<Session>, ip: IpAddr)
+            -> Result<Server> {
+        let items = ServerQuery::new(session).with_exact_ip(ip)
+            .into_iter_detailed().filter(|server| server.has_address(ip))
+            .collect::<Vec<Server>>()?;
+        utils::one(items, "Server with given IP address not found",
+                   "Too many servers found with given IP address")
+    }
+
     transparent_property! {
         #[doc = "IPv4 address to access the server (if provided)."]
         access_ipv4: Option<Ipv4Addr>
@@ -162,19 +414,66 @@ impl Server {
         description: ref Option<String>
     }
 
+    transparent_property! {
+        #[doc = "How the root disk was partitioned (if known)."]
+        disk_config: Option<protocol::DiskConfig>
+    }
+
+    update_field! {
+        #[doc = "Update the description (requires compute API version 2.19)."]
+        set_description, with_description -> description: optional String
+    }
+
     /// Flavor information used to create this server.
     pub fn flavor(&self) -> &protocol::ServerFlavor {
         &self.flavor
     }
 
+    /// Names of security groups of the server.
+    ///
+    /// Only available in detailed listings and when fetching a server.
+    pub fn security_groups(&self) -> Vec<&String> {
+        let mut result: Vec<&String> = Vec::new();
+        for group in &self.inner.security_groups {
+            if ! result.contains(&&group.name) {
+                result.push(&group.name);
+            }
+        }
+        result
+    }
+
     /// Find a floating IP, if it exists.
     ///
     /// If multiple floating IPs exist, the first is returned.
     pub fn floating_ip(&self) -> Option<IpAddr> {
-        self.inner.addresses.values()
-            .flat_map(|l| l.iter())
-            .filter(|a| a.addr_type == Some(protocol::AddressType::Floating))
-            .map(|a| a.addr).next()
+        self.floating_addresses().into_iter().map(|a| a.addr).next()
+    }
+
+    /// Addresses of the given type together with their network names.
+    pub fn addresses_of_type(&self, addr_type: protocol::AddressType)
+            -> Vec<(&String, &protocol::ServerAddress)> {
+        self.inner.addresses.iter()
+            .flat_map(|(net, l)| l.iter().map(move |a| (net, a)))
+            .filter(|&(_, a)| a.addr_type == Some(addr_type))
+            .collect()
+    }
+
+    /// All fixed addresses of the server.
+    pub fn fixed_addresses(&self) -> Vec<&protocol::ServerAddress> {
+        self.addresses_of_type(protocol::AddressType::Fixed).into_iter()
+            .map(|(_, a)| a).collect()
+    }
+
+    /// All floating addresses of the server.
+    pub fn floating_addresses(&self) -> Vec<&protocol::ServerAddress> {
+        self.addresses_of_type(protocol::AddressType::Floating).into_iter()
+            .map(|(_, a)| a).collect()
+    }
+
+    /// Whether the server has this fixed or floating IP address.
+    pub fn has_address(&self, ip: IpAddr) -> bool {
+        self.inner.addresses.values().flat_map(|l| l.iter())
+            .any(|a| a.addr == ip)
     }
 
     transparent_property! {
@@ -225,6 +524,31 @@ impl Server {
         }
     }
 
+    transparent_property! {
+        #[doc = "Name of the compute host running this server (admin-only)."]
+        host: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "When the server was last launched (if available)."]
+        launched_at: Option<DateTime<FixedOffset>>
+    }
+
+    transparent_property! {
+        #[doc = "Hostname of the hypervisor running this server (admin-only)."]
+        hypervisor_hostname: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Name of the server on the hypervisor (admin-only)."]
+        instance_name: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Whether the server is locked (requires compute API version 2.9)."]
+        locked: Option<bool>
+    }
+
     transparent_property! {
         #[doc = "Name of a key pair used with this server (if any)."]
         key_pair_name: ref Option<String>
@@ -235,6 +559,11 @@ impl Server {
         name: ref String
     }
 
+    update_field! {
+        #[doc = "Update the server name."]
+        set_name, with_name -> name
+    }
+
     transparent_property! {
         #[doc = "Metadata associated with the server."]
         metadata: ref HashMap<String, String>
@@ -245,66 +574,521 @@ impl Server {
         power_state: protocol::ServerPowerState
     }
 
+    transparent_property! {
+        #[doc = "Reservation ID of the server creation request (admin-only)."]
+        reservation_id: ref Option<String>
+    }
+
     transparent_property! {
         #[doc = "Server status."]
         status: protocol::ServerStatus
     }
 
+    transparent_property! {
+        #[doc = "Task currently running on the server (if any)."]
+        task_state: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "When the server was terminated (if available)."]
+        terminated_at: Option<DateTime<FixedOffset>>
+    }
+
     transparent_property! {
         #[doc = "Last update date and time."]
         updated_at: DateTime<FixedOffset>
     }
 
+    /// Lifecycle actions that are likely to succeed on the server.
+    ///
+    /// This is a best-effort guess based on the current status, the task
+    /// state and the locked flag, intended for e.g. disabling buttons in user
+    /// interfaces. Policies are not taken into account, so actions may still
+    /// fail, for example because they are admin-only or an administrator can
+    /// act on a locked server. Refresh the server to get an up-to-date result.
+    pub fn allowed_actions(&self) -> Vec<ServerAction> {
+        allowed_actions(self.inner.status,
+                        self.inner.task_state.as_ref().map(String::as_str),
+                        self.inner.locked)
+    }
+
+    /// Get the console output (serial log) of the server.
+    ///
+    /// If `lines` is provided, only this number of last lines is returned.
+    pub fn console_output(&self, lines: Option<u32>) -> Result<String> {
+        self.session.get_server_console_output(&self.inner.id, lines)
+    }
+
+    /// Get hypervisor diagnostics of the server.
+    ///
+    /// Usually requires administrator privileges and compute API version
+    /// 2.48 or newer.
+    pub fn diagnostics(&self) -> Result<protocol::ServerDiagnostics> {
+        self.session.get_server_diagnostics(&self.inner.id)
+    }
+
+    /// Get NUMA topology of the server.
+    ///
+    /// Host-specific details (CPU pinning, host nodes and page size) are only
+    /// returned to administrators. Requires compute API version 2.78 or newer.
+    pub fn topology(&self) -> Result<protocol::ServerTopology> {
+        self.session.get_server_topology(&self.inner.id)
+    }
+
+    /// Get a URL of an interactive remote console of the server.
+    ///
+    /// Requires compute API version 2.6 or newer.
+    pub fn get_console_url(&self, console_type: protocol::ConsoleType)
+            -> Result<String> {
+        Ok(self.session.get_server_console(&self.inner.id, console_type)?.url)
+    }
+
+    /// Attach a new port on the network to the server.
+    ///
+    /// The port is created by the Compute service, optionally with the given
+    /// fixed IP. The returned waiter can be used to wait for the port to
+    /// become active, which only happens for running servers.
+    #[cfg(feature = "network")]
+    pub fn attach_network<N>(&self, network: N, fixed_ip: Option<IpAddr>)
+            -> Result<InterfaceWaiter> where N: Into<NetworkRef> {
+        let request = protocol::InterfaceAttachmentCreate {
+            port_id: None,
+            net_id: Some(network.into().into_verified(&self.session)?),
+            fixed_ips: fixed_ip.into_iter().map(|ip| {
+                protocol::InterfaceFixedIp {
+                    ip_address: ip,
+                    subnet_id: None
+                }
+            }).collect()
+        };
+        self.attach_interface(request)
+    }
+
+    /// Attach an existing port to the server.
+    ///
+    /// The returned waiter can be used to wait for the port to become
+    /// active, which only happens for running servers.
+    #[cfg(feature = "network")]
+    pub fn attach_port<P>(&self, port: P) -> Result<InterfaceWaiter>
+            where P: Into<PortRef> {
+        let request = protocol::InterfaceAttachmentCreate {
+            port_id: Some(port.into().into_verified(&self.session)?),
+            net_id: None,
+            fixed_ips: Vec::new()
+        };
+        self.attach_interface(request)
+    }
+
+    #[cfg(feature = "network")]
+    fn attach_interface(&self, request: protocol::InterfaceAttachmentCreate)
+            -> Result<InterfaceWaiter> {
+        let attachment = self.session.attach_server_interface(&self.inner.id,
+                                                              request)?;
+        let port = Port::load(self.session.clone(), attachment.port_id)?;
+        Ok(InterfaceWaiter::new(port, self.inner.id.clone(), true))
+    }
+
+    /// Detach a port from the server.
+    ///
+    /// The returned waiter can be used to wait for the port to be detached.
+    /// Ports created by the Compute service (e.g. by `attach_network`) are
+    /// deleted once detached.
+    #[cfg(feature = "network")]
+    pub fn detach_interface<P>(&self, port: P) -> Result<InterfaceWaiter>
+            where P: Into<PortRef> {
+        let port_id = port.into().into_verified(&self.session)?;
+        let port = Port::load(self.session.clone(), &port_id)?;
+        self.session.detach_server_interface(&self.inner.id, port_id)?;
+        Ok(InterfaceWaiter::new(port, self.inner.id.clone(), false))
+    }
+
+    /// Create an image (snapshot) of the server.
+    ///
+    /// Returns the ID of the new image. The image is not usable until it
+    /// becomes active.
+    pub fn create_image<S>(&self, name: S) -> Result<String>
+            where S: Into<String> {
+        let request = protocol::ServerImageCreate {
+            name: name.into(),
+            metadata: HashMap::new()
+        };
+        self.session.create_server_image(&self.inner.id, request)
+    }
+
+    /// Add or replace a metadata item of the server.
+    ///
+    /// Other items are kept. The change is applied immediately.
+    pub fn set_metadata_item<S1, S2>(&mut self, key: S1, value: S2)
+            -> Result<()> where S1: Into<String>, S2: Into<String> {
+        let mut metadata = HashMap::new();
+        let _ = metadata.insert(key.into(), value.into());
+        self.inner.metadata = self.session.update_server_metadata(
+            &self.inner.id, metadata)?;
+        Ok(())
+    }
+
     /// Delete the server.
     pub fn delete(self) -> Result<DeletionWaiter<Server>> {
         self.session.delete_server(&self.inner.id)?;
         Ok(DeletionWaiter::new(self, Duration::new(120, 0), Duration::new(1, 0)))
     }
 
+    /// Whether the server is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the server.
+    pub fn save(&mut self) -> Result<()> {
+        let mut update = protocol::ServerUpdate::default();
+        save_fields! {
+            self -> update: name
+        };
+        save_option_fields! {
+            self -> update: description
+        };
+        self.session.update_server(&self.inner.id, update)?;
+        self.refresh()
+    }
+
+    /// Migrate the server to another host (cold migration).
+    ///
+    /// The resulting waiter waits for the `VERIFY_RESIZE` state, after which
+    /// either `confirm_resize` or `revert_resize` has to be called. This
+    /// action is admin-only by default.
+    pub fn migrate<'server>(&'server mut self)
+            -> Result<ServerStatusWaiter<'server>> {
+        self.session.server_simple_action(&self.inner.id, "migrate")?;
+        Ok(ServerStatusWaiter::new(self, protocol::ServerStatus::VerifyingResize))
+    }
+
     /// Reboot the server.
     pub fn reboot<'server>(&'server mut self, reboot_type: protocol::RebootType)
             -> Result<ServerStatusWaiter<'server>> {
         let mut args = HashMap::new();
         let _ = args.insert("type", reboot_type);
         self.session.server_action_with_args(&self.inner.id, "reboot", args)?;
-        Ok(ServerStatusWaiter {
+        Ok(ServerStatusWaiter::new(self, protocol::ServerStatus::Active))
+    }
+
+    /// Confirm a pending resize of the server.
+    pub fn confirm_resize<'server>(&'server mut self)
+            -> Result<ServerStatusWaiter<'server>> {
+        self.session.server_simple_action(&self.inner.id, "confirmResize")?;
+        Ok(ServerStatusWaiter::new(self, protocol::ServerStatus::Active))
+    }
+
+    /// Lock the server, preventing non-admin users from acting on it.
+    ///
+    /// Locking does not change the server status, so the resulting waiter
+    /// only refreshes the server.
+    pub fn lock<'server>(&'server mut self)
+            -> Result<ServerStatusWaiter<'server>> {
+        self.session.server_simple_action(&self.inner.id, "lock")?;
+        let target = self.status();
+        Ok(ServerStatusWaiter::new(self, target))
+    }
+
+    /// Rebuild the server using the provided image.
+    ///
+    /// Metadata and personality files are preserved. Use `prepare_rebuild`
+    /// for more options.
+    pub fn rebuild<'server, I>(&'server mut self, image: I)
+            -> Result<ServerStatusWaiter<'server>>
+            where I: Into<ImageRef> {
+        self.prepare_rebuild(image).run()
+    }
+
+    /// Prepare rebuilding the server using the provided image.
+    ///
+    /// Returns a `ServerRebuild` builder, call `run` on it to start the
+    /// rebuild.
+    pub fn prepare_rebuild<I>(&mut self, image: I) -> ServerRebuild
+            where I: Into<ImageRef> {
+        ServerRebuild {
             server: self,
-            target: protocol::ServerStatus::Active
-        })
+            disk_config: None,
+            image: image.into(),
+            metadata: MetadataPolicy::Preserve,
+            name: None,
+            personality: None,
+            preserve_ephemeral: None,
+        }
+    }
+
+    /// Put the server into the rescue mode.
+    pub fn rescue<'server>(&'server mut self)
+            -> Result<ServerStatusWaiter<'server>> {
+        self.session.server_simple_action(&self.inner.id, "rescue")?;
+        Ok(ServerStatusWaiter::new(self, protocol::ServerStatus::Rescuing))
+    }
+
+    /// Resize the server to the provided flavor.
+    ///
+    /// The resulting waiter waits for the `VERIFY_RESIZE` state, after which
+    /// either `confirm_resize` or `revert_resize` has to be called.
+    pub fn resize<'server, F>(&'server mut self, flavor: F)
+            -> Result<ServerStatusWaiter<'server>>
+            where F: Into<FlavorRef> {
+        self.do_resize(flavor.into(), None)
+    }
+
+    /// Resize the server to the provided flavor with the given disk config.
+    ///
+    /// Same as `resize`, but also sets how to partition the resized disk.
+    pub fn resize_with_disk_config<'server, F>(&'server mut self, flavor: F,
+                                               disk_config: protocol::DiskConfig)
+            -> Result<ServerStatusWaiter<'server>>
+            where F: Into<FlavorRef> {
+        self.do_resize(flavor.into(), Some(disk_config))
+    }
+
+    fn do_resize<'server>(&'server mut self, flavor: FlavorRef,
+                          disk_config: Option<protocol::DiskConfig>)
+            -> Result<ServerStatusWaiter<'server>> {
+        let args = protocol::ServerResize {
+            disk_config: disk_config,
+            flavorRef: flavor.into_verified(&self.session)?,
+        };
+        self.session.server_action_with_args(&self.inner.id, "resize", args)?;
+        Ok(ServerStatusWaiter::new(self, protocol::ServerStatus::VerifyingResize))
+    }
+
+    /// Revert a pending resize of the server.
+    pub fn revert_resize<'server>(&'server mut self)
+            -> Result<ServerStatusWaiter<'server>> {
+        self.session.server_simple_action(&self.inner.id, "revertResize")?;
+        Ok(ServerStatusWaiter::new(self, protocol::ServerStatus::Active))
     }
 
     /// Start the server, optionally wait for it to be active.
     pub fn start<'server>(&'server mut self)
             -> Result<ServerStatusWaiter<'server>> {
         self.session.server_simple_action(&self.inner.id, "os-start")?;
-        Ok(ServerStatusWaiter {
-            server: self,
-            target: protocol::ServerStatus::Active
-        })
+        Ok(ServerStatusWaiter::new(self, protocol::ServerStatus::Active))
     }
 
     /// Stop the server, optionally wait for it to be powered off.
     pub fn stop<'server>(&'server mut self)
             -> Result<ServerStatusWaiter<'server>> {
         self.session.server_simple_action(&self.inner.id, "os-stop")?;
-        Ok(ServerStatusWaiter {
-            server: self,
-            target: protocol::ServerStatus::ShutOff
-        })
+        Ok(ServerStatusWaiter::new(self, protocol::ServerStatus::ShutOff))
+    }
+
+    /// Unlock the server.
+    ///
+    /// Unlocking does not change the server status, so the resulting waiter
+    /// only refreshes the server.
+    pub fn unlock<'server>(&'server mut self)
+            -> Result<ServerStatusWaiter<'server>> {
+        self.session.server_simple_action(&self.inner.id, "unlock")?;
+        let target = self.status();
+        Ok(ServerStatusWaiter::new(self, target))
+    }
+
+    /// Bring the server out of the rescue mode.
+    pub fn unrescue<'server>(&'server mut self)
+            -> Result<ServerStatusWaiter<'server>> {
+        self.session.server_simple_action(&self.inner.id, "unrescue")?;
+        Ok(ServerStatusWaiter::new(self, protocol::ServerStatus::Active))
+    }
+}
+
+/// Guess the allowed actions, see `Server::allowed_actions`.
+fn allowed_actions(status: protocol::ServerStatus, task_state: Option<&str>,
+                   locked: Option<bool>) -> Vec<ServerAction> {
+    if locked == Some(true) {
+        return vec![ServerAction::Unlock];
+    }
+
+    let mut result = match task_state {
+        // Only deletion is possible while a task is running.
+        Some(task) if ! task.is_empty() => vec![ServerAction::Delete],
+        _ => match status {
+            protocol::ServerStatus::Active => vec![
+                ServerAction::CreateImage, ServerAction::Delete,
+                ServerAction::Migrate, ServerAction::Reboot,
+                ServerAction::Rebuild, ServerAction::Rescue,
+                ServerAction::Resize, ServerAction::Stop
+            ],
+            protocol::ServerStatus::ShutOff => vec![
+                ServerAction::CreateImage, ServerAction::Delete,
+                ServerAction::Migrate, ServerAction::Reboot,
+                ServerAction::Rebuild, ServerAction::Rescue,
+                ServerAction::Resize, ServerAction::Start
+            ],
+            protocol::ServerStatus::Paused |
+            protocol::ServerStatus::Suspended => vec![
+                ServerAction::CreateImage, ServerAction::Delete,
+                ServerAction::Reboot
+            ],
+            protocol::ServerStatus::Error => vec![
+                ServerAction::Delete, ServerAction::Reboot,
+                ServerAction::Rebuild
+            ],
+            protocol::ServerStatus::Rescuing => vec![
+                ServerAction::Delete, ServerAction::Unrescue
+            ],
+            protocol::ServerStatus::VerifyingResize => vec![
+                ServerAction::ConfirmResize, ServerAction::Delete,
+                ServerAction::RevertResize
+            ],
+            protocol::ServerStatus::Deleted |
+            protocol::ServerStatus::SoftDeleted => Vec::new(),
+            _ => vec![ServerAction::Delete]
+        }
+    };
+
+    if status != protocol::ServerStatus::Deleted {
+        result.push(ServerAction::Lock);
+        // The locked flag is unknown with older API versions.
+        if locked.is_none() {
+            result.push(ServerAction::Unlock);
+        }
+    }
+
+    result
+}
+
+impl<'server> ServerRebuild<'server> {
+    /// Set how to partition the root disk of the rebuilt server.
+    pub fn with_disk_config(mut self, value: protocol::DiskConfig) -> Self {
+        self.disk_config = Some(value);
+        self
+    }
+
+    /// Set how to treat the server metadata.
+    pub fn with_metadata_policy(mut self, policy: MetadataPolicy) -> Self {
+        self.metadata = policy;
+        self
+    }
+
+    /// Set a new name for the server.
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Add a personality file to inject into the rebuilt server.
+    ///
+    /// If any files are added, they replace all files injected previously.
+    pub fn with_personality_file<S, D>(mut self, path: S, contents: D) -> Self
+            where S: Into<String>, D: Into<Vec<u8>> {
+        self.personality.get_or_insert_with(Vec::new)
+            .push((path.into(), contents.into()));
+        self
+    }
+
+    /// Remove all personality files injected previously.
+    pub fn without_personality(mut self) -> Self {
+        self.personality = Some(Vec::new());
+        self
+    }
+
+    /// Whether to preserve the ephemeral partition.
+    ///
+    /// Only supported for flavors with an ephemeral disk and only by some
+    /// drivers (most notably bare metal).
+    pub fn with_preserve_ephemeral(mut self, value: bool) -> Self {
+        self.preserve_ephemeral = Some(value);
+        self
+    }
+
+    /// Start the rebuild.
+    ///
+    /// Fails with `InvalidInput` if preserving the ephemeral partition is
+    /// requested for a server without one.
+    pub fn run(self) -> Result<ServerStatusWaiter<'server>> {
+        if self.preserve_ephemeral == Some(true) &&
+                self.server.flavor.ephemeral_size == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Server {} has no ephemeral partition to preserve",
+                        self.server.id())));
+        }
+
+        let metadata = match self.metadata {
+            MetadataPolicy::Preserve => None,
+            MetadataPolicy::Replace(items) => Some(items.into_iter().collect()),
+            MetadataPolicy::Merge(items) => {
+                let mut result: BTreeMap<_, _> = self.server.inner.metadata
+                    .clone().into_iter().collect();
+                result.extend(items);
+                Some(result)
+            }
+        };
+        let personality = self.personality.map(|files| {
+            files.into_iter().map(|(path, contents)| {
+                protocol::ServerPersonality {
+                    path: path,
+                    contents: base64::encode(&contents)
+                }
+            }).collect()
+        });
+        let request = protocol::ServerRebuild {
+            disk_config: self.disk_config,
+            imageRef: self.image.into_verified(&self.server.session)?,
+            metadata: metadata,
+            name: self.name,
+            personality: personality,
+            preserve_ephemeral: self.preserve_ephemeral,
+        };
+        self.server.session.server_action_with_args(&self.server.inner.id,
+                                                    "rebuild", request)?;
+        Ok(ServerStatusWaiter::new(self.server,
+                                   protocol::ServerStatus::Active))
+    }
+}
+
+impl<'server> ServerStatusWaiter<'server> {
+    fn new(server: &'server mut Server, target: protocol::ServerStatus)
+            -> ServerStatusWaiter<'server> {
+        ServerStatusWaiter {
+            server: server,
+            target: target,
+            // TODO(dtantsur): vary depending on target?
+            wait_timeout: Duration::new(600, 0),
+            delay: Duration::new(1, 0),
+            started: Instant::now(),
+            progress: StatusTracker::new(),
+        }
+    }
+
+    /// Call the given function every time the server status changes.
+    ///
+    /// The first observed status is also reported. Collect the transitions
+    /// in the callback to inspect them after waiting.
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+            where F: FnMut(&StatusTransition<protocol::ServerStatus>)
+                     + 'static {
+        self.progress.set_callback(callback);
+        self
+    }
+
+    /// Wait at most the given time for the server to reach the status.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_timeout = timeout;
+        self
+    }
+
+    /// Check the server status with the given interval.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.delay = interval;
+        self
     }
 }
 
 impl<'server> Waiter<(), Error> for ServerStatusWaiter<'server> {
     fn default_wait_timeout(&self) -> Option<Duration> {
-        // TODO(dtantsur): vary depending on target?
-        Some(Duration::new(600, 0))
+        Some(self.wait_timeout)
     }
 
     fn default_delay(&self) -> Duration {
-        Duration::new(1, 0)
+        self.delay
     }
 
     fn timeout_error(&self) -> Error {
+        telemetry::record_waiter("server_status", self.started, false);
         Error::new(ErrorKind::OperationTimedOut,
                    format!("Timeout waiting for server {} to reach state {}",
                            self.server.id(), self.target))
@@ -312,12 +1096,15 @@ impl<'server> Waiter<(), Error> for ServerStatusWaiter<'server> {
 
     fn poll(&mut self) -> Result<Option<()>> {
         self.server.refresh()?;
+        self.progress.observe(self.server.status(), self.started);
         if self.server.status() == self.target {
             debug!("Server {} reached state {}", self.server.id(), self.target);
+            telemetry::record_waiter("server_status", self.started, true);
             Ok(Some(()))
         } else if self.server.status() == protocol::ServerStatus::Error {
             debug!("Failed to move server {} to {} - status is ERROR",
                    self.server.id(), self.target);
+            telemetry::record_waiter("server_status", self.started, false);
             Err(Error::new(ErrorKind::OperationFailed,
                            format!("Server {} got into ERROR state",
                                    self.server.id())))
@@ -329,6 +1116,110 @@ impl<'server> Waiter<(), Error> for ServerStatusWaiter<'server> {
     }
 }
 
+#[cfg(feature = "network")]
+impl InterfaceWaiter {
+    fn new(port: Port, server_id: String, attach: bool) -> InterfaceWaiter {
+        InterfaceWaiter {
+            port: port,
+            server_id: server_id,
+            attach: attach,
+            wait_timeout: Duration::new(120, 0),
+            delay: Duration::new(1, 0),
+            started: Instant::now()
+        }
+    }
+
+    /// Wait at most the given time for the port.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_timeout = timeout;
+        self
+    }
+
+    /// Check the port with the given interval.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.delay = interval;
+        self
+    }
+
+    fn is_attached(&self) -> bool {
+        match *self.port.device_id() {
+            Some(ref device) => device == &self.server_id,
+            None => false
+        }
+    }
+}
+
+#[cfg(feature = "network")]
+impl Waiter<Port, Error> for InterfaceWaiter {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        Some(self.wait_timeout)
+    }
+
+    fn default_delay(&self) -> Duration {
+        self.delay
+    }
+
+    fn timeout_error(&self) -> Error {
+        telemetry::record_waiter("server_interface", self.started, false);
+        let action = if self.attach { "attached to" } else { "detached from" };
+        Error::new(ErrorKind::OperationTimedOut,
+                   format!("Timeout waiting for port {} to be {} server {}",
+                           self.port.id(), action, self.server_id))
+    }
+
+    fn poll(&mut self) -> Result<Option<Port>> {
+        if let Err(err) = self.port.refresh() {
+            return if ! self.attach &&
+                    err.kind() == ErrorKind::ResourceNotFound {
+                debug!("Port {} was detached and deleted", self.port.id());
+                telemetry::record_waiter("server_interface", self.started,
+                                         true);
+                Ok(Some(self.port.clone()))
+            } else {
+                Err(err)
+            };
+        }
+
+        if self.attach {
+            if self.port.status() == NetworkStatus::Error {
+                telemetry::record_waiter("server_interface", self.started,
+                                         false);
+                Err(Error::new(ErrorKind::OperationFailed,
+                               format!("Port {} got into ERROR state",
+                                       self.port.id())))
+            } else if self.is_attached() &&
+                    self.port.status() == NetworkStatus::Active {
+                debug!("Port {} is attached to server {}", self.port.id(),
+                       self.server_id);
+                telemetry::record_waiter("server_interface", self.started,
+                                         true);
+                Ok(Some(self.port.clone()))
+            } else {
+                trace!("Still waiting for port {} to become active on \
+                        server {}, current status is {}", self.port.id(),
+                       self.server_id, self.port.status());
+                Ok(None)
+            }
+        } else if self.is_attached() {
+            trace!("Port {} is still attached to server {}", self.port.id(),
+                   self.server_id);
+            Ok(None)
+        } else {
+            debug!("Port {} was detached from server {}", self.port.id(),
+                   self.server_id);
+            telemetry::record_waiter("server_interface", self.started, true);
+            Ok(Some(self.port.clone()))
+        }
+    }
+}
+
+#[cfg(feature = "network")]
+impl WaiterCurrentState<Port> for InterfaceWaiter {
+    fn waiter_current_state(&self) -> &Port {
+        &self.port
+    }
+}
+
 impl<'server> WaiterCurrentState<Server> for ServerStatusWaiter<'server> {
     fn waiter_current_state(&self) -> &Server {
         &self.server
@@ -358,12 +1249,42 @@ impl ServerSummary {
     }
 }
 
+impl ServerLookupOptions {
+    /// Create options with exact matching that fail on ambiguous names.
+    pub fn new() -> ServerLookupOptions {
+        ServerLookupOptions {
+            name_match: ServerNameMatch::Exact,
+            ambiguity: ServerNameAmbiguity::Fail,
+        }
+    }
+
+    /// Set how server names are matched.
+    pub fn with_name_match(mut self, value: ServerNameMatch) -> Self {
+        self.name_match = value;
+        self
+    }
+
+    /// Set what to do when several servers match.
+    pub fn with_ambiguity(mut self, value: ServerNameAmbiguity) -> Self {
+        self.ambiguity = value;
+        self
+    }
+}
+
+impl Default for ServerLookupOptions {
+    fn default() -> ServerLookupOptions {
+        ServerLookupOptions::new()
+    }
+}
+
 impl ServerQuery {
     pub(crate) fn new(session: Rc<Session>) -> ServerQuery {
         ServerQuery {
             session: session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            max_items: None,
         }
     }
 
@@ -385,6 +1306,20 @@ impl ServerQuery {
         self
     }
 
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
     /// Add sorting to the request.
     pub fn sort_by(mut self, sort: Sort<protocol::ServerSortKey>) -> Self {
         let (field, direction) = sort.into();
@@ -405,6 +1340,12 @@ impl ServerQuery {
         self
     }
 
+    /// Include servers from all projects (admin-only).
+    pub fn with_all_tenants(mut self) -> Self {
+        self.query.push("all_tenants", true);
+        self
+    }
+
     /// Filter by availability zone.
     pub fn with_availability_zone<T: Into<String>>(mut self, value: T) -> Self {
         self.query.push_str("availability_zone", value);
@@ -417,6 +1358,12 @@ impl ServerQuery {
         self
     }
 
+    /// Filter by the compute host running the server (admin-only).
+    pub fn with_host<T: Into<String>>(mut self, value: T) -> Self {
+        self.query.push_str("host", value);
+        self
+    }
+
     /// Filter by host name.
     pub fn with_hostname<T: Into<String>>(mut self, value: T) -> Self {
         self.query.push_str("hostname", value);
@@ -441,12 +1388,31 @@ impl ServerQuery {
         self
     }
 
+    /// Filter by an exact IPv4 or IPv6 address (fixed or floating).
+    ///
+    /// Unlike `with_ip_v4` and `with_ip_v6`, the address is not treated as
+    /// a regular expression.
+    pub fn with_exact_ip<T: Into<IpAddr>>(mut self, value: T) -> Self {
+        match value.into() {
+            IpAddr::V4(ip) => self.query.push_str(
+                "ip", format!("^{}$", ip.to_string().replace(".", "\\."))),
+            IpAddr::V6(ip) => self.query.push_str("ip6", format!("^{}$", ip)),
+        }
+        self
+    }
+
     /// Filter by server name (a database regular expression).
     pub fn with_name<T: Into<String>>(mut self, value: T) -> Self {
         self.query.push_str("name", value);
         self
     }
 
+    /// Filter by server power state.
+    pub fn with_power_state(mut self, value: protocol::ServerPowerState) -> Self {
+        self.query.push("power_state", u8::from(value));
+        self
+    }
+
     /// Filter by project ID (also commonly known as tenant ID).
     pub fn with_project<T: Into<ProjectRef>>(mut self, value: T) -> Self {
         self.query.push_str("project_id", value.into());
@@ -477,6 +1443,7 @@ impl ServerQuery {
     pub fn into_iter(self) -> ResourceIterator<ServerSummary> {
         debug!("Fetching servers with {:?}", self.query);
         ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
     }
 
     /// Convert this query into an iterator executing the request.
@@ -491,6 +1458,14 @@ impl ServerQuery {
     pub fn into_iter_detailed(self) -> ResourceIterator<Server> {
         debug!("Fetching server details with {:?}", self.query);
         ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Convert this query into an iterator fetching `page_size` items per
+    /// request.
+    pub fn into_iter_paginated(self, page_size: usize)
+            -> ResourceIterator<ServerSummary> {
+        self.with_page_size(page_size).into_iter()
     }
 
     /// Execute this request and return all results.
@@ -500,6 +1475,15 @@ impl ServerQuery {
         self.into_iter().collect()
     }
 
+    /// Execute this request and return all results as full `Server` objects.
+    ///
+    /// Servers are fetched from `/servers/detail`, so no additional request
+    /// is made per server. A convenience shortcut for
+    /// `self.into_iter_detailed().collect()`.
+    pub fn detailed(self) -> Result<Vec<Server>> {
+        self.into_iter_detailed().collect()
+    }
+
     /// Return one and exactly one result.
     ///
     /// Fails with `ResourceNotFound` if the query produces no results and
@@ -516,6 +1500,126 @@ impl ServerQuery {
     }
 }
 
+// Nova rejects user data that is longer than this after base64 encoding.
+const MAX_USER_DATA_SIZE: usize = 65535;
+
+impl BlockDevice {
+    /// A bootable volume created from the given image.
+    pub fn from_image<I>(image: I, size: u32) -> BlockDevice
+            where I: Into<ImageRef> {
+        BlockDevice {
+            boot_index: Some(0),
+            delete_on_termination: true,
+            size: Some(size),
+            source: BlockDeviceSource::Image(image.into()),
+            volume_type: None,
+        }
+    }
+
+    /// A bootable volume created from the given image, sized to fit it.
+    ///
+    /// The size is the larger of the minimum disk required by the image and
+    /// its virtual size (or its size if the virtual size is unknown) rounded
+    /// up to whole GiB.
+    ///
+    /// This call does not create the volume and does not wait for it to
+    /// become available: the Compute service creates it when the server is
+    /// created and waits for it before booting.
+    ///
+    /// Fails with `InvalidInput` if the size does not fit into `u32` GiB.
+    #[cfg(feature = "image")]
+    pub fn from_image_inferred_size(image: &Image) -> Result<BlockDevice> {
+        let size = inferred_volume_size(image.minimum_required_disk(),
+                                        image.virtual_size().or(image.size()))?;
+        Ok(BlockDevice::from_image(image.id().clone(), size))
+    }
+
+    /// A bootable existing volume.
+    pub fn from_volume<S>(volume_id: S) -> BlockDevice where S: Into<String> {
+        BlockDevice {
+            boot_index: Some(0),
+            delete_on_termination: false,
+            size: None,
+            source: BlockDeviceSource::Volume(volume_id.into()),
+            volume_type: None,
+        }
+    }
+
+    /// Use the given boot index (`None` for non-bootable devices).
+    pub fn with_boot_index(mut self, boot_index: Option<u16>) -> BlockDevice {
+        self.boot_index = boot_index;
+        self
+    }
+
+    /// Whether to delete the volume when the server is deleted.
+    pub fn with_delete_on_termination(mut self, value: bool) -> BlockDevice {
+        self.delete_on_termination = value;
+        self
+    }
+
+    /// Use the given volume type for the volume to create.
+    ///
+    /// Only applies to volumes created from images and snapshots. Requires
+    /// compute API version 2.67 or newer.
+    pub fn with_volume_type<S: Into<String>>(mut self, value: S) -> BlockDevice {
+        self.volume_type = Some(value.into());
+        self
+    }
+
+    fn to_protocol(&self, session: &Session)
+            -> Result<protocol::BlockDeviceMapping> {
+        let (source_type, uuid) = match self.source {
+            BlockDeviceSource::Image(ref image) =>
+                ("image", image.clone().into_verified(session)?),
+            BlockDeviceSource::Volume(ref id) => ("volume", id.clone()),
+            BlockDeviceSource::Snapshot(ref id) => ("snapshot", id.clone()),
+        };
+
+        Ok(protocol::BlockDeviceMapping {
+            boot_index: self.boot_index,
+            delete_on_termination: self.delete_on_termination,
+            destination_type: String::from("volume"),
+            source_type: String::from(source_type),
+            uuid: uuid,
+            volume_size: self.size,
+            volume_type: self.volume_type.clone(),
+        })
+    }
+}
+
+/// Escape characters that have a special meaning in regular expressions.
+fn regex_escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Volume size in GiB required for an image.
+#[cfg(feature = "image")]
+fn inferred_volume_size(min_disk: u32, image_size: Option<u64>)
+        -> Result<u32> {
+    const GIB: u64 = 1024 * 1024 * 1024;
+    let from_size = image_size.map(|size| size / GIB + (size % GIB != 0) as u64)
+        .unwrap_or(0);
+    let size = ::std::cmp::max(::std::cmp::max(u64::from(min_disk), from_size), 1);
+    if size > u64::from(::std::u32::MAX) {
+        return Err(Error::new(ErrorKind::InvalidInput,
+                              format!("Volume size of {} GiB is too large",
+                                      size)));
+    }
+    Ok(size as u32)
+}
+
+#[inline]
+fn encoded_size(size: usize) -> usize {
+    (size + 2) / 3 * 4
+}
+
 fn convert_networks(session: &Session, networks: Vec<ServerNIC>)
         -> Result<Vec<protocol::ServerNetwork>> {
     let mut result = Vec::with_capacity(networks.len());
@@ -540,38 +1644,245 @@ impl NewServer {
             -> NewServer {
         NewServer {
             session: session,
+            availability_zone: None,
+            block_devices: Vec::new(),
+            config_drive: None,
+            default_network: None,
+            description: None,
+            default_security_groups: Vec::new(),
+            disk_config: None,
             flavor: flavor,
             image: None,
             keypair: None,
+            max_nics: None,
             metadata: HashMap::new(),
             name: name,
             networks: Vec::new(),
+            scheduler_hints: HashMap::new(),
+            security_groups: Vec::new(),
+            trusted_image_certificates: Vec::new(),
+            user_data: None,
         }
     }
 
-    /// Request creation of the server.
-    pub fn create(self) -> Result<ServerCreationWaiter> {
-        let request = protocol::ServerCreate {
-            flavorRef: self.flavor.into_verified(&self.session)?,
+    /// Apply cloud-wide defaults to this request.
+    pub(crate) fn apply_defaults(&mut self, defaults: &Defaults) {
+        self.availability_zone = defaults.availability_zone.clone();
+        self.default_network = defaults.network.clone();
+        self.default_security_groups = defaults.security_groups.clone();
+        if let Some(ref key_name) = defaults.key_name {
+            self.keypair = Some(KeyPairRef::from(key_name.clone()));
+        }
+    }
+
+    /// Validate the request without sending it.
+    ///
+    /// Detects conflicting boot sources, too large user data (including user
+    /// data for a config drive) and, if a limit is set with `set_max_nics`,
+    /// too many virtual NICs. The Compute API has no fixed NIC limit, it
+    /// depends on the hypervisor, so NICs are not checked by default.
+    ///
+    /// All found problems are reported in one `InvalidInput` error. This call
+    /// is done automatically by `create`.
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        let mut boot_indexes = HashSet::new();
+        for index in self.block_devices.iter().filter_map(|bd| bd.boot_index) {
+            if ! boot_indexes.insert(index) {
+                errors.push(format!("several block devices use boot index {}",
+                                    index));
+            }
+        }
+
+        match (self.image.is_some(), boot_indexes.contains(&0)) {
+            (true, true) => errors.push(String::from(
+                "both an image and a block device with boot index 0 are provided")),
+            (false, false) => errors.push(String::from(
+                "neither an image nor a block device with boot index 0 is provided")),
+            _ => ()
+        }
+
+        if let Some(ref user_data) = self.user_data {
+            let size = encoded_size(user_data.len());
+            if size > MAX_USER_DATA_SIZE {
+                let target = if self.config_drive == Some(true) {
+                    "user data for the config drive"
+                } else {
+                    "user data"
+                };
+                errors.push(format!("{} is {} bytes after encoding, \
+                                     the maximum is {}", target, size,
+                                    MAX_USER_DATA_SIZE));
+            }
+        }
+
+        if let Some(max_nics) = self.max_nics {
+            let nics = if self.networks.is_empty() {
+                self.default_network.iter().count()
+            } else {
+                self.networks.len()
+            };
+            if nics > max_nics {
+                errors.push(format!("{} virtual NICs requested, the maximum \
+                                     is {}", nics, max_nics));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::InvalidInput,
+                           format!("Invalid server creation request: {}",
+                                   errors.join("; "))))
+        }
+    }
+
+    fn build_request(&self) -> Result<protocol::ServerCreate> {
+        let mut block_devices = Vec::with_capacity(self.block_devices.len());
+        for item in &self.block_devices {
+            block_devices.push(item.to_protocol(&self.session)?);
+        }
+
+        let networks = if self.networks.is_empty() {
+            self.default_network.iter().cloned().map(ServerNIC::FromNetwork)
+                .collect()
+        } else {
+            self.networks.clone()
+        };
+        let security_groups = if self.security_groups.is_empty() {
+            &self.default_security_groups
+        } else {
+            &self.security_groups
+        };
+
+        Ok(protocol::ServerCreate {
+            availability_zone: self.availability_zone.clone(),
+            block_device_mapping_v2: block_devices,
+            config_drive: self.config_drive,
+            description: self.description.clone(),
+            disk_config: self.disk_config,
+            flavorRef: self.flavor.clone().into_verified(&self.session)?,
             imageRef: match self.image {
-                Some(img) => Some(img.into_verified(&self.session)?),
+                Some(ref img) => Some(img.clone().into_verified(&self.session)?),
                 None => None
             },
             key_name: match self.keypair {
-                Some(item) => Some(item.into_verified(&self.session)?),
+                Some(ref item) => Some(item.clone().into_verified(&self.session)?),
                 None => None
             },
-            metadata: self.metadata,
-            name: self.name,
-            networks: convert_networks(&self.session, self.networks)?
+            metadata: self.metadata.clone(),
+            name: self.name.clone(),
+            networks: convert_networks(&self.session, networks)?,
+            security_groups: security_groups.iter().map(|name| {
+                protocol::ServerSecurityGroup { name: name.clone() }
+            }).collect(),
+            trusted_image_certificates: self.trusted_image_certificates.clone(),
+            user_data: self.user_data.as_ref().map(|data| base64::encode(data)),
+        })
+    }
+
+    fn build_request_root(&self) -> Result<protocol::ServerCreateRoot> {
+        Ok(protocol::ServerCreateRoot {
+            server: self.build_request()?,
+            scheduler_hints: self.scheduler_hints.clone(),
+        })
+    }
+
+    /// Drop cached IDs of the flavor and images named in the error.
+    ///
+    /// Only references resolved from names are considered, and only if the
+    /// error message is Nova's "Flavor <ID> could not be found" or "Image
+    /// <ID> could not be found" for the ID they were resolved into. Networks are
+    /// not cached, so they cannot be stale. Returns `true` if any cached IDs
+    /// were dropped.
+    fn invalidate_stale_references(&self, err: &Error) -> bool {
+        let message = match err.message() {
+            Some(message) => message,
+            None => return false
+        };
+
+        let mut references = Vec::new();
+        if ! self.flavor.verified {
+            references.push(("flavor", "Flavor", self.flavor.as_ref()));
+        }
+
+        let images = self.image.iter().chain(
+            self.block_devices.iter().filter_map(|bd| match bd.source {
+                BlockDeviceSource::Image(ref image) => Some(image),
+                _ => None
+            }));
+        for image in images.filter(|image| ! image.verified) {
+            references.push(("image", "Image", image.as_ref()));
+        }
+
+        let mut result = false;
+        for (kind, title, name) in references {
+            // Match the whole sentence, short IDs like "1" appear anywhere.
+            let stale = match self.session.cached_id(kind, name) {
+                Some(ref id) => message.contains(
+                    &format!("{} {} could not be found", title, id)),
+                None => false
+            };
+            if stale {
+                result |= self.session.invalidate_id(kind, name);
+            }
+        }
+        result
+    }
+
+    /// Request creation of the server.
+    ///
+    /// If the request fails because a flavor or an image referenced by name
+    /// was not found under its cached ID, the cached ID is dropped and the
+    /// request is retried once.
+    pub fn create(self) -> Result<ServerCreationWaiter> {
+        self.validate()?;
+
+        let request = self.build_request_root()?;
+        let server_ref = match self.session.create_server(request) {
+            // Nova reports missing flavors and images with HTTP 400 or 404.
+            Err(ref err) if (err.kind() == ErrorKind::ResourceNotFound ||
+                             err.kind() == ErrorKind::InvalidInput) &&
+                    self.invalidate_stale_references(err) => {
+                debug!("Retrying creation of server {} with fresh IDs: {}",
+                       self.name, err);
+                self.session.create_server(self.build_request_root()?)?
+            },
+            other => other?
         };
 
-        let server_ref = self.session.create_server(request)?;
         Ok(ServerCreationWaiter {
-            server: Server::load(self.session, server_ref.id)?
+            server: Server::load(self.session, server_ref.id)?,
+            wait_timeout: Duration::new(1800, 0),
+            delay: Duration::new(5, 0),
+            started: Instant::now(),
+            cancellation: None,
+            progress: StatusTracker::new(),
         })
     }
 
+    /// Serialize the request body that `create` would send.
+    ///
+    /// The output is stable: fields come in a fixed order, unset fields are
+    /// omitted and metadata keys are sorted. Flavors, images, key pairs and
+    /// networks referenced by name are resolved to IDs first.
+    pub fn to_request_json(&self) -> Result<String> {
+        self.validate()?;
+        let body = self.build_request_root()?;
+        common::protocol::to_request_json(&body)
+    }
+
+    /// Name of the new server.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Add a block device to the new server.
+    pub fn add_block_device(&mut self, block_device: BlockDevice) {
+        self.block_devices.push(block_device);
+    }
+
     /// Add a virtual NIC with given fixed IP to the new server.
     ///
     /// A shorthand for `add_nic`.
@@ -598,6 +1909,47 @@ impl NewServer {
         self.add_nic(ServerNIC::WithPort(port.into()));
     }
 
+    /// Add a hint for the scheduler placing the new server.
+    ///
+    /// Which hints are supported depends on the scheduler filters enabled
+    /// in the cloud, e.g. `group` or `different_host`. Values can be strings
+    /// or lists, e.g. `vec!["<UUID>", "<UUID>"]` for `different_host`.
+    pub fn add_scheduler_hint<S, V>(&mut self, key: S, value: V)
+            where S: Into<String>, V: Into<Value> {
+        let _ = self.scheduler_hints.insert(key.into(), value.into());
+    }
+
+    /// Add a security group (name or ID) to the new server.
+    ///
+    /// Security groups provided here replace the cloud-wide defaults.
+    pub fn add_security_group<S: Into<String>>(&mut self, group: S) {
+        self.security_groups.push(group.into());
+    }
+
+    /// Set the availability zone for the new server.
+    pub fn set_availability_zone<S: Into<String>>(&mut self, value: S) {
+        self.availability_zone = Some(value.into());
+    }
+
+    /// Whether to use a config drive for the new server.
+    pub fn set_config_drive(&mut self, value: bool) {
+        self.config_drive = Some(value);
+    }
+
+    /// Set a description for the new server.
+    ///
+    /// Requires compute API version 2.19 or newer.
+    pub fn set_description<S: Into<String>>(&mut self, value: S) {
+        self.description = Some(value.into());
+    }
+
+    /// Set how to partition the root disk of the new server.
+    ///
+    /// With `Manual` the partitions of the image are kept as they are.
+    pub fn set_disk_config(&mut self, value: protocol::DiskConfig) {
+        self.disk_config = Some(value);
+    }
+
     /// Use this image as a source for the new server.
     pub fn set_image<I>(&mut self, image: I) where I: Into<ImageRef> {
         self.image = Some(image.into());
@@ -608,6 +1960,78 @@ impl NewServer {
         self.keypair = Some(keypair.into());
     }
 
+    /// Limit the number of virtual NICs checked by `validate`.
+    ///
+    /// The limit depends on the hypervisor, so there is none by default.
+    pub fn set_max_nics(&mut self, value: usize) {
+        self.max_nics = Some(value);
+    }
+
+    /// Add an ID of a certificate trusted to sign the server image.
+    ///
+    /// The Compute service verifies the image signature against the
+    /// certificates. Requires compute API version 2.63.
+    pub fn add_trusted_image_certificate<S: Into<String>>(&mut self, id: S) {
+        self.trusted_image_certificates.push(id.into());
+    }
+
+    /// Use this user data for the new server.
+    ///
+    /// The data is base64-encoded automatically.
+    pub fn set_user_data<D>(&mut self, user_data: D) where D: Into<Vec<u8>> {
+        self.user_data = Some(user_data.into());
+    }
+
+    /// Use user data from a reader for the new server.
+    ///
+    /// The reader is consumed immediately, the data is base64-encoded
+    /// automatically.
+    pub fn set_user_data_from_reader<R: Read>(&mut self, mut reader: R)
+            -> Result<()> {
+        let mut user_data = Vec::new();
+        let _ = reader.read_to_end(&mut user_data).map_err(|err| {
+            Error::new(ErrorKind::InvalidInput,
+                       format!("Cannot read user data: {}", err))
+        })?;
+        self.user_data = Some(user_data);
+        Ok(())
+    }
+
+    /// Set the availability zone for the new server.
+    pub fn with_availability_zone<S: Into<String>>(mut self, value: S)
+            -> NewServer {
+        self.set_availability_zone(value);
+        self
+    }
+
+    /// Add a block device to the new server.
+    pub fn with_block_device(mut self, block_device: BlockDevice) -> NewServer {
+        self.add_block_device(block_device);
+        self
+    }
+
+    /// Whether to use a config drive for the new server.
+    pub fn with_config_drive(mut self, value: bool) -> NewServer {
+        self.set_config_drive(value);
+        self
+    }
+
+    /// Set a description for the new server.
+    ///
+    /// Requires compute API version 2.19 or newer.
+    pub fn with_description<S: Into<String>>(mut self, value: S) -> NewServer {
+        self.set_description(value);
+        self
+    }
+
+    /// Set how to partition the root disk of the new server.
+    ///
+    /// With `Manual` the partitions of the image are kept as they are.
+    pub fn with_disk_config(mut self, value: protocol::DiskConfig) -> NewServer {
+        self.set_disk_config(value);
+        self
+    }
+
     /// Add a virtual NIC with given fixed IP to the new server.
     pub fn with_fixed_ip(mut self, fixed_ip: Ipv4Addr) -> NewServer {
         self.add_fixed_ip(fixed_ip);
@@ -628,6 +2052,12 @@ impl NewServer {
         self
     }
 
+    /// Limit the number of virtual NICs checked by `validate`.
+    pub fn with_max_nics(mut self, value: usize) -> NewServer {
+        self.set_max_nics(value);
+        self
+    }
+
     /// Add a virtual NIC from this network to the new server.
     pub fn with_network<N>(mut self, network: N) -> NewServer
             where N: Into<NetworkRef> {
@@ -642,6 +2072,22 @@ impl NewServer {
         self
     }
 
+    /// Add a hint for the scheduler placing the new server.
+    pub fn with_scheduler_hint<S, V>(mut self, key: S, value: V)
+            -> NewServer where S: Into<String>, V: Into<Value> {
+        self.add_scheduler_hint(key, value);
+        self
+    }
+
+    /// Add a security group (name or ID) to the new server.
+    ///
+    /// Security groups provided here replace the cloud-wide defaults.
+    pub fn with_security_group<S: Into<String>>(mut self, group: S)
+            -> NewServer {
+        self.add_security_group(group);
+        self
+    }
+
     /// Add an arbitrary key/value metadata pair.
     pub fn with_metadata<S1, S2>(mut self, key: S1, value: S2) -> NewServer
             where S1: Into<String>,
@@ -649,32 +2095,96 @@ impl NewServer {
         let _ = self.metadata.insert(key.into(), value.into());
         self
     }
+
+    /// Add an ID of a certificate trusted to sign the server image.
+    ///
+    /// Requires compute API version 2.63.
+    pub fn with_trusted_image_certificate<S: Into<String>>(mut self, id: S)
+            -> NewServer {
+        self.add_trusted_image_certificate(id);
+        self
+    }
+
+    /// Use this user data for the new server.
+    pub fn with_user_data<D>(mut self, user_data: D) -> NewServer
+            where D: Into<Vec<u8>> {
+        self.set_user_data(user_data);
+        self
+    }
+
+    /// Use user data from a reader for the new server.
+    pub fn with_user_data_from_reader<R: Read>(mut self, reader: R)
+            -> Result<NewServer> {
+        self.set_user_data_from_reader(reader)?;
+        Ok(self)
+    }
+}
+
+impl ServerCreationWaiter {
+    /// Wait at most the given time for the server to become active.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_timeout = timeout;
+        self
+    }
+
+    /// Check the server status with the given interval.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.delay = interval;
+        self
+    }
+
+    /// Stop waiting with a `Cancelled` error once the token is cancelled.
+    ///
+    /// The server is not deleted, use `waiter_current_state` to get it.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Call the given function every time the server status changes.
+    ///
+    /// The first observed status is also reported. Collect the transitions
+    /// in the callback to inspect them after waiting.
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+            where F: FnMut(&StatusTransition<protocol::ServerStatus>)
+                     + 'static {
+        self.progress.set_callback(callback);
+        self
+    }
 }
 
 impl Waiter<Server, Error> for ServerCreationWaiter {
     fn default_wait_timeout(&self) -> Option<Duration> {
-        Some(Duration::new(1800, 0))
+        Some(self.wait_timeout)
     }
 
     fn default_delay(&self) -> Duration {
-        Duration::new(5, 0)
+        self.delay
     }
 
     fn timeout_error(&self) -> Error {
+        telemetry::record_waiter("server_creation", self.started, false);
         Error::new(ErrorKind::OperationTimedOut,
                    format!("Timeout waiting for server {} to become ACTIVE",
                            self.server.id()))
     }
 
     fn poll(&mut self) -> Result<Option<Server>> {
+        if let Some(ref token) = self.cancellation {
+            token.check()?;
+        }
+
         self.server.refresh()?;
+        self.progress.observe(self.server.status(), self.started);
         if self.server.status() == protocol::ServerStatus::Active {
             debug!("Server {} successfully created", self.server.id());
+            telemetry::record_waiter("server_creation", self.started, true);
             // TODO(dtantsur): get rid of clone?
             Ok(Some(self.server.clone()))
         } else if self.server.status() == protocol::ServerStatus::Error {
             debug!("Failed create server {} - status is ERROR",
                    self.server.id());
+            telemetry::record_waiter("server_creation", self.started, false);
             Err(Error::new(ErrorKind::OperationFailed,
                            format!("Server {} got into ERROR state",
                                    self.server.id())))
@@ -721,9 +2231,16 @@ impl ListResources for Server {
 
     fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
             -> Result<Vec<Server>> {
+        // Servers usually share a handful of flavors, fetch each one once.
+        let mut flavors: HashMap<String, protocol::Flavor> = HashMap::new();
         let mut result = Vec::new();
         for srv in session.list_servers_detail(&query)?.into_iter() {
-            result.push(Server::new(session.clone(), srv)?);
+            if ! flavors.contains_key(&srv.flavor.id) {
+                let flavor = session.get_flavor(&srv.flavor.id)?;
+                let _ = flavors.insert(srv.flavor.id.clone(), flavor);
+            }
+            let flavor = flavors[&srv.flavor.id].clone();
+            result.push(Server::with_flavor(session.clone(), srv, flavor));
         }
         Ok(result)
     }
@@ -740,3 +2257,332 @@ impl IntoFallibleIterator for ServerQuery {
         self.into_iter()
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use eui48::MacAddress;
+    use serde_json;
+
+    use super::super::super::{Defaults, ErrorKind};
+    use super::super::super::common::{FlavorRef, ImageRef};
+    use super::super::super::utils;
+    use super::super::super::utils::test::FakeServer;
+    use super::super::protocol::{AddressType, ServerAddress, ServerPowerState,
+                                 ServerStatus};
+    use super::{allowed_actions, regex_escape, BlockDevice, NewServer, ServerAction,
+                ServerQuery};
+
+    fn new_server() -> NewServer {
+        let session = utils::test::new_session(utils::test::URL);
+        NewServer::new(Rc::new(session), String::from("test"),
+                       "m1.small".into())
+    }
+
+    const FLAVOR: &'static str = r#"{"flavor": {"disk": 1, "id": "new",
+        "name": "small", "ram": 512, "rxtx_factor": 1.0, "swap": "",
+        "vcpus": 1}}"#;
+
+    /// Start a server resolving flavor "small" to "new".
+    fn flavor_server() -> FakeServer {
+        let server = FakeServer::with_version("v2.1", "2.1");
+        server.respond("GET", "/flavors", 200,
+                       r#"{"flavors": [{"id": "new", "name": "small"}]}"#);
+        server.respond("GET", "/flavors/new", 200, FLAVOR);
+        server
+    }
+
+    /// Bodies of the server creation requests.
+    fn creation_requests(server: &FakeServer) -> Vec<String> {
+        server.requests_with_bodies().into_iter()
+            .filter(|item| item.0 == "POST /servers")
+            .map(|item| item.1).collect()
+    }
+
+    #[test]
+    fn test_create_retries_stale_flavor_once() {
+        let server = flavor_server();
+        server.respond("POST", "/servers", 400,
+                       r#"{"badRequest": {"code": 400,
+                           "message": "Flavor old could not be found."}}"#);
+        server.respond("POST", "/servers", 404,
+                       r#"{"itemNotFound": {"code": 404,
+                           "message": "Flavor new could not be found."}}"#);
+        let session = Rc::new(server.session());
+        let _ = session.resolve_id("flavor", "small",
+                                   |_| Ok(String::from("old"))).unwrap();
+
+        let err = NewServer::new(session.clone(), String::from("test"),
+                                 "small".into())
+            .with_image(ImageRef::new_verified("abcd".into()))
+            .create().err().unwrap();
+        // The failure of the retry is returned as it is.
+        assert_eq!(err.kind(), ErrorKind::ResourceNotFound);
+
+        let requests = creation_requests(&server);
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains(r#""flavorRef":"old""#));
+        assert!(requests[1].contains(r#""flavorRef":"new""#));
+        assert_eq!(session.cached_id("flavor", "small"),
+                   Some(String::from("new")));
+    }
+
+    #[test]
+    fn test_create_ignores_unrelated_errors() {
+        let server = flavor_server();
+        server.respond("POST", "/servers", 400,
+                       r#"{"badRequest": {"code": 400,
+                           "message": "Invalid key_name provided: 1."}}"#);
+        let session = Rc::new(server.session());
+        let _ = session.resolve_id("flavor", "small",
+                                   |_| Ok(String::from("1"))).unwrap();
+
+        let err = NewServer::new(session.clone(), String::from("test"),
+                                 "small".into())
+            .with_image(ImageRef::new_verified("abcd".into()))
+            .create().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(creation_requests(&server).len(), 1);
+        assert_eq!(session.cached_id("flavor", "small"), Some(String::from("1")));
+    }
+
+    #[test]
+    fn test_regex_escape() {
+        assert_eq!(regex_escape("web-1"), "web-1");
+        assert_eq!(regex_escape("web.1 (old)"), "web\\.1 \\(old\\)");
+        assert_eq!(regex_escape("a+b*c?^$|[]{}\\"),
+                   "a\\+b\\*c\\?\\^\\$\\|\\[\\]\\{\\}\\\\");
+    }
+
+    #[test]
+    fn test_validate_image() {
+        new_server().with_image("cirros").validate().unwrap();
+    }
+
+    #[test]
+    fn test_validate_boot_volume() {
+        new_server().with_block_device(BlockDevice::from_volume("vol"))
+            .validate().unwrap();
+    }
+
+    #[test]
+    fn test_validate_no_boot_source() {
+        let err = new_server().validate().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_validate_aggregated() {
+        let mut server = new_server().with_image("cirros")
+            .with_block_device(BlockDevice::from_volume("vol1"))
+            .with_block_device(BlockDevice::from_volume("vol2"))
+            .with_config_drive(true)
+            .with_user_data(vec![0u8; 65536])
+            .with_max_nics(2);
+        for _ in 0..3 {
+            server.add_network("private");
+        }
+
+        let err = server.validate().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let msg = err.to_string();
+        assert!(msg.contains("boot index 0 are provided"));
+        assert!(msg.contains("several block devices use boot index 0"));
+        assert!(msg.contains("user data for the config drive is 87384 bytes"));
+        assert!(msg.contains("3 virtual NICs requested, the maximum is 2"));
+        assert_eq!(msg.matches("; ").count(), 3);
+    }
+
+    #[test]
+    fn test_validate_nics_unlimited_by_default() {
+        let mut server = new_server().with_image("cirros");
+        for _ in 0..32 {
+            server.add_network("private");
+        }
+        server.validate().unwrap();
+        let err = server.with_max_nics(16).validate().err().unwrap();
+        assert!(err.to_string().contains("32 virtual NICs requested"));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_inferred_volume_size() {
+        use super::inferred_volume_size;
+
+        assert_eq!(inferred_volume_size(0, None).unwrap(), 1);
+        assert_eq!(inferred_volume_size(10, None).unwrap(), 10);
+        assert_eq!(inferred_volume_size(1, Some(12_716_032)).unwrap(), 1);
+        assert_eq!(inferred_volume_size(1, Some(2 * 1024 * 1024 * 1024 + 1))
+                   .unwrap(), 3);
+        assert_eq!(inferred_volume_size(20, Some(1024 * 1024 * 1024)).unwrap(),
+                   20);
+        assert_eq!(inferred_volume_size(1, Some(::std::u64::MAX)).err().unwrap()
+                   .kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_user_data_and_config_drive() {
+        let script = "#cloud-config\npackages: [nginx]\n";
+        let session = utils::test::new_session(utils::test::URL);
+        let server = NewServer::new(Rc::new(session), String::from("test"),
+                                    FlavorRef::new_verified("1".into()))
+            .with_image(ImageRef::new_verified("abcd".into()))
+            .with_user_data(script)
+            .with_config_drive(true);
+        assert_eq!(server.user_data, Some(script.as_bytes().to_vec()));
+        let request = server.build_request().unwrap();
+        assert_eq!(request.config_drive, Some(true));
+        assert_eq!(request.user_data,
+                   Some(String::from("I2Nsb3VkLWNvbmZpZwpwYWNrYWdlczogW25naW54XQo=")));
+    }
+
+    #[test]
+    fn test_user_data_from_reader() {
+        let script: &[u8] = b"#!/bin/sh\necho hello\n";
+        let server = new_server().with_image("cirros")
+            .with_user_data_from_reader(script).unwrap();
+        assert_eq!(server.user_data, Some(script.to_vec()));
+        server.validate().unwrap();
+    }
+
+    #[test]
+    fn test_defaults_overridden() {
+        let mut server = new_server();
+        server.apply_defaults(&Defaults {
+            availability_zone: Some(String::from("az1")),
+            key_name: Some(String::from("default")),
+            network: Some("private".into()),
+            security_groups: vec![String::from("default")],
+        });
+        let server = server.with_availability_zone("az2");
+        assert_eq!(server.availability_zone, Some(String::from("az2")));
+        assert!(server.keypair.is_some());
+        assert_eq!(server.default_security_groups, vec!["default"]);
+    }
+
+    #[test]
+    fn test_to_request_json() {
+        let session = utils::test::new_session(utils::test::URL);
+        let server = NewServer::new(Rc::new(session), String::from("test"),
+                                    FlavorRef::new_verified("1".into()))
+            .with_image(ImageRef::new_verified("abcd".into()))
+            .with_metadata("zone", "b")
+            .with_metadata("app", "web")
+            .with_metadata("owner", "me");
+        let expected = r#"{
+  "server": {
+    "flavorRef": "1",
+    "imageRef": "abcd",
+    "metadata": {
+      "app": "web",
+      "owner": "me",
+      "zone": "b"
+    },
+    "name": "test",
+    "networks": []
+  }
+}"#;
+        assert_eq!(server.to_request_json().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_to_request_json_placement() {
+        let session = utils::test::new_session(utils::test::URL);
+        let server = NewServer::new(Rc::new(session), String::from("test"),
+                                    FlavorRef::new_verified("1".into()))
+            .with_availability_zone("az1")
+            .with_block_device(
+                BlockDevice::from_image(ImageRef::new_verified("abcd".into()), 10)
+                    .with_volume_type("ssd"))
+            .with_scheduler_hint("group", "g1")
+            .with_scheduler_hint("different_host", vec!["s1", "s2"]);
+        let expected = r#"{
+  "server": {
+    "availability_zone": "az1",
+    "block_device_mapping_v2": [
+      {
+        "boot_index": 0,
+        "delete_on_termination": true,
+        "destination_type": "volume",
+        "source_type": "image",
+        "uuid": "abcd",
+        "volume_size": 10,
+        "volume_type": "ssd"
+      }
+    ],
+    "flavorRef": "1",
+    "name": "test",
+    "networks": []
+  },
+  "os:scheduler_hints": {
+    "different_host": [
+      "s1",
+      "s2"
+    ],
+    "group": "g1"
+  }
+}"#;
+        assert_eq!(server.to_request_json().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let session = utils::test::new_session(utils::test::URL);
+        let query = ServerQuery::new(Rc::new(session))
+            .with_all_tenants()
+            .with_flavor(FlavorRef::new_verified("1".into()))
+            .with_image(ImageRef::new_verified("abcd".into()))
+            .with_availability_zone("nova")
+            .with_power_state(ServerPowerState::Shutdown)
+            .with_host("compute-1");
+        assert_eq!(query.query.to_query_string(),
+                   "all_tenants=true&flavor=1&image=abcd&\
+                    availability_zone=nova&power_state=4&host=compute-1");
+    }
+
+    #[test]
+    fn test_allowed_actions() {
+        let actions = allowed_actions(ServerStatus::Active, None, Some(false));
+        assert!(actions.contains(&ServerAction::Stop));
+        assert!(actions.contains(&ServerAction::Lock));
+        assert!(! actions.contains(&ServerAction::Start));
+        assert!(! actions.contains(&ServerAction::Unlock));
+
+        let actions = allowed_actions(ServerStatus::ShutOff, None, None);
+        assert!(actions.contains(&ServerAction::Start));
+        assert!(actions.contains(&ServerAction::Unlock));
+        assert!(! actions.contains(&ServerAction::Stop));
+
+        assert_eq!(allowed_actions(ServerStatus::Active, None, Some(true)),
+                   vec![ServerAction::Unlock]);
+        assert_eq!(allowed_actions(ServerStatus::Active, Some("rebooting"),
+                                   Some(false)),
+                   vec![ServerAction::Delete, ServerAction::Lock]);
+        assert!(allowed_actions(ServerStatus::Deleted, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_server_address() {
+        let addr: ServerAddress = serde_json::from_str(r#"{
+            "OS-EXT-IPS-MAC:mac_addr": "fa:16:3e:4c:2c:30",
+            "OS-EXT-IPS:type": "floating",
+            "addr": "2001:db8::1",
+            "version": 6
+        }"#).unwrap();
+        assert_eq!(addr.version(), 6);
+        assert!(addr.is_floating());
+        assert_eq!(addr.addr_type, Some(AddressType::Floating));
+        assert_eq!(addr.mac_addr,
+                   Some(MacAddress::parse_str("fa:16:3e:4c:2c:30").unwrap()));
+
+        let addr: ServerAddress = serde_json::from_str(r#"{
+            "addr": "192.168.0.3",
+            "version": 4
+        }"#).unwrap();
+        assert_eq!(addr.version(), 4);
+        assert!(! addr.is_floating());
+        assert!(addr.mac_addr.is_none());
+    }
+}
@@ -0,0 +1,287 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Foundation bits exposing the DNS API.
+
+use std::fmt::Debug;
+
+use reqwest::{Method, Url};
+use serde::Serialize;
+
+use super::super::Result;
+use super::super::auth::AuthMethod;
+use super::super::session::{Session, ServiceInfo, ServiceType};
+use super::super::utils::{self, ResultExt};
+use super::protocol;
+
+
+/// Extensions for Session.
+pub trait V2API {
+    /// Create a recordset in a zone.
+    fn create_recordset<S: AsRef<str>>(&self, zone_id: S,
+                                       request: protocol::RecordsetCreate)
+        -> Result<protocol::Recordset>;
+
+    /// Create a zone.
+    fn create_zone(&self, request: protocol::ZoneCreate)
+        -> Result<protocol::Zone>;
+
+    /// Delete a recordset.
+    fn delete_recordset<S1, S2>(&self, zone_id: S1, id: S2) -> Result<()>
+        where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Delete a zone.
+    fn delete_zone<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Get a recordset in a zone.
+    ///
+    /// Designate rejects IDs that are not UUIDs, so other values are only
+    /// looked up by name.
+    fn get_recordset<S1, S2>(&self, zone_id: S1, id_or_name: S2)
+            -> Result<protocol::Recordset>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        let (zone_id, s) = (zone_id.as_ref(), id_or_name.as_ref());
+        if utils::is_uuid(s) {
+            self.get_recordset_by_id(zone_id, s)
+                .if_not_found_then(|| self.get_recordset_by_name(zone_id, s))
+        } else {
+            self.get_recordset_by_name(zone_id, s)
+        }
+    }
+
+    /// Get a recordset in a zone by its ID.
+    fn get_recordset_by_id<S1, S2>(&self, zone_id: S1, id: S2)
+        -> Result<protocol::Recordset>
+        where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Get a recordset in a zone by its name.
+    fn get_recordset_by_name<S1, S2>(&self, zone_id: S1, name: S2)
+        -> Result<protocol::Recordset>
+        where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Get a zone.
+    ///
+    /// Designate rejects IDs that are not UUIDs, so other values are only
+    /// looked up by name.
+    fn get_zone<S: AsRef<str>>(&self, id_or_name: S) -> Result<protocol::Zone> {
+        let s = id_or_name.as_ref();
+        if utils::is_uuid(s) {
+            self.get_zone_by_id(s)
+                .if_not_found_then(|| self.get_zone_by_name(s))
+        } else {
+            self.get_zone_by_name(s)
+        }
+    }
+
+    /// Get a zone by its ID.
+    fn get_zone_by_id<S: AsRef<str>>(&self, id: S) -> Result<protocol::Zone>;
+
+    /// Get a zone by its name.
+    fn get_zone_by_name<S: AsRef<str>>(&self, name: S) -> Result<protocol::Zone>;
+
+    /// List recordsets.
+    ///
+    /// Use the `zone_id` filter to limit the result to one zone.
+    fn list_recordsets<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::Recordset>>;
+
+    /// List zones.
+    fn list_zones<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::Zone>>;
+
+    /// Update a recordset.
+    fn update_recordset<S1, S2>(&self, zone_id: S1, id: S2,
+                                update: protocol::RecordsetUpdate)
+        -> Result<protocol::Recordset>
+        where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Update a zone.
+    fn update_zone<S: AsRef<str>>(&self, id: S, update: protocol::ZoneUpdate)
+        -> Result<protocol::Zone>;
+}
+
+
+/// Service type of DNS API V2.
+#[derive(Copy, Clone, Debug)]
+pub struct V2;
+
+
+const SERVICE_TYPE: &'static str = "dns";
+const VERSION_ID: &'static str = "v2";
+
+
+impl V2API for Session {
+    fn create_recordset<S: AsRef<str>>(&self, zone_id: S,
+                                       request: protocol::RecordsetCreate)
+            -> Result<protocol::Recordset> {
+        debug!("Creating a new recordset in zone {} with {:?}",
+               zone_id.as_ref(), request);
+        let recordset = self.request::<V2>(Method::Post,
+                                           &["zones", zone_id.as_ref(),
+                                             "recordsets"],
+                                           None)?
+            .json(&request).receive_json::<protocol::Recordset>()?;
+        debug!("Created recordset {:?}", recordset);
+        Ok(recordset)
+    }
+
+    fn create_zone(&self, request: protocol::ZoneCreate)
+            -> Result<protocol::Zone> {
+        debug!("Creating a new zone with {:?}", request);
+        let zone = self.request::<V2>(Method::Post, &["zones"], None)?
+            .json(&request).receive_json::<protocol::Zone>()?;
+        debug!("Created zone {:?}", zone);
+        Ok(zone)
+    }
+
+    fn delete_recordset<S1, S2>(&self, zone_id: S1, id: S2) -> Result<()>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        debug!("Deleting recordset {} from zone {}", id.as_ref(),
+               zone_id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["zones", zone_id.as_ref(), "recordsets",
+                                     id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Recordset {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn delete_zone<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting zone {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete, &["zones", id.as_ref()], None)?
+            .send()?;
+        debug!("Zone {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn get_recordset_by_id<S1, S2>(&self, zone_id: S1, id: S2)
+            -> Result<protocol::Recordset>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        trace!("Get recordset by ID {} in zone {}", id.as_ref(),
+               zone_id.as_ref());
+        let recordset = self.request::<V2>(Method::Get,
+                                           &["zones", zone_id.as_ref(),
+                                             "recordsets", id.as_ref()],
+                                           None)?
+           .receive_json::<protocol::Recordset>()?;
+        trace!("Received {:?}", recordset);
+        Ok(recordset)
+    }
+
+    fn get_recordset_by_name<S1, S2>(&self, zone_id: S1, name: S2)
+            -> Result<protocol::Recordset>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        trace!("Get recordset by name {} in zone {}", name.as_ref(),
+               zone_id.as_ref());
+        let items = self.request::<V2>(Method::Get,
+                                       &["zones", zone_id.as_ref(),
+                                         "recordsets"],
+                                       None)?
+            .query(&[("name", name.as_ref())])
+            .receive_json::<protocol::RecordsetsRoot>()?.recordsets;
+        let result = utils::one(items,
+                                "Recordset with given name or ID not found",
+                                "Too many recordsets found with given name")?;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_zone_by_id<S: AsRef<str>>(&self, id: S) -> Result<protocol::Zone> {
+        trace!("Get zone by ID {}", id.as_ref());
+        let zone = self.request::<V2>(Method::Get, &["zones", id.as_ref()], None)?
+           .receive_json::<protocol::Zone>()?;
+        trace!("Received {:?}", zone);
+        Ok(zone)
+    }
+
+    fn get_zone_by_name<S: AsRef<str>>(&self, name: S) -> Result<protocol::Zone> {
+        trace!("Get zone by name {}", name.as_ref());
+        let items = self.request::<V2>(Method::Get, &["zones"], None)?
+            .query(&[("name", name.as_ref())])
+            .receive_json::<protocol::ZonesRoot>()?.zones;
+        let result = utils::one(items, "Zone with given name or ID not found",
+                                "Too many zones found with given name")?;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn list_recordsets<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::Recordset>> {
+        trace!("Listing recordsets with {:?}", query);
+        let result = self.request::<V2>(Method::Get, &["recordsets"], None)?
+           .query(query).receive_json::<protocol::RecordsetsRoot>()?.recordsets;
+        trace!("Received recordsets: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_zones<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::Zone>> {
+        trace!("Listing zones with {:?}", query);
+        let result = self.request::<V2>(Method::Get, &["zones"], None)?
+           .query(query).receive_json::<protocol::ZonesRoot>()?.zones;
+        trace!("Received zones: {:?}", result);
+        Ok(result)
+    }
+
+    fn update_recordset<S1, S2>(&self, zone_id: S1, id: S2,
+                                update: protocol::RecordsetUpdate)
+            -> Result<protocol::Recordset>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        debug!("Updating recordset {} in zone {} with {:?}", id.as_ref(),
+               zone_id.as_ref(), update);
+        let recordset = self.request::<V2>(Method::Put,
+                                           &["zones", zone_id.as_ref(),
+                                             "recordsets", id.as_ref()],
+                                           None)?
+            .json(&update).receive_json::<protocol::Recordset>()?;
+        debug!("Updated recordset {:?}", recordset);
+        Ok(recordset)
+    }
+
+    fn update_zone<S: AsRef<str>>(&self, id: S, update: protocol::ZoneUpdate)
+            -> Result<protocol::Zone> {
+        debug!("Updating zone {} with {:?}", id.as_ref(), update);
+        let zone = self.request::<V2>(Method::Patch, &["zones", id.as_ref()], None)?
+            .json(&update).receive_json::<protocol::Zone>()?;
+        debug!("Updated zone {:?}", zone);
+        Ok(zone)
+    }
+}
+
+
+impl ServiceType for V2 {
+    fn catalog_type() -> &'static str {
+        SERVICE_TYPE
+    }
+
+    fn service_info(endpoint: Url, _auth: &AuthMethod) -> Result<ServiceInfo> {
+        // DNS endpoints in the catalog are usually unversioned, and the API
+        // has no microversions to discover.
+        let versioned = endpoint.path_segments().and_then(|segments| {
+            segments.filter(|x| !x.is_empty()).last()
+        }).map(|x| x == VERSION_ID).unwrap_or(false);
+        let root_url = if versioned {
+            endpoint
+        } else {
+            utils::url::join(endpoint, VERSION_ID)
+        };
+        Ok(ServiceInfo {
+            root_url: root_url,
+            current_version: None,
+            minimum_version: None
+        })
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSON structures and protocol bits for the DNS API.
+
+#![allow(missing_docs)]
+
+use chrono::NaiveDateTime;
+
+use super::super::common;
+
+
+protocol_enum! {
+    #[doc = "Possible statuses of zones and recordsets."]
+    enum DnsStatus {
+        Active = "ACTIVE",
+        Pending = "PENDING",
+        Error = "ERROR",
+        Deleted = "DELETED"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Type of a zone."]
+    enum ZoneType {
+        Primary = "PRIMARY",
+        Secondary = "SECONDARY"
+    }
+}
+
+impl Default for ZoneType {
+    fn default() -> ZoneType {
+        ZoneType::Primary
+    }
+}
+
+impl common::ResourceStatus for DnsStatus {
+    fn is_error(&self) -> bool {
+        *self == DnsStatus::Error
+    }
+
+    fn is_transitional(&self) -> bool {
+        *self == DnsStatus::Pending
+    }
+}
+
+/// A zone.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Zone {
+    pub created_at: NaiveDateTime,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default)]
+    pub description: Option<String>,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default)]
+    pub email: Option<String>,
+    pub id: String,
+    #[serde(default)]
+    pub masters: Vec<String>,
+    pub name: String,
+    #[serde(default)]
+    pub pool_id: Option<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub serial: u64,
+    pub status: DnsStatus,
+    #[serde(default)]
+    pub ttl: Option<u32>,
+    #[serde(rename = "type", default)]
+    pub zone_type: ZoneType,
+    #[serde(default)]
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// A zone creation request.
+#[derive(Debug, Clone, Serialize)]
+pub struct ZoneCreate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub masters: Vec<String>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+    #[serde(rename = "type")]
+    pub zone_type: ZoneType,
+}
+
+/// A zone update.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ZoneUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+}
+
+/// A list of zones.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ZonesRoot {
+    pub zones: Vec<Zone>
+}
+
+/// A recordset.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Recordset {
+    pub created_at: NaiveDateTime,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default)]
+    pub description: Option<String>,
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    #[serde(default)]
+    pub records: Vec<String>,
+    pub status: DnsStatus,
+    #[serde(default)]
+    pub ttl: Option<u32>,
+    #[serde(default)]
+    pub updated_at: Option<NaiveDateTime>,
+    pub zone_id: String,
+    #[serde(default)]
+    pub zone_name: Option<String>,
+}
+
+/// A recordset creation request.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordsetCreate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub name: String,
+    pub records: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+    #[serde(rename = "type")]
+    pub record_type: String,
+}
+
+/// A recordset update.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecordsetUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub records: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+}
+
+/// A list of recordsets.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecordsetsRoot {
+    pub recordsets: Vec<Recordset>
+}
+
+
+#[cfg(test)]
+mod test {
+    use serde_json;
+
+    use super::super::super::common::ResourceStatus;
+    use super::{DnsStatus, RecordsetCreate, RecordsetUpdate, RecordsetsRoot,
+                ZoneCreate, ZoneType, ZonesRoot};
+
+    // Trimmed down from the Designate API reference.
+    const ZONES: &'static str = r#"{"zones": [{
+        "id": "a86dba58-0043-4cc6-a1bb-69d5e86f3ca3",
+        "pool_id": "572ba08c-d929-4c70-8e42-03824bb24ca2",
+        "project_id": "4335d1f0-f793-11e2-b778-0800200c9a66",
+        "name": "example.org.",
+        "email": "joe@example.org",
+        "ttl": 7200,
+        "serial": 1404757531,
+        "status": "ACTIVE",
+        "action": "CREATE",
+        "description": "",
+        "masters": [],
+        "type": "PRIMARY",
+        "transferred_at": null,
+        "version": 1,
+        "created_at": "2014-07-07T18:25:31.275934",
+        "updated_at": null,
+        "links": {"self": "https://127.0.0.1:9001/v2/zones/a86dba58"}
+    }], "links": {}, "metadata": {"total_count": 1}}"#;
+
+    const RECORDSETS: &'static str = r#"{"recordsets": [{
+        "id": "f7b10e9b-0cae-4a91-b162-562bc6096648",
+        "project_id": "4335d1f0-f793-11e2-b778-0800200c9a66",
+        "name": "example.org.",
+        "ttl": null,
+        "records": ["10.1.0.2"],
+        "status": "PENDING",
+        "action": "CREATE",
+        "zone_id": "2150b1bf-dee2-4221-9d85-11f7886fb15f",
+        "zone_name": "example.com.",
+        "description": "This is an example record set.",
+        "type": "A",
+        "version": 1,
+        "created_at": "2014-10-24T19:59:44.000000",
+        "updated_at": null
+    }]}"#;
+
+    #[test]
+    fn test_zones_parse() {
+        let root: ZonesRoot = serde_json::from_str(ZONES).unwrap();
+        assert_eq!(root.zones.len(), 1);
+        let zone = &root.zones[0];
+        assert_eq!(zone.id, "a86dba58-0043-4cc6-a1bb-69d5e86f3ca3");
+        assert_eq!(zone.name, "example.org.");
+        assert_eq!(zone.description, None);
+        assert_eq!(zone.email.as_ref().unwrap(), "joe@example.org");
+        assert_eq!(zone.serial, 1404757531);
+        assert_eq!(zone.status, DnsStatus::Active);
+        assert_eq!(zone.ttl, Some(7200));
+        assert_eq!(zone.zone_type, ZoneType::Primary);
+        assert!(zone.masters.is_empty());
+        assert!(zone.updated_at.is_none());
+    }
+
+    #[test]
+    fn test_zone_type_default() {
+        let payload = ZONES.replace("\"type\": \"PRIMARY\",", "");
+        let root: ZonesRoot = serde_json::from_str(&payload).unwrap();
+        assert_eq!(root.zones[0].zone_type, ZoneType::Primary);
+    }
+
+    #[test]
+    fn test_recordsets_parse() {
+        let root: RecordsetsRoot = serde_json::from_str(RECORDSETS).unwrap();
+        let recordset = &root.recordsets[0];
+        assert_eq!(recordset.name, "example.org.");
+        assert_eq!(recordset.record_type, "A");
+        assert_eq!(recordset.records, vec![String::from("10.1.0.2")]);
+        assert_eq!(recordset.status, DnsStatus::Pending);
+        assert_eq!(recordset.ttl, None);
+        assert_eq!(recordset.zone_id, "2150b1bf-dee2-4221-9d85-11f7886fb15f");
+        assert_eq!(recordset.zone_name.as_ref().unwrap(), "example.com.");
+    }
+
+    #[test]
+    fn test_zone_create_serialize() {
+        let request = ZoneCreate {
+            description: None,
+            email: Some(String::from("joe@example.org")),
+            masters: Vec::new(),
+            name: String::from("example.org."),
+            ttl: Some(600),
+            zone_type: ZoneType::Primary,
+        };
+        assert_eq!(serde_json::to_string(&request).unwrap(),
+                   r#"{"email":"joe@example.org","name":"example.org.","ttl":600,"type":"PRIMARY"}"#);
+    }
+
+    #[test]
+    fn test_recordset_serialize() {
+        let request = RecordsetCreate {
+            description: None,
+            name: String::from("www.example.org."),
+            records: vec![String::from("192.0.2.1")],
+            ttl: None,
+            record_type: String::from("A"),
+        };
+        assert_eq!(serde_json::to_string(&request).unwrap(),
+                   r#"{"name":"www.example.org.","records":["192.0.2.1"],"type":"A"}"#);
+
+        let update = RecordsetUpdate {
+            ttl: Some(3600),
+            .. RecordsetUpdate::default()
+        };
+        assert_eq!(serde_json::to_string(&update).unwrap(),
+                   r#"{"ttl":3600}"#);
+    }
+
+    #[test]
+    fn test_dns_status_classification() {
+        assert!(DnsStatus::Pending.is_transitional());
+        assert!(DnsStatus::Active.is_terminal());
+        assert!(DnsStatus::Error.is_error());
+        assert!(! DnsStatus::Active.is_error());
+    }
+}
@@ -0,0 +1,372 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! DNS recordsets management via DNS API.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
+use serde::Serialize;
+
+use super::super::{Error, ErrorKind, Result};
+use super::super::common::{DeletionWaiter, ListResources, Refresh,
+                           ResourceId, ResourceIterator};
+use super::super::session::Session;
+use super::super::utils::Query;
+use super::base::V2API;
+use super::{protocol, Zone};
+
+
+/// A query to recordset list.
+#[derive(Clone, Debug)]
+pub struct RecordsetQuery {
+    session: Rc<Session>,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// Structure representing a set of DNS records with the same name and type.
+#[derive(Clone, Debug)]
+pub struct Recordset {
+    session: Rc<Session>,
+    inner: protocol::Recordset,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a recordset.
+#[derive(Clone, Debug)]
+pub struct NewRecordset {
+    session: Rc<Session>,
+    zone_id: String,
+    inner: protocol::RecordsetCreate,
+}
+
+impl Recordset {
+    /// Create a recordset object.
+    fn new(session: Rc<Session>, inner: protocol::Recordset) -> Recordset {
+        Recordset {
+            session: session,
+            inner: inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load a Recordset object.
+    pub(crate) fn load<S1, S2>(session: Rc<Session>, zone_id: S1, id: S2)
+            -> Result<Recordset> where S1: AsRef<str>, S2: AsRef<str> {
+        let inner = session.get_recordset(zone_id, id)?;
+        Ok(Recordset::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Creation date and time."]
+        created_at: NaiveDateTime
+    }
+
+    transparent_property! {
+        #[doc = "Recordset description."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Recordset name (a fully qualified domain name)."]
+        name: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the recordset."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Record type, e.g. `A` or `CNAME`."]
+        record_type: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Record values."]
+        records: ref Vec<String>
+    }
+
+    update_field! {
+        #[doc = "Replace the record values."]
+        set_records, with_records -> records: Vec<String>
+    }
+
+    transparent_property! {
+        #[doc = "Recordset status."]
+        status: protocol::DnsStatus
+    }
+
+    transparent_property! {
+        #[doc = "TTL of the records (if different from the zone default)."]
+        ttl: Option<u32>
+    }
+
+    update_field! {
+        #[doc = "Update the TTL."]
+        set_ttl, with_ttl -> ttl: optional u32
+    }
+
+    transparent_property! {
+        #[doc = "Last update date and time (if any)."]
+        updated_at: Option<NaiveDateTime>
+    }
+
+    /// Get the zone this recordset belongs to.
+    pub fn zone(&self) -> Result<Zone> {
+        Zone::load(self.session.clone(), &self.inner.zone_id)
+    }
+
+    transparent_property! {
+        #[doc = "ID of the zone this recordset belongs to."]
+        zone_id: ref String
+    }
+
+    /// Delete the recordset.
+    pub fn delete(self) -> Result<DeletionWaiter<Recordset>> {
+        self.session.delete_recordset(&self.inner.zone_id, &self.inner.id)?;
+        Ok(DeletionWaiter::new(self, Duration::new(120, 0), Duration::new(1, 0)))
+    }
+
+    /// Whether the recordset is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the recordset.
+    pub fn save(&mut self) -> Result<()> {
+        let mut update = protocol::RecordsetUpdate::default();
+        save_option_fields! {
+            self -> update: description ttl
+        };
+        save_fields! {
+            self -> update: records
+        };
+        let inner = self.session.update_recordset(&self.inner.zone_id,
+                                                  &self.inner.id, update)?;
+        self.dirty.clear();
+        self.inner = inner;
+        Ok(())
+    }
+}
+
+impl Refresh for Recordset {
+    /// Refresh the recordset.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_recordset_by_id(&self.inner.zone_id,
+                                                      &self.inner.id)?;
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+impl RecordsetQuery {
+    pub(crate) fn new(session: Rc<Session>) -> RecordsetQuery {
+        RecordsetQuery {
+            session: session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            max_items: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by a record value."]
+        set_data, with_data -> data
+    }
+
+    query_filter! {
+        #[doc = "Filter by recordset name."]
+        set_name, with_name -> name
+    }
+
+    /// Filter by record type.
+    pub fn set_record_type<T: Into<String>>(&mut self, value: T) {
+        self.query.push_str("type", value);
+    }
+
+    /// Filter by record type.
+    pub fn with_record_type<T: Into<String>>(mut self, value: T) -> Self {
+        self.set_record_type(value);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by zone ID."]
+        set_zone_id, with_zone_id -> zone_id
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<Recordset> {
+        debug!("Fetching recordsets with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<Recordset>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(mut self) -> Result<Recordset> {
+        debug!("Fetching one recordset with {:?}", self.query);
+        if self.can_paginate {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        self.into_iter().one()
+    }
+}
+
+impl NewRecordset {
+    /// Start creating a recordset.
+    pub(crate) fn new(session: Rc<Session>, zone_id: String, name: String,
+                      record_type: String) -> NewRecordset {
+        NewRecordset {
+            session: session,
+            zone_id: zone_id,
+            inner: protocol::RecordsetCreate {
+                description: None,
+                name: name,
+                records: Vec::new(),
+                ttl: None,
+                record_type: record_type,
+            },
+        }
+    }
+
+    /// Request creation of the recordset.
+    pub fn create(self) -> Result<Recordset> {
+        if self.inner.records.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "At least one record value is required"));
+        }
+
+        let inner = self.session.create_recordset(&self.zone_id, self.inner)?;
+        Ok(Recordset::new(self.session, inner))
+    }
+
+    /// Add a record value.
+    pub fn add_record<S: Into<String>>(&mut self, record: S) {
+        self.inner.records.push(record.into());
+    }
+
+    /// Add a record value.
+    pub fn with_record<S: Into<String>>(mut self, record: S) -> Self {
+        self.add_record(record);
+        self
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the recordset."]
+        set_description, with_description -> description: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set TTL of the records."]
+        set_ttl, with_ttl -> ttl: optional u32
+    }
+}
+
+impl ResourceId for Recordset {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for Recordset {
+    const DEFAULT_LIMIT: usize = 50;
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<Recordset>> {
+        Ok(session.list_recordsets(&query)?.into_iter()
+           .map(|item| Recordset::new(session.clone(), item)).collect())
+    }
+}
+
+impl IntoFallibleIterator for RecordsetQuery {
+    type Item = Recordset;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<Recordset>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<Recordset> {
+        self.into_iter()
+    }
+}
@@ -0,0 +1,493 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! DNS zones management via DNS API.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDateTime;
+use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
+use serde::Serialize;
+use waiter::{Waiter, WaiterCurrentState};
+
+use super::super::{Error, ErrorKind, Result};
+use super::super::common::{DeletionWaiter, ListResources, Refresh,
+                           ResourceId, ResourceIterator};
+use super::super::session::Session;
+use super::super::telemetry;
+use super::super::utils::Query;
+use super::base::V2API;
+use super::{protocol, NewRecordset, Recordset, RecordsetQuery};
+
+
+/// A query to zone list.
+#[derive(Clone, Debug)]
+pub struct ZoneQuery {
+    session: Rc<Session>,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// Structure representing a single DNS zone.
+#[derive(Clone, Debug)]
+pub struct Zone {
+    session: Rc<Session>,
+    inner: protocol::Zone,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a zone.
+#[derive(Clone, Debug)]
+pub struct NewZone {
+    session: Rc<Session>,
+    inner: protocol::ZoneCreate,
+}
+
+/// Waiter for a zone to become active.
+#[derive(Debug)]
+pub struct ZoneStatusWaiter {
+    zone: Zone,
+    wait_timeout: Duration,
+    delay: Duration,
+    started: Instant
+}
+
+impl Zone {
+    /// Create a zone object.
+    fn new(session: Rc<Session>, inner: protocol::Zone) -> Zone {
+        Zone {
+            session: session,
+            inner: inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load a Zone object.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<Zone> {
+        let inner = session.get_zone(id)?;
+        Ok(Zone::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Creation date and time."]
+        created_at: NaiveDateTime
+    }
+
+    transparent_property! {
+        #[doc = "Zone description."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "E-mail of the zone owner."]
+        email: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the e-mail of the zone owner."]
+        set_email, with_email -> email: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Primary servers of a secondary zone."]
+        masters: ref Vec<String>
+    }
+
+    transparent_property! {
+        #[doc = "Zone name (a fully qualified domain name)."]
+        name: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the zone."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Current serial number of the zone."]
+        serial: u64
+    }
+
+    transparent_property! {
+        #[doc = "Zone status."]
+        status: protocol::DnsStatus
+    }
+
+    transparent_property! {
+        #[doc = "Default TTL of records in the zone."]
+        ttl: Option<u32>
+    }
+
+    update_field! {
+        #[doc = "Update the default TTL."]
+        set_ttl, with_ttl -> ttl: optional u32
+    }
+
+    transparent_property! {
+        #[doc = "Last update date and time (if any)."]
+        updated_at: Option<NaiveDateTime>
+    }
+
+    transparent_property! {
+        #[doc = "Zone type."]
+        zone_type: protocol::ZoneType
+    }
+
+    /// Build a query against recordsets of this zone.
+    pub fn find_recordsets(&self) -> RecordsetQuery {
+        RecordsetQuery::new(self.session.clone()).with_zone_id(self.id().clone())
+    }
+
+    /// Find a recordset in this zone by its name or ID.
+    pub fn get_recordset<Id: AsRef<str>>(&self, id_or_name: Id)
+            -> Result<Recordset> {
+        Recordset::load(self.session.clone(), &self.inner.id, id_or_name)
+    }
+
+    /// List all recordsets of this zone.
+    pub fn list_recordsets(&self) -> Result<Vec<Recordset>> {
+        self.find_recordsets().all()
+    }
+
+    /// Prepare a new recordset in this zone for creation.
+    ///
+    /// The name must be a fully qualified domain name within the zone, the
+    /// record type is, for example, `A`, `AAAA` or `CNAME`.
+    pub fn new_recordset<S1, S2>(&self, name: S1, record_type: S2)
+            -> NewRecordset where S1: Into<String>, S2: Into<String> {
+        NewRecordset::new(self.session.clone(), self.inner.id.clone(),
+                          name.into(), record_type.into())
+    }
+
+    /// Delete the zone.
+    pub fn delete(self) -> Result<DeletionWaiter<Zone>> {
+        self.session.delete_zone(&self.inner.id)?;
+        Ok(DeletionWaiter::new(self, Duration::new(120, 0), Duration::new(1, 0)))
+    }
+
+    /// Whether the zone is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the zone.
+    ///
+    /// Use `wait_active` to wait for the changes to be propagated.
+    pub fn save(&mut self) -> Result<()> {
+        let mut update = protocol::ZoneUpdate::default();
+        save_option_fields! {
+            self -> update: description email ttl
+        };
+        let inner = self.session.update_zone(self.id(), update)?;
+        self.dirty.clear();
+        self.inner = inner;
+        Ok(())
+    }
+
+    /// Wait for the zone to become active.
+    pub fn wait_active(self) -> ZoneStatusWaiter {
+        ZoneStatusWaiter::new(self)
+    }
+}
+
+impl Refresh for Zone {
+    /// Refresh the zone.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_zone_by_id(&self.inner.id)?;
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+impl ZoneStatusWaiter {
+    fn new(zone: Zone) -> ZoneStatusWaiter {
+        ZoneStatusWaiter {
+            zone: zone,
+            wait_timeout: Duration::new(300, 0),
+            delay: Duration::new(2, 0),
+            started: Instant::now()
+        }
+    }
+
+    /// Wait at most the given time for the zone to become active.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_timeout = timeout;
+        self
+    }
+
+    /// Check the zone status with the given interval.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.delay = interval;
+        self
+    }
+}
+
+impl Waiter<Zone, Error> for ZoneStatusWaiter {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        Some(self.wait_timeout)
+    }
+
+    fn default_delay(&self) -> Duration {
+        self.delay
+    }
+
+    fn timeout_error(&self) -> Error {
+        telemetry::record_waiter("zone_status", self.started, false);
+        Error::new(ErrorKind::OperationTimedOut,
+                   format!("Timeout waiting for zone {} to become ACTIVE",
+                           self.zone.id()))
+    }
+
+    fn poll(&mut self) -> Result<Option<Zone>> {
+        self.zone.refresh()?;
+        if self.zone.status() == protocol::DnsStatus::Active {
+            debug!("Zone {} is active", self.zone.id());
+            telemetry::record_waiter("zone_status", self.started, true);
+            Ok(Some(self.zone.clone()))
+        } else if self.zone.status() == protocol::DnsStatus::Error {
+            debug!("Zone {} got into ERROR state", self.zone.id());
+            telemetry::record_waiter("zone_status", self.started, false);
+            Err(Error::new(ErrorKind::OperationFailed,
+                           format!("Zone {} got into ERROR state",
+                                   self.zone.id())))
+        } else {
+            trace!("Still waiting for zone {} to become ACTIVE, current is {}",
+                   self.zone.id(), self.zone.status());
+            Ok(None)
+        }
+    }
+}
+
+impl WaiterCurrentState<Zone> for ZoneStatusWaiter {
+    fn waiter_current_state(&self) -> &Zone {
+        &self.zone
+    }
+}
+
+impl ZoneQuery {
+    pub(crate) fn new(session: Rc<Session>) -> ZoneQuery {
+        ZoneQuery {
+            session: session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            max_items: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by e-mail of the zone owner."]
+        set_email, with_email -> email
+    }
+
+    query_filter! {
+        #[doc = "Filter by zone name."]
+        set_name, with_name -> name
+    }
+
+    /// Filter by zone status.
+    pub fn set_status(&mut self, value: protocol::DnsStatus) {
+        self.query.push_str("status", value);
+    }
+
+    /// Filter by zone status.
+    pub fn with_status(mut self, value: protocol::DnsStatus) -> Self {
+        self.set_status(value);
+        self
+    }
+
+    /// Filter by zone type.
+    pub fn set_zone_type(&mut self, value: protocol::ZoneType) {
+        self.query.push_str("type", value);
+    }
+
+    /// Filter by zone type.
+    pub fn with_zone_type(mut self, value: protocol::ZoneType) -> Self {
+        self.set_zone_type(value);
+        self
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<Zone> {
+        debug!("Fetching zones with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<Zone>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(mut self) -> Result<Zone> {
+        debug!("Fetching one zone with {:?}", self.query);
+        if self.can_paginate {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        self.into_iter().one()
+    }
+}
+
+impl NewZone {
+    /// Start creating a zone.
+    pub(crate) fn new(session: Rc<Session>, name: String) -> NewZone {
+        NewZone {
+            session: session,
+            inner: protocol::ZoneCreate {
+                description: None,
+                email: None,
+                masters: Vec::new(),
+                name: name,
+                ttl: None,
+                zone_type: protocol::ZoneType::Primary,
+            },
+        }
+    }
+
+    /// Request creation of the zone.
+    ///
+    /// Returns a waiter for the zone to become active.
+    pub fn create(self) -> Result<ZoneStatusWaiter> {
+        if self.inner.zone_type == protocol::ZoneType::Primary &&
+                self.inner.email.is_none() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "An e-mail is required for primary zones"));
+        }
+
+        let inner = self.session.create_zone(self.inner)?;
+        Ok(ZoneStatusWaiter::new(Zone::new(self.session, inner)))
+    }
+
+    /// Add a primary server to transfer a secondary zone from.
+    pub fn add_master<S: Into<String>>(&mut self, master: S) {
+        self.inner.masters.push(master.into());
+    }
+
+    /// Add a primary server to transfer a secondary zone from.
+    pub fn with_master<S: Into<String>>(mut self, master: S) -> Self {
+        self.add_master(master);
+        self
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the zone."]
+        set_description, with_description -> description: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set e-mail of the zone owner (required for primary zones)."]
+        set_email, with_email -> email: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set default TTL of records in the zone."]
+        set_ttl, with_ttl -> ttl: optional u32
+    }
+
+    creation_inner_field! {
+        #[doc = "Set type of the zone."]
+        set_zone_type, with_zone_type -> zone_type: protocol::ZoneType
+    }
+}
+
+impl ResourceId for Zone {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for Zone {
+    const DEFAULT_LIMIT: usize = 50;
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<Zone>> {
+        Ok(session.list_zones(&query)?.into_iter()
+           .map(|item| Zone::new(session.clone(), item)).collect())
+    }
+}
+
+impl IntoFallibleIterator for ZoneQuery {
+    type Item = Zone;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<Zone>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<Zone> {
+        self.into_iter()
+    }
+}
@@ -15,9 +15,11 @@
 //! Error and Result implementations.
 
 use std::fmt;
+use std::time::Duration;
 
 use reqwest::{StatusCode, UrlError};
 use reqwest::Error as HttpClientError;
+use serde_json::{Map, Value};
 
 /// Kind of an error.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -56,6 +58,11 @@ pub enum ErrorKind {
     /// Conflict in the request.
     Conflict,
 
+    /// Quota of the project is exceeded.
+    ///
+    /// Maps to HTTP 413 and to quota faults reported with HTTP 403 or 409.
+    QuotaExceeded,
+
     /// Operation has reached the specified time out.
     OperationTimedOut,
 
@@ -70,12 +77,21 @@ pub enum ErrorKind {
 
     /// Internal server error.
     ///
-    /// Maps to HTTP 5xx codes.
+    /// Maps to HTTP 5xx codes except for 503.
     InternalServerError,
 
+    /// Service is temporarily unavailable, e.g. because of maintenance.
+    ///
+    /// Maps to HTTP 503. Use `Error::is_maintenance` and `Error::retry_after`
+    /// to decide when to try again.
+    ServiceUnavailable,
+
     /// Invalid clouds.yaml file.
     InvalidConfig,
 
+    /// Operation was cancelled via a `CancellationToken`.
+    Cancelled,
+
     #[allow(missing_docs)]
     __Nonexhaustive,
 }
@@ -85,7 +101,12 @@ pub enum ErrorKind {
 pub struct Error {
     kind: ErrorKind,
     status: Option<StatusCode>,
-    message: Option<String>
+    message: Option<String>,
+    request_id: Option<String>,
+    service: Option<String>,
+    fault: Option<String>,
+    maintenance: bool,
+    retry_after: Option<Duration>
 }
 
 /// Result of an OpenStack call.
@@ -97,7 +118,12 @@ impl Error {
         Error {
             kind: kind,
             status: None,
-            message: Some(message.into())
+            message: Some(message.into()),
+            request_id: None,
+            service: None,
+            fault: None,
+            maintenance: false,
+            retry_after: None
         }
     }
 
@@ -107,15 +133,108 @@ impl Error {
         Error {
             kind: kind,
             status: status,
-            message: message
+            message: message,
+            request_id: None,
+            service: None,
+            fault: None,
+            maintenance: false,
+            retry_after: None
         }
     }
 
+    /// Add details of the request that caused the error.
+    pub(crate) fn with_request_details(mut self, service: Option<String>,
+                                       request_id: Option<String>) -> Error {
+        self.service = service;
+        self.request_id = request_id;
+        self
+    }
+
+    /// Add the fault message parsed from the response body.
+    pub(crate) fn with_fault(mut self, fault: Option<String>) -> Error {
+        self.fault = fault;
+        self
+    }
+
+    /// Add details of a service unavailability.
+    pub(crate) fn with_unavailability_details(mut self, maintenance: bool,
+                                              retry_after: Option<Duration>)
+            -> Error {
+        self.maintenance = maintenance;
+        self.retry_after = retry_after;
+        self
+    }
+
+    /// Fault message from the body of the error response (if any).
+    ///
+    /// This is the explanation provided by the service, e.g. `Instance
+    /// 1234 could not be found.`. It is also used as the error `message`.
+    pub fn fault(&self) -> Option<&String> {
+        self.fault.as_ref()
+    }
+
+    /// Whether the service reported that it is down for maintenance.
+    pub fn is_maintenance(&self) -> bool {
+        self.maintenance
+    }
+
     /// Error kind.
     pub fn kind(&self) -> ErrorKind {
         self.kind
     }
 
+    /// Error message (if any).
+    pub fn message(&self) -> Option<&String> {
+        self.message.as_ref()
+    }
+
+    /// ID of the failed request as reported by the server (if any).
+    pub fn request_id(&self) -> Option<&String> {
+        self.request_id.as_ref()
+    }
+
+    /// Catalog type of the service that returned the error (if any).
+    pub fn service(&self) -> Option<&str> {
+        self.service.as_ref().map(String::as_str)
+    }
+
+    /// Delay after which the request can be retried (if reported).
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+
+    /// HTTP status code (if any).
+    pub fn status(&self) -> Option<StatusCode> {
+        self.status
+    }
+
+    /// Structured representation of the error.
+    ///
+    /// The result is an object with the `kind` of the error, its `message`,
+    /// the `fault` reported by the service, the HTTP `status`, the
+    /// `request_id` and the `service`. Unknown values are `null`.
+    pub fn to_json(&self) -> Value {
+        let mut result = Map::new();
+        let _ = result.insert(String::from("kind"),
+                              Value::from(format!("{:?}", self.kind)));
+        let _ = result.insert(String::from("message"),
+                              Value::from(self.message.clone()
+                                  .unwrap_or_else(|| self.kind.to_string())));
+        let _ = result.insert(String::from("fault"),
+                              self.fault.clone().map(Value::from)
+                                  .unwrap_or(Value::Null));
+        let _ = result.insert(String::from("status"),
+                              self.status.map(|s| Value::from(s.as_u16()))
+                                  .unwrap_or(Value::Null));
+        let _ = result.insert(String::from("request_id"),
+                              self.request_id.clone().map(Value::from)
+                                  .unwrap_or(Value::Null));
+        let _ = result.insert(String::from("service"),
+                              self.service.clone().map(Value::from)
+                                  .unwrap_or(Value::Null));
+        Value::Object(result)
+    }
+
     /// Helper - error of kind EndpointNotFound.
     pub(crate) fn new_endpoint_not_found<D: fmt::Display>(service_type: D) -> Error {
         Error::new(
@@ -126,6 +245,22 @@ impl Error {
 }
 
 impl ErrorKind {
+    /// Error kind corresponding to an HTTP status code.
+    pub(crate) fn from_status(status: StatusCode) -> ErrorKind {
+        match status {
+            StatusCode::Unauthorized => ErrorKind::AuthenticationFailed,
+            StatusCode::Forbidden => ErrorKind::AccessDenied,
+            StatusCode::NotFound => ErrorKind::ResourceNotFound,
+            StatusCode::NotAcceptable => ErrorKind::IncompatibleApiVersion,
+            StatusCode::Conflict => ErrorKind::Conflict,
+            StatusCode::PayloadTooLarge => ErrorKind::QuotaExceeded,
+            StatusCode::ServiceUnavailable => ErrorKind::ServiceUnavailable,
+            c if c.is_client_error() => ErrorKind::InvalidInput,
+            c if c.is_server_error() => ErrorKind::InternalServerError,
+            _ => ErrorKind::InvalidResponse
+        }
+    }
+
     /// Short description of the error kind.
     pub fn description(&self) -> &'static str {
         match self {
@@ -145,6 +280,8 @@ impl ErrorKind {
                 "Incompatible or unsupported API version",
             &ErrorKind::Conflict =>
                 "Requested cannot be fulfilled due to a conflict",
+            &ErrorKind::QuotaExceeded =>
+                "Quota exceeded for the requested resource",
             &ErrorKind::OperationTimedOut =>
                 "Time out reached while waiting for the operation",
             &ErrorKind::OperationFailed =>
@@ -155,8 +292,12 @@ impl ErrorKind {
                 "Received invalid response",
             &ErrorKind::InternalServerError =>
                 "Internal server error or bad gateway",
+            &ErrorKind::ServiceUnavailable =>
+                "Service is temporarily unavailable",
             &ErrorKind::InvalidConfig =>
                 "clouds.yaml cannot be found or is invalid",
+            &ErrorKind::Cancelled =>
+                "Operation was cancelled",
             _ => unreachable!()
         }
     }
@@ -194,15 +335,8 @@ impl From<HttpClientError> for Error {
     fn from(value: HttpClientError) -> Error {
         let msg = value.to_string();
         let kind = match value.status() {
-            Some(StatusCode::Unauthorized) => ErrorKind::AuthenticationFailed,
-            Some(StatusCode::Forbidden) => ErrorKind::AccessDenied,
-            Some(StatusCode::NotFound) => ErrorKind::ResourceNotFound,
-            Some(StatusCode::NotAcceptable) => ErrorKind::IncompatibleApiVersion,
-            Some(StatusCode::Conflict) => ErrorKind::Conflict,
-            Some(c) if c.is_client_error() => ErrorKind::InvalidInput,
-            Some(c) if c.is_server_error() => ErrorKind::InternalServerError,
-            None => ErrorKind::ProtocolError,
-            _ => ErrorKind::InvalidResponse
+            Some(status) => ErrorKind::from_status(status),
+            None => ErrorKind::ProtocolError
         };
 
         Error::new_with_details(kind, value.status(), Some(msg))
@@ -215,3 +349,41 @@ impl From<UrlError> for Error {
     }
 }
 
+
+
+#[cfg(test)]
+mod test {
+    use reqwest::StatusCode;
+    use serde_json;
+
+    use super::{Error, ErrorKind};
+
+    #[test]
+    fn test_to_json() {
+        let err = Error::new_with_details(ErrorKind::ResourceNotFound,
+                                          Some(StatusCode::NotFound),
+                                          Some(String::from("No server")))
+            .with_request_details(Some(String::from("compute")),
+                                  Some(String::from("req-1234")));
+        let expected: serde_json::Value = serde_json::from_str(r#"{
+            "kind": "ResourceNotFound",
+            "message": "No server",
+            "fault": null,
+            "status": 404,
+            "request_id": "req-1234",
+            "service": "compute"
+        }"#).unwrap();
+        assert_eq!(err.to_json(), expected);
+
+        let err = Error::new(ErrorKind::InvalidInput, "Bad value");
+        let expected: serde_json::Value = serde_json::from_str(r#"{
+            "kind": "InvalidInput",
+            "message": "Bad value",
+            "fault": null,
+            "status": null,
+            "request_id": null,
+            "service": null
+        }"#).unwrap();
+        assert_eq!(err.to_json(), expected);
+    }
+}
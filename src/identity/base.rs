@@ -0,0 +1,150 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Foundation bits exposing the Identity API.
+
+use std::fmt::Debug;
+
+use reqwest::{Method, Url};
+use serde::Serialize;
+
+use super::super::Result;
+use super::super::auth::AuthMethod;
+use super::super::common;
+use super::super::session::{Session, ServiceInfo, ServiceType};
+use super::super::utils::{self, ResultExt};
+use super::protocol;
+
+
+/// Extensions for Session.
+pub trait V3API {
+    /// Get a project.
+    fn get_project<S: AsRef<str>>(&self, id_or_name: S)
+            -> Result<protocol::Project> {
+        let s = id_or_name.as_ref();
+        self.get_project_by_id(s).if_not_found_then(|| self.get_project_by_name(s))
+    }
+
+    /// Get a project by its ID.
+    fn get_project_by_id<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::Project>;
+
+    /// Get a project by its name.
+    fn get_project_by_name<S: AsRef<str>>(&self, name: S)
+        -> Result<protocol::Project>;
+
+    /// Get all parents of a project.
+    fn get_project_parents<S: AsRef<str>>(&self, id: S)
+        -> Result<Vec<protocol::Project>>;
+
+    /// Get all projects in the subtree of a project.
+    fn get_project_subtree<S: AsRef<str>>(&self, id: S)
+        -> Result<Vec<protocol::Project>>;
+
+    /// List projects.
+    fn list_projects<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::Project>>;
+}
+
+
+/// Service type of Identity API V3.
+#[derive(Copy, Clone, Debug)]
+pub struct V3;
+
+
+const SERVICE_TYPE: &'static str = "identity";
+const VERSION_ID: &'static str = "v3";
+
+
+/// Fetch a project with a hierarchy flag (e.g. `subtree_as_list`) set.
+fn get_project_with<S: AsRef<str>>(session: &Session, id: S, flag: &str)
+        -> Result<protocol::Project> {
+    session.request::<V3>(Method::Get, &["projects", id.as_ref()], None)?
+        .query(&[(flag, "")])
+        .receive_json::<protocol::ProjectRoot>().map(|root| root.project)
+}
+
+impl V3API for Session {
+    fn get_project_by_id<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::Project> {
+        trace!("Fetching project {}", id.as_ref());
+        let project = self.request::<V3>(Method::Get,
+                                         &["projects", id.as_ref()],
+                                         None)?
+           .receive_json::<protocol::ProjectRoot>()?.project;
+        trace!("Received {:?}", project);
+        Ok(project)
+    }
+
+    fn get_project_by_name<S: AsRef<str>>(&self, name: S)
+            -> Result<protocol::Project> {
+        trace!("Get project by name {}", name.as_ref());
+        let items = self.request::<V3>(Method::Get, &["projects"], None)?
+            .query(&[("name", name.as_ref())])
+            .receive_json::<protocol::ProjectsRoot>()?.projects;
+        let result = utils::one(items, "Project with given name or ID not found",
+                                "Too many projects found with given name")?;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_project_parents<S: AsRef<str>>(&self, id: S)
+            -> Result<Vec<protocol::Project>> {
+        trace!("Fetching parents of project {}", id.as_ref());
+        let project = get_project_with(self, id, "parents_as_list")?;
+        let result = project.parents.unwrap_or_default().into_iter()
+            .map(|root| root.project).collect::<Vec<_>>();
+        trace!("Received parents: {:?}", result);
+        Ok(result)
+    }
+
+    fn get_project_subtree<S: AsRef<str>>(&self, id: S)
+            -> Result<Vec<protocol::Project>> {
+        trace!("Fetching subtree of project {}", id.as_ref());
+        let project = get_project_with(self, id, "subtree_as_list")?;
+        let result = project.subtree.unwrap_or_default().into_iter()
+            .map(|root| root.project).collect::<Vec<_>>();
+        trace!("Received subtree: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_projects<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::Project>> {
+        trace!("Listing projects with {:?}", query);
+        let result = self.request::<V3>(Method::Get, &["projects"], None)?
+           .query(query).receive_json::<protocol::ProjectsRoot>()?.projects;
+        trace!("Received projects: {:?}", result);
+        Ok(result)
+    }
+}
+
+
+impl ServiceType for V3 {
+    fn catalog_type() -> &'static str {
+        SERVICE_TYPE
+    }
+
+    fn service_info(endpoint: Url, auth: &AuthMethod) -> Result<ServiceInfo> {
+        // Identity endpoints in the catalog are often unversioned.
+        let versioned = endpoint.path_segments().and_then(|segments| {
+            segments.filter(|x| !x.is_empty()).last()
+        }).map(|x| x.starts_with(VERSION_ID)).unwrap_or(false);
+        let endpoint = if versioned {
+            endpoint
+        } else {
+            utils::url::join(endpoint, VERSION_ID)
+        };
+        common::protocol::fetch_service_info(endpoint, auth, SERVICE_TYPE, VERSION_ID)
+    }
+}
@@ -14,5 +14,12 @@
 
 //! Identity API support.
 
-pub mod catalog;
-pub mod protocol;
+#[cfg(feature = "identity")]
+mod base;
+pub(crate) mod catalog;
+#[cfg(feature = "identity")]
+mod projects;
+pub(crate) mod protocol;
+
+#[cfg(feature = "identity")]
+pub use self::projects::{Project, ProjectQuery};
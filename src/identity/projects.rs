@@ -0,0 +1,355 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Project management via Identity API.
+
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
+use serde::Serialize;
+
+use super::super::{Error, Result};
+use super::super::common::{ListResources, ProjectRef, Refresh, ResourceId,
+                           ResourceIterator};
+use super::super::session::Session;
+use super::super::utils::Query;
+use super::base::V3API;
+use super::protocol;
+
+
+/// A query to project list.
+#[derive(Clone, Debug)]
+pub struct ProjectQuery {
+    session: Rc<Session>,
+    query: Query,
+}
+
+/// Structure representing a single project.
+#[derive(Clone, Debug)]
+pub struct Project {
+    session: Rc<Session>,
+    inner: protocol::Project
+}
+
+impl Project {
+    /// Create a Project object from its protocol representation.
+    fn from_inner(session: Rc<Session>, mut inner: protocol::Project) -> Project {
+        // Hierarchy is only fetched on demand.
+        inner.parents = None;
+        inner.subtree = None;
+        Project {
+            session: session,
+            inner: inner
+        }
+    }
+
+    /// Load a Project object.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<Project> {
+        let inner = session.get_project(id)?;
+        Ok(Project::from_inner(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Project description."]
+        description: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the domain that owns the project."]
+        domain_id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Whether the project is enabled."]
+        enabled: bool
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Whether the project also acts as a domain."]
+        is_domain: bool
+    }
+
+    transparent_property! {
+        #[doc = "Project name."]
+        name: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the parent project (if any)."]
+        parent_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Project tags."]
+        tags: ref Vec<String>
+    }
+
+    /// Fetch all ancestors of this project.
+    ///
+    /// Only the projects the current user has access to are returned.
+    pub fn parents(&self) -> Result<Vec<Project>> {
+        let parents = self.session.get_project_parents(&self.inner.id)?;
+        Ok(parents.into_iter().map(|item| {
+            Project::from_inner(self.session.clone(), item)
+        }).collect())
+    }
+
+    /// Fetch all descendants of this project (not including the project).
+    ///
+    /// Only the projects the current user has access to are returned.
+    pub fn subtree(&self) -> Result<Vec<Project>> {
+        let subtree = self.session.get_project_subtree(&self.inner.id)?;
+        Ok(subtree.into_iter().map(|item| {
+            Project::from_inner(self.session.clone(), item)
+        }).collect())
+    }
+}
+
+impl Refresh for Project {
+    /// Refresh the project.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_project_by_id(&self.inner.id)?;
+        Ok(())
+    }
+}
+
+impl ProjectQuery {
+    pub(crate) fn new(session: Rc<Session>) -> ProjectQuery {
+        ProjectQuery {
+            session: session,
+            query: Query::new(),
+        }
+    }
+
+    query_filter! {
+        #[doc = "Filter by domain ID."]
+        with_domain_id -> domain_id
+    }
+
+    query_filter! {
+        #[doc = "Filter by whether the project is enabled."]
+        with_enabled -> enabled: bool
+    }
+
+    query_filter! {
+        #[doc = "Filter by whether the project acts as a domain."]
+        with_is_domain -> is_domain: bool
+    }
+
+    query_filter! {
+        #[doc = "Filter by project name."]
+        with_name -> name
+    }
+
+    /// Filter by parent project ID.
+    pub fn with_parent<T: Into<ProjectRef>>(mut self, value: T) -> Self {
+        self.query.push_str("parent_id", value.into());
+        self
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<Project> {
+        debug!("Fetching projects with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<Project>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(self) -> Result<Project> {
+        debug!("Fetching one project with {:?}", self.query);
+        self.into_iter().one()
+    }
+}
+
+impl ResourceId for Project {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for Project {
+    const DEFAULT_LIMIT: usize = 50;
+
+    /// Identity API does not support marker-based pagination.
+    fn can_paginate(_session: &Session) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<Project>> {
+        Ok(session.list_projects(&query)?.into_iter().map(|item| {
+            Project::from_inner(session.clone(), item)
+        }).collect())
+    }
+}
+
+impl IntoFallibleIterator for ProjectQuery {
+    type Item = Project;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<Project>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<Project> {
+        self.into_iter()
+    }
+}
+
+impl From<Project> for ProjectRef {
+    fn from(value: Project) -> ProjectRef {
+        ProjectRef::new_verified(value.inner.id)
+    }
+}
+
+impl ProjectRef {
+    /// Verify this reference and convert to an ID, if possible.
+    #[allow(dead_code)]  // unused with some feature combinations
+    pub(crate) fn into_verified(self, session: &Session) -> Result<String> {
+        Ok(if self.verified {
+            self.value
+        } else {
+            session.get_project(&self.value)?.id
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json;
+
+    use super::super::super::{Cloud, ErrorKind};
+    use super::super::super::utils::test::FakeServer;
+    use super::super::protocol;
+
+    const PROJECT: &'static str = r#"{"project": {
+        "description": "", "domain_id": "default", "enabled": true,
+        "id": "p1", "name": "demo", "parent_id": "default",
+        "tags": ["prod"],
+        "parents": [{"project": {"domain_id": "default", "enabled": true,
+                                 "id": "default", "is_domain": true,
+                                 "name": "Default"}}]}}"#;
+
+    #[test]
+    fn test_project_deserialization() {
+        let project: protocol::Project = serde_json::from_str(
+            r#"{"description": "", "domain_id": "default", "enabled": false,
+                "id": "p1", "name": "demo"}"#).unwrap();
+        assert_eq!(project.description, None);
+        assert!(!project.enabled);
+        assert!(!project.is_domain);
+        assert_eq!(project.parent_id, None);
+        assert!(project.parents.is_none());
+        assert!(project.subtree.is_none());
+        assert!(project.tags.is_empty());
+    }
+
+    #[test]
+    fn test_get_project() {
+        let server = FakeServer::with_version("v3", "");
+        server.respond("GET", "/projects/p1", 200, PROJECT);
+        let cloud = Cloud::from(server.session());
+
+        let project = cloud.get_project("p1").unwrap();
+        assert_eq!(project.id(), "p1");
+        assert_eq!(project.name(), "demo");
+        assert_eq!(project.description(), &None);
+        assert_eq!(project.domain_id(), "default");
+        assert_eq!(project.parent_id(), &Some(String::from("default")));
+        assert_eq!(project.tags(), &vec![String::from("prod")]);
+    }
+
+    #[test]
+    fn test_get_project_by_name() {
+        let server = FakeServer::with_version("v3", "");
+        server.respond("GET", "/projects", 200,
+                       r#"{"projects": [{"domain_id": "default",
+                           "enabled": true, "id": "p1", "name": "demo"}]}"#);
+        let cloud = Cloud::from(server.session());
+
+        let project = cloud.get_project("demo").unwrap();
+        assert_eq!(project.id(), "p1");
+        let requests = server.requests();
+        assert!(requests.contains(&String::from("GET /projects/demo")));
+        assert!(requests.contains(&String::from("GET /projects?name=demo")));
+    }
+
+    #[test]
+    fn test_get_project_not_found() {
+        let server = FakeServer::with_version("v3", "");
+        server.respond("GET", "/projects", 200, r#"{"projects": []}"#);
+        let cloud = Cloud::from(server.session());
+
+        let err = cloud.get_project("missing").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::ResourceNotFound);
+    }
+
+    #[test]
+    fn test_project_parents() {
+        let server = FakeServer::with_version("v3", "");
+        server.respond("GET", "/projects/p1", 200, PROJECT);
+        let cloud = Cloud::from(server.session());
+
+        let project = cloud.get_project("p1").unwrap();
+        let parents = project.parents().unwrap();
+        assert_eq!(parents.len(), 1);
+        assert_eq!(parents[0].id(), "default");
+        assert!(parents[0].is_domain());
+        assert!(server.requests().iter()
+                .any(|req| req.starts_with("GET /projects/p1?parents_as_list")));
+    }
+
+    #[test]
+    fn test_find_projects() {
+        let server = FakeServer::with_version("v3", "");
+        server.respond("GET", "/projects", 200,
+                       r#"{"projects": [{"domain_id": "default",
+                           "enabled": true, "id": "p2", "name": "child",
+                           "parent_id": "p1"}]}"#);
+        let cloud = Cloud::from(server.session());
+
+        let projects = cloud.find_projects().with_parent("p1")
+            .with_enabled(true).all().unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].parent_id(), &Some(String::from("p1")));
+
+        // Identity API does not paginate, so only one request is expected
+        let requests = server.requests().into_iter()
+            .filter(|req| req.starts_with("GET /projects?"))
+            .collect::<Vec<_>>();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("parent_id=p1"));
+        assert!(requests[0].contains("enabled=true"));
+    }
+}
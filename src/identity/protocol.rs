@@ -44,14 +44,14 @@ pub struct PasswordIdentity {
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct Project {
+pub struct ScopedProject {
     pub name: String,
     pub domain: Domain
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ProjectScope {
-    pub project: Project
+    pub project: ScopedProject
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -96,6 +96,39 @@ pub struct TokenRoot {
     pub token: Token
 }
 
+/// A project.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Project {
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default)]
+    pub description: Option<String>,
+    pub domain_id: String,
+    pub enabled: bool,
+    pub id: String,
+    #[serde(default)]
+    pub is_domain: bool,
+    pub name: String,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub parents: Option<Vec<ProjectRoot>>,
+    #[serde(default)]
+    pub subtree: Option<Vec<ProjectRoot>>,
+    #[serde(default)]
+    pub tags: Vec<String>
+}
+
+/// A project wrapped in its root element.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProjectRoot {
+    pub project: Project
+}
+
+/// A list of projects.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProjectsRoot {
+    pub projects: Vec<Project>
+}
+
 const PASSWORD_METHOD: &'static str = "password";
 
 
@@ -130,7 +163,7 @@ impl ProjectScope {
     pub fn new<S1, S2>(project_name: S1, domain_name: S2) -> ProjectScope
             where S1: Into<String>, S2: Into<String> {
         ProjectScope {
-            project: Project {
+            project: ScopedProject {
                 name: project_name.into(),
                 domain: Domain {
                     name: domain_name.into()
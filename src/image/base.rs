@@ -15,20 +15,45 @@
 //! Foundation bits exposing the Image API.
 
 use std::fmt::Debug;
+use std::io::Read;
 
-use reqwest::{Method, Url};
+use reqwest::{Body, Method, Response, Url};
+use reqwest::header::{ContentType, Headers};
 use serde::Serialize;
 
 use super::super::Result;
 use super::super::auth::AuthMethod;
 use super::super::common;
 use super::super::session::{Session, ServiceInfo, ServiceType};
-use super::super::utils::{self, ResultExt};
+use super::super::utils::{self, GuardedRead, ResultExt};
 use super::protocol;
 
 
 /// Extensions for Session.
 pub trait V2API {
+    /// Add a member to a shared image.
+    fn add_image_member<S1, S2>(&self, id: S1, member_id: S2)
+        -> Result<protocol::ImageMember>
+        where S1: AsRef<str>, S2: Into<String>;
+
+    /// Create an image record without data.
+    fn create_image(&self, request: protocol::ImageCreate)
+        -> Result<protocol::Image>;
+
+    /// Deactivate an image.
+    fn deactivate_image<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Delete an image.
+    fn delete_image<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Remove a member from a shared image.
+    fn delete_image_member<S1, S2>(&self, id: S1, member_id: S2) -> Result<()>
+        where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Download image data.
+    fn download_image_data<S: AsRef<str>>(&self, id: S)
+        -> Result<GuardedRead<Response>>;
+
     /// Get an image.
     fn get_image<S: AsRef<str>>(&self, id_or_name: S) -> Result<protocol::Image> {
         let s = id_or_name.as_ref();
@@ -41,9 +66,47 @@ pub trait V2API {
     /// Get an image by its name.
     fn get_image_by_name<S: AsRef<str>>(&self, id: S) -> Result<protocol::Image>;
 
+    /// Get a member of a shared image.
+    fn get_image_member<S1, S2>(&self, id: S1, member_id: S2)
+        -> Result<protocol::ImageMember>
+        where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Get a task by its ID.
+    fn get_task<S: AsRef<str>>(&self, id: S) -> Result<protocol::Task>;
+
+    /// List members of a shared image.
+    fn list_image_members<S: AsRef<str>>(&self, id: S)
+        -> Result<Vec<protocol::ImageMember>>;
+
+    /// List tasks associated with an image.
+    fn list_image_tasks<S: AsRef<str>>(&self, id: S)
+        -> Result<Vec<protocol::Task>>;
+
     /// List images.
     fn list_images<Q: Serialize + Debug>(&self, query: &Q)
         -> Result<Vec<protocol::Image>>;
+
+    /// List tasks.
+    fn list_tasks<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::Task>>;
+
+    /// Reactivate an image.
+    fn reactivate_image<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Update an image with a JSON-patch document.
+    fn update_image<S: AsRef<str>>(&self, id: S,
+                                   update: Vec<protocol::ImagePatchOperation>)
+        -> Result<protocol::Image>;
+
+    /// Update the status of a member of a shared image.
+    fn update_image_member<S1, S2>(&self, id: S1, member_id: S2,
+                                   status: protocol::ImageMemberStatus)
+        -> Result<protocol::ImageMember>
+        where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Upload image data.
+    fn upload_image_data<S, R>(&self, id: S, data: R) -> Result<()>
+        where S: AsRef<str>, R: Read + Send + 'static;
 }
 
 
@@ -55,9 +118,76 @@ pub struct V2;
 const SERVICE_TYPE: &'static str = "image";
 // FIXME(dtantsur): detect versions instead of hardcoding Kilo.
 const VERSION_ID: &'static str = "v2.3";
+const PATCH_CONTENT_TYPE: &'static str =
+    "application/openstack-images-v2.1-json-patch";
 
 
 impl V2API for Session {
+    fn add_image_member<S1, S2>(&self, id: S1, member_id: S2)
+            -> Result<protocol::ImageMember>
+            where S1: AsRef<str>, S2: Into<String> {
+        let request = protocol::ImageMemberCreate { member: member_id.into() };
+        debug!("Adding member {} to image {}", request.member, id.as_ref());
+        let member = self.request::<V2>(Method::Post,
+                                        &["images", id.as_ref(), "members"],
+                                        None)?
+            .json(&request).receive_json::<protocol::ImageMember>()?;
+        debug!("Added image member {:?}", member);
+        Ok(member)
+    }
+
+    fn create_image(&self, request: protocol::ImageCreate)
+            -> Result<protocol::Image> {
+        debug!("Creating an image with {:?}", request);
+        let image = self.request::<V2>(Method::Post, &["images"], None)?
+            .json(&request).receive_json::<protocol::Image>()?;
+        debug!("Created image {:?}", image);
+        Ok(image)
+    }
+
+    fn deactivate_image<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deactivating image {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Post,
+                                   &["images", id.as_ref(), "actions",
+                                     "deactivate"],
+                                   None)?
+            .send()?;
+        debug!("Image {} was deactivated", id.as_ref());
+        Ok(())
+    }
+
+    fn delete_image<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting image {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["images", id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Image {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn delete_image_member<S1, S2>(&self, id: S1, member_id: S2) -> Result<()>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        debug!("Removing member {} from image {}", member_id.as_ref(),
+               id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["images", id.as_ref(), "members",
+                                     member_id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Member {} was removed from image {}", member_id.as_ref(),
+               id.as_ref());
+        Ok(())
+    }
+
+    fn download_image_data<S: AsRef<str>>(&self, id: S)
+            -> Result<GuardedRead<Response>> {
+        debug!("Downloading data for image {}", id.as_ref());
+        self.request::<V2>(Method::Get, &["images", id.as_ref(), "file"],
+                           None)?
+            .send_streaming()
+    }
+
     fn get_image_by_id<S: AsRef<str>>(&self, id: S) -> Result<protocol::Image> {
         trace!("Fetching image {}", id.as_ref());
         let image = self.request::<V2>(Method::Get,
@@ -79,6 +209,51 @@ impl V2API for Session {
         Ok(result)
     }
 
+    fn get_image_member<S1, S2>(&self, id: S1, member_id: S2)
+            -> Result<protocol::ImageMember>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        trace!("Fetching member {} of image {}", member_id.as_ref(),
+               id.as_ref());
+        let member = self.request::<V2>(Method::Get,
+                                        &["images", id.as_ref(), "members",
+                                          member_id.as_ref()],
+                                        None)?
+           .receive_json::<protocol::ImageMember>()?;
+        trace!("Received {:?}", member);
+        Ok(member)
+    }
+
+    fn get_task<S: AsRef<str>>(&self, id: S) -> Result<protocol::Task> {
+        trace!("Fetching task {}", id.as_ref());
+        let task = self.request::<V2>(Method::Get, &["tasks", id.as_ref()],
+                                      None)?
+           .receive_json::<protocol::Task>()?;
+        trace!("Received {:?}", task);
+        Ok(task)
+    }
+
+    fn list_image_members<S: AsRef<str>>(&self, id: S)
+            -> Result<Vec<protocol::ImageMember>> {
+        trace!("Listing members of image {}", id.as_ref());
+        let result = self.request::<V2>(Method::Get,
+                                        &["images", id.as_ref(), "members"],
+                                        None)?
+           .receive_json::<protocol::ImageMembersRoot>()?.members;
+        trace!("Received image members: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_image_tasks<S: AsRef<str>>(&self, id: S)
+            -> Result<Vec<protocol::Task>> {
+        trace!("Listing tasks of image {}", id.as_ref());
+        let result = self.request::<V2>(Method::Get,
+                                        &["images", id.as_ref(), "tasks"],
+                                        None)?
+           .receive_json::<protocol::TasksRoot>()?.tasks;
+        trace!("Received tasks: {:?}", result);
+        Ok(result)
+    }
+
     fn list_images<Q: Serialize + Debug>(&self, query: &Q)
             -> Result<Vec<protocol::Image>> {
         trace!("Listing images with {:?}", query);
@@ -87,6 +262,69 @@ impl V2API for Session {
         trace!("Received images: {:?}", result);
         Ok(result)
     }
+
+    fn list_tasks<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::Task>> {
+        trace!("Listing tasks with {:?}", query);
+        let result = self.request::<V2>(Method::Get, &["tasks"], None)?
+           .query(query).receive_json::<protocol::TasksRoot>()?.tasks;
+        trace!("Received tasks: {:?}", result);
+        Ok(result)
+    }
+
+    fn reactivate_image<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Reactivating image {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Post,
+                                   &["images", id.as_ref(), "actions",
+                                     "reactivate"],
+                                   None)?
+            .send()?;
+        debug!("Image {} was reactivated", id.as_ref());
+        Ok(())
+    }
+
+    fn update_image<S: AsRef<str>>(&self, id: S,
+                                   update: Vec<protocol::ImagePatchOperation>)
+            -> Result<protocol::Image> {
+        debug!("Updating image {} with {:?}", id.as_ref(), update);
+        let mut headers = Headers::new();
+        headers.set_raw("Content-Type", PATCH_CONTENT_TYPE);
+        let image = self.request::<V2>(Method::Patch, &["images", id.as_ref()],
+                                       None)?
+            .json(&update).headers(headers)
+            .receive_json::<protocol::Image>()?;
+        debug!("Updated image {:?}", image);
+        Ok(image)
+    }
+
+    fn update_image_member<S1, S2>(&self, id: S1, member_id: S2,
+                                   status: protocol::ImageMemberStatus)
+            -> Result<protocol::ImageMember>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        debug!("Setting status of member {} of image {} to {}",
+               member_id.as_ref(), id.as_ref(), status);
+        let request = protocol::ImageMemberUpdate { status: status };
+        let member = self.request::<V2>(Method::Put,
+                                        &["images", id.as_ref(), "members",
+                                          member_id.as_ref()],
+                                        None)?
+            .json(&request).receive_json::<protocol::ImageMember>()?;
+        debug!("Updated image member {:?}", member);
+        Ok(member)
+    }
+
+    fn upload_image_data<S, R>(&self, id: S, data: R) -> Result<()>
+            where S: AsRef<str>, R: Read + Send + 'static {
+        debug!("Uploading data for image {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Put,
+                                   &["images", id.as_ref(), "file"],
+                                   None)?
+            .header(ContentType::octet_stream())
+            .body(Body::new(data))
+            .send()?;
+        debug!("Data for image {} was uploaded", id.as_ref());
+        Ok(())
+    }
 }
 
 
@@ -14,20 +14,25 @@
 
 //! Image management via Image API.
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
 use std::rc::Rc;
 
 use chrono::{DateTime, FixedOffset};
 use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
 use serde::Serialize;
+use serde_json::Value;
 
-use super::super::{Error, Result, Sort};
-use super::super::common::{ImageRef, ListResources, Refresh, ResourceId,
-                           ResourceIterator};
+use super::super::{Error, ErrorKind, Result, Sort};
+use super::super::common::{self, ImageRef, ListResources, ProjectRef,
+                           Refresh, ResourceId, ResourceIterator};
 use super::super::session::Session;
 use super::super::utils::Query;
 use super::base::V2API;
-use super::protocol;
+use super::{protocol, ImageMember, Task};
 
 
 /// A query to image list.
@@ -36,6 +41,8 @@ pub struct ImageQuery {
     session: Rc<Session>,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
     sort: Vec<String>
 }
 
@@ -43,18 +50,62 @@ pub struct ImageQuery {
 #[derive(Clone, Debug)]
 pub struct Image {
     session: Rc<Session>,
-    inner: protocol::Image
+    inner: protocol::Image,
+    properties: HashMap<String, String>,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create an image.
+#[derive(Clone, Debug)]
+pub struct NewImage {
+    session: Rc<Session>,
+    inner: protocol::ImageCreate
+}
+
+/// Image fields that are not custom properties.
+const RESERVED_FIELDS: &'static [&'static str] = &[
+    "file", "locations", "os_hash_algo", "os_hash_value", "os_hidden",
+    "owner", "schema", "self", "stores"
+];
+
+fn convert_properties(inner: &protocol::Image) -> HashMap<String, String> {
+    inner.extra.iter().filter_map(|(key, value)| match *value {
+        Value::String(ref s) if ! RESERVED_FIELDS.contains(&key.as_str()) =>
+            Some((key.clone(), s.clone())),
+        _ => None
+    }).collect()
+}
+
+/// Escape a key for use in a JSON pointer.
+fn patch_path(key: &str) -> String {
+    format!("/{}", key.replace("~", "~0").replace("/", "~1"))
+}
+
+fn patch_replace<T: Into<Value>>(key: &str, value: T)
+        -> protocol::ImagePatchOperation {
+    protocol::ImagePatchOperation::Replace {
+        path: patch_path(key),
+        value: value.into()
+    }
 }
 
 impl Image {
+    /// Create an Image object from its protocol representation.
+    fn from_inner(session: Rc<Session>, inner: protocol::Image) -> Image {
+        let properties = convert_properties(&inner);
+        Image {
+            session: session,
+            inner: inner,
+            properties: properties,
+            dirty: HashSet::new(),
+        }
+    }
+
     /// Load a Image object.
     pub(crate) fn new<Id: AsRef<str>>(session: Rc<Session>, id: Id)
             -> Result<Image> {
         let inner = session.get_image(id)?;
-        Ok(Image {
-            session: session,
-            inner: inner
-        })
+        Ok(Image::from_inner(session, inner))
     }
 
     transparent_property! {
@@ -106,6 +157,64 @@ impl Image {
         name: ref String
     }
 
+    update_field! {
+        #[doc = "Update the image name."]
+        set_name, with_name -> name
+    }
+
+    /// Custom image properties.
+    pub fn properties(&self) -> &HashMap<String, String> {
+        &self.properties
+    }
+
+    /// Mutable access to custom image properties.
+    #[allow(unused_results)]
+    pub fn properties_mut(&mut self) -> &mut HashMap<String, String> {
+        self.dirty.insert("properties");
+        &mut self.properties
+    }
+
+    /// Set a custom property.
+    pub fn set_property<K, V>(&mut self, key: K, value: V)
+            where K: Into<String>, V: Into<String> {
+        let _ = self.properties_mut().insert(key.into(), value.into());
+    }
+
+    /// Set a custom property.
+    pub fn with_property<K, V>(mut self, key: K, value: V) -> Self
+            where K: Into<String>, V: Into<String> {
+        self.set_property(key, value);
+        self
+    }
+
+    /// Remove a custom property.
+    pub fn remove_property<K: AsRef<str>>(&mut self, key: K) {
+        let _ = self.properties_mut().remove(key.as_ref());
+    }
+
+    transparent_property! {
+        #[doc = "Whether the image is protected from deletion."]
+        protected: bool
+    }
+
+    update_field! {
+        #[doc = "Update whether the image is protected from deletion."]
+        set_protected, with_protected -> protected: bool
+    }
+
+    /// Signature of the image data, if the image is signed.
+    ///
+    /// Read from the `img_signature*` properties, all of which must be set.
+    pub fn signature(&self) -> Option<protocol::ImageSignature> {
+        let get = |key: &str| self.properties.get(key).cloned();
+        Some(protocol::ImageSignature {
+            signature: get("img_signature")?,
+            hash_method: get("img_signature_hash_method")?,
+            key_type: get("img_signature_key_type")?,
+            certificate_id: get("img_signature_certificate_uuid")?,
+        })
+    }
+
     transparent_property! {
         #[doc = "Image size in bytes."]
         size: Option<u64>
@@ -116,6 +225,42 @@ impl Image {
         status: protocol::ImageStatus
     }
 
+    transparent_property! {
+        #[doc = "Image tags."]
+        tags: ref Vec<String>
+    }
+
+    /// Mutable access to image tags.
+    #[allow(unused_results)]
+    pub fn tags_mut(&mut self) -> &mut Vec<String> {
+        self.dirty.insert("tags");
+        &mut self.inner.tags
+    }
+
+    /// Add a tag to the image (unless it is already present).
+    pub fn add_tag<S: Into<String>>(&mut self, tag: S) {
+        let tag = tag.into();
+        if ! self.inner.tags.contains(&tag) {
+            self.tags_mut().push(tag);
+        }
+    }
+
+    /// Add a tag to the image (unless it is already present).
+    pub fn with_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.add_tag(tag);
+        self
+    }
+
+    /// Remove a tag from the image.
+    pub fn remove_tag<S: AsRef<str>>(&mut self, tag: S) {
+        self.tags_mut().retain(|item| item != tag.as_ref());
+    }
+
+    update_field! {
+        #[doc = "Update the image tags."]
+        set_tags, with_tags -> tags: Vec<String>
+    }
+
     transparent_property! {
         #[doc = "Last update date and time."]
         updated_at: DateTime<FixedOffset>
@@ -130,12 +275,183 @@ impl Image {
         #[doc = "Image visibility."]
         visibility: protocol::ImageVisibility
     }
+
+    update_field! {
+        #[doc = "Update the image visibility."]
+        set_visibility, with_visibility -> visibility: protocol::ImageVisibility
+    }
+
+    /// Deactivate the image, making its data unavailable to non-admins.
+    pub fn deactivate(&mut self) -> Result<()> {
+        self.session.deactivate_image(&self.inner.id)?;
+        self.refresh()
+    }
+
+    /// Delete the image.
+    pub fn delete(self) -> Result<()> {
+        self.session.delete_image(&self.inner.id)
+    }
+
+    /// Whether the image is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Reactivate a deactivated image.
+    pub fn reactivate(&mut self) -> Result<()> {
+        self.session.reactivate_image(&self.inner.id)?;
+        self.refresh()
+    }
+
+    /// Download the image data.
+    ///
+    /// The returned reader streams the data from the Image service.
+    pub fn download(&self) -> Result<Box<Read + Send>> {
+        Ok(Box::new(self.session.download_image_data(&self.inner.id)?))
+    }
+
+    /// Download the image data into a file.
+    ///
+    /// The file is created or truncated. Returns the number of bytes written.
+    /// If the download fails or its size does not match the image size,
+    /// the file is removed.
+    pub fn download_to<P: AsRef<Path>>(&self, path: P) -> Result<u64> {
+        let path = path.as_ref();
+        let mut file = File::create(path)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput,
+                                    format!("Cannot create {:?}: {}", path, e)))?;
+        let mut data = self.download()?;
+        let result = io::copy(&mut data, &mut file)
+            .map_err(|e| Error::new(ErrorKind::OperationFailed,
+                                    format!("Failed to download image {} \
+                                             into {:?}: {}",
+                                            self.inner.id, path, e)))
+            .and_then(|written| match self.inner.size {
+                Some(size) if size != written =>
+                    Err(Error::new(ErrorKind::InvalidResponse,
+                                   format!("Downloaded {} bytes of image {}, \
+                                            expected {}", written,
+                                           self.inner.id, size))),
+                _ => Ok(written)
+            });
+
+        drop(file);
+        if result.is_err() {
+            if let Err(rm_err) = fs::remove_file(path) {
+                warn!("Failed to remove incomplete download {:?}: {}",
+                      path, rm_err);
+            }
+        }
+        result
+    }
+
+    /// Upload the image data, streaming it from the reader.
+    ///
+    /// The image must be in the `queued` state, i.e. no data uploaded yet.
+    /// The upload is not retried on authentication failures, so make sure
+    /// the token is not about to expire for large images.
+    pub fn upload<R>(&mut self, data: R) -> Result<()>
+            where R: Read + Send + 'static {
+        self.session.upload_image_data(&self.inner.id, data)?;
+        self.refresh()
+    }
+
+    /// Share the image with a project.
+    ///
+    /// The image visibility must be `shared`. The project has to accept
+    /// the image before it appears in its image list.
+    pub fn add_member<P: Into<ProjectRef>>(&self, project: P)
+            -> Result<ImageMember> {
+        let project_id = project.into().into_verified(&self.session)?;
+        let member = self.session.add_image_member(&self.inner.id, project_id)?;
+        Ok(ImageMember::new(self.session.clone(), member))
+    }
+
+    /// Projects the image is shared with.
+    ///
+    /// Members only see their own membership.
+    pub fn list_members(&self) -> Result<Vec<ImageMember>> {
+        Ok(self.session.list_image_members(&self.inner.id)?.into_iter()
+           .map(|item| ImageMember::new(self.session.clone(), item)).collect())
+    }
+
+    /// Stop sharing the image with a project.
+    pub fn remove_member<P: Into<ProjectRef>>(&self, project: P) -> Result<()> {
+        let project_id = project.into().into_verified(&self.session)?;
+        self.session.delete_image_member(&self.inner.id, project_id)
+    }
+
+    /// Tasks associated with the image, e.g. imports.
+    ///
+    /// Requires Image API 2.12 (Train) or newer.
+    pub fn tasks(&self) -> Result<Vec<Task>> {
+        Ok(self.session.list_image_tasks(&self.inner.id)?.into_iter()
+           .map(|item| Task::new(self.session.clone(), item)).collect())
+    }
+
+    /// Save the changes to the image.
+    ///
+    /// Only modified fields are sent, as a JSON-patch document.
+    pub fn save(&mut self) -> Result<()> {
+        let update = self.patch();
+        if ! update.is_empty() {
+            let inner = self.session.update_image(&self.inner.id, update)?;
+            self.properties = convert_properties(&inner);
+            self.inner = inner;
+        }
+        self.dirty.clear();
+        Ok(())
+    }
+
+    /// Build a JSON-patch document from the modified fields.
+    fn patch(&self) -> Vec<protocol::ImagePatchOperation> {
+        let mut result = Vec::new();
+        if self.dirty.contains("name") {
+            result.push(patch_replace("name", self.inner.name.clone()));
+        }
+        if self.dirty.contains("protected") {
+            result.push(patch_replace("protected", self.inner.protected));
+        }
+        if self.dirty.contains("tags") {
+            result.push(patch_replace("tags", self.inner.tags.clone()));
+        }
+        if self.dirty.contains("visibility") {
+            result.push(patch_replace("visibility",
+                                      String::from(self.inner.visibility)));
+        }
+        if self.dirty.contains("properties") {
+            let original = convert_properties(&self.inner);
+            let mut keys = original.keys().chain(self.properties.keys())
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                match (original.get(key), self.properties.get(key)) {
+                    (None, Some(value)) => result.push(
+                        protocol::ImagePatchOperation::Add {
+                            path: patch_path(key),
+                            value: Value::String(value.clone())
+                        }),
+                    (Some(old), Some(value)) if old != value =>
+                        result.push(patch_replace(key, value.clone())),
+                    (Some(_), None) => result.push(
+                        protocol::ImagePatchOperation::Remove {
+                            path: patch_path(key)
+                        }),
+                    _ => ()
+                }
+            }
+        }
+        result
+    }
 }
 
 impl Refresh for Image {
     /// Refresh the image.
     fn refresh(&mut self) -> Result<()> {
         self.inner = self.session.get_image(&self.inner.id)?;
+        self.properties = convert_properties(&self.inner);
+        self.dirty.clear();
         Ok(())
     }
 }
@@ -146,6 +462,8 @@ impl ImageQuery {
             session: session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            max_items: None,
             sort: Vec::new()
         }
     }
@@ -175,16 +493,88 @@ impl ImageQuery {
         self
     }
 
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Filter by creation date and time.
+    ///
+    /// Can be used twice to specify a range.
+    pub fn with_created_at(mut self, comparison: protocol::DateComparison,
+                           value: DateTime<FixedOffset>) -> Self {
+        self.query.push_str("created_at",
+                            format!("{}:{}", comparison, value.to_rfc3339()));
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by member status of shared images."]
+        with_member_status -> member_status: protocol::ImageMemberStatus
+    }
+
     query_filter! {
         #[doc = "Filter by image name."]
         with_name -> name
     }
 
+    /// Filter by owner.
+    pub fn with_owner<T: Into<ProjectRef>>(mut self, value: T) -> Self {
+        self.query.push_str("owner", value.into());
+        self
+    }
+
+    /// Only images shared with the current project with the given status.
+    ///
+    /// Use `ImageMemberStatus::Pending` to find images awaiting acceptance.
+    pub fn with_shared_status(self, status: protocol::ImageMemberStatus)
+            -> Self {
+        self.with_visibility(protocol::ImageVisibility::Shared)
+            .with_member_status(status)
+    }
+
+    query_filter! {
+        #[doc = "Filter by maximum image size in bytes."]
+        with_size_max -> size_max: u64
+    }
+
+    query_filter! {
+        #[doc = "Filter by minimum image size in bytes."]
+        with_size_min -> size_min: u64
+    }
+
     query_filter! {
         #[doc = "Filter by image status."]
         with_status -> status: protocol::ImageStatus
     }
 
+    /// Filter by tag.
+    ///
+    /// Can be used several times, images must have all requested tags.
+    pub fn with_tag<T: Into<String>>(mut self, value: T) -> Self {
+        self.query.push_str("tag", value);
+        self
+    }
+
+    /// Filter by last update date and time.
+    ///
+    /// Can be used twice to specify a range.
+    pub fn with_updated_at(mut self, comparison: protocol::DateComparison,
+                           value: DateTime<FixedOffset>) -> Self {
+        self.query.push_str("updated_at",
+                            format!("{}:{}", comparison, value.to_rfc3339()));
+        self
+    }
+
     query_filter! {
         #[doc = "Filter by visibility."]
         with_visibility -> visibility: protocol::ImageVisibility
@@ -202,6 +592,14 @@ impl ImageQuery {
         }
         debug!("Fetching images with {:?}", self.query);
         ResourceIterator::new(self.session.clone(), self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Convert this query into an iterator fetching `page_size` items per
+    /// request.
+    pub fn into_iter_paginated(self, page_size: usize)
+            -> ResourceIterator<Image> {
+        self.with_page_size(page_size).into_iter()
     }
 
     /// Execute this request and return all results.
@@ -227,6 +625,123 @@ impl ImageQuery {
     }
 }
 
+impl NewImage {
+    /// Start creating an image.
+    pub(crate) fn new(session: Rc<Session>, name: String) -> NewImage {
+        NewImage {
+            session: session,
+            inner: protocol::ImageCreate {
+                architecture: None,
+                container_format: None,
+                disk_format: None,
+                min_disk: None,
+                min_ram: None,
+                name: name,
+                protected: None,
+                tags: Vec::new(),
+                visibility: None,
+                properties: BTreeMap::new()
+            }
+        }
+    }
+
+    /// Request creation of the image record.
+    ///
+    /// The resulting image is in the `queued` state, use `Image::upload` to
+    /// provide the data.
+    pub fn create(self) -> Result<Image> {
+        let inner = self.session.create_image(self.inner)?;
+        Ok(Image::from_inner(self.session, inner))
+    }
+
+    /// Serialize the request body that `create` would send.
+    pub fn to_request_json(&self) -> Result<String> {
+        common::protocol::to_request_json(&self.inner)
+    }
+
+    /// Add a tag to the image.
+    pub fn add_tag<S: Into<String>>(&mut self, tag: S) {
+        self.inner.tags.push(tag.into());
+    }
+
+    /// Add a tag to the image.
+    pub fn with_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.add_tag(tag);
+        self
+    }
+
+    /// Set a custom property.
+    pub fn set_property<K, V>(&mut self, key: K, value: V)
+            where K: Into<String>, V: Into<String> {
+        let _ = self.inner.properties.insert(key.into(), value.into());
+    }
+
+    /// Set a custom property.
+    pub fn with_property<K, V>(mut self, key: K, value: V) -> Self
+            where K: Into<String>, V: Into<String> {
+        self.set_property(key, value);
+        self
+    }
+
+    /// Set the signature of the image data.
+    ///
+    /// The signature is verified by the Image service on upload.
+    pub fn set_signature(&mut self, signature: protocol::ImageSignature) {
+        self.set_property("img_signature", signature.signature);
+        self.set_property("img_signature_hash_method", signature.hash_method);
+        self.set_property("img_signature_key_type", signature.key_type);
+        self.set_property("img_signature_certificate_uuid",
+                          signature.certificate_id);
+    }
+
+    /// Set the signature of the image data.
+    pub fn with_signature(mut self, signature: protocol::ImageSignature)
+            -> Self {
+        self.set_signature(signature);
+        self
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the image architecture."]
+        set_architecture, with_architecture -> architecture: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the container format."]
+        set_container_format, with_container_format -> container_format:
+            optional protocol::ImageContainerFormat
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the disk format."]
+        set_disk_format, with_disk_format -> disk_format:
+            optional protocol::ImageDiskFormat
+    }
+
+    creation_inner_field! {
+        #[doc = "Set minimum required disk size in GiB."]
+        set_minimum_required_disk, with_minimum_required_disk -> min_disk:
+            optional u32
+    }
+
+    creation_inner_field! {
+        #[doc = "Set minimum required RAM size in MiB."]
+        set_minimum_required_ram, with_minimum_required_ram -> min_ram:
+            optional u32
+    }
+
+    creation_inner_field! {
+        #[doc = "Set whether the image is protected from deletion."]
+        set_protected, with_protected -> protected: optional bool
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the image visibility."]
+        set_visibility, with_visibility -> visibility:
+            optional protocol::ImageVisibility
+    }
+}
+
 impl ResourceId for Image {
     fn resource_id(&self) -> String {
         self.id().clone()
@@ -238,9 +753,8 @@ impl ListResources for Image {
 
     fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
             -> Result<Vec<Image>> {
-        Ok(session.list_images(&query)?.into_iter().map(|item| Image {
-            session: session.clone(),
-            inner: item
+        Ok(session.list_images(&query)?.into_iter().map(|item| {
+            Image::from_inner(session.clone(), item)
         }).collect())
     }
 }
@@ -270,7 +784,108 @@ impl ImageRef {
         Ok(if self.verified {
             self.value
         } else {
-            session.get_image(&self.value)?.id
+            session.resolve_id("image", &self.value,
+                               |s| Ok(session.get_image(s)?.id))?
         })
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use chrono::DateTime;
+    use serde_json;
+
+    use super::super::super::Sort;
+    use super::super::super::utils;
+    use super::super::protocol;
+    use super::{Image, ImageQuery};
+
+    fn image() -> Image {
+        let inner: protocol::Image = serde_json::from_str(r#"{
+            "created_at": "2018-01-01T00:00:00Z",
+            "id": "e7db3b45-8db7-47ad-8109-3fb55c2c24fd",
+            "name": "cirros",
+            "os_distro": "cirros",
+            "owner": "admin",
+            "status": "active",
+            "tags": ["small"],
+            "updated_at": "2018-01-01T00:00:00Z",
+            "visibility": "public",
+            "x/y": "z"
+        }"#).unwrap();
+        let session = utils::test::new_session(utils::test::URL);
+        Image::from_inner(Rc::new(session), inner)
+    }
+
+    #[test]
+    fn test_properties() {
+        let image = image();
+        assert_eq!(image.properties().len(), 2);
+        assert_eq!(image.properties()["os_distro"], "cirros");
+        assert!(! image.is_dirty());
+        assert!(image.patch().is_empty());
+    }
+
+    #[test]
+    fn test_patch() {
+        let mut image = image().with_name("cirros-0.4").with_tag("small")
+            .with_tag("tiny").with_property("os_distro", "cirros-ng")
+            .with_property("hw_rng_model", "virtio");
+        image.remove_property("x/y");
+        assert!(image.is_dirty());
+        let expected: serde_json::Value = serde_json::from_str(r#"[
+            {"op": "replace", "path": "/name", "value": "cirros-0.4"},
+            {"op": "replace", "path": "/tags", "value": ["small", "tiny"]},
+            {"op": "add", "path": "/hw_rng_model", "value": "virtio"},
+            {"op": "replace", "path": "/os_distro", "value": "cirros-ng"},
+            {"op": "remove", "path": "/x~1y"}
+        ]"#).unwrap();
+        assert_eq!(serde_json::to_value(image.patch()).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_signature() {
+        let mut image = image();
+        assert!(image.signature().is_none());
+        image.set_property("img_signature", "c2lnbmF0dXJl");
+        image.set_property("img_signature_hash_method", "SHA-256");
+        image.set_property("img_signature_key_type", "RSA-PSS");
+        assert!(image.signature().is_none());
+        image.set_property("img_signature_certificate_uuid", "cert");
+        let signature = image.signature().unwrap();
+        assert_eq!(signature.key_type, "RSA-PSS");
+        assert_eq!(signature.certificate_id, "cert");
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let session = utils::test::new_session(utils::test::URL);
+        let since = DateTime::parse_from_rfc3339("2018-01-01T00:00:00Z")
+            .unwrap();
+        let query = ImageQuery::new(Rc::new(session))
+            .with_visibility(protocol::ImageVisibility::Shared)
+            .with_member_status(protocol::ImageMemberStatus::Accepted)
+            .with_tag("small")
+            .with_tag("linux")
+            .with_size_min(1024)
+            .with_created_at(protocol::DateComparison::GreaterOrEqual, since)
+            .sort_by(Sort::Desc(protocol::ImageSortKey::Size));
+        assert_eq!(query.query.to_query_string(),
+                   "visibility=shared&member_status=accepted&tag=small&\
+                    tag=linux&size_min=1024&\
+                    created_at=gte%3A2018-01-01T00%3A00%3A00%2B00%3A00");
+        assert_eq!(query.sort, vec!["size:desc"]);
+    }
+
+    #[test]
+    fn test_query_shared_status() {
+        let session = utils::test::new_session(utils::test::URL);
+        let query = ImageQuery::new(Rc::new(session))
+            .with_shared_status(protocol::ImageMemberStatus::Pending);
+        assert_eq!(query.query.to_query_string(),
+                   "visibility=shared&member_status=pending");
+    }
+}
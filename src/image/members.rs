@@ -0,0 +1,112 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Members of shared images via Image API.
+
+use std::rc::Rc;
+
+use chrono::{DateTime, FixedOffset};
+
+use super::super::Result;
+use super::super::common::Refresh;
+use super::super::session::Session;
+use super::base::V2API;
+use super::protocol;
+
+
+/// A project that a shared image is shared with.
+#[derive(Clone, Debug)]
+pub struct ImageMember {
+    session: Rc<Session>,
+    inner: protocol::ImageMember,
+}
+
+impl ImageMember {
+    /// Create an image member object.
+    pub(crate) fn new(session: Rc<Session>, inner: protocol::ImageMember)
+            -> ImageMember {
+        ImageMember {
+            session: session,
+            inner: inner,
+        }
+    }
+
+    transparent_property! {
+        #[doc = "Creation date and time."]
+        created_at: DateTime<FixedOffset>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the shared image."]
+        image_id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the member project."]
+        member_id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Whether the member has accepted the image."]
+        status: protocol::ImageMemberStatus
+    }
+
+    transparent_property! {
+        #[doc = "Last update date and time."]
+        updated_at: DateTime<FixedOffset>
+    }
+
+    /// Accept the shared image.
+    ///
+    /// Only the member project can do it. Accepted images appear in the
+    /// image list of the member.
+    pub fn accept(&mut self) -> Result<()> {
+        self.set_status(protocol::ImageMemberStatus::Accepted)
+    }
+
+    /// Reject the shared image.
+    ///
+    /// Only the member project can do it.
+    pub fn reject(&mut self) -> Result<()> {
+        self.set_status(protocol::ImageMemberStatus::Rejected)
+    }
+
+    /// Set the status of the membership.
+    ///
+    /// Only the member project can do it.
+    pub fn set_status(&mut self, status: protocol::ImageMemberStatus)
+            -> Result<()> {
+        self.inner = self.session.update_image_member(&self.inner.image_id,
+                                                      &self.inner.member_id,
+                                                      status)?;
+        Ok(())
+    }
+
+    /// Stop sharing the image with this member.
+    ///
+    /// Only the image owner can do it.
+    pub fn delete(self) -> Result<()> {
+        self.session.delete_image_member(&self.inner.image_id,
+                                         &self.inner.member_id)
+    }
+}
+
+impl Refresh for ImageMember {
+    /// Refresh the image member.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_image_member(&self.inner.image_id,
+                                                   &self.inner.member_id)?;
+        Ok(())
+    }
+}
@@ -16,8 +16,14 @@
 
 mod base;
 mod images;
+mod members;
 mod protocol;
+mod tasks;
 
-pub use self::protocol::{ImageContainerFormat, ImageDiskFormat,
-                         ImageVisibility, ImageSortKey, ImageStatus};
-pub use self::images::{Image, ImageQuery};
+pub use self::protocol::{DateComparison, ImageContainerFormat,
+                         ImageDiskFormat, ImageMemberStatus, ImageSignature,
+                         ImageVisibility, ImageSortKey, ImageStatus,
+                         TaskStatus};
+pub use self::images::{Image, ImageQuery, NewImage};
+pub use self::members::ImageMember;
+pub use self::tasks::{Task, TaskQuery, TaskStatusWaiter};
@@ -17,8 +17,11 @@
 #![allow(non_snake_case)]
 #![allow(missing_docs)]
 
+use std::collections::{BTreeMap, HashMap};
+
 use chrono::{DateTime, FixedOffset};
 use reqwest::Url;
+use serde_json::Value;
 
 use super::super::common;
 
@@ -79,19 +82,59 @@ protocol_enum! {
 protocol_enum! {
     #[doc = "Available sort keys."]
     enum ImageSortKey {
+        ContainerFormat = "container_format",
         CreatedAt = "created_at",
+        DiskFormat = "disk_format",
         Id = "id",
         Name = "name",
+        Size = "size",
+        Status = "status",
         UpdatedAt = "updated_at"
     }
 }
 
+protocol_enum! {
+    #[doc = "Status of an image member."]
+    enum ImageMemberStatus {
+        Accepted = "accepted",
+        Pending = "pending",
+        Rejected = "rejected",
+        All = "all"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Comparison operator for date and time filters."]
+    enum DateComparison {
+        Equal = "eq",
+        NotEqual = "neq",
+        GreaterThan = "gt",
+        GreaterOrEqual = "gte",
+        LessThan = "lt",
+        LessOrEqual = "lte"
+    }
+}
+
 impl Default for ImageSortKey {
     fn default() -> ImageSortKey {
         ImageSortKey::CreatedAt
     }
 }
 
+impl common::ResourceStatus for ImageStatus {
+    fn is_error(&self) -> bool {
+        *self == ImageStatus::Killed
+    }
+
+    /// `Queued` is not transitional: it waits for the data to be uploaded.
+    fn is_transitional(&self) -> bool {
+        match *self {
+            ImageStatus::Saving | ImageStatus::PendingDelete => true,
+            _ => false
+        }
+    }
+}
+
 /// An image.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Image {
@@ -113,12 +156,64 @@ pub struct Image {
     pub min_ram: u32,
     pub name: String,
     #[serde(default)]
+    pub protected: bool,
+    #[serde(default)]
     pub size: Option<u64>,
     pub status: ImageStatus,
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub updated_at: DateTime<FixedOffset>,
     #[serde(default)]
     pub virtual_size: Option<u64>,
-    pub visibility: ImageVisibility
+    pub visibility: ImageVisibility,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>
+}
+
+/// Signature of image data, used to verify signed images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageSignature {
+    /// Base64-encoded signature of the image data.
+    pub signature: String,
+    /// Hash method used for signing, e.g. `SHA-256`.
+    pub hash_method: String,
+    /// Key type used for signing, e.g. `RSA-PSS`.
+    pub key_type: String,
+    /// ID of the signing certificate in the key manager.
+    pub certificate_id: String,
+}
+
+/// An image creation request.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageCreate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub architecture: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_format: Option<ImageContainerFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_format: Option<ImageDiskFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_disk: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_ram: Option<u32>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protected: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<ImageVisibility>,
+    #[serde(flatten)]
+    pub properties: BTreeMap<String, String>
+}
+
+/// A single JSON-patch operation on an image.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum ImagePatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value }
 }
 
 /// A list of images.
@@ -126,3 +221,80 @@ pub struct Image {
 pub struct ImagesRoot {
     pub images: Vec<Image>
 }
+
+protocol_enum! {
+    #[doc = "Possible task statuses."]
+    enum TaskStatus {
+        Pending = "pending",
+        Processing = "processing",
+        Success = "success",
+        Failure = "failure"
+    }
+}
+
+impl common::ResourceStatus for TaskStatus {
+    fn is_error(&self) -> bool {
+        *self == TaskStatus::Failure
+    }
+
+    fn is_transitional(&self) -> bool {
+        match *self {
+            TaskStatus::Pending | TaskStatus::Processing => true,
+            _ => false
+        }
+    }
+}
+
+/// An asynchronous task, e.g. an image import.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Task {
+    pub created_at: DateTime<FixedOffset>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    pub id: String,
+    #[serde(default)]
+    pub image_id: Option<String>,
+    #[serde(default)]
+    pub input: Option<Value>,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub result: Option<Value>,
+    pub status: TaskStatus,
+    #[serde(rename = "type")]
+    pub task_type: String,
+    pub updated_at: DateTime<FixedOffset>
+}
+
+/// A list of tasks.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TasksRoot {
+    pub tasks: Vec<Task>
+}
+
+/// A member of a shared image.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageMember {
+    pub created_at: DateTime<FixedOffset>,
+    pub image_id: String,
+    pub member_id: String,
+    pub status: ImageMemberStatus,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageMembersRoot {
+    pub members: Vec<ImageMember>
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageMemberCreate {
+    pub member: String
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageMemberUpdate {
+    pub status: ImageMemberStatus
+}
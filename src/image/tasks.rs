@@ -0,0 +1,388 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Image tasks (e.g. imports) via Image API.
+
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, FixedOffset};
+use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
+use serde::Serialize;
+use serde_json::Value;
+use waiter::{Waiter, WaiterCurrentState};
+
+use super::super::{Error, ErrorKind, Result};
+use super::super::common::{ListResources, Refresh, ResourceId,
+                           ResourceIterator, ResourceStatus};
+use super::super::session::Session;
+use super::super::telemetry;
+use super::super::utils::Query;
+use super::base::V2API;
+use super::protocol;
+
+
+/// A query to task list.
+#[derive(Clone, Debug)]
+pub struct TaskQuery {
+    session: Rc<Session>,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// Structure representing a single task.
+#[derive(Clone, Debug)]
+pub struct Task {
+    session: Rc<Session>,
+    inner: protocol::Task,
+}
+
+/// Waiter for a task to finish.
+#[derive(Debug)]
+pub struct TaskStatusWaiter {
+    task: Task,
+    wait_timeout: Duration,
+    delay: Duration,
+    started: Instant
+}
+
+impl Task {
+    /// Create a task object.
+    pub(crate) fn new(session: Rc<Session>, inner: protocol::Task) -> Task {
+        Task {
+            session: session,
+            inner: inner,
+        }
+    }
+
+    /// Load a Task object.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<Task> {
+        let inner = session.get_task(id)?;
+        Ok(Task::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Creation date and time."]
+        created_at: DateTime<FixedOffset>
+    }
+
+    transparent_property! {
+        #[doc = "When the task record will be removed."]
+        expires_at: Option<DateTime<FixedOffset>>
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the image the task works on, if known."]
+        image_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Task input as provided on creation."]
+        input: ref Option<Value>
+    }
+
+    transparent_property! {
+        #[doc = "Human-readable message, e.g. the reason of a failure."]
+        message: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Owner of the task."]
+        owner: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Result of the task, if any."]
+        result: ref Option<Value>
+    }
+
+    transparent_property! {
+        #[doc = "Task status."]
+        status: protocol::TaskStatus
+    }
+
+    transparent_property! {
+        #[doc = "Task type, e.g. `import` or `api_image_import`."]
+        task_type: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Last update date and time."]
+        updated_at: DateTime<FixedOffset>
+    }
+
+    /// Whether the task is finished, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        self.inner.status.is_terminal()
+    }
+
+    /// Wait for the task to finish.
+    ///
+    /// The waiter fails with `OperationFailed` and the task message if the
+    /// task fails.
+    pub fn wait_finished(self) -> TaskStatusWaiter {
+        TaskStatusWaiter::new(self)
+    }
+}
+
+impl Refresh for Task {
+    /// Refresh the task.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_task(&self.inner.id)?;
+        Ok(())
+    }
+}
+
+impl TaskStatusWaiter {
+    fn new(task: Task) -> TaskStatusWaiter {
+        TaskStatusWaiter {
+            task: task,
+            wait_timeout: Duration::new(1800, 0),
+            delay: Duration::new(5, 0),
+            started: Instant::now()
+        }
+    }
+
+    /// Wait at most the given time for the task to finish.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_timeout = timeout;
+        self
+    }
+
+    /// Check the task status with the given interval.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.delay = interval;
+        self
+    }
+}
+
+impl Waiter<Task, Error> for TaskStatusWaiter {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        Some(self.wait_timeout)
+    }
+
+    fn default_delay(&self) -> Duration {
+        self.delay
+    }
+
+    fn timeout_error(&self) -> Error {
+        telemetry::record_waiter("task_status", self.started, false);
+        Error::new(ErrorKind::OperationTimedOut,
+                   format!("Timeout waiting for task {} to finish, \
+                            current status is {}",
+                           self.task.id(), self.task.status()))
+    }
+
+    fn poll(&mut self) -> Result<Option<Task>> {
+        self.task.refresh()?;
+        match self.task.status() {
+            protocol::TaskStatus::Success => {
+                debug!("Task {} succeeded", self.task.id());
+                telemetry::record_waiter("task_status", self.started, true);
+                Ok(Some(self.task.clone()))
+            },
+            protocol::TaskStatus::Failure => {
+                debug!("Task {} failed: {}", self.task.id(),
+                       self.task.message());
+                telemetry::record_waiter("task_status", self.started, false);
+                Err(Error::new(ErrorKind::OperationFailed,
+                               format!("Task {} failed: {}", self.task.id(),
+                                       self.task.message())))
+            },
+            other => {
+                trace!("Still waiting for task {} to finish, current is {}",
+                       self.task.id(), other);
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl WaiterCurrentState<Task> for TaskStatusWaiter {
+    fn waiter_current_state(&self) -> &Task {
+        &self.task
+    }
+}
+
+impl TaskQuery {
+    pub(crate) fn new(session: Rc<Session>) -> TaskQuery {
+        TaskQuery {
+            session: session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            max_items: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by task status."]
+        with_status -> status: protocol::TaskStatus
+    }
+
+    /// Filter by task type.
+    pub fn with_task_type<T: Into<String>>(mut self, value: T) -> Self {
+        self.query.push_str("type", value);
+        self
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<Task> {
+        debug!("Fetching tasks with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<Task>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(mut self) -> Result<Task> {
+        debug!("Fetching one task with {:?}", self.query);
+        if self.can_paginate {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        self.into_iter().one()
+    }
+}
+
+impl ResourceId for Task {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for Task {
+    const DEFAULT_LIMIT: usize = 50;
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<Task>> {
+        Ok(session.list_tasks(&query)?.into_iter().map(|item| {
+            Task::new(session.clone(), item)
+        }).collect())
+    }
+}
+
+impl IntoFallibleIterator for TaskQuery {
+    type Item = Task;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<Task>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<Task> {
+        self.into_iter()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use serde_json;
+
+    use super::super::super::utils;
+    use super::super::protocol;
+    use super::{Task, TaskQuery};
+
+    #[test]
+    fn test_task_failure() {
+        let inner: protocol::Task = serde_json::from_str(r#"{
+            "created_at": "2018-01-01T00:00:00Z",
+            "id": "b7d9e4b5-3c1f-4b2e-9d8e-0f2c4b1a2d3e",
+            "image_id": "e7db3b45-8db7-47ad-8109-3fb55c2c24fd",
+            "input": {"import_req": {"method": {"name": "web-download"}}},
+            "message": "Remote server returned 404",
+            "result": null,
+            "status": "failure",
+            "type": "api_image_import",
+            "updated_at": "2018-01-01T00:01:00Z"
+        }"#).unwrap();
+        let session = utils::test::new_session(utils::test::URL);
+        let task = Task::new(Rc::new(session), inner);
+        assert!(task.is_finished());
+        assert_eq!(task.status(), protocol::TaskStatus::Failure);
+        assert_eq!(task.task_type(), "api_image_import");
+        assert_eq!(task.message(), "Remote server returned 404");
+        assert!(task.result().is_none());
+        assert!(task.expires_at().is_none());
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let session = utils::test::new_session(utils::test::URL);
+        let query = TaskQuery::new(Rc::new(session))
+            .with_status(protocol::TaskStatus::Processing)
+            .with_task_type("api_image_import");
+        assert_eq!(query.query.to_query_string(),
+                   "status=processing&type=api_image_import");
+    }
+}
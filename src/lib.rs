@@ -78,6 +78,8 @@
         unused_results,
         while_true)]
 
+#[allow(unused_extern_crates)]
+extern crate base64;
 #[allow(unused_extern_crates)]
 extern crate chrono;
 #[allow(unused_extern_crates)]
@@ -88,6 +90,9 @@ extern crate fallible_iterator;
 extern crate ipnet;
 #[macro_use]
 extern crate log;
+#[cfg(feature = "metrics")]
+#[macro_use]
+extern crate metrics;
 extern crate reqwest;
 extern crate serde;
 #[macro_use]
@@ -315,7 +320,7 @@ macro_rules! protocol_enum {
         $($item:ident = $val:expr),+
     }} => (
         $(#[$attr])*
-        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
         pub enum $name {
             $($item),+,
             #[doc(hidden)]
@@ -363,7 +368,7 @@ macro_rules! protocol_enum {
         $($item:ident = $val:expr),+
     }} => (
         $(#[$attr])*
-        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
         pub enum $name {
             $($item),+,
             #[doc(hidden)]
@@ -421,17 +426,27 @@ mod cloud;
 pub mod common;
 #[cfg(feature = "compute")]
 pub mod compute;
+#[cfg(feature = "dns")]
+pub mod dns;
 mod error;
-mod identity;
+pub mod identity;
 #[cfg(feature = "image")]
 pub mod image;
 #[cfg(feature = "network")]
+pub mod manifest;
+#[cfg(feature = "network")]
 pub mod network;
+#[cfg(feature = "object-storage")]
+pub mod object_storage;
 pub mod session;
+mod telemetry;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod utils;
+pub mod workflows;
 
-pub use cloud::Cloud;
-pub use common::Refresh;
+pub use cloud::{Cloud, Defaults, Quotas};
+pub use common::{Refresh, ResourceStatus, StatusTransition};
 pub use error::{Error, ErrorKind, Result};
 
 
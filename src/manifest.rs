@@ -0,0 +1,1144 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Declarative manifests of cloud resources.
+//!
+//! A manifest describes networks with their subnets, security groups with
+//! their rules and (with the `compute` feature) servers in YAML. It can be
+//! exported from existing resources and applied to a cloud idempotently:
+//! missing resources are created, drifted resources are updated where
+//! possible and, optionally, resources missing from the manifest are deleted.
+//!
+//! Networks, security groups and servers are matched by name, subnets by
+//! CIDR within their network.
+//!
+//! # Example
+//!
+//! ```yaml
+//! tag: managed-by-ci
+//! networks:
+//!   - name: backend
+//!     mtu: 1450
+//!     subnets:
+//!       - name: backend-v4
+//!         cidr: 10.10.0.0/24
+//!         dns_nameservers: [8.8.8.8]
+//! security_groups:
+//!   - name: ssh
+//!     rules:
+//!       - direction: ingress
+//!         protocol: tcp
+//!         port_range_min: 22
+//!         port_range_max: 22
+//! servers:
+//!   - name: bastion
+//!     flavor: m1.small
+//!     image: 6d1a9e3c-5b0e-4f55-9d37-3c1b2e4f5a6b
+//!     networks: [backend]
+//!     security_groups: [ssh]
+//! ```
+
+#[cfg(feature = "compute")]
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+use serde_yaml;
+use waiter::Waiter;
+
+use super::{Cloud, Error, ErrorKind, Result};
+#[cfg(feature = "compute")]
+use super::compute::{Server, ServerLookupOptions};
+use super::network::{diff_rules, Network, RuleDirection, RuleEtherType,
+                     SecurityGroup, SecurityRule, Subnet};
+use super::utils::ResultExt;
+
+
+/// Metadata key marking servers managed by a manifest.
+///
+/// Servers do not support tags, so the manifest `tag` is stored as the value
+/// of this metadata item instead.
+#[cfg(feature = "compute")]
+pub const SERVER_TAG_METADATA: &'static str = "manifest_tag";
+
+
+/// A manifest of networks, security groups and servers.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// Tag marking resources managed by this manifest.
+    ///
+    /// Resources created from the manifest get this tag, existing resources
+    /// matched by name get it on the first run. Pruning deletes resources
+    /// with this tag that are not in the manifest. Without a tag only
+    /// subnets of the networks in the manifest are pruned, see
+    /// `Manifest::apply`.
+    ///
+    /// Servers get the tag as the `SERVER_TAG_METADATA` metadata item.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Networks.
+    #[serde(default)]
+    pub networks: Vec<NetworkManifest>,
+    /// Security groups.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub security_groups: Vec<SecurityGroupManifest>,
+    /// Servers.
+    #[cfg(feature = "compute")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub servers: Vec<ServerManifest>,
+}
+
+/// Desired state of a network.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkManifest {
+    /// Network name, must be unique.
+    pub name: String,
+    /// Administrative state.
+    #[serde(default = "default_true")]
+    pub admin_state_up: bool,
+    /// Network description, not checked if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// MTU, not checked if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u32>,
+    /// Whether the network is shared between projects.
+    #[serde(default)]
+    pub shared: bool,
+    /// Subnets of the network.
+    #[serde(default)]
+    pub subnets: Vec<SubnetManifest>,
+}
+
+/// Desired state of a subnet.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubnetManifest {
+    /// Subnet CIDR, must be unique within the network.
+    pub cidr: IpNet,
+    /// Subnet name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Subnet description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Whether DHCP is enabled.
+    #[serde(default = "default_true")]
+    pub dhcp_enabled: bool,
+    /// DNS name servers, not checked if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_nameservers: Option<Vec<String>>,
+    /// Gateway IP, the first address of the CIDR is used if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway_ip: Option<IpAddr>,
+}
+
+/// Desired state of a security group.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SecurityGroupManifest {
+    /// Security group name, must be unique.
+    pub name: String,
+    /// Security group description, not checked if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Complete list of rules of the security group.
+    ///
+    /// Other rules are removed, including the egress rules that the Network
+    /// service adds to new security groups.
+    #[serde(default)]
+    pub rules: Vec<RuleManifest>,
+}
+
+/// A security group rule.
+///
+/// Rules are compared in their normalized form, see `SecurityRule`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleManifest {
+    /// Direction of the traffic.
+    pub direction: RuleDirection,
+    /// Ethernet type, IPv4 by default.
+    #[serde(default = "default_ethertype")]
+    pub ethertype: RuleEtherType,
+    /// IP protocol (name or number), any if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    /// Lower bound of the port range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_range_min: Option<u16>,
+    /// Upper bound of the port range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_range_max: Option<u16>,
+    /// Remote IP prefix, any if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_ip_prefix: Option<IpNet>,
+    /// ID of the remote security group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_group_id: Option<String>,
+    /// ID of the remote address group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_address_group_id: Option<String>,
+}
+
+/// Desired state of a server.
+///
+/// Servers cannot be updated in place, servers that differ from the
+/// manifest are reported as drifted.
+#[cfg(feature = "compute")]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerManifest {
+    /// Server name, must be unique.
+    pub name: String,
+    /// Flavor name.
+    pub flavor: String,
+    /// Image ID.
+    pub image: String,
+    /// Key pair name, not checked if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_pair: Option<String>,
+    /// Names of networks to connect, not checked if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<String>,
+    /// Names of security groups, not checked if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub security_groups: Vec<String>,
+    /// Metadata items that must be present.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Changes made by applying a manifest.
+///
+/// Resources are reported as `network/<name>`,
+/// `subnet/<network name>/<cidr>`, `security_group/<name>` and
+/// `server/<name>`.
+#[derive(Clone, Debug, Default)]
+pub struct ApplyReport {
+    /// Resources that were created.
+    pub created: Vec<String>,
+    /// Resources that were updated.
+    pub updated: Vec<String>,
+    /// Resources that were deleted.
+    pub deleted: Vec<String>,
+    /// Resources that differ from the manifest but cannot be updated.
+    ///
+    /// Delete them to have them re-created on the next run.
+    pub drifted: Vec<String>,
+}
+
+#[inline]
+fn default_true() -> bool { true }
+
+#[inline]
+fn default_ethertype() -> RuleEtherType { RuleEtherType::Ipv4 }
+
+fn subnet_key(network: &str, cidr: &IpNet) -> String {
+    format!("subnet/{}/{}", network, cidr)
+}
+
+impl ApplyReport {
+    /// Whether nothing had to be changed.
+    pub fn is_unchanged(&self) -> bool {
+        self.created.is_empty() && self.updated.is_empty() &&
+            self.deleted.is_empty() && self.drifted.is_empty()
+    }
+}
+
+impl Manifest {
+    /// Parse a manifest from YAML.
+    pub fn from_yaml<S: AsRef<str>>(value: S) -> Result<Manifest> {
+        let manifest: Manifest = serde_yaml::from_str(value.as_ref())
+            .map_err(|e| Error::new(ErrorKind::InvalidInput,
+                                    format!("Invalid manifest: {}", e)))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Serialize the manifest into YAML.
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(self).map_err(|e| {
+            Error::new(ErrorKind::InvalidInput,
+                       format!("Cannot serialize manifest: {}", e))
+        })
+    }
+
+    /// Export a manifest from existing networks.
+    ///
+    /// Subnets of the networks are fetched as well.
+    pub fn from_networks(networks: &[Network]) -> Result<Manifest> {
+        let mut result = Manifest::default();
+        for network in networks {
+            let subnets = network.subnets()?.iter()
+                .map(SubnetManifest::from_subnet).collect();
+            result.networks.push(NetworkManifest {
+                name: network.name().clone(),
+                admin_state_up: network.admin_state_up(),
+                description: network.description().clone(),
+                mtu: network.mtu(),
+                shared: network.shared(),
+                subnets: subnets,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Add existing security groups to the manifest.
+    ///
+    /// Rules of the security groups are fetched as well.
+    pub fn add_security_groups(&mut self, groups: &[SecurityGroup])
+            -> Result<()> {
+        for group in groups {
+            let rules = group.rules()?.iter()
+                .map(|rule| RuleManifest::from_rule(&rule.to_rule())).collect();
+            self.security_groups.push(SecurityGroupManifest {
+                name: group.name().clone(),
+                description: group.description().clone(),
+                rules: rules,
+            });
+        }
+        Ok(())
+    }
+
+    /// Add existing servers to the manifest.
+    ///
+    /// Servers without an image (e.g. booted from a volume) are skipped.
+    #[cfg(feature = "compute")]
+    pub fn add_servers(&mut self, servers: &[Server]) {
+        for server in servers {
+            match ServerManifest::from_server(server) {
+                Some(spec) => self.servers.push(spec),
+                None => warn!("Skipping server {} without an image",
+                              server.name())
+            }
+        }
+    }
+
+    /// Check that names of resources and subnet CIDRs are unique.
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for network in &self.networks {
+            if ! names.insert(&network.name) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Duplicate network {} in manifest", network.name)));
+            }
+
+            let mut cidrs = HashSet::new();
+            for subnet in &network.subnets {
+                if ! cidrs.insert(subnet.cidr) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Duplicate subnet {} of network {} in manifest",
+                                subnet.cidr, network.name)));
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        for group in &self.security_groups {
+            if ! names.insert(&group.name) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Duplicate security group {} in manifest",
+                            group.name)));
+            }
+        }
+
+        #[cfg(feature = "compute")]
+        {
+            let mut names = HashSet::new();
+            for server in &self.servers {
+                if ! names.insert(&server.name) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Duplicate server {} in manifest",
+                                server.name)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply the manifest to the cloud.
+    ///
+    /// Missing resources are created. Networks, subnets and security groups
+    /// (including their rules) that differ from the manifest are updated,
+    /// servers are only reported as drifted. With `prune`, subnets of the
+    /// networks that are not in the manifest are deleted, as well as
+    /// networks, security groups and servers carrying the manifest `tag`
+    /// that are not in the manifest.
+    ///
+    /// Applying the same manifest again makes no changes.
+    ///
+    /// # Warning
+    ///
+    /// Subnets are pruned regardless of the `tag`: with `prune` every subnet
+    /// of a network in the manifest that is not listed under this network is
+    /// deleted, including subnets created by other tools. List all subnets
+    /// of the network or do not prune if the network is shared with them.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::fs;
+    ///
+    /// use openstack;
+    /// use openstack::manifest::Manifest;
+    ///
+    /// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+    /// let yaml = fs::read_to_string("networks.yaml")
+    ///     .expect("Unable to read the manifest");
+    /// let manifest = Manifest::from_yaml(yaml).expect("Invalid manifest");
+    /// let report = manifest.apply(&os, true).expect("Unable to apply");
+    /// println!("Created {:?}, updated {:?}, deleted {:?}",
+    ///          report.created, report.updated, report.deleted);
+    /// ```
+    pub fn apply(&self, cloud: &Cloud, prune: bool) -> Result<ApplyReport> {
+        self.validate()?;
+        let tag = self.tag.as_ref();
+        let mut report = ApplyReport::default();
+
+        for spec in &self.networks {
+            let existing = cloud.find_networks().with_name(spec.name.clone())
+                .one().not_found_as_none()?;
+            let network = match existing {
+                Some(mut network) => {
+                    if spec.update(&mut network, tag)? {
+                        report.updated.push(format!("network/{}", spec.name));
+                    }
+                    network
+                },
+                None => {
+                    let network = spec.create(cloud, tag)?;
+                    report.created.push(format!("network/{}", spec.name));
+                    network
+                }
+            };
+
+            spec.apply_subnets(cloud, &network, prune, &mut report)?;
+        }
+
+        for spec in &self.security_groups {
+            spec.apply(cloud, tag, &mut report)?;
+        }
+
+        #[cfg(feature = "compute")]
+        {
+            for spec in &self.servers {
+                spec.apply(cloud, tag, &mut report)?;
+            }
+        }
+
+        if prune {
+            if let Some(tag) = tag {
+                // Servers go first since they use networks and security groups
+                #[cfg(feature = "compute")]
+                self.prune_servers(cloud, tag, &mut report)?;
+                self.prune_security_groups(cloud, tag, &mut report)?;
+                self.prune_networks(cloud, tag, &mut report)?;
+            }
+        }
+
+        debug!("Manifest applied: {} created, {} updated, {} deleted, \
+                {} drifted", report.created.len(), report.updated.len(),
+               report.deleted.len(), report.drifted.len());
+        Ok(report)
+    }
+
+    fn prune_networks(&self, cloud: &Cloud, tag: &String,
+                      report: &mut ApplyReport) -> Result<()> {
+        let names: HashSet<_> = self.networks.iter()
+            .map(|spec| spec.name.clone()).collect();
+        let networks = cloud.find_networks()
+            .with_tags(vec![tag.clone()]).all()?;
+        for network in networks {
+            if names.contains(network.name()) {
+                continue;
+            }
+
+            let key = format!("network/{}", network.name());
+            debug!("Pruning {} ({})", key, network.id());
+            network.delete()?.wait()?;
+            report.deleted.push(key);
+        }
+        Ok(())
+    }
+
+    fn prune_security_groups(&self, cloud: &Cloud, tag: &String,
+                             report: &mut ApplyReport) -> Result<()> {
+        let names: HashSet<_> = self.security_groups.iter()
+            .map(|spec| spec.name.clone()).collect();
+        let groups = cloud.find_security_groups()
+            .with_tags(vec![tag.clone()]).all()?;
+        for group in groups {
+            if names.contains(group.name()) {
+                continue;
+            }
+
+            let key = format!("security_group/{}", group.name());
+            debug!("Pruning {} ({})", key, group.id());
+            group.delete()?.wait()?;
+            report.deleted.push(key);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "compute")]
+    fn prune_servers(&self, cloud: &Cloud, tag: &String,
+                     report: &mut ApplyReport) -> Result<()> {
+        let names: HashSet<_> = self.servers.iter()
+            .map(|spec| spec.name.clone()).collect();
+        // Metadata cannot be used for filtering, so all servers are fetched
+        let servers = cloud.find_servers().detailed()?;
+        for server in servers {
+            if names.contains(server.name()) ||
+                    server.metadata().get(SERVER_TAG_METADATA) != Some(tag) {
+                continue;
+            }
+
+            let key = format!("server/{}", server.name());
+            debug!("Pruning {} ({})", key, server.id());
+            server.delete()?.wait()?;
+            report.deleted.push(key);
+        }
+        Ok(())
+    }
+}
+
+impl NetworkManifest {
+    fn create(&self, cloud: &Cloud, tag: Option<&String>) -> Result<Network> {
+        debug!("Creating network {}", self.name);
+        let mut request = cloud.new_network()
+            .with_name(self.name.clone())
+            .with_admin_state_up(self.admin_state_up)
+            .with_shared(self.shared);
+        if let Some(ref description) = self.description {
+            request.set_description(description.clone());
+        }
+        if let Some(mtu) = self.mtu {
+            request.set_mtu(mtu);
+        }
+        let mut network = request.create()?;
+        if let Some(tag) = tag {
+            network.add_tag(tag.clone())?;
+        }
+        Ok(network)
+    }
+
+    /// Update the network if needed, returns whether it was updated.
+    ///
+    /// The tag is added if it is missing, so that pruning finds networks
+    /// that were created before the manifest was applied.
+    fn update(&self, network: &mut Network, tag: Option<&String>)
+            -> Result<bool> {
+        let mut tagged = false;
+        if let Some(tag) = tag {
+            if ! network.tags().contains(tag) {
+                debug!("Adding tag {} to network {}", tag, self.name);
+                network.add_tag(tag.clone())?;
+                tagged = true;
+            }
+        }
+
+        if network.admin_state_up() != self.admin_state_up {
+            network.set_admin_state_up(self.admin_state_up);
+        }
+        if network.shared() != self.shared {
+            network.set_shared(self.shared);
+        }
+        if let Some(ref description) = self.description {
+            if network.description().as_ref() != Some(description) {
+                network.set_description(description.clone());
+            }
+        }
+        if let Some(mtu) = self.mtu {
+            if network.mtu() != Some(mtu) {
+                network.set_mtu(mtu);
+            }
+        }
+
+        if network.is_dirty() {
+            debug!("Updating drifted network {}", self.name);
+            network.save()?;
+            Ok(true)
+        } else {
+            Ok(tagged)
+        }
+    }
+
+    fn apply_subnets(&self, cloud: &Cloud, network: &Network, prune: bool,
+                     report: &mut ApplyReport) -> Result<()> {
+        let mut existing = network.subnets()?;
+        for spec in &self.subnets {
+            let key = subnet_key(&self.name, &spec.cidr);
+            match existing.iter().position(|item| item.cidr() == spec.cidr) {
+                Some(index) => {
+                    let mut subnet = existing.remove(index);
+                    if spec.update(&mut subnet)? {
+                        report.updated.push(key);
+                    }
+                },
+                None => {
+                    spec.create(cloud, network)?;
+                    report.created.push(key);
+                }
+            }
+        }
+
+        if prune {
+            for subnet in existing {
+                let key = subnet_key(&self.name, &subnet.cidr());
+                debug!("Pruning {} ({})", key, subnet.id());
+                subnet.delete()?.wait()?;
+                report.deleted.push(key);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SubnetManifest {
+    fn from_subnet(subnet: &Subnet) -> SubnetManifest {
+        SubnetManifest {
+            cidr: subnet.cidr(),
+            name: subnet.name().clone(),
+            description: subnet.description().clone(),
+            dhcp_enabled: subnet.dhcp_enabled(),
+            dns_nameservers: Some(subnet.dns_nameservers().clone()),
+            gateway_ip: subnet.gateway_ip(),
+        }
+    }
+
+    fn create(&self, cloud: &Cloud, network: &Network) -> Result<Subnet> {
+        debug!("Creating subnet {} in network {}", self.cidr, network.name());
+        let mut request = cloud.new_subnet(network.id().clone(), self.cidr)
+            .with_dhcp_enabled(self.dhcp_enabled);
+        if let Some(ref name) = self.name {
+            request.set_name(name.clone());
+        }
+        if let Some(ref description) = self.description {
+            request.set_description(description.clone());
+        }
+        if let Some(gateway_ip) = self.gateway_ip {
+            request.set_gateway_ip(gateway_ip);
+        }
+        if let Some(ref nameservers) = self.dns_nameservers {
+            for nameserver in nameservers {
+                request.add_dns_nameserver(nameserver.clone());
+            }
+        }
+        request.create()
+    }
+
+    /// Update the subnet if needed, returns whether it was updated.
+    ///
+    /// Fields that are missing in the manifest are not checked.
+    fn update(&self, subnet: &mut Subnet) -> Result<bool> {
+        if let Some(ref name) = self.name {
+            if subnet.name().as_ref() != Some(name) {
+                subnet.set_name(name.clone());
+            }
+        }
+        if let Some(ref description) = self.description {
+            if subnet.description().as_ref() != Some(description) {
+                subnet.set_description(description.clone());
+            }
+        }
+        if subnet.dhcp_enabled() != self.dhcp_enabled {
+            subnet.set_dhcp_enabled(self.dhcp_enabled);
+        }
+        if let Some(ref nameservers) = self.dns_nameservers {
+            if subnet.dns_nameservers() != nameservers {
+                subnet.set_dns_nameservers(nameservers.clone());
+            }
+        }
+        if let Some(gateway_ip) = self.gateway_ip {
+            if subnet.gateway_ip() != Some(gateway_ip) {
+                subnet.set_gateway_ip(gateway_ip);
+            }
+        }
+
+        if subnet.is_dirty() {
+            debug!("Updating drifted subnet {}", self.cidr);
+            subnet.save()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl SecurityGroupManifest {
+    fn apply(&self, cloud: &Cloud, tag: Option<&String>,
+             report: &mut ApplyReport) -> Result<()> {
+        let key = format!("security_group/{}", self.name);
+        let existing = cloud.find_security_groups().with_name(self.name.clone())
+            .one().not_found_as_none()?;
+        let (mut group, created) = match existing {
+            Some(group) => (group, false),
+            None => {
+                debug!("Creating security group {}", self.name);
+                let mut request = cloud.new_security_group(self.name.clone());
+                if let Some(ref description) = self.description {
+                    request.set_description(description.clone());
+                }
+                (request.create()?, true)
+            }
+        };
+
+        let mut updated = false;
+        if let Some(tag) = tag {
+            if ! group.tags().contains(tag) {
+                debug!("Adding tag {} to security group {}", tag, self.name);
+                group.add_tag(tag.clone())?;
+                updated = true;
+            }
+        }
+
+        if let Some(ref description) = self.description {
+            if group.description().as_ref() != Some(description) {
+                debug!("Updating description of security group {}",
+                       self.name);
+                group.set_description(description.clone());
+                group.save()?;
+                updated = true;
+            }
+        }
+
+        let mut current = group.rules()?;
+        let diff = diff_rules(
+            self.rules.iter().map(RuleManifest::to_rule),
+            current.iter().map(|rule| (rule.id().clone(), rule.to_rule())));
+        for rule in &diff.to_add {
+            debug!("Adding rule {:?} to security group {}", rule, self.name);
+            let _ = group.add_rule(rule)?;
+        }
+        for id in &diff.to_remove {
+            if let Some(index) = current.iter().position(|rule| rule.id() == id) {
+                debug!("Removing rule {} from security group {}", id,
+                       self.name);
+                current.remove(index).delete()?.wait()?;
+            }
+        }
+
+        if created {
+            report.created.push(key);
+        } else if updated || ! diff.is_empty() {
+            report.updated.push(key);
+        }
+        Ok(())
+    }
+}
+
+impl RuleManifest {
+    fn from_rule(rule: &SecurityRule) -> RuleManifest {
+        RuleManifest {
+            direction: rule.direction,
+            ethertype: rule.ether_type,
+            protocol: rule.protocol.clone(),
+            port_range_min: rule.port_range.map(|(min, _)| min),
+            port_range_max: rule.port_range.map(|(_, max)| max),
+            remote_ip_prefix: rule.remote_ip_prefix,
+            remote_group_id: rule.remote_group_id.clone(),
+            remote_address_group_id: rule.remote_address_group_id.clone(),
+        }
+    }
+
+    fn to_rule(&self) -> SecurityRule {
+        SecurityRule {
+            direction: self.direction,
+            ether_type: self.ethertype,
+            protocol: self.protocol.clone(),
+            port_range: match (self.port_range_min, self.port_range_max) {
+                (Some(min), Some(max)) => Some((min, max)),
+                (Some(min), None) => Some((min, min)),
+                (None, Some(max)) => Some((max, max)),
+                (None, None) => None
+            },
+            remote_ip_prefix: self.remote_ip_prefix,
+            remote_group_id: self.remote_group_id.clone(),
+            remote_address_group_id: self.remote_address_group_id.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "compute")]
+impl ServerManifest {
+    fn from_server(server: &Server) -> Option<ServerManifest> {
+        let image = server.image_id()?.clone();
+        let mut networks = server.addresses().keys().cloned()
+            .collect::<Vec<_>>();
+        networks.sort();
+        let metadata = server.metadata().iter()
+            .filter(|&(key, _)| key != SERVER_TAG_METADATA)
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Some(ServerManifest {
+            name: server.name().clone(),
+            flavor: server.flavor().original_name.clone(),
+            image: image,
+            key_pair: server.key_pair_name().clone(),
+            networks: networks,
+            security_groups: server.security_groups().into_iter().cloned()
+                .collect(),
+            metadata: metadata,
+        })
+    }
+
+    fn apply(&self, cloud: &Cloud, tag: Option<&String>,
+             report: &mut ApplyReport) -> Result<()> {
+        let key = format!("server/{}", self.name);
+        let existing = cloud.get_server_with_options(
+            &self.name, ServerLookupOptions::new()).not_found_as_none()?;
+        let mut server = match existing {
+            Some(server) => server,
+            None => {
+                let _ = self.create(cloud, tag)?;
+                report.created.push(key);
+                return Ok(());
+            }
+        };
+
+        if ! self.matches(&server) {
+            warn!("{} differs from the manifest", key);
+            report.drifted.push(key.clone());
+        }
+
+        if let Some(tag) = tag {
+            if server.metadata().get(SERVER_TAG_METADATA) != Some(tag) {
+                debug!("Adding tag {} to server {}", tag, self.name);
+                server.set_metadata_item(SERVER_TAG_METADATA, tag.clone())?;
+                report.updated.push(key);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create(&self, cloud: &Cloud, tag: Option<&String>) -> Result<Server> {
+        debug!("Creating server {}", self.name);
+        let mut request = cloud.new_server(self.name.clone(), self.flavor.clone())
+            .with_image(self.image.clone());
+        if let Some(ref key_pair) = self.key_pair {
+            request.set_keypair(key_pair.clone());
+        }
+        for network in &self.networks {
+            request.add_network(network.clone());
+        }
+        for group in &self.security_groups {
+            request.add_security_group(group.clone());
+        }
+        for (key, value) in &self.metadata {
+            request = request.with_metadata(key.clone(), value.clone());
+        }
+        if let Some(tag) = tag {
+            request = request.with_metadata(SERVER_TAG_METADATA, tag.clone());
+        }
+        request.create()?.wait()
+    }
+
+    /// Whether the server matches the manifest.
+    ///
+    /// Fields that are missing in the manifest are not checked.
+    fn matches(&self, server: &Server) -> bool {
+        let mut actual = match ServerManifest::from_server(server) {
+            Some(actual) => actual,
+            None => return false
+        };
+        if self.key_pair.is_none() {
+            actual.key_pair = None;
+        }
+        if self.networks.is_empty() {
+            actual.networks.clear();
+        }
+        if self.security_groups.is_empty() {
+            actual.security_groups.clear();
+        }
+        // Only the metadata items from the manifest are checked
+        actual.metadata = actual.metadata.into_iter()
+            .filter(|&(ref key, _)| self.metadata.contains_key(key))
+            .collect();
+
+        let mut expected = self.clone();
+        expected.networks.sort();
+        expected.security_groups.sort();
+        actual.security_groups.sort();
+        expected == actual
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::super::Cloud;
+    use super::super::network::RuleDirection;
+    use super::super::utils::test::FakeServer;
+    use super::Manifest;
+
+    const MANIFEST: &'static str = "
+tag: managed-by-ci
+networks:
+  - name: backend
+    mtu: 1450
+    subnets:
+      - name: backend-v4
+        cidr: 10.10.0.0/24
+        dns_nameservers: [8.8.8.8]
+      - cidr: fd00::/64
+        dhcp_enabled: false
+  - name: frontend
+    admin_state_up: false
+security_groups:
+  - name: ssh
+    rules:
+      - direction: ingress
+        protocol: tcp
+        port_range_min: 22
+        port_range_max: 22
+";
+
+    #[test]
+    fn test_manifest_from_yaml() {
+        let manifest = Manifest::from_yaml(MANIFEST).unwrap();
+        assert_eq!(manifest.tag.as_ref().unwrap(), "managed-by-ci");
+        assert_eq!(manifest.networks.len(), 2);
+        let backend = &manifest.networks[0];
+        assert!(backend.admin_state_up);
+        assert!(! backend.shared);
+        assert_eq!(backend.mtu, Some(1450));
+        assert_eq!(backend.subnets.len(), 2);
+        assert_eq!(backend.subnets[0].cidr, "10.10.0.0/24".parse().unwrap());
+        assert!(backend.subnets[0].dhcp_enabled);
+        assert_eq!(backend.subnets[0].dns_nameservers,
+                   Some(vec![String::from("8.8.8.8")]));
+        assert!(backend.subnets[1].name.is_none());
+        assert!(backend.subnets[1].dns_nameservers.is_none());
+        assert!(! backend.subnets[1].dhcp_enabled);
+        assert!(! manifest.networks[1].admin_state_up);
+        assert!(manifest.networks[1].subnets.is_empty());
+        assert_eq!(manifest.security_groups.len(), 1);
+        let rule = manifest.security_groups[0].rules[0].to_rule();
+        assert_eq!(rule.direction, RuleDirection::Ingress);
+        assert_eq!(rule.port_range, Some((22, 22)));
+    }
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let manifest = Manifest::from_yaml(MANIFEST).unwrap();
+        let yaml = manifest.to_yaml().unwrap();
+        assert_eq!(Manifest::from_yaml(yaml).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_manifest_invalid() {
+        assert!(Manifest::from_yaml("networks: [{name: a, mtuu: 1}]").is_err());
+        assert!(Manifest::from_yaml(
+            "networks: [{name: a}, {name: a}]").is_err());
+        assert!(Manifest::from_yaml(
+            "networks: [{name: a, subnets: [{cidr: 10.0.0.0/8}, \
+             {cidr: 10.0.0.0/8}]}]").is_err());
+        assert!(Manifest::from_yaml(
+            "security_groups: [{name: a}, {name: a}]").is_err());
+        assert!(Manifest::from_yaml(
+            "security_groups: [{name: a, rules: [{protocol: tcp}]}]").is_err());
+    }
+
+    #[cfg(feature = "compute")]
+    #[test]
+    fn test_manifest_servers() {
+        let manifest = Manifest::from_yaml("
+servers:
+  - name: bastion
+    flavor: m1.small
+    image: 6d1a9e3c
+    networks: [backend]
+    metadata: {role: bastion}
+").unwrap();
+        assert_eq!(manifest.servers.len(), 1);
+        assert_eq!(manifest.servers[0].networks, vec!["backend"]);
+        assert_eq!(manifest.servers[0].metadata.get("role").unwrap(), "bastion");
+        assert!(manifest.servers[0].key_pair.is_none());
+        assert!(Manifest::from_yaml(
+            "servers: [{name: a, flavor: f, image: i}, \
+             {name: a, flavor: f, image: i}]").is_err());
+    }
+
+    #[test]
+    fn test_manifest_apply() {
+        let server = FakeServer::with_version("v2.0", "");
+        // An existing network without the tag
+        server.respond("GET", "/networks", 200,
+                       r#"{"networks": [{"admin_state_up": true, "id": "n1",
+                           "name": "backend", "shared": false,
+                           "subnets": ["s1"], "tags": []}]}"#);
+        server.respond("GET", "/networks", 200, r#"{"networks": []}"#);
+        server.respond("PUT", "/networks/n1/tags/managed-by-ci", 201, "");
+        // A subnet with name servers not mentioned in the manifest
+        server.respond("GET", "/subnets", 200,
+                       r#"{"subnets": [{"cidr": "10.10.0.0/24",
+                           "dns_nameservers": ["1.1.1.1"],
+                           "enable_dhcp": true, "id": "s1", "ip_version": 4,
+                           "name": "", "network_id": "n1"}]}"#);
+        server.respond("GET", "/subnets", 200, r#"{"subnets": []}"#);
+        // A missing security group with the default egress rule
+        server.respond("GET", "/security-groups", 200,
+                       r#"{"security_groups": []}"#);
+        server.respond("POST", "/security-groups", 201,
+                       r#"{"security_group": {"id": "sg1", "name": "ssh"}}"#);
+        server.respond("PUT", "/security-groups/sg1/tags/managed-by-ci", 201,
+                       "");
+        server.respond("GET", "/security-group-rules", 200,
+                       r#"{"security_group_rules": [{"id": "r0",
+                           "direction": "egress", "ethertype": "IPv4",
+                           "security_group_id": "sg1"}]}"#);
+        server.respond("GET", "/security-group-rules", 200,
+                       r#"{"security_group_rules": []}"#);
+        server.respond("POST", "/security-group-rules", 201,
+                       r#"{"security_group_rule": {"id": "r1",
+                           "direction": "ingress", "ethertype": "IPv4",
+                           "protocol": "tcp", "port_range_min": 22,
+                           "port_range_max": 22,
+                           "security_group_id": "sg1"}}"#);
+        server.respond("DELETE", "/security-group-rules/r0", 204, "");
+        let cloud = Cloud::from(server.session());
+
+        let manifest = Manifest::from_yaml("
+tag: managed-by-ci
+networks:
+  - name: backend
+    subnets:
+      - cidr: 10.10.0.0/24
+security_groups:
+  - name: ssh
+    rules:
+      - direction: ingress
+        protocol: tcp
+        port_range_min: 22
+        port_range_max: 22
+").unwrap();
+        let report = manifest.apply(&cloud, false).unwrap();
+        assert_eq!(report.updated, vec!["network/backend"]);
+        assert_eq!(report.created, vec!["security_group/ssh"]);
+        assert!(report.drifted.is_empty());
+        assert!(report.deleted.is_empty());
+
+        let requests = server.requests();
+        for expected in &["PUT /networks/n1/tags/managed-by-ci",
+                          "PUT /security-groups/sg1/tags/managed-by-ci",
+                          "POST /security-group-rules",
+                          "DELETE /security-group-rules/r0"] {
+            assert!(requests.contains(&expected.to_string()),
+                    "{} not in {:?}", expected, requests);
+        }
+        // Only the tag was missing, the network itself is not updated
+        assert!(! requests.contains(&String::from("PUT /networks/n1")));
+    }
+
+    #[test]
+    fn test_manifest_apply_updates_drift() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", "/networks", 200,
+                       r#"{"networks": [{"admin_state_up": true, "id": "n1",
+                           "name": "backend", "shared": false,
+                           "subnets": ["s1"], "tags": []}]}"#);
+        server.respond("GET", "/networks", 200, r#"{"networks": []}"#);
+        server.respond("GET", "/subnets", 200,
+                       r#"{"subnets": [{"cidr": "10.10.0.0/24",
+                           "dns_nameservers": ["1.1.1.1"],
+                           "enable_dhcp": true, "gateway_ip": "10.10.0.1",
+                           "id": "s1", "ip_version": 4, "name": "",
+                           "network_id": "n1"}]}"#);
+        server.respond("GET", "/subnets", 200, r#"{"subnets": []}"#);
+        server.respond("PUT", "/subnets/s1", 200,
+                       r#"{"subnet": {"cidr": "10.10.0.0/24",
+                           "dns_nameservers": ["8.8.8.8"],
+                           "enable_dhcp": false, "gateway_ip": "10.10.0.1",
+                           "id": "s1", "ip_version": 4, "name": "",
+                           "network_id": "n1"}}"#);
+        server.respond("GET", "/security-groups", 200,
+                       r#"{"security_groups": [{"id": "sg1", "name": "ssh",
+                           "description": "old"}]}"#);
+        server.respond("PUT", "/security-groups/sg1", 200,
+                       r#"{"security_group": {"id": "sg1", "name": "ssh",
+                           "description": "SSH access"}}"#);
+        server.respond("GET", "/security-group-rules", 200,
+                       r#"{"security_group_rules": []}"#);
+        let cloud = Cloud::from(server.session());
+
+        let manifest = Manifest::from_yaml("
+networks:
+  - name: backend
+    subnets:
+      - cidr: 10.10.0.0/24
+        dhcp_enabled: false
+        dns_nameservers: [8.8.8.8]
+security_groups:
+  - name: ssh
+    description: SSH access
+").unwrap();
+        let report = manifest.apply(&cloud, false).unwrap();
+        assert_eq!(report.updated, vec!["subnet/backend/10.10.0.0/24",
+                                        "security_group/ssh"]);
+        assert!(report.drifted.is_empty());
+        assert!(report.created.is_empty());
+
+        let requests = server.requests_with_bodies();
+        let body = |line: &str| requests.iter()
+            .find(|item| item.0 == line).map(|item| item.1.clone());
+        assert_eq!(body("PUT /subnets/s1").unwrap(), concat!(
+            r#"{"subnet":{"enable_dhcp":false,"#,
+            r#""dns_nameservers":["8.8.8.8"]}}"#));
+        assert_eq!(body("PUT /security-groups/sg1").unwrap(),
+                   r#"{"security_group":{"description":"SSH access"}}"#);
+        assert!(body("PUT /networks/n1").is_none());
+    }
+
+    #[test]
+    fn test_manifest_apply_prune() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", "/networks", 200,
+                       r#"{"networks": [{"admin_state_up": true, "id": "n2",
+                           "name": "old", "subnets": [],
+                           "tags": ["managed-by-ci"]}]}"#);
+        server.respond("GET", "/networks", 200, r#"{"networks": []}"#);
+        server.respond("DELETE", "/networks/n2", 204, "");
+        server.respond("GET", "/security-groups", 200,
+                       r#"{"security_groups": [{"id": "sg2", "name": "old",
+                           "tags": ["managed-by-ci"]}]}"#);
+        server.respond("GET", "/security-groups", 200,
+                       r#"{"security_groups": []}"#);
+        server.respond("DELETE", "/security-groups/sg2", 204, "");
+        server.respond("GET", "/servers/detail", 200, r#"{"servers": []}"#);
+        let cloud = Cloud::from(server.session());
+
+        let manifest = Manifest::from_yaml("tag: managed-by-ci").unwrap();
+        let report = manifest.apply(&cloud, true).unwrap();
+        assert_eq!(report.deleted, vec!["security_group/old", "network/old"]);
+        assert!(report.created.is_empty());
+
+        let requests = server.requests();
+        assert!(requests.iter().any(|req| req.starts_with("GET /networks?") &&
+                                    req.contains("tags=managed-by-ci")));
+        assert!(requests.iter().any(
+            |req| req.starts_with("GET /security-groups?") &&
+                req.contains("tags=managed-by-ci")));
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Address groups management via Network API.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::Duration;
+
+use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
+use ipnet::IpNet;
+use serde::Serialize;
+
+use super::super::{Error, Result};
+use super::super::common::{self, DeletionWaiter, ListResources, Refresh,
+                           ResourceId, ResourceIterator};
+use super::super::session::Session;
+use super::super::utils::Query;
+use super::base::V2API;
+use super::protocol;
+
+
+/// A query to address group list.
+#[derive(Clone, Debug)]
+pub struct AddressGroupQuery {
+    session: Rc<Session>,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// Structure representing an address group - a named set of CIDRs.
+#[derive(Clone, Debug)]
+pub struct AddressGroup {
+    session: Rc<Session>,
+    inner: protocol::AddressGroup,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create an address group.
+#[derive(Clone, Debug)]
+pub struct NewAddressGroup {
+    session: Rc<Session>,
+    inner: protocol::AddressGroup,
+}
+
+impl AddressGroup {
+    /// Create an address group object.
+    fn new(session: Rc<Session>, inner: protocol::AddressGroup) -> AddressGroup {
+        AddressGroup {
+            session: session,
+            inner: inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load an AddressGroup object.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<AddressGroup> {
+        let inner = session.get_address_group(id)?;
+        Ok(AddressGroup::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Addresses (CIDRs) in the group."]
+        addresses: ref Vec<IpNet>
+    }
+
+    transparent_property! {
+        #[doc = "Address group description."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Address group name."]
+        name: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the name."]
+        set_name, with_name -> name: optional String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the address group."]
+        project_id: ref Option<String>
+    }
+
+    /// Add addresses to the group.
+    ///
+    /// The change is applied immediately.
+    pub fn add_addresses<I>(&mut self, addresses: I) -> Result<()>
+            where I: IntoIterator<Item = IpNet> {
+        let addresses = addresses.into_iter().collect();
+        self.inner = self.session.add_address_group_addresses(&self.inner.id,
+                                                              addresses)?;
+        Ok(())
+    }
+
+    /// Remove addresses from the group.
+    ///
+    /// The change is applied immediately.
+    pub fn remove_addresses<I>(&mut self, addresses: I) -> Result<()>
+            where I: IntoIterator<Item = IpNet> {
+        let addresses = addresses.into_iter().collect();
+        self.inner = self.session.remove_address_group_addresses(&self.inner.id,
+                                                                 addresses)?;
+        Ok(())
+    }
+
+    /// Delete the address group.
+    pub fn delete(self) -> Result<DeletionWaiter<AddressGroup>> {
+        self.session.delete_address_group(&self.inner.id)?;
+        Ok(DeletionWaiter::new(self, Duration::new(60, 0), Duration::new(1, 0)))
+    }
+
+    /// Whether the address group is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the address group.
+    pub fn save(&mut self) -> Result<()> {
+        let mut update = protocol::AddressGroupUpdate::default();
+        save_option_fields! {
+            self -> update: description name
+        };
+        let inner = self.session.update_address_group(self.id(), update)?;
+        self.dirty.clear();
+        self.inner = inner;
+        Ok(())
+    }
+}
+
+impl Refresh for AddressGroup {
+    /// Refresh the address group.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_address_group_by_id(&self.inner.id)?;
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+impl AddressGroupQuery {
+    pub(crate) fn new(session: Rc<Session>) -> AddressGroupQuery {
+        AddressGroupQuery {
+            session: session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            max_items: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by description."]
+        set_description, with_description -> description
+    }
+
+    query_filter! {
+        #[doc = "Filter by address group name."]
+        set_name, with_name -> name
+    }
+
+    query_filter! {
+        #[doc = "Filter by project ID."]
+        set_project_id, with_project_id -> project_id
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<AddressGroup> {
+        debug!("Fetching address groups with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Convert this query into an iterator fetching `page_size` items per
+    /// request.
+    pub fn into_iter_paginated(self, page_size: usize)
+            -> ResourceIterator<AddressGroup> {
+        self.with_page_size(page_size).into_iter()
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<AddressGroup>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(mut self) -> Result<AddressGroup> {
+        debug!("Fetching one address group with {:?}", self.query);
+        if self.can_paginate {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        self.into_iter().one()
+    }
+}
+
+impl NewAddressGroup {
+    /// Start creating an address group.
+    pub(crate) fn new(session: Rc<Session>, name: String) -> NewAddressGroup {
+        NewAddressGroup {
+            session: session,
+            inner: protocol::AddressGroup {
+                addresses: Vec::new(),
+                description: None,
+                id: String::new(),
+                name: Some(name),
+                project_id: None,
+            },
+        }
+    }
+
+    /// Request creation of the address group.
+    pub fn create(self) -> Result<AddressGroup> {
+        let inner = self.session.create_address_group(self.inner)?;
+        Ok(AddressGroup::new(self.session, inner))
+    }
+
+    /// Serialize the request body that `create` would send.
+    pub fn to_request_json(&self) -> Result<String> {
+        let body = protocol::AddressGroupRoot {
+            address_group: self.inner.clone()
+        };
+        common::protocol::to_request_json(&body)
+    }
+
+    /// Add an address (CIDR) to the group.
+    pub fn add_address(&mut self, address: IpNet) {
+        self.inner.addresses.push(address);
+    }
+
+    /// Add an address (CIDR) to the group.
+    pub fn with_address(mut self, address: IpNet) -> Self {
+        self.add_address(address);
+        self
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the address group."]
+        set_description, with_description -> description: optional String
+    }
+}
+
+impl ResourceId for AddressGroup {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for AddressGroup {
+    const DEFAULT_LIMIT: usize = 50;
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<AddressGroup>> {
+        Ok(session.list_address_groups(&query)?.into_iter()
+           .map(|item| AddressGroup::new(session.clone(), item)).collect())
+    }
+}
+
+impl IntoFallibleIterator for AddressGroupQuery {
+    type Item = AddressGroup;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<AddressGroup>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<AddressGroup> {
+        self.into_iter()
+    }
+}
@@ -16,6 +16,7 @@
 
 use std::fmt::Debug;
 
+use ipnet::IpNet;
 use reqwest::{Method, Url};
 use serde::Serialize;
 
@@ -29,15 +30,137 @@ use super::protocol;
 
 /// Extensions for Session.
 pub trait V2API {
+    /// Add addresses to an address group.
+    fn add_address_group_addresses<S: AsRef<str>>(&self, id: S,
+                                                  addresses: Vec<IpNet>)
+        -> Result<protocol::AddressGroup>;
+
+    /// Create an address group.
+    fn create_address_group(&self, request: protocol::AddressGroup)
+        -> Result<protocol::AddressGroup>;
+
+    /// Add a tag to a resource.
+    fn add_tag<S1, S2>(&self, resource: &str, id: S1, tag: S2) -> Result<()>
+        where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Add subports to a trunk.
+    fn add_trunk_subports<S: AsRef<str>>(&self, id: S,
+                                         sub_ports: Vec<protocol::SubPort>)
+        -> Result<protocol::Trunk>;
+
+    /// Create a conntrack helper of a router.
+    fn create_conntrack_helper<S: AsRef<str>>(&self, router_id: S,
+                                              request: protocol::ConntrackHelper)
+        -> Result<protocol::ConntrackHelper>;
+
+    /// Create a network.
+    fn create_network(&self, request: protocol::NetworkCreate)
+        -> Result<protocol::Network>;
+
     /// Create a port.
     fn create_port(&self, request: protocol::Port) -> Result<protocol::Port>;
 
+    /// Create several ports in one request.
+    fn create_ports(&self, request: Vec<protocol::Port>)
+        -> Result<Vec<protocol::Port>>;
+
+    /// Create a minimum bandwidth rule in a QoS policy.
+    fn create_qos_minimum_bandwidth_rule<S: AsRef<str>>(
+        &self, policy_id: S, request: protocol::QosMinimumBandwidthRule)
+        -> Result<protocol::QosMinimumBandwidthRule>;
+
+    /// Create a minimum packet rate rule in a QoS policy.
+    fn create_qos_minimum_packet_rate_rule<S: AsRef<str>>(
+        &self, policy_id: S, request: protocol::QosMinimumPacketRateRule)
+        -> Result<protocol::QosMinimumPacketRateRule>;
+
+    /// Create a packet rate limit rule in a QoS policy.
+    fn create_qos_packet_rate_limit_rule<S: AsRef<str>>(
+        &self, policy_id: S, request: protocol::QosPacketRateLimitRule)
+        -> Result<protocol::QosPacketRateLimitRule>;
+
+    /// Create a security group.
+    fn create_security_group(&self, request: protocol::SecurityGroup)
+        -> Result<protocol::SecurityGroup>;
+
+    /// Create a security group rule.
+    fn create_security_group_rule(&self, request: protocol::SecurityGroupRule)
+        -> Result<protocol::SecurityGroupRule>;
+
+    /// Create a subnet.
+    fn create_subnet(&self, request: protocol::SubnetCreate)
+        -> Result<protocol::Subnet>;
+
+    /// Create several subnets in one request.
+    fn create_subnets(&self, request: Vec<protocol::SubnetCreate>)
+        -> Result<Vec<protocol::Subnet>>;
+
+    /// Create a tap flow.
+    fn create_tap_flow(&self, request: protocol::TapFlow)
+        -> Result<protocol::TapFlow>;
+
+    /// Create a tap service.
+    fn create_tap_service(&self, request: protocol::TapService)
+        -> Result<protocol::TapService>;
+
+    /// Create a trunk.
+    fn create_trunk(&self, request: protocol::Trunk) -> Result<protocol::Trunk>;
+
+    /// Delete an address group.
+    fn delete_address_group<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Delete a conntrack helper of a router.
+    fn delete_conntrack_helper<S1, S2>(&self, router_id: S1, id: S2)
+        -> Result<()> where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Delete a network.
+    fn delete_network<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
     /// Delete a port.
     fn delete_port<S: AsRef<str>>(&self, id_or_name: S) -> Result<()>;
 
+    /// Delete a rule of a QoS policy.
+    fn delete_qos_rule<S1, S2>(&self, policy_id: S1,
+                               rule_type: protocol::QosRuleType, id: S2)
+        -> Result<()> where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Delete a router.
+    fn delete_router<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Delete a security group.
+    fn delete_security_group<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Delete a security group rule.
+    fn delete_security_group_rule<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
     /// Delete a subnet.
     fn delete_subnet<S: AsRef<str>>(&self, id: S) -> Result<()>;
 
+    /// Delete a tap flow.
+    fn delete_tap_flow<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Delete a tap service.
+    fn delete_tap_service<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Delete a trunk.
+    fn delete_trunk<S: AsRef<str>>(&self, id: S) -> Result<()>;
+
+    /// Get an address group.
+    fn get_address_group<S: AsRef<str>>(&self, id_or_name: S)
+            -> Result<protocol::AddressGroup> {
+        let s = id_or_name.as_ref();
+        self.get_address_group_by_id(s)
+            .if_not_found_then(|| self.get_address_group_by_name(s))
+    }
+
+    /// Get an address group by its ID.
+    fn get_address_group_by_id<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::AddressGroup>;
+
+    /// Get an address group by its name.
+    fn get_address_group_by_name<S: AsRef<str>>(&self, name: S)
+        -> Result<protocol::AddressGroup>;
+
     /// Get a network.
     fn get_network<S: AsRef<str>>(&self, id_or_name: S) -> Result<protocol::Network> {
         let s = id_or_name.as_ref();
@@ -50,6 +173,16 @@ pub trait V2API {
     /// Get a network by its name.
     fn get_network_by_name<S: AsRef<str>>(&self, name: S) -> Result<protocol::Network>;
 
+    /// Get network quotas of a project with their usage.
+    ///
+    /// Requires the `quota_details` extension.
+    fn get_network_quota_details<S: AsRef<str>>(&self, project_id: S)
+        -> Result<protocol::NetworkQuotaDetails>;
+
+    /// Get network quotas of a project.
+    fn get_network_quotas<S: AsRef<str>>(&self, project_id: S)
+        -> Result<protocol::NetworkQuotas>;
+
     /// Get a port.
     fn get_port<S: AsRef<str>>(&self, id_or_name: S) -> Result<protocol::Port> {
         let s = id_or_name.as_ref();
@@ -62,6 +195,42 @@ pub trait V2API {
     /// Get a port by its name.
     fn get_port_by_name<S: AsRef<str>>(&self, name: S) -> Result<protocol::Port>;
 
+    /// Get a router.
+    fn get_router<S: AsRef<str>>(&self, id_or_name: S)
+            -> Result<protocol::Router> {
+        let s = id_or_name.as_ref();
+        self.get_router_by_id(s)
+            .if_not_found_then(|| self.get_router_by_name(s))
+    }
+
+    /// Get a router by its ID.
+    fn get_router_by_id<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::Router>;
+
+    /// Get a router by its name.
+    fn get_router_by_name<S: AsRef<str>>(&self, name: S)
+        -> Result<protocol::Router>;
+
+    /// Get a security group.
+    fn get_security_group<S: AsRef<str>>(&self, id_or_name: S)
+            -> Result<protocol::SecurityGroup> {
+        let s = id_or_name.as_ref();
+        self.get_security_group_by_id(s)
+            .if_not_found_then(|| self.get_security_group_by_name(s))
+    }
+
+    /// Get a security group by its ID.
+    fn get_security_group_by_id<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::SecurityGroup>;
+
+    /// Get a security group by its name.
+    fn get_security_group_by_name<S: AsRef<str>>(&self, name: S)
+        -> Result<protocol::SecurityGroup>;
+
+    /// Get a security group rule by its ID.
+    fn get_security_group_rule<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::SecurityGroupRule>;
+
     /// Get a subnet.
     fn get_subnet<S: AsRef<str>>(&self, id_or_name: S) -> Result<protocol::Subnet> {
         let s = id_or_name.as_ref();
@@ -74,6 +243,62 @@ pub trait V2API {
     /// Get a subnet by its name.
     fn get_subnet_by_name<S: AsRef<str>>(&self, name: S) -> Result<protocol::Subnet>;
 
+    /// Get a tap flow.
+    fn get_tap_flow<S: AsRef<str>>(&self, id_or_name: S)
+            -> Result<protocol::TapFlow> {
+        let s = id_or_name.as_ref();
+        self.get_tap_flow_by_id(s)
+            .if_not_found_then(|| self.get_tap_flow_by_name(s))
+    }
+
+    /// Get a tap flow by its ID.
+    fn get_tap_flow_by_id<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::TapFlow>;
+
+    /// Get a tap flow by its name.
+    fn get_tap_flow_by_name<S: AsRef<str>>(&self, name: S)
+        -> Result<protocol::TapFlow>;
+
+    /// Get a tap service.
+    fn get_tap_service<S: AsRef<str>>(&self, id_or_name: S)
+            -> Result<protocol::TapService> {
+        let s = id_or_name.as_ref();
+        self.get_tap_service_by_id(s)
+            .if_not_found_then(|| self.get_tap_service_by_name(s))
+    }
+
+    /// Get a tap service by its ID.
+    fn get_tap_service_by_id<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::TapService>;
+
+    /// Get a tap service by its name.
+    fn get_tap_service_by_name<S: AsRef<str>>(&self, name: S)
+        -> Result<protocol::TapService>;
+
+    /// Get a trunk.
+    fn get_trunk<S: AsRef<str>>(&self, id_or_name: S)
+            -> Result<protocol::Trunk> {
+        let s = id_or_name.as_ref();
+        self.get_trunk_by_id(s)
+            .if_not_found_then(|| self.get_trunk_by_name(s))
+    }
+
+    /// Get a trunk by its ID.
+    fn get_trunk_by_id<S: AsRef<str>>(&self, id: S)
+        -> Result<protocol::Trunk>;
+
+    /// Get a trunk by its name.
+    fn get_trunk_by_name<S: AsRef<str>>(&self, name: S)
+        -> Result<protocol::Trunk>;
+
+    /// List address groups.
+    fn list_address_groups<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::AddressGroup>>;
+
+    /// List conntrack helpers of a router.
+    fn list_conntrack_helpers<S: AsRef<str>>(&self, router_id: S)
+        -> Result<Vec<protocol::ConntrackHelper>>;
+
     /// List networks.
     fn list_networks<Q: Serialize + Debug>(&self, query: &Q)
         -> Result<Vec<protocol::Network>>;
@@ -82,13 +307,121 @@ pub trait V2API {
     fn list_ports<Q: Serialize + Debug>(&self, query: &Q)
         -> Result<Vec<protocol::Port>>;
 
+    /// List minimum bandwidth rules of a QoS policy.
+    fn list_qos_minimum_bandwidth_rules<S: AsRef<str>>(&self, policy_id: S)
+        -> Result<Vec<protocol::QosMinimumBandwidthRule>>;
+
+    /// List minimum packet rate rules of a QoS policy.
+    fn list_qos_minimum_packet_rate_rules<S: AsRef<str>>(&self, policy_id: S)
+        -> Result<Vec<protocol::QosMinimumPacketRateRule>>;
+
+    /// List packet rate limit rules of a QoS policy.
+    fn list_qos_packet_rate_limit_rules<S: AsRef<str>>(&self, policy_id: S)
+        -> Result<Vec<protocol::QosPacketRateLimitRule>>;
+
+    /// List QoS rule types supported by the backend.
+    fn list_qos_rule_types(&self) -> Result<Vec<String>>;
+
+    /// List routers.
+    fn list_routers<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::Router>>;
+
+    /// List security group rules.
+    fn list_security_group_rules<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::SecurityGroupRule>>;
+
+    /// List security groups.
+    fn list_security_groups<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::SecurityGroup>>;
+
+    /// List flavors of the given network service.
+    fn list_service_flavors<S: AsRef<str>>(&self, service_type: S)
+        -> Result<Vec<protocol::ServiceFlavor>>;
+
     /// List subnets.
     fn list_subnets<Q: Serialize + Debug>(&self, query: &Q)
         -> Result<Vec<protocol::Subnet>>;
 
+    /// List tap flows.
+    fn list_tap_flows<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::TapFlow>>;
+
+    /// List tap services.
+    fn list_tap_services<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::TapService>>;
+
+    /// List trunks.
+    fn list_trunks<Q: Serialize + Debug>(&self, query: &Q)
+        -> Result<Vec<protocol::Trunk>>;
+
+    /// Move all subnets of a network into a subnet pool.
+    fn onboard_network_subnets<S1, S2>(&self, subnet_pool_id: S1,
+                                       network_id: S2) -> Result<()>
+        where S1: AsRef<str>, S2: Into<String>;
+
+    /// Remove addresses from an address group.
+    fn remove_address_group_addresses<S: AsRef<str>>(&self, id: S,
+                                                     addresses: Vec<IpNet>)
+        -> Result<protocol::AddressGroup>;
+
+    /// Remove a tag from a resource.
+    fn remove_tag<S1, S2>(&self, resource: &str, id: S1, tag: S2) -> Result<()>
+        where S1: AsRef<str>, S2: AsRef<str>;
+
+    /// Remove subports from a trunk.
+    fn remove_trunk_subports<S: AsRef<str>>(&self, id: S,
+                                            port_ids: Vec<String>)
+        -> Result<protocol::Trunk>;
+
+    /// Replace all tags of a resource.
+    fn replace_tags<S: AsRef<str>>(&self, resource: &str, id: S,
+                                   tags: Vec<String>) -> Result<Vec<String>>;
+
+    /// Update an address group.
+    fn update_address_group<S: AsRef<str>>(&self, id: S,
+                                           update: protocol::AddressGroupUpdate)
+        -> Result<protocol::AddressGroup>;
+
+    /// Update a network.
+    fn update_network<S: AsRef<str>>(&self, id: S,
+                                     update: protocol::NetworkUpdate)
+        -> Result<protocol::Network>;
+
+    /// Update network quotas of a project.
+    fn update_network_quotas<S: AsRef<str>>(&self, project_id: S,
+                                            update: protocol::NetworkQuotasUpdate)
+        -> Result<protocol::NetworkQuotas>;
+
     /// Update a port.
     fn update_port<S: AsRef<str>>(&self, id: S, update: protocol::PortUpdate)
         -> Result<protocol::Port>;
+
+    /// Update a router.
+    fn update_router<S: AsRef<str>>(&self, id: S, update: protocol::RouterUpdate)
+        -> Result<protocol::Router>;
+
+    /// Update a security group.
+    fn update_security_group<S: AsRef<str>>(
+        &self, id: S, update: protocol::SecurityGroupUpdate)
+        -> Result<protocol::SecurityGroup>;
+
+    /// Update a subnet.
+    fn update_subnet<S: AsRef<str>>(&self, id: S, update: protocol::SubnetUpdate)
+        -> Result<protocol::Subnet>;
+
+    /// Update a tap flow.
+    fn update_tap_flow<S: AsRef<str>>(&self, id: S,
+                                      update: protocol::TapFlowUpdate)
+        -> Result<protocol::TapFlow>;
+
+    /// Update a tap service.
+    fn update_tap_service<S: AsRef<str>>(&self, id: S,
+                                         update: protocol::TapServiceUpdate)
+        -> Result<protocol::TapService>;
+
+    /// Update a trunk.
+    fn update_trunk<S: AsRef<str>>(&self, id: S, update: protocol::TrunkUpdate)
+        -> Result<protocol::Trunk>;
 }
 
 
@@ -102,6 +435,83 @@ const VERSION_ID: &'static str = "v2.0";
 
 
 impl V2API for Session {
+    fn add_address_group_addresses<S: AsRef<str>>(&self, id: S,
+                                                  addresses: Vec<IpNet>)
+            -> Result<protocol::AddressGroup> {
+        debug!("Adding {:?} to address group {}", addresses, id.as_ref());
+        let body = protocol::AddressGroupAddresses { addresses: addresses };
+        let group = self.request::<V2>(Method::Put,
+                                       &["address-groups", id.as_ref(),
+                                         "add_addresses"],
+                                       None)?
+            .json(&body).receive_json::<protocol::AddressGroupRoot>()?
+            .address_group;
+        debug!("Updated address group {:?}", group);
+        Ok(group)
+    }
+
+    fn create_address_group(&self, request: protocol::AddressGroup)
+            -> Result<protocol::AddressGroup> {
+        debug!("Creating a new address group with {:?}", request);
+        let body = protocol::AddressGroupRoot { address_group: request };
+        let group = self.request::<V2>(Method::Post, &["address-groups"], None)?
+            .json(&body).receive_json::<protocol::AddressGroupRoot>()?
+            .address_group;
+        debug!("Created address group {:?}", group);
+        Ok(group)
+    }
+
+    fn add_tag<S1, S2>(&self, resource: &str, id: S1, tag: S2) -> Result<()>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        debug!("Adding tag {} to {} {}", tag.as_ref(), resource, id.as_ref());
+        let _ = self.request::<V2>(Method::Put,
+                                   &[resource, id.as_ref(), "tags", tag.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Tag {} was added to {} {}", tag.as_ref(), resource, id.as_ref());
+        Ok(())
+    }
+
+    fn add_trunk_subports<S: AsRef<str>>(&self, id: S,
+                                         sub_ports: Vec<protocol::SubPort>)
+            -> Result<protocol::Trunk> {
+        debug!("Adding subports {:?} to trunk {}", sub_ports, id.as_ref());
+        let body = protocol::SubPortsRoot { sub_ports: sub_ports };
+        // NOTE: the response is not wrapped into a root object.
+        let result = self.request::<V2>(Method::Put,
+                                        &["trunks", id.as_ref(), "add_subports"],
+                                        None)?
+            .json(&body).receive_json::<protocol::Trunk>()?;
+        debug!("Updated trunk {:?}", result);
+        Ok(result)
+    }
+
+    fn create_conntrack_helper<S: AsRef<str>>(&self, router_id: S,
+                                              request: protocol::ConntrackHelper)
+            -> Result<protocol::ConntrackHelper> {
+        debug!("Creating a new conntrack helper for router {} with {:?}",
+               router_id.as_ref(), request);
+        let body = protocol::ConntrackHelperRoot { conntrack_helper: request };
+        let result = self.request::<V2>(Method::Post,
+                                        &["routers", router_id.as_ref(),
+                                          "conntrack_helpers"],
+                                        None)?
+            .json(&body).receive_json::<protocol::ConntrackHelperRoot>()?
+            .conntrack_helper;
+        debug!("Created conntrack helper {:?}", result);
+        Ok(result)
+    }
+
+    fn create_network(&self, request: protocol::NetworkCreate)
+            -> Result<protocol::Network> {
+        debug!("Creating a new network with {:?}", request);
+        let body = protocol::NetworkCreateRoot { network: request };
+        let network = self.request::<V2>(Method::Post, &["networks"], None)?
+            .json(&body).receive_json::<protocol::NetworkRoot>()?.network;
+        debug!("Created network {:?}", network);
+        Ok(network)
+    }
+
     fn create_port(&self, request: protocol::Port) -> Result<protocol::Port> {
         debug!("Creating a new port with {:?}", request);
         let body = protocol::PortRoot { port: request };
@@ -111,6 +521,183 @@ impl V2API for Session {
         Ok(port)
     }
 
+    fn create_ports(&self, request: Vec<protocol::Port>)
+            -> Result<Vec<protocol::Port>> {
+        debug!("Creating new ports in bulk with {:?}", request);
+        let body = protocol::PortsRoot { ports: request };
+        let ports = self.request::<V2>(Method::Post, &["ports"], None)?
+            .json(&body).receive_json::<protocol::PortsRoot>()?.ports;
+        debug!("Created ports {:?}", ports);
+        Ok(ports)
+    }
+
+    fn create_qos_minimum_bandwidth_rule<S: AsRef<str>>(
+            &self, policy_id: S, request: protocol::QosMinimumBandwidthRule)
+            -> Result<protocol::QosMinimumBandwidthRule> {
+        debug!("Creating a minimum bandwidth rule in QoS policy {} with {:?}",
+               policy_id.as_ref(), request);
+        let body = protocol::QosMinimumBandwidthRuleRoot {
+            minimum_bandwidth_rule: request
+        };
+        let result = self.request::<V2>(Method::Post,
+                                        &["qos", "policies", policy_id.as_ref(),
+                                          "minimum_bandwidth_rules"],
+                                        None)?
+            .json(&body)
+            .receive_json::<protocol::QosMinimumBandwidthRuleRoot>()?
+            .minimum_bandwidth_rule;
+        debug!("Created minimum bandwidth rule {:?}", result);
+        Ok(result)
+    }
+
+    fn create_qos_minimum_packet_rate_rule<S: AsRef<str>>(
+            &self, policy_id: S, request: protocol::QosMinimumPacketRateRule)
+            -> Result<protocol::QosMinimumPacketRateRule> {
+        debug!("Creating a minimum packet rate rule in QoS policy {} with {:?}",
+               policy_id.as_ref(), request);
+        let body = protocol::QosMinimumPacketRateRuleRoot {
+            minimum_packet_rate_rule: request
+        };
+        let result = self.request::<V2>(Method::Post,
+                                        &["qos", "policies", policy_id.as_ref(),
+                                          "minimum_packet_rate_rules"],
+                                        None)?
+            .json(&body)
+            .receive_json::<protocol::QosMinimumPacketRateRuleRoot>()?
+            .minimum_packet_rate_rule;
+        debug!("Created minimum packet rate rule {:?}", result);
+        Ok(result)
+    }
+
+    fn create_qos_packet_rate_limit_rule<S: AsRef<str>>(
+            &self, policy_id: S, request: protocol::QosPacketRateLimitRule)
+            -> Result<protocol::QosPacketRateLimitRule> {
+        debug!("Creating a packet rate limit rule in QoS policy {} with {:?}",
+               policy_id.as_ref(), request);
+        let body = protocol::QosPacketRateLimitRuleRoot {
+            packet_rate_limit_rule: request
+        };
+        let result = self.request::<V2>(Method::Post,
+                                        &["qos", "policies", policy_id.as_ref(),
+                                          "packet_rate_limit_rules"],
+                                        None)?
+            .json(&body)
+            .receive_json::<protocol::QosPacketRateLimitRuleRoot>()?
+            .packet_rate_limit_rule;
+        debug!("Created packet rate limit rule {:?}", result);
+        Ok(result)
+    }
+
+    fn create_security_group(&self, request: protocol::SecurityGroup)
+            -> Result<protocol::SecurityGroup> {
+        debug!("Creating a new security group with {:?}", request);
+        let body = protocol::SecurityGroupRoot { security_group: request };
+        let result = self.request::<V2>(Method::Post, &["security-groups"],
+                                        None)?
+            .json(&body).receive_json::<protocol::SecurityGroupRoot>()?
+            .security_group;
+        debug!("Created security group {:?}", result);
+        Ok(result)
+    }
+
+    fn create_security_group_rule(&self, request: protocol::SecurityGroupRule)
+            -> Result<protocol::SecurityGroupRule> {
+        debug!("Creating a new security group rule with {:?}", request);
+        let body = protocol::SecurityGroupRuleRoot {
+            security_group_rule: request
+        };
+        let result = self.request::<V2>(Method::Post,
+                                        &["security-group-rules"], None)?
+            .json(&body).receive_json::<protocol::SecurityGroupRuleRoot>()?
+            .security_group_rule;
+        debug!("Created security group rule {:?}", result);
+        Ok(result)
+    }
+
+    fn create_subnet(&self, request: protocol::SubnetCreate)
+            -> Result<protocol::Subnet> {
+        debug!("Creating a new subnet with {:?}", request);
+        let body = protocol::SubnetCreateRoot { subnet: request };
+        let subnet = self.request::<V2>(Method::Post, &["subnets"], None)?
+            .json(&body).receive_json::<protocol::SubnetRoot>()?.subnet;
+        debug!("Created subnet {:?}", subnet);
+        Ok(subnet)
+    }
+
+    fn create_subnets(&self, request: Vec<protocol::SubnetCreate>)
+            -> Result<Vec<protocol::Subnet>> {
+        debug!("Creating new subnets in bulk with {:?}", request);
+        let body = protocol::SubnetsCreateRoot { subnets: request };
+        let subnets = self.request::<V2>(Method::Post, &["subnets"], None)?
+            .json(&body).receive_json::<protocol::SubnetsRoot>()?.subnets;
+        debug!("Created subnets {:?}", subnets);
+        Ok(subnets)
+    }
+
+    fn create_tap_flow(&self, request: protocol::TapFlow)
+            -> Result<protocol::TapFlow> {
+        debug!("Creating a new tap flow with {:?}", request);
+        let body = protocol::TapFlowRoot { tap_flow: request };
+        let result = self.request::<V2>(Method::Post, &["taas", "tap_flows"], None)?
+            .json(&body).receive_json::<protocol::TapFlowRoot>()?.tap_flow;
+        debug!("Created tap flow {:?}", result);
+        Ok(result)
+    }
+
+    fn create_tap_service(&self, request: protocol::TapService)
+            -> Result<protocol::TapService> {
+        debug!("Creating a new tap service with {:?}", request);
+        let body = protocol::TapServiceRoot { tap_service: request };
+        let result = self.request::<V2>(Method::Post,
+                                        &["taas", "tap_services"], None)?
+            .json(&body).receive_json::<protocol::TapServiceRoot>()?
+            .tap_service;
+        debug!("Created tap service {:?}", result);
+        Ok(result)
+    }
+
+    fn create_trunk(&self, request: protocol::Trunk) -> Result<protocol::Trunk> {
+        debug!("Creating a new trunk with {:?}", request);
+        let body = protocol::TrunkRoot { trunk: request };
+        let result = self.request::<V2>(Method::Post, &["trunks"], None)?
+            .json(&body).receive_json::<protocol::TrunkRoot>()?.trunk;
+        debug!("Created trunk {:?}", result);
+        Ok(result)
+    }
+
+    fn delete_address_group<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting address group {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["address-groups", id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Address group {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn delete_conntrack_helper<S1, S2>(&self, router_id: S1, id: S2)
+            -> Result<()> where S1: AsRef<str>, S2: AsRef<str> {
+        debug!("Deleting conntrack helper {} of router {}", id.as_ref(),
+               router_id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["routers", router_id.as_ref(),
+                                     "conntrack_helpers", id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Conntrack helper {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn delete_network<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting network {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["networks", id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Network {} was deleted", id.as_ref());
+        Ok(())
+    }
+
     fn delete_port<S: AsRef<str>>(&self, id: S) -> Result<()> {
         debug!("Deleting port {}", id.as_ref());
         let _ = self.request::<V2>(Method::Delete,
@@ -121,6 +708,50 @@ impl V2API for Session {
         Ok(())
     }
 
+    fn delete_qos_rule<S1, S2>(&self, policy_id: S1,
+                               rule_type: protocol::QosRuleType, id: S2)
+            -> Result<()> where S1: AsRef<str>, S2: AsRef<str> {
+        debug!("Deleting {} rule {} of QoS policy {}", rule_type, id.as_ref(),
+               policy_id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["qos", "policies", policy_id.as_ref(),
+                                     &format!("{}_rules", rule_type),
+                                     id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("QoS rule {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn delete_router<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting router {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete, &["routers", id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Router {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn delete_security_group<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting security group {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["security-groups", id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Security group {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn delete_security_group_rule<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting security group rule {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["security-group-rules", id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Security group rule {} was deleted", id.as_ref());
+        Ok(())
+    }
+
     fn delete_subnet<S: AsRef<str>>(&self, id: S) -> Result<()> {
         debug!("Deleting subnet {}", id.as_ref());
         let _ = self.request::<V2>(Method::Delete,
@@ -131,6 +762,58 @@ impl V2API for Session {
         Ok(())
     }
 
+    fn delete_tap_flow<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting tap flow {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["taas", "tap_flows", id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Tap flow {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn delete_tap_service<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting tap service {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &["taas", "tap_services", id.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Tap service {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn delete_trunk<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        debug!("Deleting trunk {}", id.as_ref());
+        let _ = self.request::<V2>(Method::Delete, &["trunks", id.as_ref()], None)?
+            .send()?;
+        debug!("Trunk {} was deleted", id.as_ref());
+        Ok(())
+    }
+
+    fn get_address_group_by_id<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::AddressGroup> {
+        trace!("Get address group by ID {}", id.as_ref());
+        let group = self.request::<V2>(Method::Get,
+                                       &["address-groups", id.as_ref()],
+                                       None)?
+           .receive_json::<protocol::AddressGroupRoot>()?.address_group;
+        trace!("Received {:?}", group);
+        Ok(group)
+    }
+
+    fn get_address_group_by_name<S: AsRef<str>>(&self, name: S)
+            -> Result<protocol::AddressGroup> {
+        trace!("Get address group by name {}", name.as_ref());
+        let items = self.request::<V2>(Method::Get, &["address-groups"], None)?
+            .query(&[("name", name.as_ref())])
+            .receive_json::<protocol::AddressGroupsRoot>()?.address_groups;
+        let result = utils::one(items,
+                                "Address group with given name or ID not found",
+                                "Too many address groups found with given name")?;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
     fn get_network_by_id<S: AsRef<str>>(&self, id: S) -> Result<protocol::Network> {
         trace!("Get network by ID {}", id.as_ref());
         let network = self.request::<V2>(Method::Get,
@@ -152,6 +835,29 @@ impl V2API for Session {
         Ok(result)
     }
 
+    fn get_network_quota_details<S: AsRef<str>>(&self, project_id: S)
+            -> Result<protocol::NetworkQuotaDetails> {
+        trace!("Get network quota details of project {}", project_id.as_ref());
+        let details = self.request::<V2>(Method::Get,
+                                         &["quotas", project_id.as_ref(),
+                                           "details"],
+                                         None)?
+           .receive_json::<protocol::NetworkQuotaDetailsRoot>()?.quota;
+        trace!("Received {:?}", details);
+        Ok(details)
+    }
+
+    fn get_network_quotas<S: AsRef<str>>(&self, project_id: S)
+            -> Result<protocol::NetworkQuotas> {
+        trace!("Get network quotas of project {}", project_id.as_ref());
+        let quotas = self.request::<V2>(Method::Get,
+                                        &["quotas", project_id.as_ref()],
+                                        None)?
+           .receive_json::<protocol::NetworkQuotasRoot>()?.quota;
+        trace!("Received {:?}", quotas);
+        Ok(quotas)
+    }
+
     fn get_port_by_id<S: AsRef<str>>(&self, id: S) -> Result<protocol::Port> {
         trace!("Get port by ID {}", id.as_ref());
         let port = self.request::<V2>(Method::Get,
@@ -173,6 +879,65 @@ impl V2API for Session {
         Ok(result)
     }
 
+    fn get_router_by_id<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::Router> {
+        trace!("Get router by ID {}", id.as_ref());
+        let result = self.request::<V2>(Method::Get, &["routers", id.as_ref()],
+                                        None)?
+           .receive_json::<protocol::RouterRoot>()?.router;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_router_by_name<S: AsRef<str>>(&self, name: S)
+            -> Result<protocol::Router> {
+        trace!("Get router by name {}", name.as_ref());
+        let items = self.request::<V2>(Method::Get, &["routers"], None)?
+            .query(&[("name", name.as_ref())])
+            .receive_json::<protocol::RoutersRoot>()?.routers;
+        let result = utils::one(items,
+                                "Router with given name or ID not found",
+                                "Too many routers found with given name")?;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_security_group_by_id<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::SecurityGroup> {
+        trace!("Get security group by ID {}", id.as_ref());
+        let result = self.request::<V2>(Method::Get,
+                                        &["security-groups", id.as_ref()],
+                                        None)?
+           .receive_json::<protocol::SecurityGroupRoot>()?.security_group;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_security_group_by_name<S: AsRef<str>>(&self, name: S)
+            -> Result<protocol::SecurityGroup> {
+        trace!("Get security group by name {}", name.as_ref());
+        let items = self.request::<V2>(Method::Get, &["security-groups"], None)?
+            .query(&[("name", name.as_ref())])
+            .receive_json::<protocol::SecurityGroupsRoot>()?.security_groups;
+        let result = utils::one(items,
+                                "Security group with given name or ID not found",
+                                "Too many security groups found with given name")?;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_security_group_rule<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::SecurityGroupRule> {
+        trace!("Get security group rule by ID {}", id.as_ref());
+        let result = self.request::<V2>(Method::Get,
+                                        &["security-group-rules", id.as_ref()],
+                                        None)?
+           .receive_json::<protocol::SecurityGroupRuleRoot>()?
+           .security_group_rule;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
     fn get_subnet_by_id<S: AsRef<str>>(&self, id: S) -> Result<protocol::Subnet> {
         trace!("Get subnet by ID {}", id.as_ref());
         let subnet = self.request::<V2>(Method::Get,
@@ -194,6 +959,99 @@ impl V2API for Session {
         Ok(result)
     }
 
+    fn get_tap_flow_by_id<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::TapFlow> {
+        trace!("Get tap flow by ID {}", id.as_ref());
+        let result = self.request::<V2>(Method::Get,
+                                        &["taas", "tap_flows", id.as_ref()],
+                                        None)?
+           .receive_json::<protocol::TapFlowRoot>()?.tap_flow;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_tap_flow_by_name<S: AsRef<str>>(&self, name: S)
+            -> Result<protocol::TapFlow> {
+        trace!("Get tap flow by name {}", name.as_ref());
+        let items = self.request::<V2>(Method::Get, &["taas", "tap_flows"], None)?
+            .query(&[("name", name.as_ref())])
+            .receive_json::<protocol::TapFlowsRoot>()?.tap_flows;
+        let result = utils::one(items,
+                                "Tap flow with given name or ID not found",
+                                "Too many tap flows found with given name")?;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_tap_service_by_id<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::TapService> {
+        trace!("Get tap service by ID {}", id.as_ref());
+        let result = self.request::<V2>(Method::Get,
+                                        &["taas", "tap_services", id.as_ref()],
+                                        None)?
+           .receive_json::<protocol::TapServiceRoot>()?.tap_service;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_tap_service_by_name<S: AsRef<str>>(&self, name: S)
+            -> Result<protocol::TapService> {
+        trace!("Get tap service by name {}", name.as_ref());
+        let items = self.request::<V2>(Method::Get, &["taas", "tap_services"], None)?
+            .query(&[("name", name.as_ref())])
+            .receive_json::<protocol::TapServicesRoot>()?.tap_services;
+        let result = utils::one(items,
+                                "Tap service with given name or ID not found",
+                                "Too many tap services found with given name")?;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_trunk_by_id<S: AsRef<str>>(&self, id: S)
+            -> Result<protocol::Trunk> {
+        trace!("Get trunk by ID {}", id.as_ref());
+        let result = self.request::<V2>(Method::Get, &["trunks", id.as_ref()],
+                                        None)?
+           .receive_json::<protocol::TrunkRoot>()?.trunk;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn get_trunk_by_name<S: AsRef<str>>(&self, name: S)
+            -> Result<protocol::Trunk> {
+        trace!("Get trunk by name {}", name.as_ref());
+        let items = self.request::<V2>(Method::Get, &["trunks"], None)?
+            .query(&[("name", name.as_ref())])
+            .receive_json::<protocol::TrunksRoot>()?.trunks;
+        let result = utils::one(items,
+                                "Trunk with given name or ID not found",
+                                "Too many trunks found with given name")?;
+        trace!("Received {:?}", result);
+        Ok(result)
+    }
+
+    fn list_address_groups<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::AddressGroup>> {
+        trace!("Listing address groups with {:?}", query);
+        let result = self.request::<V2>(Method::Get, &["address-groups"], None)?
+           .query(query).receive_json::<protocol::AddressGroupsRoot>()?
+           .address_groups;
+        trace!("Received address groups: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_conntrack_helpers<S: AsRef<str>>(&self, router_id: S)
+            -> Result<Vec<protocol::ConntrackHelper>> {
+        trace!("Listing conntrack helpers of router {}", router_id.as_ref());
+        let result = self.request::<V2>(Method::Get,
+                                        &["routers", router_id.as_ref(),
+                                          "conntrack_helpers"],
+                                        None)?
+           .receive_json::<protocol::ConntrackHelpersRoot>()?.conntrack_helpers;
+        trace!("Received conntrack helpers: {:?}", result);
+        Ok(result)
+    }
+
     fn list_networks<Q: Serialize + Debug>(&self, query: &Q)
             -> Result<Vec<protocol::Network>> {
         trace!("Listing networks with {:?}", query);
@@ -212,6 +1070,99 @@ impl V2API for Session {
         Ok(result)
     }
 
+    fn list_qos_minimum_bandwidth_rules<S: AsRef<str>>(&self, policy_id: S)
+            -> Result<Vec<protocol::QosMinimumBandwidthRule>> {
+        trace!("Listing minimum bandwidth rules of QoS policy {}",
+               policy_id.as_ref());
+        let result = self.request::<V2>(Method::Get,
+                                        &["qos", "policies", policy_id.as_ref(),
+                                          "minimum_bandwidth_rules"],
+                                        None)?
+           .receive_json::<protocol::QosMinimumBandwidthRulesRoot>()?
+           .minimum_bandwidth_rules;
+        trace!("Received minimum bandwidth rules: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_qos_minimum_packet_rate_rules<S: AsRef<str>>(&self, policy_id: S)
+            -> Result<Vec<protocol::QosMinimumPacketRateRule>> {
+        trace!("Listing minimum packet rate rules of QoS policy {}",
+               policy_id.as_ref());
+        let result = self.request::<V2>(Method::Get,
+                                        &["qos", "policies", policy_id.as_ref(),
+                                          "minimum_packet_rate_rules"],
+                                        None)?
+           .receive_json::<protocol::QosMinimumPacketRateRulesRoot>()?
+           .minimum_packet_rate_rules;
+        trace!("Received minimum packet rate rules: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_qos_packet_rate_limit_rules<S: AsRef<str>>(&self, policy_id: S)
+            -> Result<Vec<protocol::QosPacketRateLimitRule>> {
+        trace!("Listing packet rate limit rules of QoS policy {}",
+               policy_id.as_ref());
+        let result = self.request::<V2>(Method::Get,
+                                        &["qos", "policies", policy_id.as_ref(),
+                                          "packet_rate_limit_rules"],
+                                        None)?
+           .receive_json::<protocol::QosPacketRateLimitRulesRoot>()?
+           .packet_rate_limit_rules;
+        trace!("Received packet rate limit rules: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_qos_rule_types(&self) -> Result<Vec<String>> {
+        trace!("Listing supported QoS rule types");
+        let result = self.request::<V2>(Method::Get, &["qos", "rule-types"],
+                                        None)?
+           .receive_json::<protocol::QosRuleTypesRoot>()?.rule_types
+           .into_iter().map(|item| item.rule_type).collect::<Vec<_>>();
+        trace!("Received QoS rule types: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_routers<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::Router>> {
+        trace!("Listing routers with {:?}", query);
+        let result = self.request::<V2>(Method::Get, &["routers"], None)?
+           .query(query).receive_json::<protocol::RoutersRoot>()?.routers;
+        trace!("Received routers: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_security_group_rules<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::SecurityGroupRule>> {
+        trace!("Listing security group rules with {:?}", query);
+        let result = self.request::<V2>(Method::Get, &["security-group-rules"],
+                                        None)?
+           .query(query).receive_json::<protocol::SecurityGroupRulesRoot>()?
+           .security_group_rules;
+        trace!("Received security group rules: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_security_groups<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::SecurityGroup>> {
+        trace!("Listing security groups with {:?}", query);
+        let result = self.request::<V2>(Method::Get, &["security-groups"],
+                                        None)?
+           .query(query).receive_json::<protocol::SecurityGroupsRoot>()?
+           .security_groups;
+        trace!("Received security groups: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_service_flavors<S: AsRef<str>>(&self, service_type: S)
+            -> Result<Vec<protocol::ServiceFlavor>> {
+        trace!("Listing flavors of service {}", service_type.as_ref());
+        let result = self.request::<V2>(Method::Get, &["flavors"], None)?
+           .query(&[("service_type", service_type.as_ref())])
+           .receive_json::<protocol::ServiceFlavorsRoot>()?.flavors;
+        trace!("Received service flavors: {:?}", result);
+        Ok(result)
+    }
+
     fn list_subnets<Q: Serialize + Debug>(&self, query: &Q)
             -> Result<Vec<protocol::Subnet>> {
         trace!("Listing subnets with {:?}", query);
@@ -221,6 +1172,149 @@ impl V2API for Session {
         Ok(result)
     }
 
+    fn list_tap_flows<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::TapFlow>> {
+        trace!("Listing tap flows with {:?}", query);
+        let result = self.request::<V2>(Method::Get, &["taas", "tap_flows"], None)?
+           .query(query).receive_json::<protocol::TapFlowsRoot>()?.tap_flows;
+        trace!("Received tap flows: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_tap_services<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::TapService>> {
+        trace!("Listing tap services with {:?}", query);
+        let result = self.request::<V2>(Method::Get, &["taas", "tap_services"], None)?
+           .query(query).receive_json::<protocol::TapServicesRoot>()?.tap_services;
+        trace!("Received tap services: {:?}", result);
+        Ok(result)
+    }
+
+    fn list_trunks<Q: Serialize + Debug>(&self, query: &Q)
+            -> Result<Vec<protocol::Trunk>> {
+        trace!("Listing trunks with {:?}", query);
+        let result = self.request::<V2>(Method::Get, &["trunks"], None)?
+           .query(query).receive_json::<protocol::TrunksRoot>()?.trunks;
+        trace!("Received trunks: {:?}", result);
+        Ok(result)
+    }
+
+    fn onboard_network_subnets<S1, S2>(&self, subnet_pool_id: S1,
+                                       network_id: S2) -> Result<()>
+            where S1: AsRef<str>, S2: Into<String> {
+        let body = protocol::SubnetPoolOnboard { network_id: network_id.into() };
+        debug!("Onboarding subnets of network {} into subnet pool {}",
+               body.network_id, subnet_pool_id.as_ref());
+        let _ = self.request::<V2>(Method::Put,
+                                   &["subnetpools", subnet_pool_id.as_ref(),
+                                     "onboard_network_subnets"],
+                                   None)?
+            .json(&body).send()?;
+        debug!("Subnets of network {} were onboarded into subnet pool {}",
+               body.network_id, subnet_pool_id.as_ref());
+        Ok(())
+    }
+
+    fn remove_address_group_addresses<S: AsRef<str>>(&self, id: S,
+                                                     addresses: Vec<IpNet>)
+            -> Result<protocol::AddressGroup> {
+        debug!("Removing {:?} from address group {}", addresses, id.as_ref());
+        let body = protocol::AddressGroupAddresses { addresses: addresses };
+        let group = self.request::<V2>(Method::Put,
+                                       &["address-groups", id.as_ref(),
+                                         "remove_addresses"],
+                                       None)?
+            .json(&body).receive_json::<protocol::AddressGroupRoot>()?
+            .address_group;
+        debug!("Updated address group {:?}", group);
+        Ok(group)
+    }
+
+    fn remove_tag<S1, S2>(&self, resource: &str, id: S1, tag: S2) -> Result<()>
+            where S1: AsRef<str>, S2: AsRef<str> {
+        debug!("Removing tag {} from {} {}", tag.as_ref(), resource,
+               id.as_ref());
+        let _ = self.request::<V2>(Method::Delete,
+                                   &[resource, id.as_ref(), "tags", tag.as_ref()],
+                                   None)?
+            .send()?;
+        debug!("Tag {} was removed from {} {}", tag.as_ref(), resource,
+               id.as_ref());
+        Ok(())
+    }
+
+    fn remove_trunk_subports<S: AsRef<str>>(&self, id: S,
+                                            port_ids: Vec<String>)
+            -> Result<protocol::Trunk> {
+        debug!("Removing subports {:?} from trunk {}", port_ids, id.as_ref());
+        let body = protocol::SubPortRefsRoot {
+            sub_ports: port_ids.into_iter().map(|port_id| {
+                protocol::SubPortRef { port_id: port_id }
+            }).collect()
+        };
+        // NOTE: the response is not wrapped into a root object.
+        let result = self.request::<V2>(Method::Put,
+                                        &["trunks", id.as_ref(), "remove_subports"],
+                                        None)?
+            .json(&body).receive_json::<protocol::Trunk>()?;
+        debug!("Updated trunk {:?}", result);
+        Ok(result)
+    }
+
+    fn replace_tags<S: AsRef<str>>(&self, resource: &str, id: S,
+                                   tags: Vec<String>) -> Result<Vec<String>> {
+        debug!("Replacing tags of {} {} with {:?}", resource, id.as_ref(),
+               tags);
+        let body = protocol::TagsRoot { tags: tags };
+        let tags = self.request::<V2>(Method::Put,
+                                      &[resource, id.as_ref(), "tags"], None)?
+            .json(&body).receive_json::<protocol::TagsRoot>()?.tags;
+        debug!("Tags of {} {} are now {:?}", resource, id.as_ref(), tags);
+        Ok(tags)
+    }
+
+    fn update_address_group<S: AsRef<str>>(&self, id: S,
+                                           update: protocol::AddressGroupUpdate)
+            -> Result<protocol::AddressGroup> {
+        debug!("Updating address group {} with {:?}", id.as_ref(), update);
+        let body = protocol::AddressGroupUpdateRoot { address_group: update };
+        let group = self.request::<V2>(Method::Put,
+                                       &["address-groups", id.as_ref()],
+                                       None)?
+            .json(&body).receive_json::<protocol::AddressGroupRoot>()?
+            .address_group;
+        debug!("Updated address group {:?}", group);
+        Ok(group)
+    }
+
+    fn update_network<S: AsRef<str>>(&self, id: S,
+                                     update: protocol::NetworkUpdate)
+            -> Result<protocol::Network> {
+        debug!("Updating network {} with {:?}", id.as_ref(), update);
+        let body = protocol::NetworkUpdateRoot { network: update };
+        let network = self.request::<V2>(Method::Put,
+                                         &["networks", id.as_ref()],
+                                         None)?
+            .json(&body).receive_json::<protocol::NetworkRoot>()?.network;
+        debug!("Updated network {:?}", network);
+        Ok(network)
+    }
+
+    fn update_network_quotas<S: AsRef<str>>(&self, project_id: S,
+                                            update: protocol::NetworkQuotasUpdate)
+            -> Result<protocol::NetworkQuotas> {
+        debug!("Updating network quotas of project {} with {:?}",
+               project_id.as_ref(), update);
+        let body = protocol::NetworkQuotasUpdateRoot { quota: update };
+        let quotas = self.request::<V2>(Method::Put,
+                                        &["quotas", project_id.as_ref()],
+                                        None)?
+            .json(&body).receive_json::<protocol::NetworkQuotasRoot>()?.quota;
+        debug!("Updated network quotas of project {}: {:?}",
+               project_id.as_ref(), quotas);
+        Ok(quotas)
+    }
+
     fn update_port<S: AsRef<str>>(&self, id: S, update: protocol::PortUpdate)
             -> Result<protocol::Port> {
         debug!("Updating port {} with {:?}", id.as_ref(), update);
@@ -230,6 +1324,80 @@ impl V2API for Session {
         debug!("Updated port {:?}", port);
         Ok(port)
     }
+
+    fn update_router<S: AsRef<str>>(&self, id: S, update: protocol::RouterUpdate)
+            -> Result<protocol::Router> {
+        debug!("Updating router {} with {:?}", id.as_ref(), update);
+        let body = protocol::RouterUpdateRoot { router: update };
+        let result = self.request::<V2>(Method::Put, &["routers", id.as_ref()],
+                                        None)?
+            .json(&body).receive_json::<protocol::RouterRoot>()?.router;
+        debug!("Updated router {:?}", result);
+        Ok(result)
+    }
+
+    fn update_security_group<S: AsRef<str>>(
+            &self, id: S, update: protocol::SecurityGroupUpdate)
+            -> Result<protocol::SecurityGroup> {
+        debug!("Updating security group {} with {:?}", id.as_ref(), update);
+        let body = protocol::SecurityGroupUpdateRoot { security_group: update };
+        let result = self.request::<V2>(Method::Put,
+                                        &["security-groups", id.as_ref()],
+                                        None)?
+            .json(&body).receive_json::<protocol::SecurityGroupRoot>()?
+            .security_group;
+        debug!("Updated security group {:?}", result);
+        Ok(result)
+    }
+
+    fn update_subnet<S: AsRef<str>>(&self, id: S, update: protocol::SubnetUpdate)
+            -> Result<protocol::Subnet> {
+        debug!("Updating subnet {} with {:?}", id.as_ref(), update);
+        let body = protocol::SubnetUpdateRoot { subnet: update };
+        let result = self.request::<V2>(Method::Put, &["subnets", id.as_ref()],
+                                        None)?
+            .json(&body).receive_json::<protocol::SubnetRoot>()?.subnet;
+        debug!("Updated subnet {:?}", result);
+        Ok(result)
+    }
+
+    fn update_tap_flow<S: AsRef<str>>(&self, id: S,
+                                      update: protocol::TapFlowUpdate)
+            -> Result<protocol::TapFlow> {
+        debug!("Updating tap flow {} with {:?}", id.as_ref(), update);
+        let body = protocol::TapFlowUpdateRoot { tap_flow: update };
+        let result = self.request::<V2>(Method::Put,
+                                        &["taas", "tap_flows", id.as_ref()],
+                                        None)?
+            .json(&body).receive_json::<protocol::TapFlowRoot>()?.tap_flow;
+        debug!("Updated tap flow {:?}", result);
+        Ok(result)
+    }
+
+    fn update_tap_service<S: AsRef<str>>(&self, id: S,
+                                         update: protocol::TapServiceUpdate)
+            -> Result<protocol::TapService> {
+        debug!("Updating tap service {} with {:?}", id.as_ref(), update);
+        let body = protocol::TapServiceUpdateRoot { tap_service: update };
+        let result = self.request::<V2>(Method::Put,
+                                        &["taas", "tap_services", id.as_ref()],
+                                        None)?
+            .json(&body).receive_json::<protocol::TapServiceRoot>()?
+            .tap_service;
+        debug!("Updated tap service {:?}", result);
+        Ok(result)
+    }
+
+    fn update_trunk<S: AsRef<str>>(&self, id: S, update: protocol::TrunkUpdate)
+            -> Result<protocol::Trunk> {
+        debug!("Updating trunk {} with {:?}", id.as_ref(), update);
+        let body = protocol::TrunkUpdateRoot { trunk: update };
+        let result = self.request::<V2>(Method::Put, &["trunks", id.as_ref()],
+                                        None)?
+            .json(&body).receive_json::<protocol::TrunkRoot>()?.trunk;
+        debug!("Updated trunk {:?}", result);
+        Ok(result)
+    }
 }
 
 
@@ -14,15 +14,54 @@
 
 //! Network API implementation bits.
 
+mod address_groups;
 mod base;
 mod networks;
 mod ports;
 mod protocol;
+mod qos;
+mod quotas;
+mod routers;
+mod rules;
+mod security_group_rules;
+mod security_groups;
 mod subnets;
+mod taas;
+mod trunks;
 
-pub use self::networks::{Network, NetworkQuery};
+pub use self::address_groups::{AddressGroup, AddressGroupQuery,
+                               NewAddressGroup};
+pub use self::networks::{Network, NetworkQuery, NewNetwork};
+pub(crate) use self::networks::find_external_network;
 pub use self::ports::{NewPort, Port, PortIpAddress, PortIpRequest, PortQuery};
 pub use self::protocol::{AllocationPool, HostRoute, Ipv6Mode, IpVersion,
-                         NetworkStatus, NetworkSortKey, PortExtraDhcpOption,
-                         PortSortKey, SubnetSortKey};
-pub use self::subnets::{Subnet, SubnetQuery};
+                         NetworkQuotaDetails, NetworkQuotaUsage,
+                         NetworkQuotas, NetworkQuotasUpdate, NetworkStatus,
+                         NetworkSortKey, PortExtraDhcpOption, PortSortKey,
+                         PortVnicType, QosDirection, QosRuleType, RouterStatus,
+                         RuleDirection, RuleEtherType, SegmentationType,
+                         ServiceFlavor,
+                         SubPort, SubnetSortKey, TapFlowDirection,
+                         TrunkStatus};
+pub use self::qos::{QosMinimumBandwidthRule, QosMinimumPacketRateRule,
+                    QosPacketRateLimitRule};
+pub(crate) use self::qos::{create_minimum_bandwidth_rule,
+                           create_minimum_packet_rate_rule,
+                           create_packet_rate_limit_rule,
+                           list_minimum_bandwidth_rules,
+                           list_minimum_packet_rate_rules,
+                           list_packet_rate_limit_rules, list_qos_rule_types};
+pub(crate) use self::quotas::{get_quota_details, get_quotas, update_quotas};
+pub use self::routers::{ConntrackHelper, NewConntrackHelper, Router,
+                        RouterInterface, RouterQuery};
+pub(crate) use self::routers::{list_conntrack_helpers, list_router_flavors,
+                               list_router_interfaces};
+pub use self::rules::{diff_rules, RuleDiff, SecurityRule};
+pub use self::security_group_rules::{SecurityGroupRule,
+                                     SecurityGroupRuleQuery};
+pub use self::security_groups::{NewSecurityGroup, SecurityGroup,
+                                SecurityGroupQuery};
+pub use self::subnets::{NewSubnet, Subnet, SubnetQuery};
+pub use self::taas::{NewTapFlow, NewTapService, TapFlow, TapFlowQuery,
+                     TapService, TapServiceQuery};
+pub use self::trunks::{NewTrunk, Trunk, TrunkQuery};
@@ -14,20 +14,22 @@
 
 //! Network management via Network API.
 
+use std::collections::HashSet;
 use std::rc::Rc;
 use std::fmt::Debug;
+use std::time::Duration;
 
 use chrono::{DateTime, FixedOffset};
 use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
 use serde::Serialize;
 
-use super::super::{Error, Result, Sort};
-use super::super::common::{ListResources, NetworkRef, Refresh, ResourceId,
-                           ResourceIterator};
+use super::super::{Error, ErrorKind, Result, Sort};
+use super::super::common::{self, DeletionWaiter, ListResources, NetworkRef,
+                           Refresh, ResourceId, ResourceIterator};
 use super::super::session::Session;
 use super::super::utils::Query;
 use super::base::V2API;
-use super::protocol;
+use super::{protocol, Subnet, SubnetQuery};
 
 
 /// A query to network list.
@@ -36,24 +38,40 @@ pub struct NetworkQuery {
     session: Rc<Session>,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
 }
 
 /// Structure representing a single network.
 #[derive(Clone, Debug)]
 pub struct Network {
     session: Rc<Session>,
-    inner: protocol::Network
+    inner: protocol::Network,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a network.
+#[derive(Clone, Debug)]
+pub struct NewNetwork {
+    session: Rc<Session>,
+    inner: protocol::NetworkCreate,
 }
 
 impl Network {
+    /// Create a Network object from its protocol representation.
+    fn from_inner(session: Rc<Session>, inner: protocol::Network) -> Network {
+        Network {
+            session: session,
+            inner: inner,
+            dirty: HashSet::new(),
+        }
+    }
+
     /// Load a Network object.
     pub(crate) fn new<Id: AsRef<str>>(session: Rc<Session>, id: Id)
             -> Result<Network> {
         let inner = session.get_network(id)?;
-        Ok(Network {
-            session: session,
-            inner: inner
-        })
+        Ok(Network::from_inner(session, inner))
     }
 
     transparent_property! {
@@ -61,6 +79,16 @@ impl Network {
         admin_state_up: bool
     }
 
+    update_field! {
+        #[doc = "Update the administrative state."]
+        set_admin_state_up, with_admin_state_up -> admin_state_up: bool
+    }
+
+    transparent_property! {
+        #[doc = "Availability zones requested for the network."]
+        availability_zone_hints: ref Vec<String>
+    }
+
     transparent_property! {
         #[doc = "The availability zones for the network (if available)."]
         availability_zones: ref Vec<String>
@@ -76,16 +104,31 @@ impl Network {
         description: ref Option<String>
     }
 
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
     transparent_property! {
         #[doc = "DNS domain for the network (if available)."]
         dns_domain: ref Option<String>
     }
 
+    update_field! {
+        #[doc = "Update the DNS domain."]
+        set_dns_domain, with_dns_domain -> dns_domain: optional String
+    }
+
     transparent_property! {
         #[doc = "Whether the network is external (if available)."]
         external: Option<bool>
     }
 
+    update_field! {
+        #[doc = "Update whether the network is external (admin-only)."]
+        set_external, with_external -> external: optional bool
+    }
+
     transparent_property! {
         #[doc = "Unique ID."]
         id: ref String
@@ -106,26 +149,154 @@ impl Network {
         mtu: Option<u32>
     }
 
+    update_field! {
+        #[doc = "Update the MTU."]
+        set_mtu, with_mtu -> mtu: optional u32
+    }
+
     transparent_property! {
         #[doc = "Network name."]
         name: ref String
     }
 
+    update_field! {
+        #[doc = "Update the network name."]
+        set_name, with_name -> name
+    }
+
+    transparent_property! {
+        #[doc = "Whether port security is enabled by default (if available)."]
+        port_security_enabled: Option<bool>
+    }
+
+    update_field! {
+        #[doc = "Update whether port security is enabled by default."]
+        set_port_security_enabled, with_port_security_enabled ->
+            port_security_enabled: optional bool
+    }
+
+    transparent_property! {
+        #[doc = "Provider network type (admin-only, if available)."]
+        provider_network_type: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Provider physical network (admin-only, if available)."]
+        provider_physical_network: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Provider segmentation ID (admin-only, if available)."]
+        provider_segmentation_id: Option<u32>
+    }
+
     transparent_property! {
         #[doc = "Whether the network is shared."]
         shared: bool
     }
 
+    update_field! {
+        #[doc = "Update whether the network is shared (admin-only)."]
+        set_shared, with_shared -> shared: bool
+    }
+
+    /// IDs of the subnets of the network.
+    pub fn subnet_ids(&self) -> &Vec<String> {
+        &self.inner.subnets
+    }
+
+    /// Fetch the subnets of the network.
+    pub fn subnets(&self) -> Result<Vec<Subnet>> {
+        if self.inner.subnets.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        SubnetQuery::new(self.session.clone())
+            .with_network(self.inner.id.clone()).all()
+    }
+
+    transparent_property! {
+        #[doc = "Tags of the network."]
+        tags: ref Vec<String>
+    }
+
+    /// Add a tag to the network.
+    ///
+    /// The change is applied immediately.
+    pub fn add_tag<S: Into<String>>(&mut self, tag: S) -> Result<()> {
+        let tag = tag.into();
+        self.session.add_tag("networks", &self.inner.id, &tag)?;
+        if !self.inner.tags.contains(&tag) {
+            self.inner.tags.push(tag);
+        }
+        Ok(())
+    }
+
+    /// Remove a tag from the network.
+    ///
+    /// The change is applied immediately.
+    pub fn remove_tag<S: AsRef<str>>(&mut self, tag: S) -> Result<()> {
+        self.session.remove_tag("networks", &self.inner.id, tag.as_ref())?;
+        self.inner.tags.retain(|item| item != tag.as_ref());
+        Ok(())
+    }
+
+    /// Replace all tags of the network.
+    ///
+    /// The change is applied immediately.
+    pub fn set_tags(&mut self, tags: Vec<String>) -> Result<()> {
+        self.inner.tags = self.session.replace_tags("networks", &self.inner.id,
+                                                    tags)?;
+        Ok(())
+    }
+
     transparent_property! {
         #[doc = "Last update data and time (if available)."]
         updated_at: Option<DateTime<FixedOffset>>
     }
+
+    /// Delete the network.
+    pub fn delete(self) -> Result<DeletionWaiter<Network>> {
+        self.session.delete_network(&self.inner.id)?;
+        Ok(DeletionWaiter::new(self, Duration::new(60, 0), Duration::new(1, 0)))
+    }
+
+    /// Move all subnets of the network into a subnet pool.
+    ///
+    /// Requires the `subnet_onboard` extension. The subnets must not
+    /// overlap with the prefixes already allocated from the pool.
+    pub fn onboard_subnets<S: AsRef<str>>(&self, subnet_pool_id: S)
+            -> Result<()> {
+        self.session.onboard_network_subnets(subnet_pool_id,
+                                             self.inner.id.clone())
+    }
+
+    /// Whether the network is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the network.
+    pub fn save(&mut self) -> Result<()> {
+        let mut update = protocol::NetworkUpdate::default();
+        save_fields! {
+            self -> update: admin_state_up name shared
+        };
+        save_option_fields! {
+            self -> update: description dns_domain external mtu
+                port_security_enabled
+        };
+        self.inner = self.session.update_network(self.id(), update)?;
+        self.dirty.clear();
+        Ok(())
+    }
 }
 
 impl Refresh for Network {
     /// Refresh the network.
     fn refresh(&mut self) -> Result<()> {
         self.inner = self.session.get_network(&self.inner.id)?;
+        self.dirty.clear();
         Ok(())
     }
 }
@@ -136,6 +307,8 @@ impl NetworkQuery {
             session: session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            max_items: None,
         }
     }
 
@@ -157,6 +330,20 @@ impl NetworkQuery {
         self
     }
 
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
     /// Add sorting to the request.
     pub fn sort_by(mut self, sort: Sort<protocol::NetworkSortKey>) -> Self {
         let (field, direction) = sort.into();
@@ -165,12 +352,102 @@ impl NetworkQuery {
         self
     }
 
+    /// Traverse the results backwards, starting from the end or the marker.
+    ///
+    /// Items are returned in the reverse sorting order. Together with
+    /// `with_marker` this allows paging backwards: the last item of a page
+    /// is the marker for the previous page.
+    pub fn with_page_reverse(mut self) -> Self {
+        self.query.push("page_reverse", true);
+        self
+    }
+
+    /// Filter by a requested availability zone.
+    pub fn with_availability_zone_hint<T: Into<String>>(mut self, value: T)
+            -> Self {
+        self.query.push_str("availability_zone_hints", value);
+        self
+    }
+
+    /// Filter by whether the network is external.
+    ///
+    /// External networks are used for router gateways and floating IPs.
+    pub fn with_external(mut self, value: bool) -> Self {
+        self.query.push("router:external", value);
+        self
+    }
+
     /// Filter by network name (a database regular expression).
     pub fn with_name<T: Into<String>>(mut self, value: T) -> Self {
         self.query.push_str("name", value);
         self
     }
 
+    /// Filter by provider network type, e.g. `vlan` (admin-only).
+    pub fn with_provider_network_type<T: Into<String>>(mut self, value: T)
+            -> Self {
+        self.query.push_str("provider:network_type", value);
+        self
+    }
+
+    /// Filter by provider physical network (admin-only).
+    pub fn with_provider_physical_network<T: Into<String>>(mut self, value: T)
+            -> Self {
+        self.query.push_str("provider:physical_network", value);
+        self
+    }
+
+    /// Filter by provider segmentation ID (admin-only).
+    pub fn with_provider_segmentation_id(mut self, value: u32) -> Self {
+        self.query.push("provider:segmentation_id", value);
+        self
+    }
+
+    /// Filter by whether the network is shared.
+    pub fn with_shared(mut self, value: bool) -> Self {
+        self.query.push("shared", value);
+        self
+    }
+
+    /// Filter by IDs, matching any of them.
+    ///
+    /// Note that an empty list does not filter anything.
+    pub fn with_ids<I, S>(mut self, ids: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        for id in ids {
+            self.query.push_str("id", id);
+        }
+        self
+    }
+
+    /// Filter by tags (all of them must be present).
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("tags", tags);
+        self
+    }
+
+    /// Filter by tags (any of them must be present).
+    pub fn with_any_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("tags-any", tags);
+        self
+    }
+
+    /// Filter out resources having all of these tags.
+    pub fn with_not_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("not-tags", tags);
+        self
+    }
+
+    /// Filter out resources having any of these tags.
+    pub fn with_not_any_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("not-tags-any", tags);
+        self
+    }
+
     /// Convert this query into an iterator executing the request.
     ///
     /// Returns a `FallibleIterator`, which is an iterator with each `next`
@@ -180,6 +457,14 @@ impl NetworkQuery {
     pub fn into_iter(self) -> ResourceIterator<Network> {
         debug!("Fetching networks with {:?}", self.query);
         ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Convert this query into an iterator fetching `page_size` items per
+    /// request.
+    pub fn into_iter_paginated(self, page_size: usize)
+            -> ResourceIterator<Network> {
+        self.with_page_size(page_size).into_iter()
     }
 
     /// Execute this request and return all results.
@@ -205,6 +490,130 @@ impl NetworkQuery {
     }
 }
 
+impl NewNetwork {
+    /// Start creating a network.
+    pub(crate) fn new(session: Rc<Session>) -> NewNetwork {
+        NewNetwork {
+            session: session,
+            inner: protocol::NetworkCreate::default(),
+        }
+    }
+
+    /// Request creation of the network.
+    pub fn create(self) -> Result<Network> {
+        let inner = self.session.create_network(self.inner)?;
+        Ok(Network::from_inner(self.session, inner))
+    }
+
+    /// Serialize the request body that `create` would send.
+    pub fn to_request_json(&self) -> Result<String> {
+        let body = protocol::NetworkCreateRoot { network: self.inner.clone() };
+        common::protocol::to_request_json(&body)
+    }
+
+    creation_inner_field! {
+        #[doc = "Set administrative status for the network."]
+        set_admin_state_up, with_admin_state_up -> admin_state_up: optional bool
+    }
+
+    /// Request the network to be placed in the availability zone.
+    ///
+    /// Can be called several times to add more zones.
+    pub fn add_availability_zone_hint<S: Into<String>>(&mut self, value: S) {
+        self.inner.availability_zone_hints.push(value.into());
+    }
+
+    /// Request the network to be placed in the availability zone.
+    ///
+    /// Can be called several times to add more zones.
+    pub fn with_availability_zone_hint<S: Into<String>>(mut self, value: S)
+            -> Self {
+        self.add_availability_zone_hint(value);
+        self
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the network."]
+        set_description, with_description -> description: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set DNS domain for the network."]
+        set_dns_domain, with_dns_domain -> dns_domain: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set whether the network is external (admin-only)."]
+        set_external, with_external -> external: optional bool
+    }
+
+    creation_inner_field! {
+        #[doc = "Set MTU for the network."]
+        set_mtu, with_mtu -> mtu: optional u32
+    }
+
+    creation_inner_field! {
+        #[doc = "Set a name for the network."]
+        set_name, with_name -> name: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set whether port security is enabled by default."]
+        set_port_security_enabled, with_port_security_enabled ->
+            port_security_enabled: optional bool
+    }
+
+    creation_inner_field! {
+        #[doc = "Set provider network type (admin-only)."]
+        set_provider_network_type, with_provider_network_type ->
+            provider_network_type: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set provider physical network (admin-only)."]
+        set_provider_physical_network, with_provider_physical_network ->
+            provider_physical_network: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set provider segmentation ID (admin-only)."]
+        set_provider_segmentation_id, with_provider_segmentation_id ->
+            provider_segmentation_id: optional u32
+    }
+
+    creation_inner_field! {
+        #[doc = "Set whether the network is shared (admin-only)."]
+        set_shared, with_shared -> shared: optional bool
+    }
+}
+
+/// Find the external network to use for floating IPs and router gateways.
+///
+/// If several external networks exist, the one marked as default is used.
+pub(crate) fn find_external_network(session: Rc<Session>) -> Result<Network> {
+    let mut networks = NetworkQuery::new(session).with_external(true).all()?;
+    match networks.len() {
+        0 => Err(Error::new(ErrorKind::ResourceNotFound,
+                            "No external networks found")),
+        1 => Ok(networks.pop().unwrap()),
+        _ => {
+            let (mut defaults, others): (Vec<Network>, Vec<Network>) =
+                networks.into_iter().partition(|net| net.is_default() == Some(true));
+            if defaults.len() == 1 {
+                Ok(defaults.pop().unwrap())
+            } else {
+                let names = defaults.iter().chain(others.iter())
+                    .map(|net| format!("{} ({})", net.id(), net.name()))
+                    .collect::<Vec<String>>();
+                Err(Error::new(ErrorKind::TooManyItems,
+                               format!("Several external networks found and \
+                                        none is the default: {}",
+                                       names.join(", "))))
+            }
+        }
+    }
+}
+
 impl ResourceId for Network {
     fn resource_id(&self) -> String {
         self.id().clone()
@@ -216,9 +625,8 @@ impl ListResources for Network {
 
     fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
             -> Result<Vec<Network>> {
-        Ok(session.list_networks(&query)?.into_iter().map(|item| Network {
-            session: session.clone(),
-            inner: item
+        Ok(session.list_networks(&query)?.into_iter().map(|item| {
+            Network::from_inner(session.clone(), item)
         }).collect())
     }
 }
@@ -14,7 +14,7 @@
 
 //! Ports management via Port API.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::fmt::Debug;
 use std::mem;
@@ -23,12 +23,16 @@ use std::time::Duration;
 
 use chrono::{DateTime, FixedOffset};
 use eui48::MacAddress;
+use ipnet::Contains;
 use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
 use serde::Serialize;
+use serde_json::Value;
 
-use super::super::{Error, Result, Sort};
-use super::super::common::{DeletionWaiter, ListResources, NetworkRef, PortRef,
-                           Refresh, ResourceId, ResourceIterator, SubnetRef};
+use super::super::{Error, ErrorKind, Result, Sort};
+use super::super::cloud::Defaults;
+use super::super::common::{self, DeletionWaiter, ListResources, NetworkRef,
+                           PortRef, Refresh, ResourceId, ResourceIterator,
+                           SubnetRef};
 use super::super::session::Session;
 use super::super::utils::Query;
 use super::base::V2API;
@@ -41,6 +45,8 @@ pub struct PortQuery {
     session: Rc<Session>,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
 }
 
 /// A fixed IP address of a port.
@@ -59,6 +65,7 @@ pub struct Port {
     session: Rc<Session>,
     inner: protocol::Port,
     fixed_ips: Vec<PortIpAddress>,
+    new_fixed_ips: Option<Vec<PortIpRequest>>,
     dirty: HashSet<&'static str>,
 }
 
@@ -80,12 +87,20 @@ pub struct NewPort {
     inner: protocol::Port,
     network: NetworkRef,
     fixed_ips: Vec<PortIpRequest>,
+    default_security_groups: Vec<String>,
 }
 
 fn convert_fixed_ips(session: &Rc<Session>, inner: &mut protocol::Port)
         -> Vec<PortIpAddress> {
     let mut fixed_ips = Vec::new();
     mem::swap(&mut inner.fixed_ips, &mut fixed_ips);
+    convert_ip_list(session, fixed_ips)
+}
+
+/// Convert fixed IPs received from the server.
+pub(crate) fn convert_ip_list(session: &Rc<Session>,
+                              fixed_ips: Vec<protocol::FixedIp>)
+        -> Vec<PortIpAddress> {
     fixed_ips.into_iter().map(|ip| PortIpAddress {
         session: session.clone(),
         ip_address: ip.ip_address,
@@ -93,6 +108,56 @@ fn convert_fixed_ips(session: &Rc<Session>, inner: &mut protocol::Port)
     }).collect()
 }
 
+/// Convert fixed IP requests, checking that subnets belong to the network.
+pub(crate) fn verify_fixed_ips(session: &Session, network_id: &str,
+                    requests: Vec<PortIpRequest>)
+        -> Result<Vec<protocol::FixedIp>> {
+    let mut subnets = HashMap::new();
+    let mut result = Vec::with_capacity(requests.len());
+    for request in requests {
+        let (ip, subnet) = match request {
+            PortIpRequest::IpAddress(ip) => {
+                result.push(protocol::FixedIp {
+                    ip_address: ip,
+                    subnet_id: String::new()
+                });
+                continue;
+            },
+            PortIpRequest::AnyIpFromSubnet(subnet) => (None, subnet),
+            PortIpRequest::IpFromSubnet(ip, subnet) => (Some(ip), subnet)
+        };
+
+        if !subnets.contains_key(&subnet.value) {
+            let inner = session.get_subnet(&subnet.value)?;
+            if inner.network_id != network_id {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Subnet {} does not belong to network {}",
+                            subnet.value, network_id)));
+            }
+            let _ = subnets.insert(subnet.value.clone(), inner);
+        }
+        let inner = &subnets[&subnet.value];
+
+        if let Some(ip) = ip {
+            if !inner.cidr.contains(&ip) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("IP address {} does not belong to subnet {} ({})",
+                            ip, inner.id, inner.cidr)));
+            }
+        }
+
+        let unspecified = net::IpAddr::V4(net::Ipv4Addr::new(0, 0, 0, 0));
+        result.push(protocol::FixedIp {
+            ip_address: ip.unwrap_or(unspecified),
+            subnet_id: inner.id.clone()
+        });
+    }
+
+    Ok(result)
+}
+
 impl Port {
     /// Load a Port object.
     pub(crate) fn new(session: Rc<Session>, mut inner: protocol::Port) -> Port {
@@ -101,6 +166,7 @@ impl Port {
             session: session,
             inner: inner,
             fixed_ips: fixed_ips,
+            new_fixed_ips: None,
             dirty: HashSet::new(),
         }
     }
@@ -122,6 +188,49 @@ impl Port {
         set_admin_state_up, with_admin_state_up -> admin_state_up: bool
     }
 
+    transparent_property! {
+        #[doc = "ID of the host the port is bound to (admin-only)."]
+        binding_host_id: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the host the port is bound to (admin-only)."]
+        set_binding_host_id, with_binding_host_id -> binding_host_id: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Binding information for the host driver (admin-only)."]
+        binding_profile: ref HashMap<String, Value>
+    }
+
+    /// Mutable access to the binding profile (admin-only).
+    #[allow(unused_results)]
+    pub fn binding_profile_mut(&mut self) -> &mut HashMap<String, Value> {
+        self.dirty.insert("binding_profile");
+        &mut self.inner.binding_profile
+    }
+
+    update_field! {
+        #[doc = "Update the binding profile (admin-only)."]
+        set_binding_profile, with_binding_profile -> binding_profile: HashMap<String, Value>
+    }
+
+    transparent_property! {
+        #[doc = "Type of the VIF the port is bound with (admin-only)."]
+        binding_vif_type: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Type of the virtual NIC the port is bound to."]
+        binding_vnic_type: Option<protocol::PortVnicType>
+    }
+
+    update_field! {
+        #[doc = "Update the type of the virtual NIC."]
+        set_binding_vnic_type, with_binding_vnic_type -> binding_vnic_type:
+            optional protocol::PortVnicType
+    }
+
     /// Whether the `device_owner` is a Compute server.
     pub fn attached_to_server(&self) -> bool {
         match self.inner.device_owner {
@@ -203,11 +312,58 @@ impl Port {
     }
 
     /// Fixed IP addresses of the port.
+    ///
+    /// Changes to fixed IPs are only reflected here after `save`.
     pub fn fixed_ips(&self) -> &Vec<PortIpAddress> {
         &self.fixed_ips
     }
 
-    // TODO(dtantsur): updating fixed IPs with validation
+    #[allow(unused_results)]
+    fn fixed_ip_requests_mut(&mut self) -> &mut Vec<PortIpRequest> {
+        self.dirty.insert("fixed_ips");
+        let current = &self.fixed_ips;
+        self.new_fixed_ips.get_or_insert_with(|| {
+            current.iter().map(|ip| PortIpRequest::IpFromSubnet(
+                ip.ip_address, SubnetRef::new_verified(ip.subnet_id.clone())
+            )).collect()
+        })
+    }
+
+    /// Add a fixed IP address to the port.
+    ///
+    /// The subnet (if any) is verified to belong to the port's network on
+    /// `save`.
+    pub fn add_fixed_ip(&mut self, request: PortIpRequest) {
+        self.fixed_ip_requests_mut().push(request);
+    }
+
+    /// Add a fixed IP address to the port.
+    pub fn with_fixed_ip(mut self, request: PortIpRequest) -> Self {
+        self.add_fixed_ip(request);
+        self
+    }
+
+    /// Remove a fixed IP address from the port.
+    pub fn remove_fixed_ip(&mut self, ip_address: net::IpAddr) {
+        self.fixed_ip_requests_mut().retain(|item| match *item {
+            PortIpRequest::IpAddress(ip) |
+                PortIpRequest::IpFromSubnet(ip, _) => ip != ip_address,
+            PortIpRequest::AnyIpFromSubnet(..) => true
+        });
+    }
+
+    /// Replace all fixed IP addresses of the port.
+    ///
+    /// Subnets are verified to belong to the port's network on `save`.
+    pub fn set_fixed_ips(&mut self, requests: Vec<PortIpRequest>) {
+        *self.fixed_ip_requests_mut() = requests;
+    }
+
+    /// Replace all fixed IP addresses of the port.
+    pub fn with_fixed_ips(mut self, requests: Vec<PortIpRequest>) -> Self {
+        self.set_fixed_ips(requests);
+        self
+    }
 
     transparent_property! {
         #[doc = "MAC address of the port."]
@@ -244,11 +400,78 @@ impl Port {
         network_id: ref String
     }
 
+    transparent_property! {
+        #[doc = "Whether port security is enabled (if available)."]
+        port_security_enabled: Option<bool>
+    }
+
+    update_field! {
+        #[doc = "Update whether port security is enabled (requires no security groups to disable)."]
+        set_port_security_enabled, with_port_security_enabled ->
+            port_security_enabled: optional bool
+    }
+
+    transparent_property! {
+        #[doc = "ID of the QoS policy applied to the port."]
+        qos_policy_id: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Apply a QoS policy to the port."]
+        set_qos_policy_id, with_qos_policy_id -> qos_policy_id: optional String
+    }
+
+    transparent_property! {
+        #[doc = "IDs of security groups applied to the port."]
+        security_groups: ref Vec<String>
+    }
+
+    update_field! {
+        #[doc = "Update security groups applied to the port."]
+        set_security_groups, with_security_groups -> security_groups:
+            Vec<String>
+    }
+
     transparent_property! {
         #[doc = "Port status."]
         status: protocol::NetworkStatus
     }
 
+    transparent_property! {
+        #[doc = "Tags of the port."]
+        tags: ref Vec<String>
+    }
+
+    /// Add a tag to the port.
+    ///
+    /// The change is applied immediately.
+    pub fn add_tag<S: Into<String>>(&mut self, tag: S) -> Result<()> {
+        let tag = tag.into();
+        self.session.add_tag("ports", &self.inner.id, &tag)?;
+        if !self.inner.tags.contains(&tag) {
+            self.inner.tags.push(tag);
+        }
+        Ok(())
+    }
+
+    /// Remove a tag from the port.
+    ///
+    /// The change is applied immediately.
+    pub fn remove_tag<S: AsRef<str>>(&mut self, tag: S) -> Result<()> {
+        self.session.remove_tag("ports", &self.inner.id, tag.as_ref())?;
+        self.inner.tags.retain(|item| item != tag.as_ref());
+        Ok(())
+    }
+
+    /// Replace all tags of the port.
+    ///
+    /// The change is applied immediately.
+    pub fn set_tags(&mut self, tags: Vec<String>) -> Result<()> {
+        self.inner.tags = self.session.replace_tags("ports", &self.inner.id,
+                                                    tags)?;
+        Ok(())
+    }
+
     transparent_property! {
         #[doc = "Last update data and time (if available)."]
         updated_at: Option<DateTime<FixedOffset>>
@@ -269,14 +492,22 @@ impl Port {
     pub fn save(&mut self) -> Result<()> {
         let mut update = protocol::PortUpdate::default();
         save_fields! {
-            self -> update: admin_state_up extra_dhcp_opts mac_address
+            self -> update: admin_state_up binding_profile extra_dhcp_opts
+                mac_address security_groups
         };
         save_option_fields! {
-            self -> update: description device_id device_owner dns_domain
-                dns_name name
+            self -> update: binding_host_id binding_vnic_type description
+                device_id device_owner dns_domain dns_name name
+                port_security_enabled qos_policy_id
         };
+        if let Some(requests) = self.new_fixed_ips.clone() {
+            update.fixed_ips = Some(verify_fixed_ips(&self.session,
+                                                     &self.inner.network_id,
+                                                     requests)?);
+        }
         let mut inner = self.session.update_port(self.id(), update)?;
         self.fixed_ips = convert_fixed_ips(&self.session, &mut inner);
+        self.new_fixed_ips = None;
         self.dirty.clear();
         self.inner = inner;
         Ok(())
@@ -288,6 +519,7 @@ impl Refresh for Port {
     fn refresh(&mut self) -> Result<()> {
         self.inner = self.session.get_port(&self.inner.id)?;
         self.fixed_ips = convert_fixed_ips(&self.session, &mut self.inner);
+        self.new_fixed_ips = None;
         self.dirty.clear();
         Ok(())
     }
@@ -306,6 +538,8 @@ impl PortQuery {
             session: session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            max_items: None,
         }
     }
 
@@ -327,6 +561,20 @@ impl PortQuery {
         self
     }
 
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
     /// Add sorting to the request.
     pub fn sort_by(mut self, sort: Sort<protocol::PortSortKey>) -> Self {
         let (field, direction) = sort.into();
@@ -335,6 +583,16 @@ impl PortQuery {
         self
     }
 
+    /// Traverse the results backwards, starting from the end or the marker.
+    ///
+    /// Items are returned in the reverse sorting order. Together with
+    /// `with_marker` this allows paging backwards: the last item of a page
+    /// is the marker for the previous page.
+    pub fn with_page_reverse(mut self) -> Self {
+        self.query.push("page_reverse", true);
+        self
+    }
+
     query_filter! {
         #[doc = "Filter by administrative state."]
         set_admin_state_up, with_admin_state_up -> admin_state_up: bool
@@ -365,6 +623,12 @@ impl PortQuery {
         set_name, with_name -> name
     }
 
+    query_filter! {
+        #[doc = "Filter by whether port security is enabled."]
+        set_port_security_enabled, with_port_security_enabled ->
+            port_security_enabled: bool
+    }
+
     /// Filter by network.
     ///
     /// # Warning
@@ -389,6 +653,45 @@ impl PortQuery {
         set_status, with_status -> status: protocol::NetworkStatus
     }
 
+    /// Filter by IDs, matching any of them.
+    ///
+    /// Note that an empty list does not filter anything.
+    pub fn with_ids<I, S>(mut self, ids: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        for id in ids {
+            self.query.push_str("id", id);
+        }
+        self
+    }
+
+    /// Filter by tags (all of them must be present).
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("tags", tags);
+        self
+    }
+
+    /// Filter by tags (any of them must be present).
+    pub fn with_any_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("tags-any", tags);
+        self
+    }
+
+    /// Filter out resources having all of these tags.
+    pub fn with_not_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("not-tags", tags);
+        self
+    }
+
+    /// Filter out resources having any of these tags.
+    pub fn with_not_any_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("not-tags-any", tags);
+        self
+    }
+
     /// Convert this query into an iterator executing the request.
     ///
     /// Returns a `FallibleIterator`, which is an iterator with each `next`
@@ -398,6 +701,14 @@ impl PortQuery {
     pub fn into_iter(self) -> ResourceIterator<Port> {
         debug!("Fetching ports with {:?}", self.query);
         ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Convert this query into an iterator fetching `page_size` items per
+    /// request.
+    pub fn into_iter_paginated(self, page_size: usize)
+            -> ResourceIterator<Port> {
+        self.with_page_size(page_size).into_iter()
     }
 
     /// Execute this request and return all results.
@@ -431,6 +742,10 @@ impl NewPort {
             session: session,
             inner: protocol::Port {
                 admin_state_up: true,
+                binding_host_id: None,
+                binding_profile: HashMap::new(),
+                binding_vif_type: None,
+                binding_vnic_type: None,
                 created_at: None,
                 description: None,
                 device_id: None,
@@ -444,22 +759,35 @@ impl NewPort {
                 name: None,
                 // Will be replaced in create()
                 network_id: String::new(),
+                port_security_enabled: None,
                 project_id: None,
+                qos_policy_id: None,
                 security_groups: Vec::new(),
                 // Dummy value, not used when serializing
                 status: protocol::NetworkStatus::Active,
+                tags: Vec::new(),
                 updated_at: None,
             },
             network: network,
             fixed_ips: Vec::new(),
+            default_security_groups: Vec::new(),
         }
     }
 
-    /// Request creation of the port.
-    pub fn create(mut self) -> Result<Port> {
-        self.inner.network_id = self.network.into_verified(&self.session)?;
-        for request in self.fixed_ips {
-            self.inner.fixed_ips.push(match request {
+    /// Apply cloud-wide defaults to this request.
+    pub(crate) fn apply_defaults(&mut self, defaults: &Defaults) {
+        self.default_security_groups = defaults.security_groups.clone();
+    }
+
+    fn build_request(&self) -> Result<protocol::Port> {
+        let mut request = self.inner.clone();
+        request.network_id = self.network.clone().into_verified(&self.session)?;
+        if request.security_groups.is_empty() &&
+                request.port_security_enabled != Some(false) {
+            request.security_groups = self.default_security_groups.clone();
+        }
+        for item in &self.fixed_ips {
+            request.fixed_ips.push(match item.clone() {
                 PortIpRequest::IpAddress(ip) => protocol::FixedIp {
                     ip_address: ip,
                     subnet_id: Default::default()
@@ -475,10 +803,42 @@ impl NewPort {
             });
         }
 
-        let port = self.session.create_port(self.inner)?;
+        Ok(request)
+    }
+
+    /// Request creation of the port.
+    pub fn create(self) -> Result<Port> {
+        let request = self.build_request()?;
+        let port = self.session.create_port(request)?;
         Ok(Port::new(self.session, port))
     }
 
+    /// Request creation of several ports in one API call.
+    ///
+    /// Either all ports are created or none of them.
+    pub(crate) fn create_bulk(session: Rc<Session>, ports: Vec<NewPort>)
+            -> Result<Vec<Port>> {
+        if ports.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut requests = Vec::with_capacity(ports.len());
+        for item in &ports {
+            requests.push(item.build_request()?);
+        }
+
+        Ok(session.create_ports(requests)?.into_iter()
+           .map(|item| Port::new(session.clone(), item)).collect())
+    }
+
+    /// Serialize the request body that `create` would send.
+    ///
+    /// The network and subnets referenced by name are resolved to IDs first.
+    pub fn to_request_json(&self) -> Result<String> {
+        let body = protocol::PortRoot { port: self.build_request()? };
+        common::protocol::to_request_json(&body)
+    }
+
     creation_inner_field! {
         #[doc = "Set administrative status for the port."]
         set_admin_state_up, with_admin_state_up -> admin_state_up: bool
@@ -486,6 +846,29 @@ impl NewPort {
 
     // TODO(dtantsur): allowed_address_pairs
 
+    creation_inner_field! {
+        #[doc = "Set the host to bind the port to (admin-only)."]
+        set_binding_host_id, with_binding_host_id -> binding_host_id:
+            optional String
+    }
+
+    /// Binding information for the host driver (admin-only).
+    pub fn binding_profile(&mut self) -> &mut HashMap<String, Value> {
+        &mut self.inner.binding_profile
+    }
+
+    creation_inner_field! {
+        #[doc = "Set binding information for the host driver (admin-only)."]
+        set_binding_profile, with_binding_profile -> binding_profile:
+            HashMap<String, Value>
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the type of the virtual NIC, e.g. `Direct` for SR-IOV."]
+        set_binding_vnic_type, with_binding_vnic_type -> binding_vnic_type:
+            optional protocol::PortVnicType
+    }
+
     creation_inner_field! {
         #[doc = "Set description of the port."]
         set_description, with_description -> description: optional String
@@ -543,7 +926,31 @@ impl NewPort {
         set_name, with_name -> name: optional String
     }
 
-    // TODO(dtantsur): security groups
+    creation_inner_field! {
+        #[doc = "Set whether port security is enabled (the network default is used otherwise)."]
+        set_port_security_enabled, with_port_security_enabled ->
+            port_security_enabled: optional bool
+    }
+
+    creation_inner_field! {
+        #[doc = "Set a QoS policy to apply to the port."]
+        set_qos_policy_id, with_qos_policy_id -> qos_policy_id: optional String
+    }
+
+    /// Add a security group ID to the new port.
+    ///
+    /// Security groups provided here replace the cloud-wide defaults.
+    pub fn add_security_group<S: Into<String>>(&mut self, group: S) {
+        self.inner.security_groups.push(group.into());
+    }
+
+    /// Add a security group ID to the new port.
+    ///
+    /// Security groups provided here replace the cloud-wide defaults.
+    pub fn with_security_group<S: Into<String>>(mut self, group: S) -> Self {
+        self.add_security_group(group);
+        self
+    }
 }
 
 impl ResourceId for Port {
@@ -591,3 +998,42 @@ impl PortRef {
         })
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    use serde_json::Value;
+
+    use super::super::super::common::NetworkRef;
+    use super::super::super::utils;
+    use super::NewPort;
+
+    #[test]
+    fn test_to_request_json_binding_profile() {
+        let mut profile = HashMap::new();
+        for (key, value) in vec![("zeta", "1"), ("pci_slot", "0000:0a:00.1"),
+                                 ("alpha", "2"), ("physical_network", "ph")] {
+            let _ = profile.insert(String::from(key), Value::from(value));
+        }
+        let session = utils::test::new_session(utils::test::URL);
+        let port = NewPort::new(Rc::new(session),
+                                NetworkRef::new_verified("net".into()))
+            .with_binding_profile(profile);
+        let expected = r#"{
+  "port": {
+    "admin_state_up": true,
+    "binding:profile": {
+      "alpha": "2",
+      "pci_slot": "0000:0a:00.1",
+      "physical_network": "ph",
+      "zeta": "1"
+    },
+    "network_id": "net"
+  }
+}"#;
+        assert_eq!(port.to_request_json().unwrap(), expected);
+    }
+}
@@ -17,12 +17,14 @@
 #![allow(non_snake_case)]
 #![allow(missing_docs)]
 
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::net;
 
 use chrono::{DateTime, FixedOffset};
 use eui48::MacAddress;
 use ipnet;
+use serde_json::Value;
 
 use super::super::common;
 
@@ -61,6 +63,28 @@ impl Default for NetworkSortKey {
     }
 }
 
+impl common::ResourceStatus for NetworkStatus {
+    fn is_error(&self) -> bool {
+        *self == NetworkStatus::Error
+    }
+
+    fn is_transitional(&self) -> bool {
+        *self == NetworkStatus::Building
+    }
+}
+
+protocol_enum! {
+    #[doc = "Type of the virtual NIC a port is bound to."]
+    enum PortVnicType {
+        Baremetal = "baremetal",
+        Direct = "direct",
+        DirectPhysical = "direct-physical",
+        Macvtap = "macvtap",
+        Normal = "normal",
+        VirtioForwarder = "virtio-forwarder"
+    }
+}
+
 protocol_enum! {
     #[doc = "Available sort keys."]
     enum PortSortKey {
@@ -99,11 +123,114 @@ protocol_enum! {
     }
 }
 
+protocol_enum! {
+    #[doc = "Direction of traffic a QoS rule applies to."]
+    enum QosDirection {
+        Egress = "egress",
+        Ingress = "ingress"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Type of a QoS rule."]
+    enum QosRuleType {
+        BandwidthLimit = "bandwidth_limit",
+        DscpMarking = "dscp_marking",
+        MinimumBandwidth = "minimum_bandwidth",
+        MinimumPacketRate = "minimum_packet_rate",
+        PacketRateLimit = "packet_rate_limit"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Direction of a security group rule."]
+    enum RuleDirection {
+        Ingress = "ingress",
+        Egress = "egress"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Ethernet type of a security group rule."]
+    enum RuleEtherType {
+        Ipv4 = "IPv4",
+        Ipv6 = "IPv6"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Direction of traffic mirrored by a tap flow."]
+    enum TapFlowDirection {
+        Ingress = "IN",
+        Egress = "OUT",
+        Both = "BOTH"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Segmentation type of a trunk subport."]
+    enum SegmentationType {
+        Inherit = "inherit",
+        Vlan = "vlan"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Possible trunk statuses."]
+    enum TrunkStatus {
+        Active = "ACTIVE",
+        Building = "BUILD",
+        Degraded = "DEGRADED",
+        Down = "DOWN",
+        Error = "ERROR"
+    }
+}
+
+impl common::ResourceStatus for TrunkStatus {
+    /// `Degraded` (some subports failed to be added) is also an error.
+    fn is_error(&self) -> bool {
+        match *self {
+            TrunkStatus::Degraded | TrunkStatus::Error => true,
+            _ => false
+        }
+    }
+
+    fn is_transitional(&self) -> bool {
+        *self == TrunkStatus::Building
+    }
+}
+
+protocol_enum! {
+    #[doc = "Possible router statuses."]
+    enum RouterStatus {
+        Active = "ACTIVE",
+        Allocating = "ALLOCATING",
+        Building = "BUILD",
+        Down = "DOWN",
+        Error = "ERROR"
+    }
+}
+
+impl common::ResourceStatus for RouterStatus {
+    fn is_error(&self) -> bool {
+        *self == RouterStatus::Error
+    }
+
+    fn is_transitional(&self) -> bool {
+        match *self {
+            RouterStatus::Allocating | RouterStatus::Building => true,
+            _ => false
+        }
+    }
+}
+
 /// An network.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Network {
     pub admin_state_up: bool,
     #[serde(default)]
+    pub availability_zone_hints: Vec<String>,
+    #[serde(default)]
     pub availability_zones: Vec<String>,
     #[serde(default)]
     pub created_at: Option<DateTime<FixedOffset>>,
@@ -122,11 +249,21 @@ pub struct Network {
     pub mtu: Option<u32>,
     pub name: String,
     #[serde(default)]
+    pub port_security_enabled: Option<bool>,
+    #[serde(default)]
     pub project_id: Option<String>,
+    #[serde(rename = "provider:network_type", default)]
+    pub provider_network_type: Option<String>,
+    #[serde(rename = "provider:physical_network", default)]
+    pub provider_physical_network: Option<String>,
+    #[serde(rename = "provider:segmentation_id", default)]
+    pub provider_segmentation_id: Option<u32>,
     #[serde(default)]
     pub shared: bool,
     pub subnets: Vec<String>,
     #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
     pub updated_at: Option<DateTime<FixedOffset>>,
 }
 
@@ -136,6 +273,71 @@ pub struct NetworkRoot {
     pub network: Network
 }
 
+/// A network creation request.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NetworkCreate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin_state_up: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub availability_zone_hints: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_domain: Option<String>,
+    #[serde(rename = "router:external", skip_serializing_if = "Option::is_none")]
+    pub external: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_security_enabled: Option<bool>,
+    #[serde(rename = "provider:network_type",
+            skip_serializing_if = "Option::is_none")]
+    pub provider_network_type: Option<String>,
+    #[serde(rename = "provider:physical_network",
+            skip_serializing_if = "Option::is_none")]
+    pub provider_physical_network: Option<String>,
+    #[serde(rename = "provider:segmentation_id",
+            skip_serializing_if = "Option::is_none")]
+    pub provider_segmentation_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared: Option<bool>,
+}
+
+/// A network creation request.
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkCreateRoot {
+    pub network: NetworkCreate
+}
+
+/// A network update.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NetworkUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin_state_up: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_domain: Option<String>,
+    #[serde(rename = "router:external", skip_serializing_if = "Option::is_none")]
+    pub external: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_security_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared: Option<bool>,
+}
+
+/// A network update.
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkUpdateRoot {
+    pub network: NetworkUpdate
+}
+
 /// A list of networks.
 #[derive(Debug, Clone, Deserialize)]
 pub struct NetworksRoot {
@@ -183,6 +385,56 @@ impl PortExtraDhcpOption {
     }
 }
 
+/// An address group.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AddressGroup {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<ipnet::IpNet>,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing)]
+    pub id: String,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+}
+
+/// An address group update.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AddressGroupUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// An address group.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AddressGroupRoot {
+    pub address_group: AddressGroup
+}
+
+/// An address group update.
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressGroupUpdateRoot {
+    pub address_group: AddressGroupUpdate
+}
+
+/// A list of address groups.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddressGroupsRoot {
+    pub address_groups: Vec<AddressGroup>
+}
+
+/// Addresses to add to or remove from an address group.
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressGroupAddresses {
+    pub addresses: Vec<ipnet::IpNet>
+}
+
 /// A port's IP address.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FixedIp {
@@ -196,6 +448,19 @@ pub struct FixedIp {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Port {
     pub admin_state_up: bool,
+    #[serde(rename = "binding:host_id",
+            deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub binding_host_id: Option<String>,
+    #[serde(rename = "binding:profile", default,
+            skip_serializing_if = "HashMap::is_empty",
+            serialize_with = "common::protocol::ser_sorted_map")]
+    pub binding_profile: HashMap<String, Value>,
+    #[serde(rename = "binding:vif_type", default, skip_serializing)]
+    pub binding_vif_type: Option<String>,
+    #[serde(rename = "binding:vnic_type", default,
+            skip_serializing_if = "Option::is_none")]
+    pub binding_vnic_type: Option<PortVnicType>,
     #[serde(default, skip_serializing)]
     pub created_at: Option<DateTime<FixedOffset>>,
     #[serde(deserialize_with = "common::protocol::empty_as_none", default,
@@ -227,12 +492,18 @@ pub struct Port {
     pub name: Option<String>,
     pub network_id: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_security_enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub project_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qos_policy_id: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub security_groups: Vec<String>,
     #[serde(skip_serializing)]
     pub status: NetworkStatus,
     #[serde(default, skip_serializing)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing)]
     pub updated_at: Option<DateTime<FixedOffset>>,
 }
 
@@ -241,6 +512,12 @@ pub struct Port {
 pub struct PortUpdate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub admin_state_up: Option<bool>,
+    #[serde(rename = "binding:host_id", skip_serializing_if = "Option::is_none")]
+    pub binding_host_id: Option<String>,
+    #[serde(rename = "binding:profile", skip_serializing_if = "Option::is_none")]
+    pub binding_profile: Option<HashMap<String, Value>>,
+    #[serde(rename = "binding:vnic_type", skip_serializing_if = "Option::is_none")]
+    pub binding_vnic_type: Option<PortVnicType>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -261,6 +538,10 @@ pub struct PortUpdate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_security_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qos_policy_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub security_groups: Option<Vec<String>>,
 }
 
@@ -268,6 +549,9 @@ impl Default for PortUpdate {
     fn default() -> PortUpdate {
         PortUpdate {
             admin_state_up: None,
+            binding_host_id: None,
+            binding_profile: None,
+            binding_vnic_type: None,
             description: None,
             device_id: None,
             device_owner: None,
@@ -277,6 +561,8 @@ impl Default for PortUpdate {
             fixed_ips: None,
             mac_address: None,
             name: None,
+            port_security_enabled: None,
+            qos_policy_id: None,
             security_groups: None
         }
     }
@@ -295,13 +581,13 @@ pub struct PortUpdateRoot {
 }
 
 /// A list of ports.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PortsRoot {
     pub ports: Vec<Port>
 }
 
 /// An allocation pool.
-#[derive(Copy, Debug, Clone, Deserialize)]
+#[derive(Copy, Debug, Clone, Deserialize, Serialize)]
 pub struct AllocationPool {
     /// Start IP address.
     pub start: net::IpAddr,
@@ -310,7 +596,7 @@ pub struct AllocationPool {
 }
 
 /// A host router.
-#[derive(Copy, Debug, Clone, Deserialize)]
+#[derive(Copy, Debug, Clone, Deserialize, Serialize)]
 pub struct HostRoute {
     /// Destination network.
     pub destination: ipnet::IpNet,
@@ -349,17 +635,699 @@ pub struct Subnet {
     #[serde(default)]
     pub project_id: Option<String>,
     #[serde(default)]
+    pub subnetpool_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
     pub updated_at: Option<DateTime<FixedOffset>>,
 }
 
+/// A subnet creation request.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SubnetCreate {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allocation_pools: Vec<AllocationPool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cidr: Option<ipnet::IpNet>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "enable_dhcp", skip_serializing_if = "Option::is_none")]
+    pub dhcp_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dns_nameservers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway_ip: Option<net::IpAddr>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub host_routes: Vec<HostRoute>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip_version: Option<IpVersion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6_address_mode: Option<Ipv6Mode>,
+    #[serde(rename = "ipv6_ra_mode", skip_serializing_if = "Option::is_none")]
+    pub ipv6_router_advertisement_mode: Option<Ipv6Mode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub network_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefixlen: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subnetpool_id: Option<String>,
+}
+
 /// A subnet.
 #[derive(Debug, Clone, Deserialize)]
 pub struct SubnetRoot {
     pub subnet: Subnet
 }
 
+/// A subnet creation request.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubnetCreateRoot {
+    pub subnet: SubnetCreate
+}
+
+/// A bulk subnet creation request.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubnetsCreateRoot {
+    pub subnets: Vec<SubnetCreate>
+}
+
+/// A subnet update.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SubnetUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "enable_dhcp", skip_serializing_if = "Option::is_none")]
+    pub dhcp_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_nameservers: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway_ip: Option<net::IpAddr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// A subnet update.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubnetUpdateRoot {
+    pub subnet: SubnetUpdate
+}
+
+/// Network quotas of a project.
+///
+/// A value of -1 means no limit. Quotas for resources provided by
+/// extensions are missing when the extension is not enabled.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkQuotas {
+    /// Number of floating IPs.
+    #[serde(rename = "floatingip", default)]
+    pub floating_ips: Option<i64>,
+    /// Number of networks.
+    #[serde(rename = "network")]
+    pub networks: i64,
+    /// Number of ports.
+    #[serde(rename = "port")]
+    pub ports: i64,
+    /// Number of routers.
+    #[serde(rename = "router", default)]
+    pub routers: Option<i64>,
+    /// Number of security group rules.
+    #[serde(rename = "security_group_rule", default)]
+    pub security_group_rules: Option<i64>,
+    /// Number of security groups.
+    #[serde(rename = "security_group", default)]
+    pub security_groups: Option<i64>,
+    /// Number of subnets.
+    #[serde(rename = "subnet")]
+    pub subnets: i64,
+}
+
+/// An update to network quotas of a project.
+///
+/// Only set fields are changed, use -1 to remove a limit.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NetworkQuotasUpdate {
+    /// Number of floating IPs.
+    #[serde(rename = "floatingip", skip_serializing_if = "Option::is_none")]
+    pub floating_ips: Option<i64>,
+    /// Number of networks.
+    #[serde(rename = "network", skip_serializing_if = "Option::is_none")]
+    pub networks: Option<i64>,
+    /// Number of ports.
+    #[serde(rename = "port", skip_serializing_if = "Option::is_none")]
+    pub ports: Option<i64>,
+    /// Number of routers.
+    #[serde(rename = "router", skip_serializing_if = "Option::is_none")]
+    pub routers: Option<i64>,
+    /// Number of security group rules.
+    #[serde(rename = "security_group_rule",
+            skip_serializing_if = "Option::is_none")]
+    pub security_group_rules: Option<i64>,
+    /// Number of security groups.
+    #[serde(rename = "security_group", skip_serializing_if = "Option::is_none")]
+    pub security_groups: Option<i64>,
+    /// Number of subnets.
+    #[serde(rename = "subnet", skip_serializing_if = "Option::is_none")]
+    pub subnets: Option<i64>,
+}
+
+/// Usage of a single network quota.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct NetworkQuotaUsage {
+    /// Maximum allowed number, -1 means no limit.
+    pub limit: i64,
+    /// Number reserved by requests in progress.
+    #[serde(default)]
+    pub reserved: i64,
+    /// Number in use.
+    pub used: i64,
+}
+
+/// Network quotas of a project with their usage.
+///
+/// Quotas for resources provided by extensions are missing when the
+/// extension is not enabled.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkQuotaDetails {
+    /// Floating IPs.
+    #[serde(rename = "floatingip", default)]
+    pub floating_ips: Option<NetworkQuotaUsage>,
+    /// Networks.
+    #[serde(rename = "network")]
+    pub networks: NetworkQuotaUsage,
+    /// Ports.
+    #[serde(rename = "port")]
+    pub ports: NetworkQuotaUsage,
+    /// Routers.
+    #[serde(rename = "router", default)]
+    pub routers: Option<NetworkQuotaUsage>,
+    /// Security group rules.
+    #[serde(rename = "security_group_rule", default)]
+    pub security_group_rules: Option<NetworkQuotaUsage>,
+    /// Security groups.
+    #[serde(rename = "security_group", default)]
+    pub security_groups: Option<NetworkQuotaUsage>,
+    /// Subnets.
+    #[serde(rename = "subnet")]
+    pub subnets: NetworkQuotaUsage,
+}
+
+/// Network quotas with usage.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkQuotaDetailsRoot {
+    pub quota: NetworkQuotaDetails
+}
+
+/// Network quotas.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkQuotasRoot {
+    pub quota: NetworkQuotas
+}
+
+/// An update to network quotas.
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkQuotasUpdateRoot {
+    pub quota: NetworkQuotasUpdate
+}
+
+/// A list of tags.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TagsRoot {
+    pub tags: Vec<String>
+}
+
+/// A request to onboard subnets of a network into a subnet pool.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubnetPoolOnboard {
+    pub network_id: String
+}
+
 /// A list of subnets.
 #[derive(Debug, Clone, Deserialize)]
 pub struct SubnetsRoot {
     pub subnets: Vec<Subnet>
 }
+
+/// A tap service (port mirroring destination).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TapService {
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing)]
+    pub id: String,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub port_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(skip_serializing, default)]
+    pub status: Option<NetworkStatus>,
+}
+
+/// A tap service update.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TapServiceUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// A tap service.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TapServiceRoot {
+    pub tap_service: TapService
+}
+
+/// A tap service update.
+#[derive(Debug, Clone, Serialize)]
+pub struct TapServiceUpdateRoot {
+    pub tap_service: TapServiceUpdate
+}
+
+/// A list of tap services.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TapServicesRoot {
+    pub tap_services: Vec<TapService>
+}
+
+/// A tap flow (port mirroring source).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TapFlow {
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub direction: TapFlowDirection,
+    #[serde(skip_serializing)]
+    pub id: String,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    pub source_port: String,
+    #[serde(skip_serializing, default)]
+    pub status: Option<NetworkStatus>,
+    pub tap_service_id: String,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub vlan_filter: Option<String>,
+}
+
+/// A tap flow update.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TapFlowUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// A tap flow.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TapFlowRoot {
+    pub tap_flow: TapFlow
+}
+
+/// A tap flow update.
+#[derive(Debug, Clone, Serialize)]
+pub struct TapFlowUpdateRoot {
+    pub tap_flow: TapFlowUpdate
+}
+
+/// A list of tap flows.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TapFlowsRoot {
+    pub tap_flows: Vec<TapFlow>
+}
+
+/// A conntrack helper of a router.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConntrackHelper {
+    pub helper: String,
+    #[serde(skip_serializing)]
+    pub id: String,
+    pub port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    pub protocol: String,
+}
+
+/// A conntrack helper.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConntrackHelperRoot {
+    pub conntrack_helper: ConntrackHelper
+}
+
+/// A list of conntrack helpers.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConntrackHelpersRoot {
+    pub conntrack_helpers: Vec<ConntrackHelper>
+}
+
+/// External gateway of a router.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExternalGateway {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_snat: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_fixed_ips: Vec<FixedIp>,
+    pub network_id: String,
+}
+
+/// A router.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Router {
+    pub admin_state_up: bool,
+    #[serde(default)]
+    pub created_at: Option<DateTime<FixedOffset>>,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub distributed: Option<bool>,
+    #[serde(default)]
+    pub external_gateway_info: Option<ExternalGateway>,
+    #[serde(default)]
+    pub ha: Option<bool>,
+    pub id: String,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    pub status: RouterStatus,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<FixedOffset>>,
+}
+
+/// A router.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouterRoot {
+    pub router: Router
+}
+
+/// A router update.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RouterUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_gateway_info: Option<ExternalGateway>,
+}
+
+/// A router update.
+#[derive(Debug, Clone, Serialize)]
+pub struct RouterUpdateRoot {
+    pub router: RouterUpdate
+}
+
+/// A list of routers.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoutersRoot {
+    pub routers: Vec<Router>
+}
+
+/// A flavor of a network service, e.g. of routers.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceFlavor {
+    /// Flavor description.
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default)]
+    pub description: Option<String>,
+    /// Whether the flavor can be used.
+    #[serde(default = "default_flavor_enabled")]
+    pub enabled: bool,
+    /// Unique ID.
+    pub id: String,
+    /// Flavor name.
+    pub name: String,
+    /// Type of the service, e.g. `L3_ROUTER_NAT` for routers.
+    pub service_type: String,
+}
+
+/// A list of service flavors.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceFlavorsRoot {
+    pub flavors: Vec<ServiceFlavor>
+}
+
+#[inline]
+fn default_flavor_enabled() -> bool { true }
+
+/// A QoS rule guaranteeing minimum bandwidth.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QosMinimumBandwidthRule {
+    /// Direction of the traffic.
+    pub direction: QosDirection,
+    /// Unique ID.
+    #[serde(skip_serializing)]
+    pub id: String,
+    /// Guaranteed bandwidth in kbps.
+    pub min_kbps: u64,
+}
+
+/// A minimum bandwidth rule.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QosMinimumBandwidthRuleRoot {
+    pub minimum_bandwidth_rule: QosMinimumBandwidthRule
+}
+
+/// A list of minimum bandwidth rules.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QosMinimumBandwidthRulesRoot {
+    pub minimum_bandwidth_rules: Vec<QosMinimumBandwidthRule>
+}
+
+/// A QoS rule guaranteeing minimum packet rate.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QosMinimumPacketRateRule {
+    /// Direction of the traffic.
+    pub direction: QosDirection,
+    /// Unique ID.
+    #[serde(skip_serializing)]
+    pub id: String,
+    /// Guaranteed rate in kilo-packets per second.
+    pub min_kpps: u64,
+}
+
+/// A minimum packet rate rule.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QosMinimumPacketRateRuleRoot {
+    pub minimum_packet_rate_rule: QosMinimumPacketRateRule
+}
+
+/// A list of minimum packet rate rules.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QosMinimumPacketRateRulesRoot {
+    pub minimum_packet_rate_rules: Vec<QosMinimumPacketRateRule>
+}
+
+/// A QoS rule limiting the packet rate.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QosPacketRateLimitRule {
+    /// Direction of the traffic.
+    pub direction: QosDirection,
+    /// Unique ID.
+    #[serde(skip_serializing)]
+    pub id: String,
+    /// Maximum burst in kilo-packets, defaults to 80% of the maximum rate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_burst_kpps: Option<u64>,
+    /// Maximum rate in kilo-packets per second.
+    pub max_kpps: u64,
+}
+
+/// A packet rate limit rule.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QosPacketRateLimitRuleRoot {
+    pub packet_rate_limit_rule: QosPacketRateLimitRule
+}
+
+/// A list of packet rate limit rules.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QosPacketRateLimitRulesRoot {
+    pub packet_rate_limit_rules: Vec<QosPacketRateLimitRule>
+}
+
+/// A QoS rule type supported by the backend.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QosRuleTypeItem {
+    #[serde(rename = "type")]
+    pub rule_type: String,
+}
+
+/// A list of supported QoS rule types.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QosRuleTypesRoot {
+    pub rule_types: Vec<QosRuleTypeItem>
+}
+
+/// A subport of a trunk.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SubPort {
+    /// ID of the child port.
+    pub port_id: String,
+    /// Segmentation ID (e.g. VLAN ID), inherited from the network if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segmentation_id: Option<u32>,
+    /// Segmentation type.
+    pub segmentation_type: SegmentationType,
+    #[doc(hidden)]
+    #[serde(skip)]
+    pub __nonexhaustive: PhantomData<()>,
+}
+
+impl SubPort {
+    /// Create a VLAN subport.
+    pub fn new_vlan<S: Into<String>>(port_id: S, vlan_id: u32) -> SubPort {
+        SubPort {
+            port_id: port_id.into(),
+            segmentation_id: Some(vlan_id),
+            segmentation_type: SegmentationType::Vlan,
+            __nonexhaustive: PhantomData,
+        }
+    }
+
+    /// Create a subport inheriting segmentation from its network.
+    pub fn new_inherit<S: Into<String>>(port_id: S) -> SubPort {
+        SubPort {
+            port_id: port_id.into(),
+            segmentation_id: None,
+            segmentation_type: SegmentationType::Inherit,
+            __nonexhaustive: PhantomData,
+        }
+    }
+}
+
+/// A subport to remove from a trunk.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubPortRef {
+    pub port_id: String,
+}
+
+/// A trunk.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Trunk {
+    pub admin_state_up: bool,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing)]
+    pub id: String,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub port_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(skip_serializing, default)]
+    pub status: Option<TrunkStatus>,
+    #[serde(default)]
+    pub sub_ports: Vec<SubPort>,
+}
+
+/// A trunk update.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrunkUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin_state_up: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// A trunk.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrunkRoot {
+    pub trunk: Trunk
+}
+
+/// A trunk update.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrunkUpdateRoot {
+    pub trunk: TrunkUpdate
+}
+
+/// A list of trunks.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrunksRoot {
+    pub trunks: Vec<Trunk>
+}
+
+/// Subports to add to a trunk.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubPortsRoot {
+    pub sub_ports: Vec<SubPort>
+}
+
+/// Subports to remove from a trunk.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubPortRefsRoot {
+    pub sub_ports: Vec<SubPortRef>
+}
+
+/// A security group.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SecurityGroup {
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing)]
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub tags: Vec<String>,
+}
+
+/// A security group.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SecurityGroupRoot {
+    pub security_group: SecurityGroup
+}
+
+/// A list of security groups.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityGroupsRoot {
+    pub security_groups: Vec<SecurityGroup>
+}
+
+/// A security group update.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SecurityGroupUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// A security group update.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecurityGroupUpdateRoot {
+    pub security_group: SecurityGroupUpdate
+}
+
+/// A security group rule.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SecurityGroupRule {
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub direction: RuleDirection,
+    pub ethertype: RuleEtherType,
+    #[serde(skip_serializing)]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_range_max: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_range_min: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_address_group_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_group_id: Option<String>,
+    #[serde(deserialize_with = "common::protocol::empty_as_none", default,
+            skip_serializing_if = "Option::is_none")]
+    pub remote_ip_prefix: Option<ipnet::IpNet>,
+    pub security_group_id: String,
+}
+
+/// A security group rule.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SecurityGroupRuleRoot {
+    pub security_group_rule: SecurityGroupRule
+}
+
+/// A list of security group rules.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityGroupRulesRoot {
+    pub security_group_rules: Vec<SecurityGroupRule>
+}
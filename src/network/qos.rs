@@ -0,0 +1,393 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Quality of service rules via Network API.
+//!
+//! Requires the `qos` extension. Policies are applied to ports via
+//! `Port::set_qos_policy_id`.
+
+use std::rc::Rc;
+
+use super::super::Result;
+use super::super::session::Session;
+use super::base::V2API;
+use super::protocol;
+
+
+const QOS_RULE_TYPES: &'static [protocol::QosRuleType] = &[
+    protocol::QosRuleType::BandwidthLimit,
+    protocol::QosRuleType::DscpMarking,
+    protocol::QosRuleType::MinimumBandwidth,
+    protocol::QosRuleType::MinimumPacketRate,
+    protocol::QosRuleType::PacketRateLimit,
+];
+
+/// A QoS rule guaranteeing minimum bandwidth.
+#[derive(Clone, Debug)]
+pub struct QosMinimumBandwidthRule {
+    session: Rc<Session>,
+    policy_id: String,
+    inner: protocol::QosMinimumBandwidthRule,
+}
+
+/// A QoS rule guaranteeing minimum packet rate.
+#[derive(Clone, Debug)]
+pub struct QosMinimumPacketRateRule {
+    session: Rc<Session>,
+    policy_id: String,
+    inner: protocol::QosMinimumPacketRateRule,
+}
+
+/// A QoS rule limiting the packet rate.
+#[derive(Clone, Debug)]
+pub struct QosPacketRateLimitRule {
+    session: Rc<Session>,
+    policy_id: String,
+    inner: protocol::QosPacketRateLimitRule,
+}
+
+impl QosMinimumBandwidthRule {
+    fn new(session: Rc<Session>, policy_id: String,
+           inner: protocol::QosMinimumBandwidthRule)
+            -> QosMinimumBandwidthRule {
+        QosMinimumBandwidthRule {
+            session: session,
+            policy_id: policy_id,
+            inner: inner,
+        }
+    }
+
+    transparent_property! {
+        #[doc = "Direction of the traffic."]
+        direction: protocol::QosDirection
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Guaranteed bandwidth in kbps."]
+        min_kbps: u64
+    }
+
+    /// ID of the QoS policy the rule belongs to.
+    pub fn policy_id(&self) -> &String {
+        &self.policy_id
+    }
+
+    /// Delete the rule.
+    pub fn delete(self) -> Result<()> {
+        self.session.delete_qos_rule(&self.policy_id,
+                                     protocol::QosRuleType::MinimumBandwidth,
+                                     &self.inner.id)
+    }
+}
+
+impl QosMinimumPacketRateRule {
+    fn new(session: Rc<Session>, policy_id: String,
+           inner: protocol::QosMinimumPacketRateRule)
+            -> QosMinimumPacketRateRule {
+        QosMinimumPacketRateRule {
+            session: session,
+            policy_id: policy_id,
+            inner: inner,
+        }
+    }
+
+    transparent_property! {
+        #[doc = "Direction of the traffic."]
+        direction: protocol::QosDirection
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Guaranteed rate in kilo-packets per second."]
+        min_kpps: u64
+    }
+
+    /// ID of the QoS policy the rule belongs to.
+    pub fn policy_id(&self) -> &String {
+        &self.policy_id
+    }
+
+    /// Delete the rule.
+    pub fn delete(self) -> Result<()> {
+        self.session.delete_qos_rule(&self.policy_id,
+                                     protocol::QosRuleType::MinimumPacketRate,
+                                     &self.inner.id)
+    }
+}
+
+impl QosPacketRateLimitRule {
+    fn new(session: Rc<Session>, policy_id: String,
+           inner: protocol::QosPacketRateLimitRule)
+            -> QosPacketRateLimitRule {
+        QosPacketRateLimitRule {
+            session: session,
+            policy_id: policy_id,
+            inner: inner,
+        }
+    }
+
+    transparent_property! {
+        #[doc = "Direction of the traffic."]
+        direction: protocol::QosDirection
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Maximum burst in kilo-packets (if set explicitly)."]
+        max_burst_kpps: Option<u64>
+    }
+
+    transparent_property! {
+        #[doc = "Maximum rate in kilo-packets per second."]
+        max_kpps: u64
+    }
+
+    /// ID of the QoS policy the rule belongs to.
+    pub fn policy_id(&self) -> &String {
+        &self.policy_id
+    }
+
+    /// Delete the rule.
+    pub fn delete(self) -> Result<()> {
+        self.session.delete_qos_rule(&self.policy_id,
+                                     protocol::QosRuleType::PacketRateLimit,
+                                     &self.inner.id)
+    }
+}
+
+/// Convert rule type names, skipping unknown ones.
+fn convert_rule_types(names: Vec<String>) -> Vec<protocol::QosRuleType> {
+    names.into_iter().filter_map(|name| {
+        let found = QOS_RULE_TYPES.iter()
+            .find(|rule_type| rule_type.to_string() == name).cloned();
+        if found.is_none() {
+            debug!("Ignoring unknown QoS rule type {}", name);
+        }
+        found
+    }).collect()
+}
+
+/// List QoS rule types supported by the backend.
+pub(crate) fn list_qos_rule_types(session: &Session)
+        -> Result<Vec<protocol::QosRuleType>> {
+    Ok(convert_rule_types(session.list_qos_rule_types()?))
+}
+
+/// Create a minimum bandwidth rule in a QoS policy.
+pub(crate) fn create_minimum_bandwidth_rule(session: Rc<Session>,
+                                            policy_id: String, min_kbps: u64,
+                                            direction: protocol::QosDirection)
+        -> Result<QosMinimumBandwidthRule> {
+    let inner = session.create_qos_minimum_bandwidth_rule(
+        &policy_id, protocol::QosMinimumBandwidthRule {
+            direction: direction,
+            id: String::new(),
+            min_kbps: min_kbps,
+        })?;
+    Ok(QosMinimumBandwidthRule::new(session, policy_id, inner))
+}
+
+/// Create a minimum packet rate rule in a QoS policy.
+pub(crate) fn create_minimum_packet_rate_rule(session: Rc<Session>,
+                                              policy_id: String, min_kpps: u64,
+                                              direction: protocol::QosDirection)
+        -> Result<QosMinimumPacketRateRule> {
+    let inner = session.create_qos_minimum_packet_rate_rule(
+        &policy_id, protocol::QosMinimumPacketRateRule {
+            direction: direction,
+            id: String::new(),
+            min_kpps: min_kpps,
+        })?;
+    Ok(QosMinimumPacketRateRule::new(session, policy_id, inner))
+}
+
+/// Create a packet rate limit rule in a QoS policy.
+pub(crate) fn create_packet_rate_limit_rule(session: Rc<Session>,
+                                            policy_id: String, max_kpps: u64,
+                                            max_burst_kpps: Option<u64>,
+                                            direction: protocol::QosDirection)
+        -> Result<QosPacketRateLimitRule> {
+    let inner = session.create_qos_packet_rate_limit_rule(
+        &policy_id, protocol::QosPacketRateLimitRule {
+            direction: direction,
+            id: String::new(),
+            max_burst_kpps: max_burst_kpps,
+            max_kpps: max_kpps,
+        })?;
+    Ok(QosPacketRateLimitRule::new(session, policy_id, inner))
+}
+
+/// List minimum bandwidth rules of a QoS policy.
+pub(crate) fn list_minimum_bandwidth_rules(session: Rc<Session>,
+                                           policy_id: String)
+        -> Result<Vec<QosMinimumBandwidthRule>> {
+    Ok(session.list_qos_minimum_bandwidth_rules(&policy_id)?.into_iter()
+       .map(|item| {
+           QosMinimumBandwidthRule::new(session.clone(), policy_id.clone(),
+                                        item)
+       }).collect())
+}
+
+/// List minimum packet rate rules of a QoS policy.
+pub(crate) fn list_minimum_packet_rate_rules(session: Rc<Session>,
+                                             policy_id: String)
+        -> Result<Vec<QosMinimumPacketRateRule>> {
+    Ok(session.list_qos_minimum_packet_rate_rules(&policy_id)?.into_iter()
+       .map(|item| {
+           QosMinimumPacketRateRule::new(session.clone(), policy_id.clone(),
+                                         item)
+       }).collect())
+}
+
+/// List packet rate limit rules of a QoS policy.
+pub(crate) fn list_packet_rate_limit_rules(session: Rc<Session>,
+                                           policy_id: String)
+        -> Result<Vec<QosPacketRateLimitRule>> {
+    Ok(session.list_qos_packet_rate_limit_rules(&policy_id)?.into_iter()
+       .map(|item| {
+           QosPacketRateLimitRule::new(session.clone(), policy_id.clone(),
+                                       item)
+       }).collect())
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::super::super::Cloud;
+    use super::super::super::utils::test::FakeServer;
+    use super::super::protocol::{QosDirection, QosRuleType};
+    use super::convert_rule_types;
+
+    const RULES: &'static str = "/qos/policies/p1/packet_rate_limit_rules";
+
+    #[test]
+    fn test_convert_rule_types() {
+        let names = vec![String::from("bandwidth_limit"),
+                         String::from("vendor_specific"),
+                         String::from("minimum_bandwidth")];
+        assert_eq!(convert_rule_types(names),
+                   vec![QosRuleType::BandwidthLimit,
+                        QosRuleType::MinimumBandwidth]);
+    }
+
+    #[test]
+    fn test_create_minimum_bandwidth_rule() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("POST", "/qos/policies/p1/minimum_bandwidth_rules", 201,
+                       r#"{"minimum_bandwidth_rule": {"id": "r1",
+                           "direction": "egress", "min_kbps": 1000}}"#);
+        let cloud = Cloud::from(server.session());
+
+        let rule = cloud.create_qos_minimum_bandwidth_rule(
+            "p1", 1000, QosDirection::Egress).unwrap();
+        assert_eq!(rule.id(), "r1");
+        assert_eq!(rule.policy_id(), "p1");
+        assert_eq!(rule.min_kbps(), 1000);
+        assert_eq!(rule.direction(), QosDirection::Egress);
+
+        let request = server.requests_with_bodies().into_iter()
+            .find(|item| item.0.starts_with("POST ")).unwrap();
+        assert_eq!(request.0, "POST /qos/policies/p1/minimum_bandwidth_rules");
+        assert_eq!(request.1, concat!(
+            r#"{"minimum_bandwidth_rule":"#,
+            r#"{"direction":"egress","min_kbps":1000}}"#));
+    }
+
+    #[test]
+    fn test_create_packet_rate_limit_rule() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("POST", RULES, 201,
+                       r#"{"packet_rate_limit_rule": {"id": "r1",
+                           "direction": "ingress", "max_kpps": 1000,
+                           "max_burst_kpps": 0}}"#);
+        let cloud = Cloud::from(server.session());
+
+        let _ = cloud.create_qos_packet_rate_limit_rule(
+            "p1", 1000, None, QosDirection::Ingress).unwrap();
+        let rule = cloud.create_qos_packet_rate_limit_rule(
+            "p1", 1000, Some(200), QosDirection::Ingress).unwrap();
+        assert_eq!(rule.id(), "r1");
+        assert_eq!(rule.max_kpps(), 1000);
+
+        let bodies = server.requests_with_bodies().into_iter()
+            .filter(|item| item.0 == format!("POST {}", RULES))
+            .map(|item| item.1)
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, vec![
+            concat!(r#"{"packet_rate_limit_rule":"#,
+                    r#"{"direction":"ingress","max_kpps":1000}}"#),
+            concat!(r#"{"packet_rate_limit_rule":"#,
+                    r#"{"direction":"ingress","max_burst_kpps":200,"#,
+                    r#""max_kpps":1000}}"#),
+        ]);
+    }
+
+    #[test]
+    fn test_create_minimum_packet_rate_rule() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("POST", "/qos/policies/p1/minimum_packet_rate_rules",
+                       201,
+                       r#"{"minimum_packet_rate_rule": {"id": "r1",
+                           "direction": "egress", "min_kpps": 100}}"#);
+        let cloud = Cloud::from(server.session());
+
+        let rule = cloud.create_qos_minimum_packet_rate_rule(
+            "p1", 100, QosDirection::Egress).unwrap();
+        assert_eq!(rule.id(), "r1");
+        assert_eq!(rule.min_kpps(), 100);
+
+        let request = server.requests_with_bodies().into_iter()
+            .find(|item| item.0.starts_with("POST ")).unwrap();
+        assert_eq!(request.1, concat!(
+            r#"{"minimum_packet_rate_rule":"#,
+            r#"{"direction":"egress","min_kpps":100}}"#));
+    }
+
+    #[test]
+    fn test_list_and_delete_rules() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", RULES, 200,
+                       r#"{"packet_rate_limit_rules": [
+                           {"id": "r1", "direction": "egress",
+                            "max_kpps": 1000, "max_burst_kpps": 800},
+                           {"id": "r2", "direction": "ingress",
+                            "max_kpps": 500, "max_burst_kpps": 400}]}"#);
+        server.respond("DELETE", &format!("{}/r2", RULES), 204, "");
+        let cloud = Cloud::from(server.session());
+
+        let mut rules = cloud.list_qos_packet_rate_limit_rules("p1").unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].id(), "r1");
+        assert_eq!(rules[0].max_burst_kpps(), Some(800));
+        assert_eq!(rules[1].direction(), QosDirection::Ingress);
+        assert_eq!(rules[1].policy_id(), "p1");
+
+        rules.pop().unwrap().delete().unwrap();
+        assert_eq!(server.requests().last().unwrap(),
+                   &format!("DELETE {}/r2", RULES));
+    }
+}
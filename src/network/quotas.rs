@@ -0,0 +1,45 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Quota management via Network API.
+
+use super::super::Result;
+use super::super::common::ProjectRef;
+use super::super::session::Session;
+use super::base::V2API;
+use super::protocol;
+
+
+/// Get network quotas of a project with their usage.
+pub(crate) fn get_quota_details(session: &Session, project: ProjectRef)
+        -> Result<protocol::NetworkQuotaDetails> {
+    let project_id = project.into_verified(session)?;
+    session.get_network_quota_details(project_id)
+}
+
+/// Get network quotas of a project.
+pub(crate) fn get_quotas(session: &Session, project: ProjectRef)
+        -> Result<protocol::NetworkQuotas> {
+    let project_id = project.into_verified(session)?;
+    session.get_network_quotas(project_id)
+}
+
+/// Update network quotas of a project.
+pub(crate) fn update_quotas(session: &Session, project: ProjectRef,
+                            update: protocol::NetworkQuotasUpdate)
+        -> Result<protocol::NetworkQuotas> {
+    let project_id = project.into_verified(session)?;
+    session.update_network_quotas(project_id, update)
+}
@@ -0,0 +1,643 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Routers and router extensions (conntrack helpers, flavors and
+//! interfaces) via Network API.
+//!
+//! Conntrack helpers require the `l3-conntrack-helper` extension, router
+//! flavors require the `flavors` extension. Without them all calls fail
+//! with `ResourceNotFound`.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::mem;
+use std::rc::Rc;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset};
+use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
+use serde::Serialize;
+
+use super::super::{Error, ErrorKind, Result};
+use super::super::common::{DeletionWaiter, ListResources, Refresh,
+                           ResourceId, ResourceIterator};
+use super::super::session::Session;
+use super::super::utils::Query;
+use super::base::V2API;
+use super::ports::{convert_ip_list, verify_fixed_ips};
+use super::{protocol, Port, PortIpAddress, PortIpRequest, PortQuery, Subnet,
+            SubnetQuery};
+
+
+/// Service type of router flavors.
+const ROUTER_SERVICE_TYPE: &'static str = "L3_ROUTER_NAT";
+
+/// Device owners of ports connecting a router to its internal subnets.
+const ROUTER_INTERFACE_OWNERS: &'static [&'static str] = &[
+    "network:router_interface",
+    "network:router_interface_distributed",
+    "network:ha_router_replicated_interface",
+];
+
+/// A conntrack helper, enabling a netfilter helper for traffic of a router.
+#[derive(Clone, Debug)]
+pub struct ConntrackHelper {
+    session: Rc<Session>,
+    router_id: String,
+    inner: protocol::ConntrackHelper,
+}
+
+/// A request to create a conntrack helper.
+#[derive(Clone, Debug)]
+pub struct NewConntrackHelper {
+    session: Rc<Session>,
+    router_id: String,
+    inner: protocol::ConntrackHelper,
+}
+
+/// A query to router list.
+#[derive(Clone, Debug)]
+pub struct RouterQuery {
+    session: Rc<Session>,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// Structure representing a single router.
+#[derive(Clone, Debug)]
+pub struct Router {
+    session: Rc<Session>,
+    inner: protocol::Router,
+    external_fixed_ips: Vec<PortIpAddress>,
+    new_enable_snat: Option<bool>,
+    new_external_fixed_ips: Option<Vec<PortIpRequest>>,
+}
+
+/// An interface of a router: its port together with the attached subnets.
+#[derive(Clone, Debug)]
+pub struct RouterInterface {
+    port: Port,
+    subnets: Vec<Subnet>,
+}
+
+impl ConntrackHelper {
+    fn new(session: Rc<Session>, router_id: String,
+           inner: protocol::ConntrackHelper) -> ConntrackHelper {
+        ConntrackHelper {
+            session: session,
+            router_id: router_id,
+            inner: inner,
+        }
+    }
+
+    transparent_property! {
+        #[doc = "Name of the netfilter helper, e.g. `ftp` or `tftp`."]
+        helper: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Port the helper is applied to."]
+        port: u16
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the helper."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Network protocol, e.g. `tcp` or `udp`."]
+        protocol: ref String
+    }
+
+    /// ID of the router the helper belongs to.
+    pub fn router_id(&self) -> &String {
+        &self.router_id
+    }
+
+    /// Delete the conntrack helper.
+    pub fn delete(self) -> Result<()> {
+        self.session.delete_conntrack_helper(&self.router_id, &self.inner.id)
+    }
+}
+
+impl NewConntrackHelper {
+    /// Start creating a conntrack helper.
+    pub(crate) fn new(session: Rc<Session>, router_id: String, helper: String,
+                      protocol: String, port: u16) -> NewConntrackHelper {
+        NewConntrackHelper {
+            session: session,
+            router_id: router_id,
+            inner: protocol::ConntrackHelper {
+                helper: helper,
+                id: String::new(),
+                port: port,
+                project_id: None,
+                protocol: protocol,
+            },
+        }
+    }
+
+    /// Request creation of the conntrack helper.
+    pub fn create(self) -> Result<ConntrackHelper> {
+        let inner = self.session.create_conntrack_helper(&self.router_id,
+                                                         self.inner)?;
+        Ok(ConntrackHelper::new(self.session, self.router_id, inner))
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the project owning the helper (admin-only)."]
+        set_project_id, with_project_id -> project_id: optional String
+    }
+}
+
+impl Router {
+    /// Create a router object.
+    fn new(session: Rc<Session>, mut inner: protocol::Router) -> Router {
+        let external_fixed_ips = convert_gateway_ips(&session, &mut inner);
+        Router {
+            session: session,
+            inner: inner,
+            external_fixed_ips: external_fixed_ips,
+            new_enable_snat: None,
+            new_external_fixed_ips: None,
+        }
+    }
+
+    /// Load a Router object.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<Router> {
+        let inner = session.get_router(id)?;
+        Ok(Router::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "The administrative state of the router."]
+        admin_state_up: bool
+    }
+
+    transparent_property! {
+        #[doc = "Creation data and time (if available)."]
+        created_at: Option<DateTime<FixedOffset>>
+    }
+
+    transparent_property! {
+        #[doc = "Router description."]
+        description: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Whether the router is distributed (if known)."]
+        distributed: Option<bool>
+    }
+
+    /// Whether source NAT is enabled on the external gateway.
+    ///
+    /// `None` if the router has no external gateway or the value is not
+    /// visible to the caller (by default only to administrators).
+    pub fn enable_snat(&self) -> Option<bool> {
+        self.inner.external_gateway_info.as_ref()
+            .and_then(|gateway| gateway.enable_snat)
+    }
+
+    /// Enable or disable source NAT on the external gateway.
+    ///
+    /// Disabling SNAT is required for routed setups without NAT. Only
+    /// administrators can change it by default.
+    pub fn set_enable_snat(&mut self, value: bool) {
+        self.new_enable_snat = Some(value);
+    }
+
+    /// Enable or disable source NAT on the external gateway.
+    pub fn with_enable_snat(mut self, value: bool) -> Self {
+        self.set_enable_snat(value);
+        self
+    }
+
+    /// IP addresses of the router on the external network.
+    ///
+    /// Changes to the addresses are only reflected here after `save`.
+    pub fn external_fixed_ips(&self) -> &Vec<PortIpAddress> {
+        &self.external_fixed_ips
+    }
+
+    /// Replace the IP addresses of the router on the external network.
+    ///
+    /// The subnets (if any) are verified to belong to the external network
+    /// on `save`. Only administrators can request specific addresses by
+    /// default.
+    pub fn set_external_fixed_ips(&mut self, value: Vec<PortIpRequest>) {
+        self.new_external_fixed_ips = Some(value);
+    }
+
+    /// Replace the IP addresses of the router on the external network.
+    pub fn with_external_fixed_ips(mut self, value: Vec<PortIpRequest>)
+            -> Self {
+        self.set_external_fixed_ips(value);
+        self
+    }
+
+    /// ID of the external network the router is connected to (if any).
+    pub fn external_network_id(&self) -> Option<&String> {
+        self.inner.external_gateway_info.as_ref()
+            .map(|gateway| &gateway.network_id)
+    }
+
+    transparent_property! {
+        #[doc = "Whether the router is highly available (if known)."]
+        ha: Option<bool>
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Router name."]
+        name: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the router."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Router status."]
+        status: protocol::RouterStatus
+    }
+
+    transparent_property! {
+        #[doc = "List of tags."]
+        tags: ref Vec<String>
+    }
+
+    transparent_property! {
+        #[doc = "Last update data and time (if available)."]
+        updated_at: Option<DateTime<FixedOffset>>
+    }
+
+    /// List conntrack helpers of the router.
+    pub fn conntrack_helpers(&self) -> Result<Vec<ConntrackHelper>> {
+        list_conntrack_helpers(self.session.clone(), self.inner.id.clone())
+    }
+
+    /// List internal interfaces of the router with their subnets.
+    ///
+    /// The gateway port is not included.
+    pub fn interfaces(&self) -> Result<Vec<RouterInterface>> {
+        list_router_interfaces(self.session.clone(), self.inner.id.clone())
+    }
+
+    /// Delete the router.
+    ///
+    /// Its interfaces have to be removed first.
+    pub fn delete(self) -> Result<DeletionWaiter<Router>> {
+        self.session.delete_router(&self.inner.id)?;
+        Ok(DeletionWaiter::new(self, Duration::new(60, 0), Duration::new(1, 0)))
+    }
+
+    /// Whether the router is modified.
+    pub fn is_dirty(&self) -> bool {
+        self.new_enable_snat.is_some() || self.new_external_fixed_ips.is_some()
+    }
+
+    /// Save the changes to the external gateway of the router.
+    ///
+    /// Fails with `InvalidInput` if the router has no external gateway.
+    pub fn save(&mut self) -> Result<()> {
+        if ! self.is_dirty() {
+            return Ok(());
+        }
+
+        let mut gateway = match self.inner.external_gateway_info {
+            Some(ref gateway) => gateway.clone(),
+            None => return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Router {} has no external gateway",
+                        self.inner.id)))
+        };
+        if let Some(enable_snat) = self.new_enable_snat {
+            gateway.enable_snat = Some(enable_snat);
+        }
+        gateway.external_fixed_ips = match self.new_external_fixed_ips {
+            Some(ref requests) => verify_fixed_ips(&self.session,
+                                                   &gateway.network_id,
+                                                   requests.clone())?,
+            // Keep the current addresses.
+            None => self.external_fixed_ips.iter().map(|ip| protocol::FixedIp {
+                ip_address: ip.ip_address,
+                subnet_id: ip.subnet_id.clone()
+            }).collect()
+        };
+
+        let update = protocol::RouterUpdate {
+            external_gateway_info: Some(gateway)
+        };
+        let inner = self.session.update_router(&self.inner.id, update)?;
+        *self = Router::new(self.session.clone(), inner);
+        Ok(())
+    }
+}
+
+impl Refresh for Router {
+    /// Refresh the router.
+    fn refresh(&mut self) -> Result<()> {
+        let inner = self.session.get_router_by_id(&self.inner.id)?;
+        *self = Router::new(self.session.clone(), inner);
+        Ok(())
+    }
+}
+
+impl RouterQuery {
+    pub(crate) fn new(session: Rc<Session>) -> RouterQuery {
+        RouterQuery {
+            session: session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            max_items: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by administrative state."]
+        set_admin_state_up, with_admin_state_up -> admin_state_up: bool
+    }
+
+    query_filter! {
+        #[doc = "Filter by description."]
+        set_description, with_description -> description
+    }
+
+    query_filter! {
+        #[doc = "Filter by router name."]
+        set_name, with_name -> name
+    }
+
+    query_filter! {
+        #[doc = "Filter by project ID."]
+        set_project_id, with_project_id -> project_id
+    }
+
+    query_filter! {
+        #[doc = "Filter by router status."]
+        set_status, with_status -> status: protocol::RouterStatus
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<Router> {
+        debug!("Fetching routers with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<Router>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(mut self) -> Result<Router> {
+        debug!("Fetching one router with {:?}", self.query);
+        if self.can_paginate {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        self.into_iter().one()
+    }
+}
+
+impl RouterInterface {
+    /// Fixed IP addresses of the router on the attached subnets.
+    pub fn fixed_ips(&self) -> &Vec<PortIpAddress> {
+        self.port.fixed_ips()
+    }
+
+    /// Port connecting the router to the subnets.
+    pub fn port(&self) -> &Port {
+        &self.port
+    }
+
+    /// Subnets the interface is attached to.
+    ///
+    /// Usually there is one, but a port may have addresses from several
+    /// subnets of the same network (e.g. IPv4 and IPv6).
+    pub fn subnets(&self) -> &Vec<Subnet> {
+        &self.subnets
+    }
+
+    /// Convert into the port.
+    pub fn into_port(self) -> Port {
+        self.port
+    }
+}
+
+/// Take the external fixed IPs out of the router.
+fn convert_gateway_ips(session: &Rc<Session>, inner: &mut protocol::Router)
+        -> Vec<PortIpAddress> {
+    match inner.external_gateway_info {
+        Some(ref mut gateway) => convert_ip_list(
+            session, mem::replace(&mut gateway.external_fixed_ips, Vec::new())),
+        None => Vec::new()
+    }
+}
+
+/// List conntrack helpers of a router.
+pub(crate) fn list_conntrack_helpers(session: Rc<Session>, router_id: String)
+        -> Result<Vec<ConntrackHelper>> {
+    Ok(session.list_conntrack_helpers(&router_id)?.into_iter().map(|item| {
+        ConntrackHelper::new(session.clone(), router_id.clone(), item)
+    }).collect())
+}
+
+/// List flavors that can be used for routers.
+pub(crate) fn list_router_flavors(session: &Session)
+        -> Result<Vec<protocol::ServiceFlavor>> {
+    session.list_service_flavors(ROUTER_SERVICE_TYPE)
+}
+
+/// List internal interfaces of a router with their subnets.
+///
+/// The gateway port is not included. All subnets are fetched in one request.
+pub(crate) fn list_router_interfaces(session: Rc<Session>, router_id: String)
+        -> Result<Vec<RouterInterface>> {
+    let ports = PortQuery::new(session.clone()).with_device_id(router_id)
+        .into_iter()
+        .filter(|port| match *port.device_owner() {
+            Some(ref owner) =>
+                ROUTER_INTERFACE_OWNERS.contains(&owner.as_str()),
+            None => false
+        })
+        .collect::<Vec<Port>>()?;
+
+    let mut subnet_ids = Vec::new();
+    for port in &ports {
+        for ip in port.fixed_ips() {
+            if ! subnet_ids.contains(&ip.subnet_id) {
+                subnet_ids.push(ip.subnet_id.clone());
+            }
+        }
+    }
+
+    // An empty list of IDs would not filter anything.
+    let subnets: HashMap<String, Subnet> = if subnet_ids.is_empty() {
+        HashMap::new()
+    } else {
+        SubnetQuery::new(session).with_ids(subnet_ids).all()?
+            .into_iter().map(|subnet| (subnet.id().clone(), subnet)).collect()
+    };
+
+    Ok(ports.into_iter().map(|port| {
+        let mut port_subnets: Vec<Subnet> = Vec::new();
+        for ip in port.fixed_ips() {
+            if port_subnets.iter().any(|s| s.id() == &ip.subnet_id) {
+                continue;
+            }
+
+            match subnets.get(&ip.subnet_id) {
+                Some(subnet) => port_subnets.push(subnet.clone()),
+                None => warn!("Subnet {} of router port {} was not found",
+                              ip.subnet_id, port.id())
+            }
+        }
+
+        RouterInterface {
+            port: port,
+            subnets: port_subnets,
+        }
+    }).collect())
+}
+
+impl ResourceId for Router {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for Router {
+    const DEFAULT_LIMIT: usize = 50;
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<Router>> {
+        Ok(session.list_routers(&query)?.into_iter()
+           .map(|item| Router::new(session.clone(), item)).collect())
+    }
+}
+
+impl IntoFallibleIterator for RouterQuery {
+    type Item = Router;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<Router>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<Router> {
+        self.into_iter()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use serde_json;
+
+    use super::super::protocol;
+
+    #[test]
+    fn test_router_update_serialization() {
+        let gateway: protocol::ExternalGateway = serde_json::from_str(
+            r#"{"network_id": "public", "enable_snat": true,
+                "external_fixed_ips": [{"subnet_id": "ext",
+                                        "ip_address": "172.24.4.2"}]}"#)
+            .unwrap();
+        let update = protocol::RouterUpdateRoot {
+            router: protocol::RouterUpdate {
+                external_gateway_info: Some(protocol::ExternalGateway {
+                    enable_snat: Some(false),
+                    .. gateway
+                })
+            }
+        };
+        assert_eq!(serde_json::to_string(&update).unwrap(), concat!(
+            r#"{"router":{"external_gateway_info":{"enable_snat":false,"#,
+            r#""external_fixed_ips":[{"ip_address":"172.24.4.2","#,
+            r#""subnet_id":"ext"}],"network_id":"public"}}}"#));
+    }
+
+    #[test]
+    fn test_router_deserialization() {
+        let router: protocol::Router = serde_json::from_str(
+            r#"{"admin_state_up": true, "id": "r1", "name": "",
+                "status": "ALLOCATING", "external_gateway_info": null,
+                "distributed": false, "ha": true, "tags": ["a"]}"#)
+            .unwrap();
+        assert_eq!(router.name, None);
+        assert_eq!(router.status, protocol::RouterStatus::Allocating);
+        assert!(router.external_gateway_info.is_none());
+        assert_eq!(router.ha, Some(true));
+    }
+}
@@ -0,0 +1,312 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Security group rules normalization and comparison.
+
+use std::collections::HashMap;
+
+use ipnet::IpNet;
+
+use super::protocol::{RuleDirection, RuleEtherType};
+
+
+/// A security group rule definition.
+///
+/// Two rules that allow the same traffic may be written differently, e.g.
+/// protocol `6` is the same as `tcp` and port range `1-65535` is the same as
+/// no port range. Use `normalized` to get a canonical form suitable for
+/// comparison.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SecurityRule {
+    /// Direction of the traffic.
+    pub direction: RuleDirection,
+    /// Ethernet type.
+    pub ether_type: RuleEtherType,
+    /// IP protocol (name or number), `None` for any.
+    pub protocol: Option<String>,
+    /// Inclusive range of ports, `None` for any.
+    pub port_range: Option<(u16, u16)>,
+    /// Remote IP prefix, `None` for any.
+    pub remote_ip_prefix: Option<IpNet>,
+    /// ID of the remote security group (if any).
+    pub remote_group_id: Option<String>,
+    /// ID of the remote address group (if any).
+    ///
+    /// Requires the `address-group` Network API extension.
+    pub remote_address_group_id: Option<String>,
+}
+
+/// Operations required to turn one set of rules into another.
+#[derive(Debug, Clone, Default)]
+pub struct RuleDiff {
+    /// Rules that have to be created.
+    pub to_add: Vec<SecurityRule>,
+    /// IDs of rules that have to be deleted.
+    pub to_remove: Vec<String>,
+}
+
+fn normalize_protocol(protocol: &str) -> Option<String> {
+    match protocol.trim().to_lowercase().as_ref() {
+        "" | "any" | "0" => None,
+        "1" => Some(String::from("icmp")),
+        "6" => Some(String::from("tcp")),
+        "17" => Some(String::from("udp")),
+        "58" | "icmpv6" | "ipv6-icmp" => Some(String::from("ipv6-icmp")),
+        other => Some(String::from(other))
+    }
+}
+
+fn has_ports(protocol: &Option<String>) -> bool {
+    match *protocol {
+        Some(ref p) => p == "tcp" || p == "udp" || p == "sctp" || p == "udplite",
+        None => false
+    }
+}
+
+fn is_icmp(protocol: &Option<String>) -> bool {
+    match *protocol {
+        Some(ref p) => p == "icmp" || p == "ipv6-icmp",
+        None => false
+    }
+}
+
+impl SecurityRule {
+    /// Create a rule allowing any traffic in the given direction.
+    pub fn new(direction: RuleDirection, ether_type: RuleEtherType) -> SecurityRule {
+        SecurityRule {
+            direction: direction,
+            ether_type: ether_type,
+            protocol: None,
+            port_range: None,
+            remote_ip_prefix: None,
+            remote_group_id: None,
+            remote_address_group_id: None,
+        }
+    }
+
+    /// Limit the rule to the given protocol.
+    pub fn with_protocol<S: Into<String>>(mut self, protocol: S) -> SecurityRule {
+        self.protocol = Some(protocol.into());
+        self
+    }
+
+    /// Limit the rule to the given inclusive port range.
+    pub fn with_port_range(mut self, min: u16, max: u16) -> SecurityRule {
+        self.port_range = Some((min, max));
+        self
+    }
+
+    /// Limit the rule to the given remote IP prefix.
+    pub fn with_remote_ip_prefix(mut self, prefix: IpNet) -> SecurityRule {
+        self.remote_ip_prefix = Some(prefix);
+        self
+    }
+
+    /// Limit the rule to the given remote security group.
+    pub fn with_remote_group_id<S: Into<String>>(mut self, id: S) -> SecurityRule {
+        self.remote_group_id = Some(id.into());
+        self
+    }
+
+    /// Limit the rule to the given remote address group.
+    pub fn with_remote_address_group_id<S: Into<String>>(mut self, id: S)
+            -> SecurityRule {
+        self.remote_address_group_id = Some(id.into());
+        self
+    }
+
+    /// Convert the rule to its canonical form.
+    ///
+    /// * protocol names are lower-cased, well-known numbers become names,
+    ///   `any` becomes `None`,
+    /// * port ranges are ordered and the full range becomes `None`,
+    ///   ICMP type and code are kept as they are, port ranges are dropped
+    ///   for other protocols without ports,
+    /// * remote IP prefixes are truncated to their network address,
+    ///   `0.0.0.0/0` and `::/0` become `None`.
+    pub fn normalized(&self) -> SecurityRule {
+        let protocol = self.protocol.as_ref().and_then(|p| normalize_protocol(p));
+
+        let port_range = if has_ports(&protocol) {
+            match self.port_range {
+                Some((a, b)) if a > b => Some((b, a)),
+                other => other
+            }.and_then(|range| match range {
+                (1, 65535) | (0, 65535) => None,
+                range => Some(range)
+            })
+        } else if is_icmp(&protocol) {
+            // ICMP uses the range for type and code, so it is kept as it is
+            self.port_range
+        } else {
+            None
+        };
+
+        let remote_ip_prefix = self.remote_ip_prefix.map(|p| p.trunc())
+            .and_then(|p| if p.prefix_len() == 0 { None } else { Some(p) });
+        let ether_type = match remote_ip_prefix {
+            Some(IpNet::V4(..)) => RuleEtherType::Ipv4,
+            Some(IpNet::V6(..)) => RuleEtherType::Ipv6,
+            None => self.ether_type
+        };
+
+        SecurityRule {
+            direction: self.direction,
+            ether_type: ether_type,
+            protocol: protocol,
+            port_range: port_range,
+            remote_ip_prefix: remote_ip_prefix,
+            remote_group_id: self.remote_group_id.clone(),
+            remote_address_group_id: self.remote_address_group_id.clone(),
+        }
+    }
+
+    /// Whether two rules allow the same traffic.
+    pub fn is_equivalent(&self, other: &SecurityRule) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl RuleDiff {
+    /// Whether no changes are required.
+    pub fn is_empty(&self) -> bool {
+        self.to_add.is_empty() && self.to_remove.is_empty()
+    }
+}
+
+/// Calculate the minimum changes to turn the current rules into the desired.
+///
+/// The current rules are provided as pairs of a rule ID and a rule. Rules are
+/// compared in their normalized form, duplicates in the current rules are
+/// scheduled for removal. Rules to add keep the order of the desired rules,
+/// rules to remove keep the order of the current rules.
+pub fn diff_rules<I, J>(desired: I, current: J) -> RuleDiff
+        where I: IntoIterator<Item = SecurityRule>,
+              J: IntoIterator<Item = (String, SecurityRule)> {
+    let mut missing = Vec::new();
+    let mut wanted: HashMap<SecurityRule, usize> = HashMap::new();
+    for rule in desired {
+        let normalized = rule.normalized();
+        if ! wanted.contains_key(&normalized) {
+            let _ = wanted.insert(normalized, missing.len());
+            missing.push(Some(rule));
+        }
+    }
+
+    let mut result = RuleDiff::default();
+    for (id, rule) in current {
+        match wanted.remove(&rule.normalized()) {
+            Some(index) => missing[index] = None,
+            None => result.to_remove.push(id)
+        }
+    }
+
+    result.to_add.extend(missing.into_iter().filter_map(|rule| rule));
+    result
+}
+
+
+#[cfg(test)]
+mod test {
+    use ipnet::IpNet;
+
+    use super::super::protocol::{RuleDirection, RuleEtherType};
+    use super::{diff_rules, SecurityRule};
+
+    fn ingress() -> SecurityRule {
+        SecurityRule::new(RuleDirection::Ingress, RuleEtherType::Ipv4)
+    }
+
+    #[test]
+    fn test_normalize() {
+        let rule = ingress().with_protocol("6").with_port_range(65535, 1)
+            .with_remote_ip_prefix("10.0.0.1/8".parse::<IpNet>().unwrap());
+        let norm = rule.normalized();
+        assert_eq!(norm.protocol, Some(String::from("tcp")));
+        assert_eq!(norm.port_range, None);
+        assert_eq!(norm.remote_ip_prefix,
+                   Some("10.0.0.0/8".parse::<IpNet>().unwrap()));
+    }
+
+    #[test]
+    fn test_normalize_any() {
+        let rule = ingress().with_protocol("ANY").with_port_range(22, 22)
+            .with_remote_ip_prefix("0.0.0.0/0".parse::<IpNet>().unwrap());
+        assert_eq!(rule.normalized(), ingress());
+    }
+
+    #[test]
+    fn test_normalize_ipv6_prefix() {
+        let rule = ingress()
+            .with_remote_ip_prefix("2001:db8::1/32".parse::<IpNet>().unwrap());
+        assert_eq!(rule.normalized().ether_type, RuleEtherType::Ipv6);
+    }
+
+    #[test]
+    fn test_diff() {
+        let ssh = ingress().with_protocol("tcp").with_port_range(22, 22);
+        let http = ingress().with_protocol("tcp").with_port_range(80, 80);
+        let icmp = ingress().with_protocol("icmp");
+        let current = vec![
+            (String::from("1"), ingress().with_protocol("6").with_port_range(22, 22)),
+            (String::from("2"), icmp.clone()),
+            (String::from("3"), ssh.clone()),
+        ];
+        let diff = diff_rules(vec![ssh, http.clone()], current);
+        assert_eq!(diff.to_add, vec![http]);
+        assert_eq!(diff.to_remove, vec![String::from("2"), String::from("3")]);
+    }
+
+    #[test]
+    fn test_diff_address_group() {
+        let ssh = ingress().with_protocol("tcp").with_port_range(22, 22);
+        let ssh_group = ssh.clone().with_remote_address_group_id("group");
+        let diff = diff_rules(vec![ssh_group.clone()],
+                              vec![(String::from("1"), ssh)]);
+        assert_eq!(diff.to_add, vec![ssh_group]);
+        assert_eq!(diff.to_remove, vec![String::from("1")]);
+    }
+
+    #[test]
+    fn test_normalize_icmp() {
+        // Type 8 (echo request) with code 0 must not be swapped
+        let rule = ingress().with_protocol("1").with_port_range(8, 0);
+        let norm = rule.normalized();
+        assert_eq!(norm.protocol, Some(String::from("icmp")));
+        assert_eq!(norm.port_range, Some((8, 0)));
+        assert!(! rule.is_equivalent(&ingress().with_protocol("icmp")
+                                     .with_port_range(0, 8)));
+    }
+
+    #[test]
+    fn test_diff_order() {
+        let rules = (1..20).map(|port| {
+            ingress().with_protocol("tcp").with_port_range(port, port)
+        }).collect::<Vec<_>>();
+        let current = vec![(String::from("1"), rules[3].clone())];
+        let diff = diff_rules(rules.clone(), current);
+        let mut expected = rules.clone();
+        let _ = expected.remove(3);
+        assert_eq!(diff.to_add, expected);
+        assert!(diff.to_remove.is_empty());
+    }
+
+    #[test]
+    fn test_diff_empty() {
+        let ssh = ingress().with_protocol("tcp").with_port_range(22, 22);
+        let diff = diff_rules(vec![ssh.clone()],
+                              vec![(String::from("1"), ssh)]);
+        assert!(diff.is_empty());
+    }
+}
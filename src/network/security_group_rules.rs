@@ -0,0 +1,363 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Security group rules via Network API.
+
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::Duration;
+
+use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
+use ipnet::IpNet;
+use serde::Serialize;
+
+use super::super::{Error, Result};
+use super::super::common::{DeletionWaiter, ListResources, Refresh,
+                           ResourceId, ResourceIterator};
+use super::super::session::Session;
+use super::super::utils::Query;
+use super::base::V2API;
+use super::{protocol, SecurityGroup, SecurityRule};
+
+
+/// A query to security group rule list.
+#[derive(Clone, Debug)]
+pub struct SecurityGroupRuleQuery {
+    session: Rc<Session>,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// Structure representing a security group rule.
+#[derive(Clone, Debug)]
+pub struct SecurityGroupRule {
+    session: Rc<Session>,
+    inner: protocol::SecurityGroupRule,
+}
+
+impl SecurityGroupRule {
+    /// Create a security group rule object.
+    fn new(session: Rc<Session>, inner: protocol::SecurityGroupRule)
+            -> SecurityGroupRule {
+        SecurityGroupRule {
+            session: session,
+            inner: inner,
+        }
+    }
+
+    /// Load a SecurityGroupRule object.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<SecurityGroupRule> {
+        let inner = session.get_security_group_rule(id)?;
+        Ok(SecurityGroupRule::new(session, inner))
+    }
+
+    /// Create a rule in the security group.
+    pub(crate) fn create(session: Rc<Session>, security_group_id: String,
+                         rule: &SecurityRule) -> Result<SecurityGroupRule> {
+        let (min, max) = match rule.port_range {
+            Some((min, max)) => (Some(min), Some(max)),
+            None => (None, None)
+        };
+        let request = protocol::SecurityGroupRule {
+            description: None,
+            direction: rule.direction,
+            ethertype: rule.ether_type,
+            id: String::new(),
+            port_range_max: max,
+            port_range_min: min,
+            project_id: None,
+            protocol: rule.protocol.clone(),
+            remote_address_group_id: rule.remote_address_group_id.clone(),
+            remote_group_id: rule.remote_group_id.clone(),
+            remote_ip_prefix: rule.remote_ip_prefix,
+            security_group_id: security_group_id,
+        };
+        let inner = session.create_security_group_rule(request)?;
+        Ok(SecurityGroupRule::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Rule description."]
+        description: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Direction of the traffic."]
+        direction: protocol::RuleDirection
+    }
+
+    transparent_property! {
+        #[doc = "Ethernet type."]
+        ethertype: protocol::RuleEtherType
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Upper bound of the port range (if any)."]
+        port_range_max: Option<u16>
+    }
+
+    transparent_property! {
+        #[doc = "Lower bound of the port range (if any)."]
+        port_range_min: Option<u16>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the rule."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "IP protocol (name or number), `None` for any."]
+        protocol: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the remote address group (if any)."]
+        remote_address_group_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the remote security group (if any)."]
+        remote_group_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Remote IP prefix (if any)."]
+        remote_ip_prefix: Option<IpNet>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the security group the rule belongs to."]
+        security_group_id: ref String
+    }
+
+    /// Get the security group the rule belongs to.
+    pub fn security_group(&self) -> Result<SecurityGroup> {
+        SecurityGroup::load(self.session.clone(),
+                            &self.inner.security_group_id)
+    }
+
+    /// Convert to a rule definition, e.g. for use with `diff_rules`.
+    pub fn to_rule(&self) -> SecurityRule {
+        SecurityRule {
+            direction: self.inner.direction,
+            ether_type: self.inner.ethertype,
+            protocol: self.inner.protocol.clone(),
+            port_range: match (self.inner.port_range_min,
+                               self.inner.port_range_max) {
+                (Some(min), Some(max)) => Some((min, max)),
+                (Some(min), None) => Some((min, min)),
+                (None, Some(max)) => Some((max, max)),
+                (None, None) => None
+            },
+            remote_ip_prefix: self.inner.remote_ip_prefix,
+            remote_group_id: self.inner.remote_group_id.clone(),
+            remote_address_group_id: self.inner.remote_address_group_id.clone(),
+        }
+    }
+
+    /// Delete the security group rule.
+    pub fn delete(self) -> Result<DeletionWaiter<SecurityGroupRule>> {
+        self.session.delete_security_group_rule(&self.inner.id)?;
+        Ok(DeletionWaiter::new(self, Duration::new(60, 0), Duration::new(1, 0)))
+    }
+}
+
+impl Refresh for SecurityGroupRule {
+    /// Refresh the security group rule.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_security_group_rule(&self.inner.id)?;
+        Ok(())
+    }
+}
+
+impl SecurityGroupRuleQuery {
+    pub(crate) fn new(session: Rc<Session>) -> SecurityGroupRuleQuery {
+        SecurityGroupRuleQuery {
+            session: session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            max_items: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by description."]
+        set_description, with_description -> description
+    }
+
+    query_filter! {
+        #[doc = "Filter by direction of the traffic."]
+        set_direction, with_direction -> direction: protocol::RuleDirection
+    }
+
+    query_filter! {
+        #[doc = "Filter by ethernet type."]
+        set_ethertype, with_ethertype -> ethertype: protocol::RuleEtherType
+    }
+
+    /// Filter by the exact port range.
+    ///
+    /// Use the same value twice to find rules opening a single port.
+    pub fn set_port_range(&mut self, min: u16, max: u16) {
+        self.query.push("port_range_min", min);
+        self.query.push("port_range_max", max);
+    }
+
+    /// Filter by the exact port range.
+    ///
+    /// Use the same value twice to find rules opening a single port.
+    pub fn with_port_range(mut self, min: u16, max: u16) -> Self {
+        self.set_port_range(min, max);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by project ID."]
+        set_project_id, with_project_id -> project_id
+    }
+
+    query_filter! {
+        #[doc = "Filter by IP protocol as stored in the rule, e.g. `tcp`."]
+        set_protocol, with_protocol -> protocol
+    }
+
+    query_filter! {
+        #[doc = "Filter by remote security group ID."]
+        set_remote_group_id, with_remote_group_id -> remote_group_id
+    }
+
+    /// Filter by remote IP prefix.
+    ///
+    /// Rules allowing any address may have no prefix at all, not `0.0.0.0/0`.
+    pub fn set_remote_ip_prefix(&mut self, value: IpNet) {
+        self.query.push("remote_ip_prefix", value);
+    }
+
+    /// Filter by remote IP prefix.
+    ///
+    /// Rules allowing any address may have no prefix at all, not `0.0.0.0/0`.
+    pub fn with_remote_ip_prefix(mut self, value: IpNet) -> Self {
+        self.set_remote_ip_prefix(value);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by security group ID."]
+        set_security_group_id, with_security_group_id -> security_group_id
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<SecurityGroupRule> {
+        debug!("Fetching security group rules with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<SecurityGroupRule>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(mut self) -> Result<SecurityGroupRule> {
+        debug!("Fetching one security group rule with {:?}", self.query);
+        if self.can_paginate {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        self.into_iter().one()
+    }
+}
+
+impl ResourceId for SecurityGroupRule {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for SecurityGroupRule {
+    const DEFAULT_LIMIT: usize = 50;
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<SecurityGroupRule>> {
+        Ok(session.list_security_group_rules(&query)?.into_iter()
+           .map(|item| SecurityGroupRule::new(session.clone(), item)).collect())
+    }
+}
+
+impl IntoFallibleIterator for SecurityGroupRuleQuery {
+    type Item = SecurityGroupRule;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<SecurityGroupRule>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<SecurityGroupRule> {
+        self.into_iter()
+    }
+}
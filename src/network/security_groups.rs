@@ -0,0 +1,404 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Security groups management via Network API.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::Duration;
+
+use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
+use serde::Serialize;
+
+use super::super::{Error, Result};
+use super::super::common::{DeletionWaiter, ListResources, Refresh,
+                           ResourceId, ResourceIterator};
+use super::super::session::Session;
+use super::super::utils::Query;
+use super::base::V2API;
+use super::{protocol, SecurityGroupRule, SecurityGroupRuleQuery,
+            SecurityRule};
+
+
+/// A query to security group list.
+#[derive(Clone, Debug)]
+pub struct SecurityGroupQuery {
+    session: Rc<Session>,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// Structure representing a security group.
+#[derive(Clone, Debug)]
+pub struct SecurityGroup {
+    session: Rc<Session>,
+    inner: protocol::SecurityGroup,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a security group.
+#[derive(Clone, Debug)]
+pub struct NewSecurityGroup {
+    session: Rc<Session>,
+    inner: protocol::SecurityGroup,
+}
+
+impl SecurityGroup {
+    /// Create a security group object.
+    fn new(session: Rc<Session>, inner: protocol::SecurityGroup)
+            -> SecurityGroup {
+        SecurityGroup {
+            session: session,
+            inner: inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load a SecurityGroup object.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<SecurityGroup> {
+        let inner = session.get_security_group(id)?;
+        Ok(SecurityGroup::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Security group description."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Security group name."]
+        name: ref String
+    }
+
+    update_field! {
+        #[doc = "Update the name."]
+        set_name, with_name -> name
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the security group."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Tags of the security group."]
+        tags: ref Vec<String>
+    }
+
+    /// Add a tag to the security group.
+    ///
+    /// The change is applied immediately.
+    pub fn add_tag<S: Into<String>>(&mut self, tag: S) -> Result<()> {
+        let tag = tag.into();
+        self.session.add_tag("security-groups", &self.inner.id, &tag)?;
+        if !self.inner.tags.contains(&tag) {
+            self.inner.tags.push(tag);
+        }
+        Ok(())
+    }
+
+    /// Remove a tag from the security group.
+    ///
+    /// The change is applied immediately.
+    pub fn remove_tag<S: AsRef<str>>(&mut self, tag: S) -> Result<()> {
+        self.session.remove_tag("security-groups", &self.inner.id,
+                                tag.as_ref())?;
+        self.inner.tags.retain(|item| item != tag.as_ref());
+        Ok(())
+    }
+
+    /// Build a query against rules of the security group.
+    ///
+    /// The query can be narrowed down further, e.g. by direction or port
+    /// range.
+    pub fn find_rules(&self) -> SecurityGroupRuleQuery {
+        SecurityGroupRuleQuery::new(self.session.clone())
+            .with_security_group_id(self.inner.id.clone())
+    }
+
+    /// Rules of the security group.
+    pub fn rules(&self) -> Result<Vec<SecurityGroupRule>> {
+        self.find_rules().all()
+    }
+
+    /// Add a rule to the security group.
+    ///
+    /// The change is applied immediately. Use `diff_rules` to find out which
+    /// rules have to be added.
+    pub fn add_rule(&self, rule: &SecurityRule) -> Result<SecurityGroupRule> {
+        SecurityGroupRule::create(self.session.clone(), self.inner.id.clone(),
+                                  rule)
+    }
+
+    /// Delete the security group.
+    pub fn delete(self) -> Result<DeletionWaiter<SecurityGroup>> {
+        self.session.delete_security_group(&self.inner.id)?;
+        Ok(DeletionWaiter::new(self, Duration::new(60, 0), Duration::new(1, 0)))
+    }
+
+    /// Whether the security group is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the security group.
+    pub fn save(&mut self) -> Result<()> {
+        let mut update = protocol::SecurityGroupUpdate::default();
+        save_fields! {
+            self -> update: name
+        };
+        save_option_fields! {
+            self -> update: description
+        };
+        self.inner = self.session.update_security_group(self.id(), update)?;
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+impl Refresh for SecurityGroup {
+    /// Refresh the security group.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_security_group_by_id(&self.inner.id)?;
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+impl SecurityGroupQuery {
+    pub(crate) fn new(session: Rc<Session>) -> SecurityGroupQuery {
+        SecurityGroupQuery {
+            session: session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            max_items: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by description."]
+        set_description, with_description -> description
+    }
+
+    query_filter! {
+        #[doc = "Filter by security group name."]
+        set_name, with_name -> name
+    }
+
+    query_filter! {
+        #[doc = "Filter by project ID."]
+        set_project_id, with_project_id -> project_id
+    }
+
+    /// Filter by tags (all of them must be present).
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("tags", tags);
+        self
+    }
+
+    /// Filter by tags (any of them must be present).
+    pub fn with_any_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("tags-any", tags);
+        self
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<SecurityGroup> {
+        debug!("Fetching security groups with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<SecurityGroup>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(mut self) -> Result<SecurityGroup> {
+        debug!("Fetching one security group with {:?}", self.query);
+        if self.can_paginate {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        self.into_iter().one()
+    }
+}
+
+impl NewSecurityGroup {
+    /// Start creating a security group.
+    pub(crate) fn new(session: Rc<Session>, name: String) -> NewSecurityGroup {
+        NewSecurityGroup {
+            session: session,
+            inner: protocol::SecurityGroup {
+                description: None,
+                id: String::new(),
+                name: name,
+                project_id: None,
+                tags: Vec::new(),
+            },
+        }
+    }
+
+    /// Request creation of the security group.
+    ///
+    /// Note that the Network service adds default egress rules to new
+    /// security groups.
+    pub fn create(self) -> Result<SecurityGroup> {
+        let inner = self.session.create_security_group(self.inner)?;
+        Ok(SecurityGroup::new(self.session, inner))
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the security group."]
+        set_description, with_description -> description: optional String
+    }
+}
+
+impl ResourceId for SecurityGroup {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for SecurityGroup {
+    const DEFAULT_LIMIT: usize = 50;
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<SecurityGroup>> {
+        Ok(session.list_security_groups(&query)?.into_iter()
+           .map(|item| SecurityGroup::new(session.clone(), item)).collect())
+    }
+}
+
+impl IntoFallibleIterator for SecurityGroupQuery {
+    type Item = SecurityGroup;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<SecurityGroup>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<SecurityGroup> {
+        self.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::Cloud;
+    use super::super::super::utils::test::FakeServer;
+    use super::super::protocol;
+
+    #[test]
+    fn test_find_rules() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", "/security-groups/sg1", 200,
+                       r#"{"security_group": {"id": "sg1", "name": "web",
+                           "description": "", "tags": []}}"#);
+        server.respond("GET", "/security-group-rules", 200,
+                       r#"{"security_group_rules": [{"id": "r1",
+                           "direction": "ingress", "ethertype": "IPv4",
+                           "protocol": "tcp", "port_range_min": 22,
+                           "port_range_max": 22,
+                           "remote_ip_prefix": "0.0.0.0/0",
+                           "security_group_id": "sg1"}]}"#);
+        // The second page, requested with the marker of the first one.
+        server.respond("GET", "/security-group-rules", 200,
+                       r#"{"security_group_rules": []}"#);
+        let cloud = Cloud::from(server.session());
+
+        let group = cloud.get_security_group("sg1").unwrap();
+        let rules = group.find_rules()
+            .with_direction(protocol::RuleDirection::Ingress)
+            .with_port_range(22, 22)
+            .all().unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].id(), "r1");
+        assert_eq!(rules[0].security_group().unwrap().name(), "web");
+
+        let queries = server.requests().into_iter()
+            .filter(|req| req.starts_with("GET /security-group-rules?"))
+            .collect::<Vec<_>>();
+        assert_eq!(queries.len(), 2);
+        for query in &queries {
+            assert!(query.contains("security_group_id=sg1"));
+            assert!(query.contains("direction=ingress"));
+            assert!(query.contains("port_range_min=22"));
+            assert!(query.contains("port_range_max=22"));
+        }
+        assert!(!queries[0].contains("marker="));
+        assert!(queries[1].contains("marker=r1"));
+    }
+}
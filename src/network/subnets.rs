@@ -14,6 +14,7 @@
 
 //! Subnets management via Network API.
 
+use std::collections::HashSet;
 use std::rc::Rc;
 use std::fmt::Debug;
 use std::net;
@@ -24,9 +25,9 @@ use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
 use ipnet;
 use serde::Serialize;
 
-use super::super::{Error, Result, Sort};
-use super::super::common::{DeletionWaiter, ListResources, NetworkRef, SubnetRef,
-                           Refresh, ResourceId, ResourceIterator};
+use super::super::{Error, ErrorKind, Result, Sort};
+use super::super::common::{self, DeletionWaiter, ListResources, NetworkRef,
+                           SubnetRef, Refresh, ResourceId, ResourceIterator};
 use super::super::session::Session;
 use super::super::utils::Query;
 use super::base::V2API;
@@ -39,13 +40,24 @@ pub struct SubnetQuery {
     session: Rc<Session>,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
 }
 
 /// Structure representing a subnet - a virtual NIC.
 #[derive(Clone, Debug)]
 pub struct Subnet {
     session: Rc<Session>,
-    inner: protocol::Subnet
+    inner: protocol::Subnet,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a subnet.
+#[derive(Clone, Debug)]
+pub struct NewSubnet {
+    session: Rc<Session>,
+    inner: protocol::SubnetCreate,
+    network: NetworkRef,
 }
 
 impl Subnet {
@@ -53,7 +65,8 @@ impl Subnet {
     pub(crate) fn new(session: Rc<Session>, inner: protocol::Subnet) -> Subnet {
         Subnet {
             session: session,
-            inner: inner
+            inner: inner,
+            dirty: HashSet::new(),
         }
     }
 
@@ -84,21 +97,42 @@ impl Subnet {
         description: ref Option<String>
     }
 
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
     transparent_property! {
         #[doc = "Whether DHCP is enabled."]
         dhcp_enabled: bool
     }
 
+    update_field! {
+        #[doc = "Update whether DHCP is enabled."]
+        set_dhcp_enabled, with_dhcp_enabled -> dhcp_enabled: bool
+    }
+
     transparent_property! {
         #[doc = "List of DNS servers."]
         dns_nameservers: ref Vec<String>
     }
 
+    update_field! {
+        #[doc = "Update the list of DNS servers."]
+        set_dns_nameservers, with_dns_nameservers ->
+            dns_nameservers: Vec<String>
+    }
+
     transparent_property! {
         #[doc = "Gateway IP address (if any)."]
         gateway_ip: Option<net::IpAddr>
     }
 
+    update_field! {
+        #[doc = "Update the gateway IP address."]
+        set_gateway_ip, with_gateway_ip -> gateway_ip: optional net::IpAddr
+    }
+
     transparent_property! {
         #[doc = "Statically configured routes."]
         host_routes: ref Vec<protocol::HostRoute>
@@ -129,6 +163,11 @@ impl Subnet {
         name: ref Option<String>
     }
 
+    update_field! {
+        #[doc = "Update the name."]
+        set_name, with_name -> name: optional String
+    }
+
     /// Get network associated with this subnet.
     pub fn network(&self) -> Result<Network> {
         Network::new(self.session.clone(), &self.inner.network_id)
@@ -139,6 +178,46 @@ impl Subnet {
         network_id: ref String
     }
 
+    transparent_property! {
+        #[doc = "ID of the subnet pool this subnet belongs to (if any)."]
+        subnetpool_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Tags of the subnet."]
+        tags: ref Vec<String>
+    }
+
+    /// Add a tag to the subnet.
+    ///
+    /// The change is applied immediately.
+    pub fn add_tag<S: Into<String>>(&mut self, tag: S) -> Result<()> {
+        let tag = tag.into();
+        self.session.add_tag("subnets", &self.inner.id, &tag)?;
+        if !self.inner.tags.contains(&tag) {
+            self.inner.tags.push(tag);
+        }
+        Ok(())
+    }
+
+    /// Remove a tag from the subnet.
+    ///
+    /// The change is applied immediately.
+    pub fn remove_tag<S: AsRef<str>>(&mut self, tag: S) -> Result<()> {
+        self.session.remove_tag("subnets", &self.inner.id, tag.as_ref())?;
+        self.inner.tags.retain(|item| item != tag.as_ref());
+        Ok(())
+    }
+
+    /// Replace all tags of the subnet.
+    ///
+    /// The change is applied immediately.
+    pub fn set_tags(&mut self, tags: Vec<String>) -> Result<()> {
+        self.inner.tags = self.session.replace_tags("subnets", &self.inner.id,
+                                                    tags)?;
+        Ok(())
+    }
+
     transparent_property! {
         #[doc = "Last update data and time (if available)."]
         updated_at: Option<DateTime<FixedOffset>>
@@ -149,12 +228,32 @@ impl Subnet {
         self.session.delete_subnet(&self.inner.id)?;
         Ok(DeletionWaiter::new(self, Duration::new(60, 0), Duration::new(1, 0)))
     }
+
+    /// Whether the subnet is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the subnet.
+    pub fn save(&mut self) -> Result<()> {
+        let mut update = protocol::SubnetUpdate::default();
+        save_fields! {
+            self -> update: dhcp_enabled dns_nameservers
+        };
+        save_option_fields! {
+            self -> update: description gateway_ip name
+        };
+        self.inner = self.session.update_subnet(self.id(), update)?;
+        self.dirty.clear();
+        Ok(())
+    }
 }
 
 impl Refresh for Subnet {
     /// Refresh the subnet.
     fn refresh(&mut self) -> Result<()> {
         self.inner = self.session.get_subnet(&self.inner.id)?;
+        self.dirty.clear();
         Ok(())
     }
 }
@@ -165,6 +264,8 @@ impl SubnetQuery {
             session: session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            max_items: None,
         }
     }
 
@@ -186,6 +287,20 @@ impl SubnetQuery {
         self
     }
 
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
     /// Add sorting to the request.
     pub fn sort_by(mut self, sort: Sort<protocol::SubnetSortKey>) -> Self {
         let (field, direction) = sort.into();
@@ -194,6 +309,16 @@ impl SubnetQuery {
         self
     }
 
+    /// Traverse the results backwards, starting from the end or the marker.
+    ///
+    /// Items are returned in the reverse sorting order. Together with
+    /// `with_marker` this allows paging backwards: the last item of a page
+    /// is the marker for the previous page.
+    pub fn with_page_reverse(mut self) -> Self {
+        self.query.push("page_reverse", true);
+        self
+    }
+
     query_filter! {
         #[doc = "Filter by CIDR."]
         set_cidr, with_cidr -> cidr: ipnet::IpNet
@@ -250,6 +375,45 @@ impl SubnetQuery {
         self
     }
 
+    /// Filter by IDs, matching any of them.
+    ///
+    /// Note that an empty list does not filter anything.
+    pub fn with_ids<I, S>(mut self, ids: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        for id in ids {
+            self.query.push_str("id", id);
+        }
+        self
+    }
+
+    /// Filter by tags (all of them must be present).
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("tags", tags);
+        self
+    }
+
+    /// Filter by tags (any of them must be present).
+    pub fn with_any_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("tags-any", tags);
+        self
+    }
+
+    /// Filter out resources having all of these tags.
+    pub fn with_not_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("not-tags", tags);
+        self
+    }
+
+    /// Filter out resources having any of these tags.
+    pub fn with_not_any_tags<I, S>(mut self, tags: I) -> Self
+            where I: IntoIterator<Item = S>, S: Into<String> {
+        self.query.push_list("not-tags-any", tags);
+        self
+    }
+
     /// Convert this query into an iterator executing the request.
     ///
     /// Returns a `FallibleIterator`, which is an iterator with each `next`
@@ -259,6 +423,14 @@ impl SubnetQuery {
     pub fn into_iter(self) -> ResourceIterator<Subnet> {
         debug!("Fetching subnets with {:?}", self.query);
         ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Convert this query into an iterator fetching `page_size` items per
+    /// request.
+    pub fn into_iter_paginated(self, page_size: usize)
+            -> ResourceIterator<Subnet> {
+        self.with_page_size(page_size).into_iter()
     }
 
     /// Execute this request and return all results.
@@ -284,6 +456,215 @@ impl SubnetQuery {
     }
 }
 
+impl NewSubnet {
+    /// Start creating a subnet.
+    pub(crate) fn new(session: Rc<Session>, network: NetworkRef,
+                      cidr: ipnet::IpNet) -> NewSubnet {
+        let ip_version = match cidr {
+            ipnet::IpNet::V4(..) => protocol::IpVersion::V4,
+            ipnet::IpNet::V6(..) => protocol::IpVersion::V6,
+        };
+        NewSubnet {
+            session: session,
+            inner: protocol::SubnetCreate {
+                cidr: Some(cidr),
+                ip_version: Some(ip_version),
+                ..Default::default()
+            },
+            network: network,
+        }
+    }
+
+    /// Validate the request without sending it.
+    ///
+    /// Detects IPv6 modes used with IPv4 subnets, conflicting IPv6 modes
+    /// and auto-configuration on subnets with a prefix other than /64. All
+    /// found problems are reported in one `InvalidInput` error. This call is
+    /// done automatically by `create`.
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        let address_mode = self.inner.ipv6_address_mode;
+        let ra_mode = self.inner.ipv6_router_advertisement_mode;
+
+        if self.inner.ip_version == Some(protocol::IpVersion::V4) {
+            if address_mode.is_some() || ra_mode.is_some() {
+                errors.push(String::from("IPv6 modes cannot be used with \
+                                          IPv4 subnets"));
+            }
+        } else {
+            if let (Some(address_mode), Some(ra_mode)) = (address_mode, ra_mode) {
+                if address_mode != ra_mode {
+                    errors.push(format!("IPv6 address mode {} does not match \
+                                         router advertisement mode {}",
+                                        String::from(address_mode),
+                                        String::from(ra_mode)));
+                }
+            }
+
+            let auto_config = [address_mode, ra_mode].iter().any(|mode| {
+                *mode == Some(protocol::Ipv6Mode::Slaac) ||
+                    *mode == Some(protocol::Ipv6Mode::DhcpStateless)
+            });
+            if let Some(cidr) = self.inner.cidr {
+                if auto_config && cidr.prefix_len() != 64 {
+                    errors.push(format!("IPv6 auto-configuration requires \
+                                         a /64 prefix, got {}", cidr));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::InvalidInput,
+                           format!("Invalid subnet creation request: {}",
+                                   errors.join("; "))))
+        }
+    }
+
+    fn build_request(&self) -> Result<protocol::SubnetCreate> {
+        self.validate()?;
+        let mut request = self.inner.clone();
+        request.network_id = self.network.clone().into_verified(&self.session)?;
+        Ok(request)
+    }
+
+    /// Request creation of the subnet.
+    pub fn create(self) -> Result<Subnet> {
+        let request = self.build_request()?;
+        let inner = self.session.create_subnet(request)?;
+        Ok(Subnet::new(self.session, inner))
+    }
+
+    /// Request creation of several subnets in one API call.
+    ///
+    /// Either all subnets are created or none of them.
+    pub(crate) fn create_bulk(session: Rc<Session>, subnets: Vec<NewSubnet>)
+            -> Result<Vec<Subnet>> {
+        if subnets.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut requests = Vec::with_capacity(subnets.len());
+        for item in &subnets {
+            requests.push(item.build_request()?);
+        }
+
+        Ok(session.create_subnets(requests)?.into_iter()
+           .map(|item| Subnet::new(session.clone(), item)).collect())
+    }
+
+    /// Serialize the request body that `create` would send.
+    ///
+    /// The network referenced by name is resolved to an ID first.
+    pub fn to_request_json(&self) -> Result<String> {
+        let body = protocol::SubnetCreateRoot { subnet: self.build_request()? };
+        common::protocol::to_request_json(&body)
+    }
+
+    /// Add an allocation pool for DHCP.
+    pub fn add_allocation_pool(&mut self, start: net::IpAddr, end: net::IpAddr) {
+        self.inner.allocation_pools.push(protocol::AllocationPool {
+            start: start,
+            end: end
+        });
+    }
+
+    /// Add an allocation pool for DHCP.
+    pub fn with_allocation_pool(mut self, start: net::IpAddr, end: net::IpAddr)
+            -> Self {
+        self.add_allocation_pool(start, end);
+        self
+    }
+
+    /// Add a DNS server.
+    pub fn add_dns_nameserver<S: Into<String>>(&mut self, value: S) {
+        self.inner.dns_nameservers.push(value.into());
+    }
+
+    /// Add a DNS server.
+    pub fn with_dns_nameserver<S: Into<String>>(mut self, value: S) -> Self {
+        self.add_dns_nameserver(value);
+        self
+    }
+
+    /// Add a static route.
+    pub fn add_host_route(&mut self, destination: ipnet::IpNet,
+                          next_hop: net::IpAddr) {
+        self.inner.host_routes.push(protocol::HostRoute {
+            destination: destination,
+            next_hop: next_hop
+        });
+    }
+
+    /// Add a static route.
+    pub fn with_host_route(mut self, destination: ipnet::IpNet,
+                           next_hop: net::IpAddr) -> Self {
+        self.add_host_route(destination, next_hop);
+        self
+    }
+
+    /// Use SLAAC for both address assignment and router advertisements.
+    pub fn slaac(self) -> Self {
+        self.with_ipv6_modes(protocol::Ipv6Mode::Slaac)
+    }
+
+    /// Use stateful DHCPv6 for both address assignment and router
+    /// advertisements.
+    pub fn dhcpv6_stateful(self) -> Self {
+        self.with_ipv6_modes(protocol::Ipv6Mode::DhcpStateful)
+    }
+
+    /// Use stateless DHCPv6 for both address assignment and router
+    /// advertisements.
+    pub fn dhcpv6_stateless(self) -> Self {
+        self.with_ipv6_modes(protocol::Ipv6Mode::DhcpStateless)
+    }
+
+    fn with_ipv6_modes(mut self, mode: protocol::Ipv6Mode) -> Self {
+        self.inner.ipv6_address_mode = Some(mode);
+        self.inner.ipv6_router_advertisement_mode = Some(mode);
+        self
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the subnet."]
+        set_description, with_description -> description: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set whether DHCP is enabled."]
+        set_dhcp_enabled, with_dhcp_enabled -> dhcp_enabled: optional bool
+    }
+
+    creation_inner_field! {
+        #[doc = "Set gateway IP address."]
+        set_gateway_ip, with_gateway_ip -> gateway_ip: optional net::IpAddr
+    }
+
+    creation_inner_field! {
+        #[doc = "Set address assignment mode for IPv6."]
+        set_ipv6_address_mode, with_ipv6_address_mode ->
+            ipv6_address_mode: optional protocol::Ipv6Mode
+    }
+
+    creation_inner_field! {
+        #[doc = "Set router advertisement mode for IPv6."]
+        set_ipv6_router_advertisement_mode, with_ipv6_router_advertisement_mode ->
+            ipv6_router_advertisement_mode: optional protocol::Ipv6Mode
+    }
+
+    creation_inner_field! {
+        #[doc = "Set a name for the subnet."]
+        set_name, with_name -> name: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set ID of the subnet pool to allocate the subnet from."]
+        set_subnetpool_id, with_subnetpool_id -> subnetpool_id: optional String
+    }
+}
+
 impl ResourceId for Subnet {
     fn resource_id(&self) -> String {
         self.id().clone()
@@ -330,3 +711,45 @@ impl SubnetRef {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::super::super::ErrorKind;
+    use super::super::super::common::NetworkRef;
+    use super::super::super::utils;
+    use super::super::protocol::Ipv6Mode;
+    use super::NewSubnet;
+
+    fn new_subnet(cidr: &str) -> NewSubnet {
+        let session = utils::test::new_session(utils::test::URL);
+        NewSubnet::new(Rc::new(session), NetworkRef::new_verified("net".into()),
+                       cidr.parse().unwrap())
+    }
+
+    #[test]
+    fn test_validate_slaac() {
+        new_subnet("2001:db8::/64").slaac().validate().unwrap();
+        new_subnet("2001:db8::/64").dhcpv6_stateful().validate().unwrap();
+    }
+
+    #[test]
+    fn test_validate_ipv6_modes_on_ipv4() {
+        let err = new_subnet("192.168.1.0/24").slaac().validate()
+            .err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_validate_ipv6_modes_mismatch() {
+        let err = new_subnet("2001:db8::/56")
+            .with_ipv6_address_mode(Ipv6Mode::DhcpStateful)
+            .with_ipv6_router_advertisement_mode(Ipv6Mode::Slaac)
+            .validate().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let msg = err.to_string();
+        assert!(msg.contains("does not match"));
+        assert!(msg.contains("/64 prefix"));
+    }
+}
@@ -0,0 +1,785 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Port mirroring (Tap-as-a-Service) via Network API.
+//!
+//! Requires the `taas` extension, which is not deployed on every cloud.
+//! Without it all calls fail with `ResourceNotFound`.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::Duration;
+
+use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
+use serde::Serialize;
+
+use super::super::{Error, Result};
+use super::super::common::{self, DeletionWaiter, ListResources, PortRef,
+                           Refresh, ResourceId, ResourceIterator};
+use super::super::session::Session;
+use super::super::utils::Query;
+use super::base::V2API;
+use super::{protocol, Port};
+
+
+/// A query to tap service list.
+#[derive(Clone, Debug)]
+pub struct TapServiceQuery {
+    session: Rc<Session>,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// A query to tap flow list.
+#[derive(Clone, Debug)]
+pub struct TapFlowQuery {
+    session: Rc<Session>,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// Structure representing a tap service - a port receiving mirrored traffic.
+#[derive(Clone, Debug)]
+pub struct TapService {
+    session: Rc<Session>,
+    inner: protocol::TapService,
+    dirty: HashSet<&'static str>,
+}
+
+/// Structure representing a tap flow - a port whose traffic is mirrored.
+#[derive(Clone, Debug)]
+pub struct TapFlow {
+    session: Rc<Session>,
+    inner: protocol::TapFlow,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a tap service.
+#[derive(Clone, Debug)]
+pub struct NewTapService {
+    session: Rc<Session>,
+    inner: protocol::TapService,
+    port: PortRef,
+}
+
+/// A request to create a tap flow.
+#[derive(Clone, Debug)]
+pub struct NewTapFlow {
+    session: Rc<Session>,
+    inner: protocol::TapFlow,
+    tap_service: String,
+    source_port: PortRef,
+}
+
+impl TapService {
+    /// Create a tap service object.
+    fn new(session: Rc<Session>, inner: protocol::TapService) -> TapService {
+        TapService {
+            session: session,
+            inner: inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load a TapService object.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<TapService> {
+        let inner = session.get_tap_service(id)?;
+        Ok(TapService::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Tap service description."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Tap service name."]
+        name: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the name."]
+        set_name, with_name -> name: optional String
+    }
+
+    /// Get the port receiving the mirrored traffic.
+    pub fn port(&self) -> Result<Port> {
+        Port::load(self.session.clone(), &self.inner.port_id)
+    }
+
+    transparent_property! {
+        #[doc = "ID of the port receiving the mirrored traffic."]
+        port_id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the tap service."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Tap service status (if known)."]
+        status: Option<protocol::NetworkStatus>
+    }
+
+    /// Delete the tap service.
+    pub fn delete(self) -> Result<DeletionWaiter<TapService>> {
+        self.session.delete_tap_service(&self.inner.id)?;
+        Ok(DeletionWaiter::new(self, Duration::new(60, 0), Duration::new(1, 0)))
+    }
+
+    /// Whether the tap service is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the tap service.
+    pub fn save(&mut self) -> Result<()> {
+        let mut update = protocol::TapServiceUpdate::default();
+        save_option_fields! {
+            self -> update: description name
+        };
+        let inner = self.session.update_tap_service(self.id(), update)?;
+        self.dirty.clear();
+        self.inner = inner;
+        Ok(())
+    }
+}
+
+impl Refresh for TapService {
+    /// Refresh the tap service.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_tap_service_by_id(&self.inner.id)?;
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+impl TapFlow {
+    /// Create a tap flow object.
+    fn new(session: Rc<Session>, inner: protocol::TapFlow) -> TapFlow {
+        TapFlow {
+            session: session,
+            inner: inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load a TapFlow object.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<TapFlow> {
+        let inner = session.get_tap_flow(id)?;
+        Ok(TapFlow::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Tap flow description."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Direction of the mirrored traffic."]
+        direction: protocol::TapFlowDirection
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Tap flow name."]
+        name: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the name."]
+        set_name, with_name -> name: optional String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the tap flow."]
+        project_id: ref Option<String>
+    }
+
+    /// Get the port whose traffic is mirrored.
+    pub fn source_port(&self) -> Result<Port> {
+        Port::load(self.session.clone(), &self.inner.source_port)
+    }
+
+    /// ID of the port whose traffic is mirrored.
+    pub fn source_port_id(&self) -> &String {
+        &self.inner.source_port
+    }
+
+    transparent_property! {
+        #[doc = "Tap flow status (if known)."]
+        status: Option<protocol::NetworkStatus>
+    }
+
+    /// Get the tap service receiving the traffic.
+    pub fn tap_service(&self) -> Result<TapService> {
+        TapService::load(self.session.clone(), &self.inner.tap_service_id)
+    }
+
+    transparent_property! {
+        #[doc = "ID of the tap service receiving the traffic."]
+        tap_service_id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "VLAN IDs to mirror (e.g. `1-5,9`), all VLANs if empty."]
+        vlan_filter: ref Option<String>
+    }
+
+    /// Delete the tap flow.
+    pub fn delete(self) -> Result<DeletionWaiter<TapFlow>> {
+        self.session.delete_tap_flow(&self.inner.id)?;
+        Ok(DeletionWaiter::new(self, Duration::new(60, 0), Duration::new(1, 0)))
+    }
+
+    /// Whether the tap flow is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the tap flow.
+    pub fn save(&mut self) -> Result<()> {
+        let mut update = protocol::TapFlowUpdate::default();
+        save_option_fields! {
+            self -> update: description name
+        };
+        let inner = self.session.update_tap_flow(self.id(), update)?;
+        self.dirty.clear();
+        self.inner = inner;
+        Ok(())
+    }
+}
+
+impl Refresh for TapFlow {
+    /// Refresh the tap flow.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_tap_flow_by_id(&self.inner.id)?;
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+impl TapServiceQuery {
+    pub(crate) fn new(session: Rc<Session>) -> TapServiceQuery {
+        TapServiceQuery {
+            session: session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            max_items: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by tap service name."]
+        set_name, with_name -> name
+    }
+
+    /// Filter by the port receiving the mirrored traffic.
+    ///
+    /// # Warning
+    ///
+    /// Due to architectural limitations, names do not work here.
+    pub fn set_port<P: Into<PortRef>>(&mut self, value: P) {
+        self.query.push_str("port_id", value.into());
+    }
+
+    /// Filter by the port receiving the mirrored traffic.
+    ///
+    /// # Warning
+    ///
+    /// Due to architectural limitations, names do not work here.
+    pub fn with_port<P: Into<PortRef>>(mut self, value: P) -> Self {
+        self.set_port(value);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by project ID."]
+        set_project_id, with_project_id -> project_id
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<TapService> {
+        debug!("Fetching tap services with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<TapService>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(mut self) -> Result<TapService> {
+        debug!("Fetching one tap service with {:?}", self.query);
+        if self.can_paginate {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        self.into_iter().one()
+    }
+}
+
+impl TapFlowQuery {
+    pub(crate) fn new(session: Rc<Session>) -> TapFlowQuery {
+        TapFlowQuery {
+            session: session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            max_items: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by tap flow name."]
+        set_name, with_name -> name
+    }
+
+    query_filter! {
+        #[doc = "Filter by project ID."]
+        set_project_id, with_project_id -> project_id
+    }
+
+    /// Filter by the port whose traffic is mirrored.
+    ///
+    /// # Warning
+    ///
+    /// Due to architectural limitations, names do not work here.
+    pub fn set_source_port<P: Into<PortRef>>(&mut self, value: P) {
+        self.query.push_str("source_port", value.into());
+    }
+
+    /// Filter by the port whose traffic is mirrored.
+    ///
+    /// # Warning
+    ///
+    /// Due to architectural limitations, names do not work here.
+    pub fn with_source_port<P: Into<PortRef>>(mut self, value: P) -> Self {
+        self.set_source_port(value);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by tap service ID."]
+        set_tap_service_id, with_tap_service_id -> tap_service_id
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<TapFlow> {
+        debug!("Fetching tap flows with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<TapFlow>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(mut self) -> Result<TapFlow> {
+        debug!("Fetching one tap flow with {:?}", self.query);
+        if self.can_paginate {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        self.into_iter().one()
+    }
+}
+
+impl NewTapService {
+    /// Start creating a tap service.
+    pub(crate) fn new(session: Rc<Session>, port: PortRef) -> NewTapService {
+        NewTapService {
+            session: session,
+            inner: protocol::TapService {
+                description: None,
+                id: String::new(),
+                name: None,
+                port_id: String::new(),
+                project_id: None,
+                status: None,
+            },
+            port: port,
+        }
+    }
+
+    fn build_request(&self) -> Result<protocol::TapService> {
+        let mut request = self.inner.clone();
+        request.port_id = self.port.clone().into_verified(&self.session)?;
+        Ok(request)
+    }
+
+    /// Request creation of the tap service.
+    pub fn create(self) -> Result<TapService> {
+        let request = self.build_request()?;
+        let inner = self.session.create_tap_service(request)?;
+        Ok(TapService::new(self.session, inner))
+    }
+
+    /// Serialize the request body that `create` would send.
+    ///
+    /// The port referenced by name is resolved to an ID first.
+    pub fn to_request_json(&self) -> Result<String> {
+        let body = protocol::TapServiceRoot {
+            tap_service: self.build_request()?
+        };
+        common::protocol::to_request_json(&body)
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the tap service."]
+        set_description, with_description -> description: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set name of the tap service."]
+        set_name, with_name -> name: optional String
+    }
+}
+
+impl NewTapFlow {
+    /// Start creating a tap flow.
+    pub(crate) fn new(session: Rc<Session>, tap_service: String,
+                      source_port: PortRef,
+                      direction: protocol::TapFlowDirection) -> NewTapFlow {
+        NewTapFlow {
+            session: session,
+            inner: protocol::TapFlow {
+                description: None,
+                direction: direction,
+                id: String::new(),
+                name: None,
+                project_id: None,
+                source_port: String::new(),
+                status: None,
+                tap_service_id: String::new(),
+                vlan_filter: None,
+            },
+            tap_service: tap_service,
+            source_port: source_port,
+        }
+    }
+
+    fn build_request(&self) -> Result<protocol::TapFlow> {
+        let mut request = self.inner.clone();
+        request.source_port = self.source_port.clone()
+            .into_verified(&self.session)?;
+        request.tap_service_id = self.session
+            .get_tap_service(&self.tap_service)?.id;
+        Ok(request)
+    }
+
+    /// Request creation of the tap flow.
+    pub fn create(self) -> Result<TapFlow> {
+        let request = self.build_request()?;
+        let inner = self.session.create_tap_flow(request)?;
+        Ok(TapFlow::new(self.session, inner))
+    }
+
+    /// Serialize the request body that `create` would send.
+    ///
+    /// The tap service and port referenced by name are resolved to IDs first.
+    pub fn to_request_json(&self) -> Result<String> {
+        let body = protocol::TapFlowRoot { tap_flow: self.build_request()? };
+        common::protocol::to_request_json(&body)
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the tap flow."]
+        set_description, with_description -> description: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set name of the tap flow."]
+        set_name, with_name -> name: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Mirror only these VLAN IDs (e.g. `1-5,9`)."]
+        set_vlan_filter, with_vlan_filter -> vlan_filter: optional String
+    }
+}
+
+impl ResourceId for TapService {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for TapService {
+    const DEFAULT_LIMIT: usize = 50;
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<TapService>> {
+        Ok(session.list_tap_services(&query)?.into_iter()
+           .map(|item| TapService::new(session.clone(), item)).collect())
+    }
+}
+
+impl IntoFallibleIterator for TapServiceQuery {
+    type Item = TapService;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<TapService>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<TapService> {
+        self.into_iter()
+    }
+}
+
+impl ResourceId for TapFlow {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for TapFlow {
+    const DEFAULT_LIMIT: usize = 50;
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<TapFlow>> {
+        Ok(session.list_tap_flows(&query)?.into_iter()
+           .map(|item| TapFlow::new(session.clone(), item)).collect())
+    }
+}
+
+impl IntoFallibleIterator for TapFlowQuery {
+    type Item = TapFlow;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<TapFlow>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<TapFlow> {
+        self.into_iter()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use serde_json;
+
+    use super::super::super::Cloud;
+    use super::super::super::utils::test::FakeServer;
+    use super::super::protocol;
+
+    #[test]
+    fn test_tap_flow_deserialization() {
+        let flow: protocol::TapFlow = serde_json::from_str(
+            r#"{"id": "tf1", "name": "", "description": "",
+                "direction": "BOTH", "source_port": "p2",
+                "tap_service_id": "ts1", "status": "ACTIVE",
+                "vlan_filter": "1-5,9"}"#).unwrap();
+        assert_eq!(flow.name, None);
+        assert_eq!(flow.description, None);
+        assert_eq!(flow.direction, protocol::TapFlowDirection::Both);
+        assert_eq!(flow.status, Some(protocol::NetworkStatus::Active));
+        assert_eq!(flow.vlan_filter, Some(String::from("1-5,9")));
+
+        assert!(serde_json::from_str::<protocol::TapFlow>(
+            r#"{"id": "tf1", "direction": "SIDEWAYS", "source_port": "p2",
+                "tap_service_id": "ts1"}"#).is_err());
+    }
+
+    #[test]
+    fn test_tap_flow_serialization() {
+        let flow = protocol::TapFlow {
+            description: None,
+            direction: protocol::TapFlowDirection::Egress,
+            id: String::from("tf1"),
+            name: Some(String::from("flow")),
+            project_id: None,
+            source_port: String::from("p2"),
+            status: Some(protocol::NetworkStatus::Active),
+            tap_service_id: String::from("ts1"),
+            vlan_filter: Some(String::from("10")),
+        };
+        let body = protocol::TapFlowRoot { tap_flow: flow };
+        assert_eq!(serde_json::to_string(&body).unwrap(), concat!(
+            r#"{"tap_flow":{"direction":"OUT","name":"flow","#,
+            r#""source_port":"p2","tap_service_id":"ts1","vlan_filter":"10"}}"#));
+    }
+
+    #[test]
+    fn test_tap_service_roundtrip() {
+        let service: protocol::TapService = serde_json::from_str(
+            r#"{"id": "ts1", "name": "ids", "port_id": "p1",
+                "project_id": "demo", "status": "DOWN"}"#).unwrap();
+        assert_eq!(service.status, Some(protocol::NetworkStatus::Down));
+        let body = protocol::TapServiceRoot { tap_service: service };
+        assert_eq!(serde_json::to_string(&body).unwrap(), concat!(
+            r#"{"tap_service":{"name":"ids","port_id":"p1","#,
+            r#""project_id":"demo"}}"#));
+
+        let update = protocol::TapServiceUpdateRoot {
+            tap_service: protocol::TapServiceUpdate {
+                name: Some(String::from("new")),
+                .. Default::default()
+            }
+        };
+        assert_eq!(serde_json::to_string(&update).unwrap(),
+                   r#"{"tap_service":{"name":"new"}}"#);
+    }
+
+    #[test]
+    fn test_new_tap_flow_request() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", "/ports/p2", 200,
+                       r#"{"port": {"admin_state_up": true, "id": "p2",
+                           "name": "web", "mac_address": "fa:16:3e:00:00:01",
+                           "network_id": "net", "status": "ACTIVE"}}"#);
+        // The tap service is referenced by name
+        server.respond("GET", "/taas/tap_services", 200,
+                       r#"{"tap_services": [{"id": "ts1", "name": "ids",
+                           "port_id": "p1", "status": "ACTIVE"}]}"#);
+        let cloud = Cloud::from(server.session());
+
+        let flow = cloud.new_tap_flow("ids", "p2",
+                                      protocol::TapFlowDirection::Ingress)
+            .with_vlan_filter("10-20");
+        let expected = r#"{
+  "tap_flow": {
+    "direction": "IN",
+    "source_port": "p2",
+    "tap_service_id": "ts1",
+    "vlan_filter": "10-20"
+  }
+}"#;
+        assert_eq!(flow.to_request_json().unwrap(), expected);
+        assert!(server.requests().iter()
+                .any(|req| req.starts_with("GET /taas/tap_services?") &&
+                     req.contains("name=ids")));
+    }
+}
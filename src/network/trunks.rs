@@ -0,0 +1,572 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Trunk ports (VLAN-aware instances) via Network API.
+//!
+//! Requires the `trunk` extension. Without it all calls fail with
+//! `ResourceNotFound`.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::Duration;
+
+use fallible_iterator::{IntoFallibleIterator, FallibleIterator};
+use serde::Serialize;
+
+use super::super::{Error, Result};
+use super::super::common::{self, DeletionWaiter, ListResources, PortRef,
+                           Refresh, ResourceId, ResourceIterator};
+use super::super::session::Session;
+use super::super::utils::Query;
+use super::base::V2API;
+use super::{protocol, Port};
+
+
+/// A query to trunk list.
+#[derive(Clone, Debug)]
+pub struct TrunkQuery {
+    session: Rc<Session>,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// Structure representing a trunk - a parent port carrying tagged subports.
+#[derive(Clone, Debug)]
+pub struct Trunk {
+    session: Rc<Session>,
+    inner: protocol::Trunk,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a trunk.
+#[derive(Clone, Debug)]
+pub struct NewTrunk {
+    session: Rc<Session>,
+    inner: protocol::Trunk,
+    port: PortRef,
+}
+
+impl Trunk {
+    /// Create a trunk object.
+    fn new(session: Rc<Session>, inner: protocol::Trunk) -> Trunk {
+        Trunk {
+            session: session,
+            inner: inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load a Trunk object.
+    pub(crate) fn load<Id: AsRef<str>>(session: Rc<Session>, id: Id)
+            -> Result<Trunk> {
+        let inner = session.get_trunk(id)?;
+        Ok(Trunk::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "The administrative state of the trunk."]
+        admin_state_up: bool
+    }
+
+    update_field! {
+        #[doc = "Update the administrative state."]
+        set_admin_state_up, with_admin_state_up -> admin_state_up: bool
+    }
+
+    transparent_property! {
+        #[doc = "Trunk description."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Trunk name."]
+        name: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the name."]
+        set_name, with_name -> name: optional String
+    }
+
+    /// Get the parent port of the trunk.
+    pub fn port(&self) -> Result<Port> {
+        Port::load(self.session.clone(), &self.inner.port_id)
+    }
+
+    transparent_property! {
+        #[doc = "ID of the parent port, which carries untagged traffic."]
+        port_id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the trunk."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Trunk status (if known)."]
+        status: Option<protocol::TrunkStatus>
+    }
+
+    transparent_property! {
+        #[doc = "Subports of the trunk."]
+        sub_ports: ref Vec<protocol::SubPort>
+    }
+
+    /// Add subports to the trunk.
+    ///
+    /// The change is applied immediately.
+    pub fn add_subports<I>(&mut self, sub_ports: I) -> Result<()>
+            where I: IntoIterator<Item = protocol::SubPort> {
+        let sub_ports = sub_ports.into_iter().collect();
+        self.inner = self.session.add_trunk_subports(&self.inner.id, sub_ports)?;
+        Ok(())
+    }
+
+    /// Add a port as a VLAN subport with the given VLAN ID.
+    ///
+    /// The change is applied immediately.
+    pub fn add_vlan_subport<P: Into<PortRef>>(&mut self, port: P, vlan_id: u32)
+            -> Result<()> {
+        let port_id = port.into().into_verified(&self.session)?;
+        self.add_subports(vec![protocol::SubPort::new_vlan(port_id, vlan_id)])
+    }
+
+    /// Remove subports from the trunk.
+    ///
+    /// The change is applied immediately.
+    pub fn remove_subports<I, P>(&mut self, ports: I) -> Result<()>
+            where I: IntoIterator<Item = P>, P: Into<PortRef> {
+        let mut port_ids = Vec::new();
+        for port in ports {
+            port_ids.push(port.into().into_verified(&self.session)?);
+        }
+        self.inner = self.session.remove_trunk_subports(&self.inner.id,
+                                                        port_ids)?;
+        Ok(())
+    }
+
+    /// Delete the trunk.
+    ///
+    /// The subports have to be removed first.
+    pub fn delete(self) -> Result<DeletionWaiter<Trunk>> {
+        self.session.delete_trunk(&self.inner.id)?;
+        Ok(DeletionWaiter::new(self, Duration::new(60, 0), Duration::new(1, 0)))
+    }
+
+    /// Whether the trunk is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the trunk.
+    pub fn save(&mut self) -> Result<()> {
+        let mut update = protocol::TrunkUpdate::default();
+        save_fields! {
+            self -> update: admin_state_up
+        };
+        save_option_fields! {
+            self -> update: description name
+        };
+        let inner = self.session.update_trunk(self.id(), update)?;
+        self.dirty.clear();
+        self.inner = inner;
+        Ok(())
+    }
+}
+
+impl Refresh for Trunk {
+    /// Refresh the trunk.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_trunk_by_id(&self.inner.id)?;
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+impl TrunkQuery {
+    pub(crate) fn new(session: Rc<Session>) -> TrunkQuery {
+        TrunkQuery {
+            session: session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            max_items: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to fetch per request.
+    ///
+    /// Unlike `with_limit`, this keeps automatic pagination.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stop after receiving this number of items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by administrative state."]
+        set_admin_state_up, with_admin_state_up -> admin_state_up: bool
+    }
+
+    query_filter! {
+        #[doc = "Filter by description."]
+        set_description, with_description -> description
+    }
+
+    query_filter! {
+        #[doc = "Filter by trunk name."]
+        set_name, with_name -> name
+    }
+
+    /// Filter by the parent port.
+    ///
+    /// # Warning
+    ///
+    /// Due to architectural limitations, names do not work here.
+    pub fn set_port<P: Into<PortRef>>(&mut self, value: P) {
+        self.query.push_str("port_id", value.into());
+    }
+
+    /// Filter by the parent port.
+    ///
+    /// # Warning
+    ///
+    /// Due to architectural limitations, names do not work here.
+    pub fn with_port<P: Into<PortRef>>(mut self, value: P) -> Self {
+        self.set_port(value);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by project ID."]
+        set_project_id, with_project_id -> project_id
+    }
+
+    query_filter! {
+        #[doc = "Filter by trunk status."]
+        set_status, with_status -> status: protocol::TrunkStatus
+    }
+
+    /// Convert this query into an iterator executing the request.
+    ///
+    /// Returns a `FallibleIterator`, which is an iterator with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_iter(self) -> ResourceIterator<Trunk> {
+        debug!("Fetching trunks with {:?}", self.query);
+        ResourceIterator::new(self.session, self.query)
+            .with_limits(self.page_size, self.max_items)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub fn all(self) -> Result<Vec<Trunk>> {
+        self.into_iter().collect()
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub fn one(mut self) -> Result<Trunk> {
+        debug!("Fetching one trunk with {:?}", self.query);
+        if self.can_paginate {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        self.into_iter().one()
+    }
+}
+
+impl NewTrunk {
+    /// Start creating a trunk.
+    pub(crate) fn new(session: Rc<Session>, port: PortRef) -> NewTrunk {
+        NewTrunk {
+            session: session,
+            inner: protocol::Trunk {
+                admin_state_up: true,
+                description: None,
+                id: String::new(),
+                name: None,
+                port_id: String::new(),
+                project_id: None,
+                status: None,
+                sub_ports: Vec::new(),
+            },
+            port: port,
+        }
+    }
+
+    fn build_request(&self) -> Result<protocol::Trunk> {
+        let mut request = self.inner.clone();
+        request.port_id = self.port.clone().into_verified(&self.session)?;
+        Ok(request)
+    }
+
+    /// Request creation of the trunk.
+    pub fn create(self) -> Result<Trunk> {
+        let request = self.build_request()?;
+        let inner = self.session.create_trunk(request)?;
+        Ok(Trunk::new(self.session, inner))
+    }
+
+    /// Serialize the request body that `create` would send.
+    ///
+    /// The parent port referenced by name is resolved to an ID first.
+    pub fn to_request_json(&self) -> Result<String> {
+        let body = protocol::TrunkRoot {
+            trunk: self.build_request()?
+        };
+        common::protocol::to_request_json(&body)
+    }
+
+    /// Add a subport to the trunk.
+    pub fn add_sub_port(&mut self, sub_port: protocol::SubPort) {
+        self.inner.sub_ports.push(sub_port);
+    }
+
+    /// Add a subport to the trunk.
+    pub fn with_sub_port(mut self, sub_port: protocol::SubPort) -> Self {
+        self.add_sub_port(sub_port);
+        self
+    }
+
+    creation_inner_field! {
+        #[doc = "Set administrative status for the trunk."]
+        set_admin_state_up, with_admin_state_up -> admin_state_up: bool
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the trunk."]
+        set_description, with_description -> description: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set name of the trunk."]
+        set_name, with_name -> name: optional String
+    }
+}
+
+impl ResourceId for Trunk {
+    fn resource_id(&self) -> String {
+        self.id().clone()
+    }
+}
+
+impl ListResources for Trunk {
+    const DEFAULT_LIMIT: usize = 50;
+
+    fn list_resources<Q: Serialize + Debug>(session: Rc<Session>, query: Q)
+            -> Result<Vec<Trunk>> {
+        Ok(session.list_trunks(&query)?.into_iter()
+           .map(|item| Trunk::new(session.clone(), item)).collect())
+    }
+}
+
+impl IntoFallibleIterator for TrunkQuery {
+    type Item = Trunk;
+
+    type Error = Error;
+
+    type IntoIter = ResourceIterator<Trunk>;
+
+    fn into_fallible_iterator(self) -> ResourceIterator<Trunk> {
+        self.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json;
+
+    use super::super::super::Cloud;
+    use super::super::super::common::ResourceStatus;
+    use super::super::super::utils::test::FakeServer;
+    use super::super::protocol;
+
+    #[test]
+    fn test_trunk_deserialization() {
+        let trunk: protocol::Trunk = serde_json::from_str(
+            r#"{"admin_state_up": true, "description": "", "id": "t1",
+                "name": "trunk", "port_id": "p1", "status": "ACTIVE",
+                "sub_ports": [{"port_id": "p2", "segmentation_id": 100,
+                               "segmentation_type": "vlan"},
+                              {"port_id": "p3",
+                               "segmentation_type": "inherit"}]}"#).unwrap();
+        assert_eq!(trunk.description, None);
+        assert_eq!(trunk.name, Some(String::from("trunk")));
+        assert_eq!(trunk.status, Some(protocol::TrunkStatus::Active));
+        assert_eq!(trunk.sub_ports.len(), 2);
+        assert_eq!(trunk.sub_ports[0].segmentation_id, Some(100));
+        assert_eq!(trunk.sub_ports[0].segmentation_type,
+                   protocol::SegmentationType::Vlan);
+        assert_eq!(trunk.sub_ports[1].segmentation_id, None);
+        assert_eq!(trunk.sub_ports[1].segmentation_type,
+                   protocol::SegmentationType::Inherit);
+
+        assert!(serde_json::from_str::<protocol::Trunk>(
+            r#"{"admin_state_up": true, "id": "t1", "port_id": "p1",
+                "status": "EXPLODED"}"#).is_err());
+    }
+
+    #[test]
+    fn test_trunk_serialization() {
+        let body = protocol::TrunkRoot {
+            trunk: protocol::Trunk {
+                admin_state_up: false,
+                description: None,
+                id: String::from("t1"),
+                name: Some(String::from("trunk")),
+                port_id: String::from("p1"),
+                project_id: None,
+                status: Some(protocol::TrunkStatus::Building),
+                sub_ports: vec![protocol::SubPort::new_vlan("p2", 42),
+                                protocol::SubPort::new_inherit("p3")],
+            }
+        };
+        assert_eq!(serde_json::to_string(&body).unwrap(), concat!(
+            r#"{"trunk":{"admin_state_up":false,"name":"trunk","#,
+            r#""port_id":"p1","sub_ports":["#,
+            r#"{"port_id":"p2","segmentation_id":42,"segmentation_type":"vlan"},"#,
+            r#"{"port_id":"p3","segmentation_type":"inherit"}]}}"#));
+
+        let update = protocol::TrunkUpdateRoot {
+            trunk: protocol::TrunkUpdate {
+                admin_state_up: Some(true),
+                .. Default::default()
+            }
+        };
+        assert_eq!(serde_json::to_string(&update).unwrap(),
+                   r#"{"trunk":{"admin_state_up":true}}"#);
+    }
+
+    #[test]
+    fn test_trunk_status() {
+        assert!(protocol::TrunkStatus::Building.is_transitional());
+        assert!(!protocol::TrunkStatus::Building.is_terminal());
+        assert!(protocol::TrunkStatus::Degraded.is_error());
+        assert!(protocol::TrunkStatus::Error.is_error());
+        assert!(!protocol::TrunkStatus::Active.is_error());
+        assert!(protocol::TrunkStatus::Active.is_terminal());
+        assert!(protocol::TrunkStatus::Down.is_terminal());
+    }
+
+    #[test]
+    fn test_new_trunk_request() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", "/ports/p1", 200,
+                       r#"{"port": {"admin_state_up": true, "id": "p1",
+                           "name": "parent", "mac_address": "fa:16:3e:00:00:01",
+                           "network_id": "net", "status": "ACTIVE"}}"#);
+        let cloud = Cloud::from(server.session());
+
+        let trunk = cloud.new_trunk("p1").with_name("trunk")
+            .with_sub_port(protocol::SubPort::new_vlan("p2", 100));
+        let expected = r#"{
+  "trunk": {
+    "admin_state_up": true,
+    "name": "trunk",
+    "port_id": "p1",
+    "sub_ports": [
+      {
+        "port_id": "p2",
+        "segmentation_id": 100,
+        "segmentation_type": "vlan"
+      }
+    ]
+  }
+}"#;
+        assert_eq!(trunk.to_request_json().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_trunk_subports() {
+        let server = FakeServer::with_version("v2.0", "");
+        server.respond("GET", "/trunks/t1", 200,
+                       r#"{"trunk": {"admin_state_up": true, "id": "t1",
+                           "port_id": "p1", "status": "ACTIVE",
+                           "sub_ports": []}}"#);
+        server.respond("GET", "/ports/p2", 200,
+                       r#"{"port": {"admin_state_up": true, "id": "p2",
+                           "mac_address": "fa:16:3e:00:00:02",
+                           "network_id": "net", "status": "ACTIVE"}}"#);
+        server.respond("PUT", "/trunks/t1/add_subports", 200,
+                       r#"{"admin_state_up": true, "id": "t1",
+                           "port_id": "p1", "status": "ACTIVE",
+                           "sub_ports": [{"port_id": "p2",
+                                          "segmentation_id": 100,
+                                          "segmentation_type": "vlan"}]}"#);
+        server.respond("PUT", "/trunks/t1/remove_subports", 200,
+                       r#"{"admin_state_up": true, "id": "t1",
+                           "port_id": "p1", "status": "ACTIVE",
+                           "sub_ports": []}"#);
+        let cloud = Cloud::from(server.session());
+
+        let mut trunk = cloud.get_trunk("t1").unwrap();
+        assert!(trunk.sub_ports().is_empty());
+
+        trunk.add_vlan_subport("p2", 100).unwrap();
+        assert_eq!(trunk.sub_ports().len(), 1);
+        assert_eq!(trunk.sub_ports()[0].port_id, "p2");
+
+        trunk.remove_subports(vec!["p2"]).unwrap();
+        assert!(trunk.sub_ports().is_empty());
+
+        let requests = server.requests();
+        assert!(requests.contains(&String::from("PUT /trunks/t1/add_subports")));
+        assert!(requests.contains(
+            &String::from("PUT /trunks/t1/remove_subports")));
+    }
+}
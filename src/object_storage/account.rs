@@ -0,0 +1,102 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Account management via Object Storage API.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::super::Result;
+use super::super::common::Refresh;
+use super::super::session::Session;
+use super::base::V1API;
+use super::protocol;
+
+
+/// Structure representing the current object storage account.
+#[derive(Clone, Debug)]
+pub struct Account {
+    session: Rc<Session>,
+    inner: protocol::Account
+}
+
+impl Account {
+    /// Load the current Account object.
+    pub(crate) fn load(session: Rc<Session>) -> Result<Account> {
+        let inner = session.get_account()?;
+        Ok(Account {
+            session: session,
+            inner: inner
+        })
+    }
+
+    transparent_property! {
+        #[doc = "Total number of bytes stored in the account."]
+        bytes_used: u64
+    }
+
+    transparent_property! {
+        #[doc = "Number of containers in the account."]
+        container_count: u64
+    }
+
+    transparent_property! {
+        #[doc = "Account metadata (keys are lower case)."]
+        metadata: ref HashMap<String, String>
+    }
+
+    transparent_property! {
+        #[doc = "Number of objects in the account."]
+        object_count: u64
+    }
+
+    /// Maximum number of bytes allowed in the account (if set).
+    pub fn quota_bytes(&self) -> Option<u64> {
+        self.inner.metadata.get("quota-bytes").and_then(|x| x.parse().ok())
+    }
+
+    /// Maximum number of objects allowed in the account (if set).
+    pub fn quota_count(&self) -> Option<u64> {
+        self.inner.metadata.get("quota-count").and_then(|x| x.parse().ok())
+    }
+
+    /// Set an account metadata item.
+    pub fn set_metadata<K, V>(&mut self, key: K, value: V) -> Result<()>
+            where K: Into<String>, V: Into<String> {
+        let mut items = HashMap::new();
+        let _ = items.insert(key.into(), value.into());
+        self.update_metadata(items, Vec::new())
+    }
+
+    /// Remove an account metadata item.
+    pub fn remove_metadata<K: Into<String>>(&mut self, key: K) -> Result<()> {
+        self.update_metadata(HashMap::new(), vec![key.into()])
+    }
+
+    /// Set and remove several account metadata items at once.
+    pub fn update_metadata(&mut self, set: HashMap<String, String>,
+                           remove: Vec<String>) -> Result<()> {
+        self.session.update_account_metadata(set, remove)?;
+        self.refresh()
+    }
+}
+
+impl Refresh for Account {
+    /// Refresh the account information.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_account()?;
+        Ok(())
+    }
+}
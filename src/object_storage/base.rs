@@ -0,0 +1,150 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Foundation bits exposing the Object Storage API.
+
+use std::collections::HashMap;
+
+use reqwest::{Method, Url};
+use reqwest::header::{ContentLength, Headers};
+
+use super::super::Result;
+use super::super::auth::AuthMethod;
+use super::super::session::{Session, ServiceInfo, ServiceType};
+use super::protocol;
+
+
+/// Extensions for Session.
+pub trait V1API {
+    /// Copy an object on the server side.
+    fn copy_object(&self, container: &str, name: &str,
+                   dest_container: &str, dest_name: &str) -> Result<()>;
+
+    /// Get information about the current account.
+    fn get_account(&self) -> Result<protocol::Account>;
+
+    /// Get information about an object.
+    fn get_object(&self, container: &str, name: &str)
+        -> Result<protocol::Object>;
+
+    /// Set and remove account metadata items.
+    fn update_account_metadata(&self, set: HashMap<String, String>,
+                               remove: Vec<String>) -> Result<()>;
+
+    /// Replace object metadata.
+    ///
+    /// Items that are not provided are removed.
+    fn update_object_metadata(&self, container: &str, name: &str,
+                              metadata: HashMap<String, String>) -> Result<()>;
+}
+
+
+/// Service type of Object Storage API V1.
+#[derive(Copy, Clone, Debug)]
+pub struct V1;
+
+
+const SERVICE_TYPE: &'static str = "object-store";
+
+
+impl V1API for Session {
+    fn copy_object(&self, container: &str, name: &str,
+                   dest_container: &str, dest_name: &str) -> Result<()> {
+        debug!("Copying object {}/{} to {}/{}", container, name,
+               dest_container, dest_name);
+        let mut headers = Headers::new();
+        headers.set_raw("X-Copy-From",
+                        protocol::quote_object_path(container, name));
+        headers.set(ContentLength(0));
+        let _ = self.request::<V1>(Method::Put, &[dest_container, dest_name],
+                                   None)?
+            .headers(headers).send()?;
+        debug!("Object {}/{} was copied to {}/{}", container, name,
+               dest_container, dest_name);
+        Ok(())
+    }
+
+    fn get_account(&self) -> Result<protocol::Account> {
+        trace!("Fetching object storage account information");
+        let resp = self.request::<V1>(Method::Head, &[], None)?.send()?;
+        let account = protocol::Account::from_headers(resp.headers())?;
+        trace!("Received {:?}", account);
+        Ok(account)
+    }
+
+    fn get_object(&self, container: &str, name: &str)
+            -> Result<protocol::Object> {
+        trace!("Fetching object {}/{}", container, name);
+        let resp = self.request::<V1>(Method::Head, &[container, name], None)?
+            .send()?;
+        let object = protocol::Object::from_headers(String::from(container),
+                                                    String::from(name),
+                                                    resp.headers())?;
+        trace!("Received {:?}", object);
+        Ok(object)
+    }
+
+    fn update_account_metadata(&self, set: HashMap<String, String>,
+                               remove: Vec<String>) -> Result<()> {
+        debug!("Updating account metadata: setting {:?}, removing {:?}",
+               set, remove);
+        let mut headers = Headers::new();
+        for (key, value) in set {
+            headers.set_raw(format!("{}{}", protocol::ACCOUNT_META_PREFIX, key),
+                            value);
+        }
+        for key in remove {
+            headers.set_raw(format!("{}{}", protocol::REMOVE_ACCOUNT_META_PREFIX,
+                                    key),
+                            "x");
+        }
+        let _ = self.request::<V1>(Method::Post, &[], None)?
+            .headers(headers).send()?;
+        debug!("Account metadata was updated");
+        Ok(())
+    }
+
+    fn update_object_metadata(&self, container: &str, name: &str,
+                              metadata: HashMap<String, String>) -> Result<()> {
+        debug!("Setting metadata of object {}/{} to {:?}", container, name,
+               metadata);
+        let mut headers = Headers::new();
+        for (key, value) in metadata {
+            headers.set_raw(format!("{}{}", protocol::OBJECT_META_PREFIX, key),
+                            value);
+        }
+        let _ = self.request::<V1>(Method::Post, &[container, name], None)?
+            .headers(headers).send()?;
+        debug!("Metadata of object {}/{} was updated", container, name);
+        Ok(())
+    }
+}
+
+
+impl ServiceType for V1 {
+    fn catalog_type() -> &'static str {
+        SERVICE_TYPE
+    }
+
+    fn service_info(endpoint: Url, _auth: &AuthMethod) -> Result<ServiceInfo> {
+        // The catalog points directly at the account, there is no version
+        // discovery in the Object Storage API.
+        Ok(ServiceInfo {
+            root_url: endpoint,
+            current_version: None,
+            minimum_version: None
+        })
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+
+//! Object management via Object Storage API.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::super::Result;
+use super::super::common::Refresh;
+use super::super::session::Session;
+use super::base::V1API;
+use super::protocol;
+
+
+/// Structure representing an object in a container.
+#[derive(Clone, Debug)]
+pub struct Object {
+    session: Rc<Session>,
+    inner: protocol::Object
+}
+
+impl Object {
+    /// Load an Object.
+    pub(crate) fn load<C, N>(session: Rc<Session>, container: C, name: N)
+            -> Result<Object> where C: AsRef<str>, N: AsRef<str> {
+        let inner = session.get_object(container.as_ref(), name.as_ref())?;
+        Ok(Object {
+            session: session,
+            inner: inner
+        })
+    }
+
+    transparent_property! {
+        #[doc = "Name of the container the object is in."]
+        container: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Object size in bytes."]
+        content_length: u64
+    }
+
+    transparent_property! {
+        #[doc = "Content type of the object (if known)."]
+        content_type: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ETag (MD5 checksum of the content for regular objects)."]
+        etag: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Object metadata (keys are lower case)."]
+        metadata: ref HashMap<String, String>
+    }
+
+    transparent_property! {
+        #[doc = "Object name."]
+        name: ref String
+    }
+
+    /// Copy the object on the server side.
+    ///
+    /// The content is not downloaded. The metadata is copied as well.
+    /// Returns the new object.
+    pub fn copy_to<C, N>(&self, container: C, name: N) -> Result<Object>
+            where C: AsRef<str>, N: AsRef<str> {
+        self.session.copy_object(&self.inner.container, &self.inner.name,
+                                 container.as_ref(), name.as_ref())?;
+        Object::load(self.session.clone(), container, name)
+    }
+
+    /// Set an object metadata item.
+    pub fn set_metadata<K, V>(&mut self, key: K, value: V) -> Result<()>
+            where K: Into<String>, V: Into<String> {
+        let mut items = HashMap::new();
+        let _ = items.insert(key.into(), value.into());
+        self.update_metadata(items, Vec::new())
+    }
+
+    /// Remove an object metadata item.
+    pub fn remove_metadata<K: Into<String>>(&mut self, key: K) -> Result<()> {
+        self.update_metadata(HashMap::new(), vec![key.into()])
+    }
+
+    /// Set and remove several object metadata items at once.
+    ///
+    /// The content is not uploaded again. Object Storage replaces all
+    /// metadata on update, so the items that are not changed are sent
+    /// as well; call `refresh` first if the metadata may be outdated.
+    pub fn update_metadata(&mut self, set: HashMap<String, String>,
+                           remove: Vec<String>) -> Result<()> {
+        let mut metadata = self.inner.metadata.clone();
+        for key in remove {
+            let _ = metadata.remove(&key.to_lowercase());
+        }
+        for (key, value) in set {
+            let _ = metadata.insert(key.to_lowercase(), value);
+        }
+        self.session.update_object_metadata(&self.inner.container,
+                                            &self.inner.name, metadata)?;
+        self.refresh()
+    }
+}
+
+impl Refresh for Object {
+    /// Refresh the object information.
+    fn refresh(&mut self) -> Result<()> {
+        self.inner = self.session.get_object(&self.inner.container,
+                                             &self.inner.name)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Header-based protocol bits for the Object Storage API.
+
+#![allow(missing_docs)]
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use reqwest::header::Headers;
+
+use super::super::{Error, ErrorKind, Result};
+
+
+pub const ACCOUNT_META_PREFIX: &'static str = "x-account-meta-";
+pub const REMOVE_ACCOUNT_META_PREFIX: &'static str = "x-remove-account-meta-";
+pub const OBJECT_META_PREFIX: &'static str = "x-object-meta-";
+
+/// Account information.
+#[derive(Clone, Debug)]
+pub struct Account {
+    pub bytes_used: u64,
+    pub container_count: u64,
+    pub metadata: HashMap<String, String>,
+    pub object_count: u64,
+}
+
+/// Object information.
+#[derive(Clone, Debug)]
+pub struct Object {
+    pub container: String,
+    pub content_length: u64,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub metadata: HashMap<String, String>,
+    pub name: String,
+}
+
+fn parse_header<T: FromStr>(headers: &Headers, name: &str) -> Option<T> {
+    headers.get_raw(name).and_then(|raw| raw.one())
+        .and_then(|value| ::std::str::from_utf8(value).ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+fn require_header<T: FromStr>(headers: &Headers, name: &str) -> Result<T> {
+    parse_header(headers, name).ok_or_else(|| {
+        Error::new(ErrorKind::InvalidResponse,
+                   format!("Missing or invalid {} header", name))
+    })
+}
+
+fn parse_metadata(headers: &Headers, prefix: &str) -> HashMap<String, String> {
+    headers.iter().filter_map(|view| {
+        let name = view.name().to_lowercase();
+        if name.starts_with(prefix) {
+            Some((String::from(&name[prefix.len()..]), view.value_string()))
+        } else {
+            None
+        }
+    }).collect()
+}
+
+/// Quote a container and an object name for use in headers like X-Copy-From.
+pub fn quote_object_path(container: &str, name: &str) -> String {
+    let mut result = String::from("/");
+    for byte in container.bytes().chain(Some(b'/')).chain(name.bytes()) {
+        match byte {
+            b'A' ... b'Z' | b'a' ... b'z' | b'0' ... b'9' |
+            b'-' | b'.' | b'_' | b'~' | b'/' => result.push(byte as char),
+            _ => result.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    result
+}
+
+impl Account {
+    pub fn from_headers(headers: &Headers) -> Result<Account> {
+        Ok(Account {
+            bytes_used: require_header(headers, "X-Account-Bytes-Used")?,
+            container_count: require_header(headers,
+                                            "X-Account-Container-Count")?,
+            metadata: parse_metadata(headers, ACCOUNT_META_PREFIX),
+            object_count: require_header(headers, "X-Account-Object-Count")?,
+        })
+    }
+}
+
+impl Object {
+    pub fn from_headers(container: String, name: String, headers: &Headers)
+            -> Result<Object> {
+        Ok(Object {
+            container: container,
+            content_length: require_header(headers, "Content-Length")?,
+            content_type: parse_header(headers, "Content-Type"),
+            etag: parse_header::<String>(headers, "Etag")
+                .map(|etag| String::from(etag.trim_matches('"'))),
+            metadata: parse_metadata(headers, OBJECT_META_PREFIX),
+            name: name,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use reqwest::header::Headers;
+
+    use super::{Object, quote_object_path};
+
+    #[test]
+    fn test_quote_object_path() {
+        assert_eq!(quote_object_path("backups", "db/2018-05-01.tar.gz"),
+                   "/backups/db/2018-05-01.tar.gz");
+        assert_eq!(quote_object_path("my files", "50% off?.txt"),
+                   "/my%20files/50%25%20off%3F.txt");
+    }
+
+    #[test]
+    fn test_object_from_headers() {
+        let mut headers = Headers::new();
+        headers.set_raw("Content-Length", "512");
+        headers.set_raw("Content-Type", "application/gzip");
+        headers.set_raw("Etag", "\"d41d8cd98f00b204e9800998ecf8427e\"");
+        headers.set_raw("X-Object-Meta-Retention", "30d");
+        headers.set_raw("X-Timestamp", "1525132800.00000");
+        let object = Object::from_headers(String::from("backups"),
+                                          String::from("db.tar.gz"),
+                                          &headers).unwrap();
+        assert_eq!(object.content_length, 512);
+        assert_eq!(object.content_type.unwrap(), "application/gzip");
+        assert_eq!(object.etag.unwrap(), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(object.metadata.len(), 1);
+        assert_eq!(object.metadata.get("retention").unwrap(), "30d");
+    }
+}
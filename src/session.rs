@@ -15,17 +15,31 @@
 //! Session structure definition.
 
 use std::cell::Ref;
+use std::cmp;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::str;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use log;
-use reqwest::{Body, Method, RequestBuilder as ReqwestRB, Response, Url};
+use reqwest::{Body, Method, RequestBuilder as ReqwestRB, Response, StatusCode,
+              Url};
 use reqwest::header::{Header, Headers};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
+use serde_json::{self, Value};
 
-use super::Result;
+use super::{Error, ErrorKind, Result};
 use super::auth::AuthMethod;
-use super::common::ApiVersion;
-use super::utils;
+use super::common::{self, ApiVersion, ApiVersionRequest};
+use super::{telemetry, utils};
+
+const REQUEST_ID_HEADER: &'static str = "X-Openstack-Request-Id";
+
+/// How long (in seconds) names resolved into IDs are cached.
+const RESOLVED_ID_TTL: u64 = 300;
 
 /// Information about API endpoint.
 #[derive(Clone, Debug)]
@@ -38,6 +52,21 @@ pub struct ServiceInfo {
     pub minimum_version: Option<ApiVersion>
 }
 
+/// A major API version reported by a service.
+#[derive(Clone, Debug)]
+pub struct ServiceVersion {
+    /// Version ID, e.g. `v2.1`.
+    pub id: String,
+    /// Version status, e.g. `CURRENT`, `SUPPORTED` or `DEPRECATED`.
+    pub status: String,
+    /// Root endpoint of the version (if reported).
+    pub root_url: Option<Url>,
+    /// Maximum supported API version (if microversions are supported).
+    pub current_version: Option<ApiVersion>,
+    /// Minimum supported API version (if microversions are supported).
+    pub minimum_version: Option<ApiVersion>
+}
+
 /// Trait representing a service type.
 pub trait ServiceType {
     /// Service type to pass to the catalog.
@@ -48,24 +77,315 @@ pub trait ServiceType {
 
     /// Return headers to set for this API version.
     fn api_version_headers(_version: ApiVersion) -> Option<Headers> { None }
+
+    /// The highest API version that can be requested by default.
+    ///
+    /// Higher versions may change responses in ways this crate does not
+    /// understand. `None` means no limit.
+    fn max_api_version() -> Option<ApiVersion> { None }
+}
+
+/// Policy for retrying requests that were rate-limited or hit an unavailable
+/// service (HTTP 429 and 503).
+///
+/// The delay between attempts doubles starting with the initial delay, but
+/// never exceeds the maximum delay. A `Retry-After` header in seconds takes
+/// precedence over the computed delay (still capped by the maximum).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Create a policy with the given maximum number of attempts.
+    ///
+    /// The number includes the first attempt, so `1` disables retries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of attempts is zero.
+    pub fn new(max_attempts: u32) -> RetryPolicy {
+        assert!(max_attempts > 0, "Number of attempts must be positive");
+        RetryPolicy {
+            max_attempts: max_attempts,
+            .. RetryPolicy::default()
+        }
+    }
+
+    /// A policy that never retries.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new(1)
+    }
+
+    /// Maximum number of attempts, including the first one.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Delay before the first retry.
+    pub fn initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    /// Set the delay before the first retry.
+    pub fn with_initial_delay(mut self, delay: Duration) -> RetryPolicy {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Maximum delay between attempts.
+    pub fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    /// Set the maximum delay between attempts.
+    pub fn with_max_delay(mut self, delay: Duration) -> RetryPolicy {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Delay before the given retry (starting with 1).
+    fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        let delay = retry_after.unwrap_or_else(|| {
+            let factor = 1u32.checked_shl(retry - 1).unwrap_or(u32::max_value());
+            self.initial_delay.checked_mul(factor).unwrap_or(self.max_delay)
+        });
+        cmp::min(delay, self.max_delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts with delays starting with one second, up to 30 seconds.
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Whether the status code means that the request can be retried later.
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TooManyRequests ||
+        status == StatusCode::ServiceUnavailable
+}
+
+/// Parse the Retry-After header, if it contains a number of seconds.
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let raw = resp.headers().get_raw("Retry-After")?.one()?;
+    str::from_utf8(raw).ok()?.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Whether the body of an HTTP 503 response announces a maintenance.
+fn is_maintenance(body: &str) -> bool {
+    body.to_lowercase().contains("maintenance")
+}
+
+/// Extract the fault message from the body of an error response.
+///
+/// Understands the common formats, e.g. `{"message": "..."}`,
+/// `{"itemNotFound": {"message": "...", "code": 404}}` and
+/// `{"error_message": "{\"faultstring\": \"...\"}"}`.
+fn fault_message(body: &str) -> Option<String> {
+    serde_json::from_str::<Value>(body).ok().and_then(|value| find_fault(&value))
+}
+
+fn find_fault(value: &Value) -> Option<String> {
+    let object = value.as_object()?;
+    for key in &["message", "faultstring", "description", "detail", "title"] {
+        if let Some(&Value::String(ref message)) = object.get(*key) {
+            if ! message.is_empty() {
+                return Some(message.clone());
+            }
+        }
+    }
+
+    if let Some(&Value::String(ref inner)) = object.get("error_message") {
+        return fault_message(inner).or_else(|| Some(inner.clone()));
+    }
+
+    object.values().filter_map(|nested| match *nested {
+        Value::Array(ref items) => items.first().and_then(find_fault),
+        ref other => find_fault(other)
+    }).next()
+}
+
+/// Error kind of an error response, taking known fault types into account.
+///
+/// Quota faults are reported by Nova with HTTP 403 and by Neutron with
+/// HTTP 409 and the `OverQuota` type.
+fn error_kind(status: StatusCode, body: &str, fault: Option<&String>)
+        -> ErrorKind {
+    let kind = ErrorKind::from_status(status);
+    if kind != ErrorKind::AccessDenied && kind != ErrorKind::Conflict {
+        return kind;
+    }
+
+    let is_over_quota = serde_json::from_str::<Value>(body).ok()
+        .and_then(|value| value.as_object().map(|object| {
+            object.contains_key("overLimit") || object.values().any(|item| {
+                item.get("type").and_then(Value::as_str) == Some("OverQuota")
+            })
+        })).unwrap_or(false);
+    let is_quota_message = fault.map(|message| {
+        message.to_lowercase().starts_with("quota exceeded")
+    }).unwrap_or(false);
+
+    if is_over_quota || is_quota_message {
+        ErrorKind::QuotaExceeded
+    } else {
+        kind
+    }
+}
+
+/// Whether requests with the given method can be safely repeated.
+fn is_idempotent(method: &Method) -> bool {
+    match *method {
+        Method::Get | Method::Head | Method::Options | Method::Put |
+            Method::Delete => true,
+        _ => false
+    }
 }
 
 /// An HTTP request builder.
 ///
 /// This is a thin wrapper around reqwest's RequestBuilder with error handling.
+///
+/// Requests created by a `Session` are retried once with a fresh token if
+/// they fail with HTTP 401. Idempotent requests (GET, HEAD, OPTIONS, PUT
+/// and DELETE) are also retried on HTTP 429 and 503 according to the
+/// session's `RetryPolicy`.
 #[derive(Debug)]
 pub struct RequestBuilder {
     inner: ReqwestRB,
+    replay: Option<Replay>,
+    limiter: Option<utils::Semaphore>,
+    permit: Option<utils::SemaphoreGuard>,
+    retry_policy: RetryPolicy,
+    service: Option<String>,
+    cache: Option<(ResponseCache, Method, Url)>,
+}
+
+/// Cache of JSON responses to GET requests.
+///
+/// Entries expire after the TTL and are invalidated by successful non-GET
+/// requests to the same resource, its sub-resources or parent collections.
+/// The cache is shared between clones, which can be used from different
+/// threads. Identical GET requests made while one is in flight wait for its
+/// response instead of being sent again.
+#[derive(Clone)]
+pub struct ResponseCache {
+    ttl: Duration,
+    state: Arc<(Mutex<CacheState>, Condvar)>,
+}
+
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    in_flight: HashSet<String>,
+    generation: u64,
+    subscribers: Vec<Arc<Fn(&str) + Send + Sync>>,
+}
+
+/// Marks a request as in flight, un-marking it on drop.
+struct InFlight<'c> {
+    cache: &'c ResponseCache,
+    key: String,
+}
+
+struct CacheEntry {
+    created: Instant,
+    url: String,
+    value: Value,
+}
+
+/// Information required to re-create a request after re-authentication.
+#[derive(Clone, Debug)]
+struct Replay {
+    auth: Box<AuthMethod>,
+    method: Method,
+    url: Url,
+    headers: Headers,
+    query: Vec<(String, String)>,
+    json: Option<Value>,
+}
+
+impl Replay {
+    /// Key identifying the request in a `ResponseCache`.
+    ///
+    /// Headers are included, since they may carry an API version.
+    fn cache_key(&self) -> String {
+        let mut url = self.url.clone();
+        if ! self.query.is_empty() {
+            let _ = url.query_pairs_mut().extend_pairs(&self.query);
+        }
+        format!("{} {}", url, self.headers).trim_right().to_string()
+    }
+
+    fn build(self) -> Result<ReqwestRB> {
+        let mut builder = self.auth.request(self.method, self.url)?.into_inner();
+        if ! self.query.is_empty() {
+            let _ = builder.query(&self.query);
+        }
+        if let Some(json) = self.json {
+            let _ = builder.json(&json);
+        }
+        // Explicit headers (e.g. a custom Content-Type) take precedence.
+        let _ = builder.headers(self.headers);
+        Ok(builder)
+    }
+}
+
+fn query_pairs<T: Serialize>(query: &T) -> Option<Vec<(String, String)>> {
+    match serde_json::to_value(query).ok()? {
+        Value::Array(items) => items.into_iter().map(|item| match item {
+            Value::Array(ref pair) if pair.len() == 2 => match (&pair[0], &pair[1]) {
+                (&Value::String(ref key), &Value::String(ref value)) =>
+                    Some((key.clone(), value.clone())),
+                _ => None
+            },
+            _ => None
+        }).collect(),
+        _ => None
+    }
 }
 
 impl RequestBuilder {
     /// Create a RequestBuilder by wrapping a reqwest's one.
     pub fn new(inner: ReqwestRB) -> RequestBuilder {
         RequestBuilder {
-            inner: inner
+            inner: inner,
+            replay: None,
+            limiter: None,
+            permit: None,
+            retry_policy: RetryPolicy::none(),
+            service: None,
+            cache: None
         }
     }
 
+    /// Do not retry this request on HTTP 429 and 503.
+    ///
+    /// Use it for operations that are not safe to repeat.
+    pub fn without_retries(&mut self) -> &mut RequestBuilder {
+        self.retry_policy = RetryPolicy::none();
+        self
+    }
+
+    /// Allow re-sending this request with a fresh token on HTTP 401.
+    fn enable_replay(&mut self, auth: Box<AuthMethod>, method: Method, url: Url) {
+        self.replay = Some(Replay {
+            auth: auth,
+            method: method,
+            url: url,
+            headers: Headers::new(),
+            query: Vec::new(),
+            json: None
+        });
+    }
+
     /// Access to the inner object.
     pub fn inner_mut(&mut self) -> &mut ReqwestRB {
         &mut self.inner
@@ -78,57 +398,421 @@ impl RequestBuilder {
 
     /// Add a Header to this Request.
     pub fn header<H: Header>(&mut self, header: H) -> &mut RequestBuilder {
-        let _ = self.inner.header(header);
-        self
+        let mut headers = Headers::new();
+        headers.set(header);
+        self.headers(headers)
     }
 
     /// Add a set of Headers to the existing ones on this Request.
     pub fn headers(&mut self, headers: Headers) -> &mut RequestBuilder {
+        if let Some(ref mut replay) = self.replay {
+            replay.headers.extend(headers.iter());
+        }
         let _ = self.inner.headers(headers);
         self
     }
 
     /// Set the request body.
+    ///
+    /// Requests with an arbitrary body are not retried on HTTP 401.
     pub fn body<T: Into<Body>>(&mut self, body: T) -> &mut RequestBuilder {
+        self.replay = None;
         let _ = self.inner.body(body);
         self
     }
 
     /// Modify the query string of the URL.
     pub fn query<T: Serialize>(&mut self, query: &T) -> &mut RequestBuilder {
+        let pairs = if self.replay.is_some() { query_pairs(query) } else { None };
+        match pairs {
+            Some(pairs) => self.replay.as_mut().unwrap().query.extend(pairs),
+            None => self.replay = None
+        }
         let _ = self.inner.query(query);
         self
     }
 
     /// Send a JSON body.
     pub fn json<T: Serialize>(&mut self, json: &T) -> &mut RequestBuilder {
+        let value = if self.replay.is_some() {
+            serde_json::to_value(json).ok()
+        } else {
+            None
+        };
+        match value {
+            Some(value) => self.replay.as_mut().unwrap().json = Some(value),
+            None => self.replay = None
+        }
         let _ = self.inner.json(json);
         self
     }
 
     /// Construct the Request and sends it the target URL, returning a Response.
+    ///
+    /// Error responses are converted into an `Error` with the HTTP status,
+    /// the request ID and the fault message from the response body.
+    ///
+    /// HTTP 503 responses result in a `ServiceUnavailable` error, which
+    /// reports whether the service is down for maintenance and when to retry.
+    ///
+    /// If the number of requests to the service is limited, the request
+    /// keeps its slot until this builder is dropped, so read the response
+    /// body before dropping it.
     pub fn send(&mut self) -> Result<Response> {
-        _log(self.inner.send()?).error_for_status().map_err(From::from)
+        let mut resp = self.send_with_retry()?;
+        let status = resp.status();
+        if ! status.is_client_error() && ! status.is_server_error() {
+            if let Some((ref cache, ref method, ref url)) = self.cache {
+                if *method != Method::Get && *method != Method::Head {
+                    cache.invalidate(url.as_str());
+                }
+            }
+            return Ok(resp);
+        }
+
+        let request_id = resp.headers().get_raw(REQUEST_ID_HEADER)
+            .and_then(|h| h.one())
+            .map(|buf| String::from_utf8_lossy(buf).into_owned());
+        let body = resp.text().unwrap_or_default();
+        trace!("HTTP request to {} returned {}; error: {:?}",
+               resp.url(), status, body);
+        let fault = fault_message(&body);
+
+        let err = if status == StatusCode::ServiceUnavailable {
+            let maintenance = is_maintenance(&body);
+            let message = if maintenance {
+                "Service is down for maintenance"
+            } else {
+                "Service is overloaded or down"
+            };
+            Error::new_with_details(ErrorKind::ServiceUnavailable,
+                                    Some(status), Some(String::from(message)))
+                .with_unavailability_details(maintenance, retry_after(&resp))
+        } else {
+            let kind = error_kind(status, &body, fault.as_ref());
+            let message = fault.clone()
+                .unwrap_or_else(|| format!("HTTP {}", status));
+            Error::new_with_details(kind, Some(status), Some(message))
+        };
+
+        Err(err.with_request_details(self.service.clone(), request_id)
+            .with_fault(fault))
+    }
+
+    /// Send the request and return a reader of the response body.
+    ///
+    /// Unlike `send`, the request limit slot is kept until the reader is
+    /// dropped, so the body can be streamed after this builder is gone.
+    #[allow(dead_code)] // unused with --no-default-features
+    pub(crate) fn send_streaming(&mut self)
+            -> Result<utils::GuardedRead<Response>> {
+        let resp = self.send()?;
+        Ok(utils::GuardedRead::new(resp, self.permit.take()))
     }
 
     /// Construct the Request, send it and receive a JSON.
+    ///
+    /// If the session has a `ResponseCache`, GET requests are served from it
+    /// when possible.
     pub fn receive_json<T: DeserializeOwned>(&mut self) -> Result<T> {
-        _log(self.inner.send()?).error_for_status()?.json().map_err(From::from)
+        let key = match (self.cache.as_ref(), self.replay.as_ref()) {
+            (Some(&(ref cache, Method::Get, _)), Some(replay)) =>
+                Some((cache.clone(), replay.cache_key())),
+            _ => None
+        };
+
+        let value = match key {
+            Some((cache, key)) => {
+                let url = self.replay.as_ref().unwrap().url.to_string();
+                cache.get_or_fetch(key, url, || Ok(self.send()?.json()?))?
+            },
+            None => self.send()?.json()?
+        };
+
+        serde_json::from_value(value).map_err(|err| {
+            Error::new(ErrorKind::InvalidResponse,
+                       format!("Cannot parse the response: {}", err))
+        })
+    }
+
+    fn send_with_retry(&mut self) -> Result<Response> {
+        // The permit is released when the builder is dropped, i.e. after
+        // the response body has been consumed by `receive_json`.
+        if self.permit.is_none() {
+            self.permit = self.limiter.as_ref().map(|sem| sem.acquire());
+        }
+        let mut resp = self.send_once()?;
+        if resp.status() == StatusCode::Unauthorized {
+            let mut replay = match self.replay.clone() {
+                Some(replay) => replay,
+                None => return Ok(resp)
+            };
+            debug!("HTTP 401 received from {}, re-authenticating and \
+                    retrying the request", resp.url());
+            if let Some(ref service) = self.service {
+                telemetry::record_retry(service, "unauthorized");
+            }
+            replay.auth.refresh()?;
+            self.inner = replay.build()?;
+            resp = self.send_once()?;
+        }
+
+        let mut retry = 1;
+        while is_transient(resp.status()) &&
+                retry < self.retry_policy.max_attempts {
+            let replay = match self.replay.clone() {
+                Some(replay) => replay,
+                None => break
+            };
+            let delay = self.retry_policy.delay(retry, retry_after(&resp));
+            debug!("HTTP {} received from {}, retrying the request in {:?}",
+                   resp.status(), resp.url(), delay);
+            if let Some(ref service) = self.service {
+                let reason = if resp.status() == StatusCode::TooManyRequests {
+                    "rate_limited"
+                } else {
+                    "unavailable"
+                };
+                telemetry::record_retry(service, reason);
+            }
+            thread::sleep(delay);
+            self.inner = replay.build()?;
+            resp = self.send_once()?;
+            retry += 1;
+        }
+
+        Ok(resp)
+    }
+
+    fn send_once(&mut self) -> Result<Response> {
+        let resp = _log(self.inner.send()?);
+        if let Some(ref service) = self.service {
+            telemetry::record_request(service, resp.status().as_u16());
+        }
+        Ok(resp)
     }
 }
 
-fn _log(mut resp: Response) -> Response {
-    if log_enabled!(log::Level::Trace) {
-        let details = if resp.status().is_client_error() || resp.status().is_server_error() {
-            resp.text().ok()
+impl ResponseCache {
+    /// Create a cache keeping responses for the given time.
+    pub fn new(ttl: Duration) -> ResponseCache {
+        ResponseCache {
+            ttl: ttl,
+            state: Arc::new((Mutex::new(CacheState {
+                entries: HashMap::new(),
+                in_flight: HashSet::new(),
+                generation: 0,
+                subscribers: Vec::new(),
+            }), Condvar::new())),
+        }
+    }
+
+    /// Time to keep responses for.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Number of cached responses, including expired ones.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().entries.is_empty()
+    }
+
+    /// Drop all cached responses.
+    pub fn clear(&self) {
+        let urls = {
+            let mut state = self.lock();
+            state.generation += 1;
+            state.entries.drain().map(|(_, entry)| entry.url)
+                .collect::<Vec<_>>()
+        };
+        self.notify(urls);
+    }
+
+    /// Drop cached responses related to the given URL.
+    ///
+    /// Responses for the URL itself, its sub-resources and its parent
+    /// collections are dropped, regardless of the query string. Paths are
+    /// compared by whole segments, so `/servers/1` does not affect
+    /// `/servers/12`.
+    pub fn invalidate<S: AsRef<str>>(&self, url: S) {
+        let url = url.as_ref().split('?').next().unwrap_or("");
+        let urls = {
+            let mut state = self.lock();
+            state.generation += 1;
+            let keys = state.entries.iter().filter(|&(_, entry)| {
+                let path = entry.url.split('?').next().unwrap_or("");
+                is_path_prefix(url, path) || is_path_prefix(path, url)
+            }).map(|(key, _)| key.clone()).collect::<Vec<_>>();
+            keys.into_iter().filter_map(|key| state.entries.remove(&key))
+                .map(|entry| entry.url).collect::<Vec<_>>()
+        };
+        self.notify(urls);
+    }
+
+    /// Call the callback with the URL of every dropped or expired response.
+    ///
+    /// Useful to refresh views that depend on the cached data. Callbacks
+    /// are called without holding any locks, possibly from other threads.
+    pub fn subscribe<F>(&self, callback: F)
+            where F: Fn(&str) + Send + Sync + 'static {
+        self.lock().subscribers.push(Arc::new(callback));
+    }
+
+    /// Get a fresh cached response or fetch and cache it.
+    ///
+    /// If an identical request is in flight, waits for its response.
+    fn get_or_fetch<F>(&self, key: String, url: String, fetch: F)
+            -> Result<Value> where F: FnOnce() -> Result<Value> {
+        let (expired, generation) = {
+            let &(_, ref cvar) = &*self.state;
+            let mut state = self.lock();
+            let mut expired = None;
+            loop {
+                let fresh = state.entries.get(&key)
+                    .map(|entry| entry.created.elapsed() < self.ttl);
+                match fresh {
+                    Some(true) => {
+                        trace!("Using cached response for {}", key);
+                        return Ok(state.entries[&key].value.clone());
+                    },
+                    Some(false) => {
+                        expired = state.entries.remove(&key)
+                            .map(|entry| entry.url);
+                    },
+                    None => ()
+                }
+
+                if ! state.in_flight.contains(&key) {
+                    break;
+                }
+                trace!("Waiting for an identical request to {}", url);
+                state = cvar.wait(state).unwrap_or_else(|e| e.into_inner());
+            }
+            let _ = state.in_flight.insert(key.clone());
+            (expired, state.generation)
+        };
+        self.notify(expired);
+
+        let _in_flight = InFlight {
+            cache: self,
+            key: key.clone(),
+        };
+        let value = fetch()?;
+        let mut state = self.lock();
+        // Do not cache responses that may predate an invalidation.
+        if state.generation == generation {
+            let _ = state.entries.insert(key, CacheEntry {
+                created: Instant::now(),
+                url: url,
+                value: value.clone(),
+            });
+        }
+        Ok(value)
+    }
+
+    fn lock(&self) -> MutexGuard<CacheState> {
+        self.state.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn notify<I: IntoIterator<Item = String>>(&self, urls: I) {
+        let subscribers = self.lock().subscribers.clone();
+        for url in urls {
+            trace!("Cached response for {} was dropped", url);
+            for callback in &subscribers {
+                callback(&url);
+            }
+        }
+    }
+}
+
+impl<'c> Drop for InFlight<'c> {
+    fn drop(&mut self) {
+        let &(_, ref cvar) = &*self.cache.state;
+        let _ = self.cache.lock().in_flight.remove(&self.key);
+        cvar.notify_all();
+    }
+}
+
+impl fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        f.debug_struct("ResponseCache")
+            .field("ttl", &self.ttl)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Whether the path is equal to the prefix or is below it.
+///
+/// Paths are compared by whole segments, a trailing slash is ignored.
+fn is_path_prefix(prefix: &str, path: &str) -> bool {
+    let prefix = prefix.trim_right_matches('/');
+    let path = path.trim_right_matches('/');
+    path.starts_with(prefix) &&
+        (path.len() == prefix.len() || path[prefix.len()..].starts_with('/'))
+}
+
+/// Normalize an endpoint interface, accepting legacy names like `publicURL`.
+fn normalize_interface(interface: String) -> String {
+    if interface.ends_with("URL") {
+        interface[..interface.len() - 3].to_lowercase()
+    } else {
+        interface.to_lowercase()
+    }
+}
+
+/// Service-specific environment variables `OS_<TYPE>_<NAME>`.
+///
+/// Returns service types with variable names and values. Variables that are
+/// not valid Unicode are skipped.
+fn service_env_vars(name: &str) -> Vec<(String, String, String)> {
+    service_vars(env::vars_os().filter_map(|(var, value)| {
+        match (var.into_string(), value.into_string()) {
+            (Ok(var), Ok(value)) => Some((var, value)),
+            _ => None
+        }
+    }), name)
+}
+
+/// Pick service-specific variables `OS_<TYPE>_<NAME>` from the given pairs.
+///
+/// Returns service types with variable names and values.
+fn service_vars<I>(vars: I, name: &str) -> Vec<(String, String, String)>
+        where I: IntoIterator<Item = (String, String)> {
+    let suffix = format!("_{}", name);
+    vars.into_iter().filter_map(|(var, value)| {
+        if var.len() > 3 + suffix.len() && var.starts_with("OS_") &&
+                var.ends_with(&suffix) {
+            let service_type = var[3..var.len() - suffix.len()]
+                .to_lowercase().replace('_', "-");
+            Some((service_type, var, value))
         } else {
             None
-        };
+        }
+    }).collect()
+}
 
-        // TODO(dtantsur): proper error parsing
-        trace!("HTTP request to {} returned {}; error: {:?}",
-               resp.url(), resp.status(), details);
+/// Lower an exact version request to the maximum supported version.
+fn clamp_api_version_request(request: ApiVersionRequest,
+                             max_version: Option<ApiVersion>)
+        -> ApiVersionRequest {
+    match (request, max_version) {
+        (ApiVersionRequest::Exact(version), Some(max)) if version > max => {
+            warn!("API version {} is not supported by this library, \
+                   using {} instead", version, max);
+            ApiVersionRequest::Exact(max)
+        },
+        (other, _) => other
     }
+}
+
+fn _log(resp: Response) -> Response {
+    trace!("HTTP request to {} returned {}", resp.url(), resp.status());
     resp
 }
 
@@ -142,8 +826,17 @@ fn _log(mut resp: Response) -> Response {
 #[derive(Debug, Clone)]
 pub struct Session {
     auth: Box<AuthMethod>,
-    cached_info: utils::MapCache<&'static str, ServiceInfo>,
-    endpoint_interface: String
+    cached_info: utils::MapCache<(&'static str, String), ServiceInfo>,
+    endpoint_interface: String,
+    endpoint_interfaces: HashMap<String, String>,
+    endpoint_overrides: HashMap<String, Url>,
+    limits: HashMap<&'static str, utils::Semaphore>,
+    resolved_ids: utils::MapCache<(&'static str, String), (String, Instant)>,
+    retry_policy: RetryPolicy,
+    api_versions: HashMap<&'static str, ApiVersion>,
+    env_api_versions: HashMap<String, ApiVersionRequest>,
+    negotiated_api_versions: utils::MapCache<&'static str, Option<ApiVersion>>,
+    response_cache: Option<ResponseCache>
 }
 
 
@@ -157,17 +850,25 @@ impl Session {
         Session {
             auth: Box::new(auth_method),
             cached_info: utils::MapCache::new(),
-            endpoint_interface: ep
+            endpoint_interface: ep,
+            endpoint_interfaces: HashMap::new(),
+            endpoint_overrides: HashMap::new(),
+            limits: HashMap::new(),
+            resolved_ids: utils::MapCache::new(),
+            retry_policy: RetryPolicy::default(),
+            api_versions: HashMap::new(),
+            env_api_versions: HashMap::new(),
+            negotiated_api_versions: utils::MapCache::new(),
+            response_cache: None
         }
     }
 
     /// Set endpoint interface to use.
     ///
-    /// This call clears the cached service information.
+    /// Legacy names like `internalURL` are also accepted.
     pub fn set_endpoint_interface<S>(&mut self, endpoint_interface: S)
             where S: Into<String> {
-        self.cached_info = utils::MapCache::new();
-        self.endpoint_interface = endpoint_interface.into();
+        self.endpoint_interface = normalize_interface(endpoint_interface.into());
     }
 
     /// Convert this session into one using the given endpoint interface.
@@ -177,6 +878,246 @@ impl Session {
         self
     }
 
+    /// Set the endpoint interface from environment variables.
+    ///
+    /// `OS_INTERFACE` is used, falling back to the legacy `OS_ENDPOINT_TYPE`.
+    /// The interface for one service is taken from `OS_<TYPE>_INTERFACE`,
+    /// e.g. `OS_COMPUTE_INTERFACE`. Nothing is changed if none is set.
+    pub fn set_endpoint_interface_from_env(&mut self) {
+        if let Ok(value) = env::var("OS_INTERFACE")
+                .or_else(|_| env::var("OS_ENDPOINT_TYPE")) {
+            debug!("Using endpoint interface {} from the environment", value);
+            self.set_endpoint_interface(value);
+        }
+
+        self.set_service_endpoint_interfaces_from(service_env_vars("INTERFACE"));
+    }
+
+    fn set_service_endpoint_interfaces_from(&mut self,
+                                            vars: Vec<(String, String, String)>) {
+        for (service_type, var, value) in vars {
+            debug!("Using endpoint interface {} for service {} from {}",
+                   value, service_type, var);
+            self.set_service_endpoint_interface(service_type, value);
+        }
+    }
+
+    /// Set endpoint overrides from environment variables.
+    ///
+    /// The `OS_<TYPE>_ENDPOINT_OVERRIDE` variables are used, e.g.
+    /// `OS_NETWORK_ENDPOINT_OVERRIDE`. Fails if a variable is not a valid URL.
+    pub fn set_endpoint_overrides_from_env(&mut self) -> Result<()> {
+        self.set_endpoint_overrides_from(service_env_vars("ENDPOINT_OVERRIDE"))
+    }
+
+    fn set_endpoint_overrides_from(&mut self,
+                                   vars: Vec<(String, String, String)>)
+            -> Result<()> {
+        for (service_type, var, value) in vars {
+            let endpoint = Url::parse(&value).map_err(|err| {
+                Error::new(ErrorKind::InvalidInput,
+                           format!("Invalid URL {} in {}: {}", value, var, err))
+            })?;
+            debug!("Using endpoint {} for service {} from {}", endpoint,
+                   service_type, var);
+            self.set_endpoint_override(service_type, endpoint);
+        }
+        Ok(())
+    }
+
+    /// Use the given endpoint interface for the service type.
+    ///
+    /// Overrides the session-wide endpoint interface for this service only.
+    #[allow(unused_results)]
+    pub fn set_service_endpoint_interface<S1, S2>(&mut self, service_type: S1,
+                                                  endpoint_interface: S2)
+            where S1: Into<String>, S2: Into<String> {
+        self.endpoint_interfaces.insert(
+            service_type.into(), normalize_interface(endpoint_interface.into()));
+    }
+
+    /// Convert this session into one using the given interface for a service.
+    pub fn with_service_endpoint_interface<S1, S2>(mut self, service_type: S1,
+                                                   endpoint_interface: S2)
+            -> Session where S1: Into<String>, S2: Into<String> {
+        self.set_service_endpoint_interface(service_type, endpoint_interface);
+        self
+    }
+
+    /// Use the given endpoint for the service type instead of the catalog.
+    ///
+    /// This call drops the cached information about this service.
+    #[allow(unused_results)]
+    pub fn set_endpoint_override<S>(&mut self, service_type: S, endpoint: Url)
+            where S: Into<String> {
+        let service_type = service_type.into();
+        self.cached_info.retain(|&(cached, _)| cached != service_type);
+        self.endpoint_overrides.insert(service_type, endpoint);
+    }
+
+    /// Convert this session into one using the given endpoint for a service.
+    pub fn with_endpoint_override<S>(mut self, service_type: S, endpoint: Url)
+            -> Session where S: Into<String> {
+        self.set_endpoint_override(service_type, endpoint);
+        self
+    }
+
+    /// Drop the cached information about the given service.
+    ///
+    /// Service information (the root URL and the supported API versions) is
+    /// discovered on the first request to a service and cached for each
+    /// endpoint interface. Use this call if the service has been upgraded.
+    pub fn invalidate_service_info<Srv: ServiceType>(&self) {
+        self.cached_info.retain(|&(cached, _)| cached != Srv::catalog_type());
+        let _ = self.negotiated_api_versions.remove(&Srv::catalog_type());
+    }
+
+    /// Drop the cached information about all services.
+    pub fn clear_service_info(&self) {
+        self.cached_info.clear();
+        self.negotiated_api_versions.clear();
+    }
+
+    /// Limit the number of in-flight requests to the given service.
+    ///
+    /// By default the number of requests is not limited. `None` removes
+    /// the limit. The limit is shared between clones of this session.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the limit is zero.
+    #[allow(unused_results)]
+    pub fn set_request_limit<Srv>(&mut self, limit: Option<usize>)
+            where Srv: ServiceType {
+        match limit {
+            Some(value) => {
+                assert!(value > 0, "Request limit must be positive");
+                self.limits.insert(Srv::catalog_type(),
+                                   utils::Semaphore::new(value));
+            },
+            None => {
+                self.limits.remove(Srv::catalog_type());
+            }
+        }
+    }
+
+    /// Convert this session into one limiting in-flight requests to a service.
+    pub fn with_request_limit<Srv>(mut self, limit: Option<usize>) -> Session
+            where Srv: ServiceType {
+        self.set_request_limit::<Srv>(limit);
+        self
+    }
+
+    /// API version used by default for the given service (if any).
+    ///
+    /// Versions requested in the environment are only reported once they
+    /// have been negotiated on the first request to the service.
+    pub fn api_version<Srv: ServiceType>(&self) -> Option<ApiVersion> {
+        self.api_versions.get(Srv::catalog_type()).cloned().or_else(|| {
+            self.negotiated_api_versions.get_ref(&Srv::catalog_type())
+                .and_then(|version| *version)
+        })
+    }
+
+    /// Negotiate the API version to use by default for the given service.
+    ///
+    /// The version is checked against the versions supported by the service
+    /// and by this crate. Calls that require a higher version still use it.
+    /// Returns the resulting version, `None` means no explicit version.
+    #[allow(unused_results)]
+    pub fn set_api_version<Srv>(&mut self, request: ApiVersionRequest)
+            -> Result<Option<ApiVersion>> where Srv: ServiceType {
+        let version = self.get_service_info_ref::<Srv>()?
+            .negotiate_api_version(request, Srv::max_api_version())?;
+        match version {
+            Some(value) => {
+                debug!("Using API version {} for {} by default", value,
+                       Srv::catalog_type());
+                self.api_versions.insert(Srv::catalog_type(), value);
+            },
+            None => {
+                self.api_versions.remove(Srv::catalog_type());
+            }
+        }
+        Ok(version)
+    }
+
+    /// Request default API versions from environment variables.
+    ///
+    /// The `OS_<TYPE>_API_VERSION` variables are used for all services, e.g.
+    /// `OS_COMPUTE_API_VERSION`, with values as accepted by
+    /// `ApiVersionRequest::from_str`. The variables are only read here, the
+    /// versions are negotiated on the first request to each service.
+    ///
+    /// Versions above the maximum supported by this crate are lowered to it.
+    /// Invalid values and versions not supported by the server are ignored
+    /// with a warning. Versions set with `set_api_version` take precedence.
+    pub fn set_api_versions_from_env(&mut self) {
+        self.set_api_versions_from(service_env_vars("API_VERSION"));
+    }
+
+    #[allow(unused_results)]
+    fn set_api_versions_from(&mut self, vars: Vec<(String, String, String)>) {
+        self.env_api_versions.clear();
+        for (service_type, var, value) in vars {
+            match value.parse() {
+                Ok(request) => {
+                    debug!("Requesting API version {} for {} from {}", value,
+                           service_type, var);
+                    self.env_api_versions.insert(service_type, request);
+                },
+                Err(err) => warn!("Ignoring {}: {}", var, err)
+            }
+        }
+        self.negotiated_api_versions.clear();
+    }
+
+    /// Policy for retrying rate-limited requests.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// Set the timeout for HTTP requests.
+    ///
+    /// The timeout covers connecting as well as reading the response, the
+    /// default is 30 seconds. The HTTP client of the authentication method
+    /// is replaced, which fails with `InvalidConfig` if it is not supported,
+    /// e.g. for a client passed to `Identity::new_with_client`.
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.auth.set_timeout(timeout)
+    }
+
+    /// Convert this session into one using the given timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Result<Session> {
+        self.set_timeout(timeout)?;
+        Ok(self)
+    }
+
+    /// Set the policy for retrying rate-limited requests.
+    ///
+    /// Only idempotent requests are retried.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    /// Convert this session into one using the given retry policy.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Session {
+        self.set_retry_policy(policy);
+        self
+    }
+
+    /// Cache of GET responses, if enabled.
+    pub fn response_cache(&self) -> Option<&ResponseCache> {
+        self.response_cache.as_ref()
+    }
+
+    /// Set or disable the cache of GET responses.
+    ///
+    /// The cache is shared between clones of this session.
+    pub fn set_response_cache(&mut self, cache: Option<ResponseCache>) {
+        self.response_cache = cache;
+    }
+
     /// Get a reference to the authentication method in use.
     pub fn auth_method(&self) -> &AuthMethod {
         self.auth.as_ref()
@@ -206,20 +1147,195 @@ impl Session {
                                      api_version: Option<ApiVersion>)
             -> Result<RequestBuilder> {
         let url = self.get_endpoint::<Srv>(path)?;
+        let api_version = match (api_version,
+                                 self.default_api_version::<Srv>()) {
+            (Some(required), Some(default)) => Some(cmp::max(required, default)),
+            (required, default) => required.or(default)
+        };
         trace!("Sending HTTP {} request to {} with API version {:?}",
                method, url, api_version);
         let maybe_headers = api_version.and_then(|ver| {
             Srv::api_version_headers(ver)
         });
-        let mut builder = self.auth.request(method, url)?;
+        let mut builder = self.auth.request(method.clone(), url.clone())?;
+        if is_idempotent(&method) {
+            builder.retry_policy = self.retry_policy;
+        }
+        builder.cache = self.response_cache.clone().map(|cache| {
+            (cache, method.clone(), url.clone())
+        });
+        builder.enable_replay(self.auth.clone(), method, url);
+        builder.limiter = self.limits.get(Srv::catalog_type()).cloned();
+        builder.service = Some(String::from(Srv::catalog_type()));
         if let Some(headers) = maybe_headers {
             let _unused = builder.headers(headers);
         }
         Ok(builder)
     }
 
-    fn ensure_service_info<Srv>(&self) -> Result<()> where Srv: ServiceType {
-        self.cached_info.ensure_value(Srv::catalog_type(), |_| {
+    /// Discover API versions of a service from the catalog.
+    ///
+    /// Versioned endpoints often report only their own version.
+    pub fn probe_versions<S: AsRef<str>>(&self, service_type: S)
+            -> Result<Vec<ServiceVersion>> {
+        let service_type = service_type.as_ref();
+        let endpoint = self.get_catalog_endpoint(service_type)?;
+        let versions = common::protocol::fetch_versions(
+            endpoint, self.auth_method(), service_type)?;
+        Ok(versions.into_iter().map(From::from).collect())
+    }
+
+    /// Discover API versions of all services from the catalog.
+    ///
+    /// Failures to discover versions of a service do not stop the probing,
+    /// they are reported for this service instead.
+    pub fn probe_all_versions(&self)
+            -> Result<BTreeMap<String, Result<Vec<ServiceVersion>>>> {
+        let mut result = BTreeMap::new();
+        for service_type in self.auth.service_types()? {
+            let versions = self.probe_versions(&service_type);
+            if let Err(ref err) = versions {
+                warn!("Failed to discover versions of service {}: {}",
+                      service_type, err);
+            }
+            let _ = result.insert(service_type, versions);
+        }
+        Ok(result)
+    }
+
+    /// Make an HTTP request to an arbitrary service from the catalog.
+    ///
+    /// This is an escape hatch for APIs that are not covered by this crate.
+    /// The path is appended to the catalog endpoint of the service as is,
+    /// no version discovery is done and no API version headers are sent.
+    /// Authentication, token refresh, request limits and retries work the
+    /// same way as for other requests.
+    pub fn raw_request<S: AsRef<str>>(&self, service_type: S, method: Method,
+                                      path: &[&str])
+            -> Result<RequestBuilder> {
+        let service_type = service_type.as_ref();
+        let endpoint = self.get_catalog_endpoint(service_type)?;
+        let url = utils::url::extend(endpoint, path);
+        trace!("Sending raw HTTP {} request to {}", method, url);
+        let mut builder = self.auth.request(method.clone(), url.clone())?;
+        if is_idempotent(&method) {
+            builder.retry_policy = self.retry_policy;
+        }
+        builder.cache = self.response_cache.clone().map(|cache| {
+            (cache, method.clone(), url.clone())
+        });
+        builder.enable_replay(self.auth.clone(), method, url);
+        builder.limiter = self.limits.get(service_type).cloned();
+        builder.service = Some(String::from(service_type));
+        Ok(builder)
+    }
+
+    /// Resolve a name or an ID of a resource into an ID, caching the result.
+    ///
+    /// Cached results expire after `RESOLVED_ID_TTL` seconds, so that
+    /// resources re-created under the same name are eventually picked up.
+    #[allow(dead_code)] // unused with --no-default-features
+    pub(crate) fn resolve_id<F>(&self, kind: &'static str, id_or_name: &str,
+                                resolve: F) -> Result<String>
+            where F: FnOnce(&str) -> Result<String> {
+        let key = (kind, String::from(id_or_name));
+        let expired = match self.resolved_ids.get_ref(&key) {
+            Some(value) =>
+                value.1.elapsed() >= Duration::from_secs(RESOLVED_ID_TTL),
+            None => false
+        };
+        if expired {
+            trace!("Cached ID of {} {} has expired", kind, id_or_name);
+            let _ = self.resolved_ids.remove(&key);
+        }
+
+        self.resolved_ids.ensure_value(key.clone(), |k| {
+            trace!("Resolving {} {} into an ID", kind, k.1);
+            resolve(&k.1).map(|id| (id, Instant::now()))
+        })?;
+        Ok(self.resolved_ids.get_ref(&key).unwrap().0.clone())
+    }
+
+    /// Get a cached resolution of a name or an ID (if any).
+    #[allow(dead_code)] // unused with --no-default-features
+    pub(crate) fn cached_id(&self, kind: &'static str, id_or_name: &str)
+            -> Option<String> {
+        self.resolved_ids.get_ref(&(kind, String::from(id_or_name)))
+            .map(|value| value.0.clone())
+    }
+
+    /// Drop a cached resolution of a name or an ID.
+    ///
+    /// Returns `true` if a cached value existed.
+    #[allow(dead_code)] // unused with --no-default-features
+    pub(crate) fn invalidate_id(&self, kind: &'static str, id_or_name: &str)
+            -> bool {
+        let removed = self.resolved_ids
+            .remove(&(kind, String::from(id_or_name))).is_some();
+        if removed {
+            debug!("Invalidated cached ID of {} {}", kind, id_or_name);
+        }
+        removed
+    }
+
+    /// API version to use by default for the service (if any).
+    ///
+    /// The version requested in the environment is negotiated on first use.
+    fn default_api_version<Srv: ServiceType>(&self) -> Option<ApiVersion> {
+        let service_type = Srv::catalog_type();
+        if let Some(version) = self.api_versions.get(service_type) {
+            return Some(*version);
+        }
+        let request = match self.env_api_versions.get(service_type) {
+            Some(request) => *request,
+            None => return None
+        };
+        // Negotiation never fails, failures are logged and ignored.
+        let _ = self.negotiated_api_versions.ensure_value(service_type, |_| {
+            Ok(self.negotiate_env_api_version::<Srv>(request))
+        });
+        self.api_version::<Srv>()
+    }
+
+    fn negotiate_env_api_version<Srv: ServiceType>(&self,
+                                                   request: ApiVersionRequest)
+            -> Option<ApiVersion> {
+        let service_type = Srv::catalog_type();
+        let info = match self.get_service_info_ref::<Srv>() {
+            Ok(info) => info,
+            Err(err) => {
+                warn!("Cannot negotiate API version for {}: {}",
+                      service_type, err);
+                return None;
+            }
+        };
+        if info.current_version.is_none() {
+            debug!("Ignoring the API version requested for {}: the service \
+                    does not support API versions", service_type);
+            return None;
+        }
+
+        let max_version = Srv::max_api_version();
+        let request = clamp_api_version_request(request, max_version);
+        match info.negotiate_api_version(request, max_version) {
+            Ok(version) => {
+                if let Some(value) = version {
+                    debug!("Using API version {} for {} by default", value,
+                           service_type);
+                }
+                version
+            },
+            Err(err) => {
+                warn!("Ignoring the API version requested for {}: {}",
+                      service_type, err);
+                None
+            }
+        }
+    }
+
+    fn ensure_service_info<Srv>(&self, key: (&'static str, String))
+            -> Result<()> where Srv: ServiceType {
+        self.cached_info.ensure_value(key, |_| {
             self.get_catalog_endpoint(Srv::catalog_type())
                 .and_then(|ep| Srv::service_info(ep, self.auth_method()))
         })?;
@@ -227,20 +1343,89 @@ impl Session {
         Ok(())
     }
 
+    /// Key of the cached service information: service type and interface.
+    fn service_info_key<Srv: ServiceType>(&self) -> (&'static str, String) {
+        (Srv::catalog_type(), self.get_endpoint_interface(Srv::catalog_type()))
+    }
+
+    /// Get the endpoint interface to use for the service type.
+    ///
+    /// The interface set for the service type is used first, then the
+    /// session-wide endpoint interface.
+    fn get_endpoint_interface(&self, service_type: &str) -> String {
+        self.endpoint_interfaces.get(service_type).cloned()
+            .unwrap_or_else(|| self.endpoint_interface.clone())
+    }
+
+    /// Get an endpoint for the service type.
+    ///
+    /// Overridden endpoints are used first, then the service catalog.
     fn get_catalog_endpoint<S>(&self, service_type: S) -> Result<Url>
             where S: Into<String> {
-        self.auth.get_endpoint(service_type.into(),
-                               Some(self.endpoint_interface.clone()))
+        let service_type = service_type.into();
+        if let Some(endpoint) = self.endpoint_overrides.get(&service_type) {
+            debug!("Using overridden endpoint {} for service {}", endpoint,
+                   service_type);
+            return Ok(endpoint.clone());
+        }
+
+        let interface = self.get_endpoint_interface(&service_type);
+        self.auth.get_endpoint(service_type, Some(interface))
     }
 
     pub(crate) fn get_service_info_ref<Srv>(&self)
             -> Result<Ref<ServiceInfo>> where Srv: ServiceType {
-        self.ensure_service_info::<Srv>()?;
-        Ok(self.cached_info.get_ref(&Srv::catalog_type()).unwrap())
+        let key = self.service_info_key::<Srv>();
+        self.ensure_service_info::<Srv>(key.clone())?;
+        Ok(self.cached_info.get_ref(&key).unwrap())
+    }
+}
+
+impl From<common::protocol::Version> for ServiceVersion {
+    fn from(value: common::protocol::Version) -> ServiceVersion {
+        ServiceVersion {
+            root_url: value.links.into_iter().find(|x| &x.rel == "self")
+                .map(|link| link.href),
+            id: value.id,
+            status: value.status,
+            current_version: value.version,
+            minimum_version: value.min_version
+        }
     }
 }
 
 impl ServiceInfo {
+    /// Pick an API version according to the request.
+    ///
+    /// Versions above `max_version` are not picked for `Latest` and are
+    /// rejected for `Exact`.
+    pub fn negotiate_api_version(&self, request: ApiVersionRequest,
+                                 max_version: Option<ApiVersion>)
+            -> Result<Option<ApiVersion>> {
+        let version = match request {
+            ApiVersionRequest::Minimum => return Ok(None),
+            ApiVersionRequest::Latest => match (self.current_version,
+                                                max_version) {
+                (Some(current), Some(max)) => cmp::min(current, max),
+                (Some(current), None) => current,
+                (None, _) => return Ok(None)
+            },
+            ApiVersionRequest::Exact(version) => version
+        };
+
+        if max_version.map(|max| version > max).unwrap_or(false) {
+            Err(Error::new(ErrorKind::IncompatibleApiVersion,
+                           format!("API version {} is not supported by this \
+                                    library", version)))
+        } else if ! self.supports_api_version(version) {
+            Err(Error::new(ErrorKind::IncompatibleApiVersion,
+                           format!("API version {} is not supported by the \
+                                    server", version)))
+        } else {
+            Ok(Some(version))
+        }
+    }
+
     /// Whether this service supports the given API version.
     ///
     /// Defaults to false if cannot be determined.
@@ -256,7 +1441,219 @@ impl ServiceInfo {
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    use reqwest::{Method, StatusCode, Url};
+    use serde_json::Value;
+
+    use super::super::{ErrorKind, Result};
+    use super::super::auth::{AuthMethod, NoAuth};
+    use super::super::common::{ApiVersion, ApiVersionRequest};
     use super::super::utils;
+    use super::super::utils::test::FakeServer;
+    use super::{clamp_api_version_request, error_kind, fault_message,
+                is_maintenance, is_path_prefix, normalize_interface,
+                query_pairs, service_vars, RequestBuilder,
+                ResponseCache, RetryPolicy, ServiceInfo, Session};
+
+    /// Authentication counting its refreshes.
+    #[derive(Clone, Debug)]
+    struct CountingAuth {
+        inner: NoAuth,
+        refreshed: Arc<AtomicUsize>,
+    }
+
+    impl AuthMethod for CountingAuth {
+        fn get_endpoint(&self, service_type: String,
+                        endpoint_interface: Option<String>) -> Result<Url> {
+            self.inner.get_endpoint(service_type, endpoint_interface)
+        }
+
+        fn request(&self, method: Method, url: Url) -> Result<RequestBuilder> {
+            self.inner.request(method, url)
+        }
+
+        fn refresh(&mut self) -> Result<()> {
+            let _ = self.refreshed.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn counting_session(server: &FakeServer) -> (Session, Arc<AtomicUsize>) {
+        let refreshed = Arc::new(AtomicUsize::new(0));
+        let auth = CountingAuth {
+            inner: NoAuth::new(server.url().as_str()).unwrap(),
+            refreshed: refreshed.clone(),
+        };
+        (Session::new(auth), refreshed)
+    }
+
+    fn post_thing(session: &Session) -> Result<()> {
+        let mut body = HashMap::new();
+        let _ = body.insert("name", "thing");
+        let _ = session.raw_request("fake", Method::Post, &["things"])?
+            .json(&body).send()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_on_unauthorized() {
+        let server = FakeServer::new();
+        server.respond("POST", "/things", 401,
+                       r#"{"error": {"message": "Token expired"}}"#);
+        server.respond("POST", "/things", 200, "{}");
+        let (session, refreshed) = counting_session(&server);
+
+        post_thing(&session).unwrap();
+        assert_eq!(refreshed.load(Ordering::SeqCst), 1);
+        let requests = server.requests_with_bodies();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0, "POST /things");
+        assert_eq!(requests[0].1, r#"{"name":"thing"}"#);
+        assert_eq!(requests[1], requests[0]);
+    }
+
+    #[test]
+    fn test_retry_on_unauthorized_only_once() {
+        let server = FakeServer::new();
+        server.respond("POST", "/things", 401,
+                       r#"{"error": {"message": "Bad token"}}"#);
+        let (session, refreshed) = counting_session(&server);
+
+        let err = post_thing(&session).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::AuthenticationFailed);
+        assert_eq!(refreshed.load(Ordering::SeqCst), 1);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_error_kind() {
+        let nova = r#"{"forbidden": {"code": 403, "message":
+            "Quota exceeded for instances: Requested 1, but already used 10 of 10 instances"}}"#;
+        assert_eq!(error_kind(StatusCode::Forbidden, nova,
+                              fault_message(nova).as_ref()),
+                   ErrorKind::QuotaExceeded);
+        let neutron = r#"{"NeutronError": {"type": "OverQuota",
+            "message": "Quota exceeded for resources: ['port'].", "detail": ""}}"#;
+        assert_eq!(error_kind(StatusCode::Conflict, neutron, None),
+                   ErrorKind::QuotaExceeded);
+        let conflict = r#"{"NeutronError": {"type": "IpAddressInUse",
+            "message": "IP address 10.0.0.2 already allocated", "detail": ""}}"#;
+        assert_eq!(error_kind(StatusCode::Conflict, conflict,
+                              fault_message(conflict).as_ref()),
+                   ErrorKind::Conflict);
+        assert_eq!(error_kind(StatusCode::Forbidden, "", None),
+                   ErrorKind::AccessDenied);
+        assert_eq!(error_kind(StatusCode::Unauthorized, "", None),
+                   ErrorKind::AuthenticationFailed);
+        assert_eq!(error_kind(StatusCode::PayloadTooLarge, "", None),
+                   ErrorKind::QuotaExceeded);
+    }
+
+    #[test]
+    fn test_fault_message() {
+        assert_eq!(fault_message(r#"{"itemNotFound": {"code": 404,
+            "message": "Instance abcd could not be found."}}"#),
+                   Some(String::from("Instance abcd could not be found.")));
+        assert_eq!(fault_message(r#"{"NeutronError": {"type": "PortNotFound",
+            "message": "Port abcd could not be found.", "detail": ""}}"#),
+                   Some(String::from("Port abcd could not be found.")));
+        assert_eq!(fault_message(r#"{"error": {"code": 401,
+            "title": "Unauthorized", "message": "Invalid token"}}"#),
+                   Some(String::from("Invalid token")));
+        assert_eq!(fault_message(r#"{"error_message":
+            "{\"faultstring\": \"Node abcd is locked\", \"debuginfo\": null}"}"#),
+                   Some(String::from("Node abcd is locked")));
+        assert_eq!(fault_message(r#"{"errors": [{"title": "Bad Request",
+            "detail": "Invalid marker"}]}"#),
+                   Some(String::from("Invalid marker")));
+        assert_eq!(fault_message("<html>Not Found</html>"), None);
+        assert_eq!(fault_message(""), None);
+    }
+
+    #[test]
+    fn test_is_maintenance() {
+        assert!(is_maintenance("<h1>Cloud is under MAINTENANCE</h1>"));
+        assert!(is_maintenance(r#"{"error": "maintenance in progress"}"#));
+        assert!(! is_maintenance("Service Unavailable"));
+        assert!(! is_maintenance(""));
+    }
+
+    #[test]
+    fn test_negotiate_api_version() {
+        let info = ServiceInfo {
+            root_url: Url::parse(utils::test::URL).unwrap(),
+            current_version: Some(ApiVersion(2, 60)),
+            minimum_version: Some(ApiVersion(2, 1)),
+        };
+        let max = Some(ApiVersion(2, 46));
+        assert_eq!(info.negotiate_api_version(ApiVersionRequest::Minimum, max)
+                   .unwrap(), None);
+        assert_eq!(info.negotiate_api_version(ApiVersionRequest::Latest, max)
+                   .unwrap(), Some(ApiVersion(2, 46)));
+        assert_eq!(info.negotiate_api_version(ApiVersionRequest::Latest, None)
+                   .unwrap(), Some(ApiVersion(2, 60)));
+        assert_eq!(info.negotiate_api_version(
+                ApiVersionRequest::Exact(ApiVersion(2, 26)), max).unwrap(),
+            Some(ApiVersion(2, 26)));
+        assert_eq!(info.negotiate_api_version(
+                ApiVersionRequest::Exact(ApiVersion(2, 50)), max)
+            .unwrap_err().kind(), ErrorKind::IncompatibleApiVersion);
+        assert_eq!(info.negotiate_api_version(
+                ApiVersionRequest::Exact(ApiVersion(2, 70)), None)
+            .unwrap_err().kind(), ErrorKind::IncompatibleApiVersion);
+    }
+
+    #[test]
+    fn test_clamp_api_version_request() {
+        let max = Some(ApiVersion(2, 46));
+        assert_eq!(clamp_api_version_request(
+                ApiVersionRequest::Exact(ApiVersion(2, 60)), max),
+            ApiVersionRequest::Exact(ApiVersion(2, 46)));
+        assert_eq!(clamp_api_version_request(
+                ApiVersionRequest::Exact(ApiVersion(2, 26)), max),
+            ApiVersionRequest::Exact(ApiVersion(2, 26)));
+        assert_eq!(clamp_api_version_request(
+                ApiVersionRequest::Exact(ApiVersion(2, 60)), None),
+            ApiVersionRequest::Exact(ApiVersion(2, 60)));
+        assert_eq!(clamp_api_version_request(ApiVersionRequest::Latest, max),
+                   ApiVersionRequest::Latest);
+    }
+
+    #[test]
+    fn test_retry_policy_delay() {
+        let policy = RetryPolicy::new(5)
+            .with_max_delay(Duration::from_secs(5));
+        assert_eq!(policy.delay(1, None), Duration::from_secs(1));
+        assert_eq!(policy.delay(2, None), Duration::from_secs(2));
+        assert_eq!(policy.delay(3, None), Duration::from_secs(4));
+        assert_eq!(policy.delay(4, None), Duration::from_secs(5));
+        assert_eq!(policy.delay(40, None), Duration::from_secs(5));
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(3))),
+                   Duration::from_secs(3));
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(60))),
+                   Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_session_retry_policy() {
+        let s = utils::test::new_session(utils::test::URL);
+        assert_eq!(s.retry_policy(), RetryPolicy::default());
+        let s = s.with_retry_policy(RetryPolicy::none());
+        assert_eq!(s.retry_policy().max_attempts(), 1);
+    }
+
+    #[test]
+    fn test_query_pairs() {
+        let pairs = query_pairs(&[("name", "foo"), ("limit", "2")]).unwrap();
+        assert_eq!(pairs, vec![(String::from("name"), String::from("foo")),
+                               (String::from("limit"), String::from("2"))]);
+        assert!(query_pairs(&[("limit", 2)]).is_none());
+    }
 
     #[test]
     fn test_session_new() {
@@ -265,6 +1662,130 @@ mod test {
         assert_eq!(&ep.to_string(), utils::test::URL);
     }
 
+    #[test]
+    fn test_session_endpoint_override() {
+        let url = Url::parse("https://internal.example.com/v2").unwrap();
+        let s = utils::test::new_session(utils::test::URL)
+            .with_endpoint_override("fake", url.clone());
+        assert_eq!(s.get_catalog_endpoint("fake").unwrap(), url);
+        assert_eq!(&s.get_catalog_endpoint("other").unwrap().to_string(),
+                   utils::test::URL);
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|&(var, value)| {
+            (String::from(var), String::from(value))
+        }).collect()
+    }
+
+    #[test]
+    fn test_service_vars() {
+        let result = service_vars(vars(&[
+            ("OS_COMPUTE_API_VERSION", "2.latest"),
+            ("OS_CONTAINER_INFRA_API_VERSION", "1.1"),
+            ("OS_API_VERSION", "3"),
+            ("OS_COMPUTE_INTERFACE", "internal"),
+            ("COMPUTE_API_VERSION", "2.1"),
+        ]), "API_VERSION");
+        assert_eq!(result, vec![
+            (String::from("compute"), String::from("OS_COMPUTE_API_VERSION"),
+             String::from("2.latest")),
+            (String::from("container-infra"),
+             String::from("OS_CONTAINER_INFRA_API_VERSION"),
+             String::from("1.1")),
+        ]);
+    }
+
+    #[test]
+    fn test_set_api_versions_from() {
+        let mut s = utils::test::new_session(utils::test::URL);
+        s.set_api_versions_from(service_vars(vars(&[
+            ("OS_FAKE_API_VERSION", "2.latest"),
+            ("OS_INVALID_API_VERSION", "2.x"),
+        ]), "API_VERSION"));
+        assert_eq!(s.env_api_versions.get("fake"),
+                   Some(&ApiVersionRequest::Latest));
+        assert!(!s.env_api_versions.contains_key("invalid"));
+    }
+
+    #[test]
+    fn test_set_endpoints_from() {
+        let env = vars(&[
+            ("OS_FAKE_INTERFACE", "internalURL"),
+            ("OS_FAKE_ENDPOINT_OVERRIDE", "http://127.0.0.1:9696/"),
+        ]);
+        let mut s = utils::test::new_session(utils::test::URL);
+        s.set_service_endpoint_interfaces_from(
+            service_vars(env.clone(), "INTERFACE"));
+        s.set_endpoint_overrides_from(
+            service_vars(env, "ENDPOINT_OVERRIDE")).unwrap();
+        assert_eq!(s.get_endpoint_interface("fake"), "internal");
+        assert_eq!(s.get_catalog_endpoint("fake").unwrap().as_str(),
+                   "http://127.0.0.1:9696/");
+    }
+
+    #[test]
+    fn test_invalid_endpoint_override_from() {
+        let mut s = utils::test::new_session(utils::test::URL);
+        let result = s.set_endpoint_overrides_from(service_vars(vars(&[
+            ("OS_FAKE_ENDPOINT_OVERRIDE", "not a URL"),
+        ]), "ENDPOINT_OVERRIDE"));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_normalize_interface() {
+        assert_eq!(normalize_interface(String::from("internal")), "internal");
+        assert_eq!(normalize_interface(String::from("Admin")), "admin");
+        assert_eq!(normalize_interface(String::from("publicURL")), "public");
+        assert_eq!(normalize_interface(String::from("internalURL")),
+                   "internal");
+    }
+
+    #[test]
+    fn test_session_timeout() {
+        let s = utils::test::new_session(utils::test::URL)
+            .with_timeout(Duration::from_secs(5)).unwrap();
+        let _ = s.request::<utils::test::FakeServiceType>(Method::Get, &["x"],
+                                                          None).unwrap();
+    }
+
+    #[test]
+    fn test_raw_request_service() {
+        let s = utils::test::new_session(utils::test::URL);
+        let builder = s.raw_request("object-store", Method::Get, &["info"])
+            .unwrap();
+        assert_eq!(builder.service.as_ref().map(String::as_str),
+                   Some("object-store"));
+    }
+
+    #[test]
+    fn test_session_request_limit() {
+        let s = utils::test::new_session(utils::test::URL)
+            .with_request_limit::<utils::test::FakeServiceType>(Some(4));
+        assert_eq!(s.limits.get("fake").unwrap().limit(), 4);
+        let s = s.with_request_limit::<utils::test::FakeServiceType>(None);
+        assert!(s.limits.is_empty());
+    }
+
+    #[test]
+    fn test_session_resolve_id() {
+        let s = utils::test::new_session(utils::test::URL);
+        let id = s.resolve_id("fake", "name", |_| Ok(String::from("id1")))
+            .unwrap();
+        assert_eq!(&id, "id1");
+        let id = s.resolve_id("fake", "name", |_| panic!("not cached"))
+            .unwrap();
+        assert_eq!(&id, "id1");
+        assert!(s.invalidate_id("fake", "name"));
+        assert!(!s.invalidate_id("fake", "name"));
+        let id = s.resolve_id("fake", "name", |_| Ok(String::from("id2")))
+            .unwrap();
+        assert_eq!(&id, "id2");
+        assert_eq!(s.cached_id("fake", "name"), Some(String::from("id2")));
+        assert_eq!(s.cached_id("fake", "other"), None);
+    }
+
     #[test]
     fn test_session_get_endpoint() {
         let s = utils::test::new_session(utils::test::URL);
@@ -273,6 +1794,28 @@ mod test {
         assert_eq!(&ep.to_string(), utils::test::URL);
     }
 
+    #[test]
+    fn test_session_service_info_cache() {
+        let key = |iface: &str| ("fake", String::from(iface));
+        let mut s = utils::test::new_session(utils::test::URL);
+        let _ = s.get_endpoint::<utils::test::FakeServiceType>(&[]).unwrap();
+        assert!(s.cached_info.get_ref(&key("public")).is_some());
+
+        s.set_endpoint_interface("internal");
+        assert!(s.cached_info.get_ref(&key("public")).is_some());
+        assert!(s.cached_info.get_ref(&key("internal")).is_none());
+        let _ = s.get_endpoint::<utils::test::FakeServiceType>(&[]).unwrap();
+        assert!(s.cached_info.get_ref(&key("internal")).is_some());
+
+        s.invalidate_service_info::<utils::test::FakeServiceType>();
+        assert!(s.cached_info.get_ref(&key("public")).is_none());
+        assert!(s.cached_info.get_ref(&key("internal")).is_none());
+
+        let _ = s.get_endpoint::<utils::test::FakeServiceType>(&[]).unwrap();
+        s.clear_service_info();
+        assert!(s.cached_info.get_ref(&key("internal")).is_none());
+    }
+
     #[test]
     fn test_session_get_endpoint_with_path() {
         let s = utils::test::new_session(utils::test::URL);
@@ -280,4 +1823,87 @@ mod test {
             .unwrap();
         assert_eq!(ep.to_string(), format!("{}foo/bar", utils::test::URL));
     }
+
+    #[test]
+    fn test_response_cache_invalidate() {
+        let cache = ResponseCache::new(Duration::from_secs(60));
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let dropped_clone = dropped.clone();
+        cache.subscribe(move |url| {
+            dropped_clone.lock().unwrap().push(url.to_string())
+        });
+
+        for url in &["http://127.0.0.1/servers?limit=2",
+                     "http://127.0.0.1/servers/abcd",
+                     "http://127.0.0.1/servers/abcdef",
+                     "http://127.0.0.1/flavors/1"] {
+            let _ = cache.get_or_fetch(url.to_string(), url.to_string(),
+                                       || Ok(Value::Null)).unwrap();
+        }
+        assert_eq!(cache.len(), 4);
+
+        cache.invalidate("http://127.0.0.1/servers/abcd/action");
+        assert_eq!(cache.len(), 2);
+        let mut dropped = dropped.lock().unwrap().clone();
+        dropped.sort();
+        assert_eq!(dropped, vec!["http://127.0.0.1/servers/abcd",
+                                 "http://127.0.0.1/servers?limit=2"]);
+    }
+
+    #[test]
+    fn test_response_cache_fetches_once() {
+        let cache = ResponseCache::new(Duration::from_secs(60));
+        let url = String::from("http://127.0.0.1/servers");
+        let value = cache.get_or_fetch(url.clone(), url.clone(),
+                                       || Ok(Value::from(1))).unwrap();
+        assert_eq!(value, Value::from(1));
+        let value = cache.get_or_fetch(url.clone(), url.clone(), || {
+            panic!("The cached response must be used")
+        }).unwrap();
+        assert_eq!(value, Value::from(1));
+    }
+
+    #[test]
+    fn test_response_cache_deduplicates_in_flight() {
+        let cache = ResponseCache::new(Duration::from_secs(60));
+        let url = String::from("http://127.0.0.1/servers");
+        let fetches = Arc::new(Mutex::new(0));
+        let threads: Vec<_> = (0..4).map(|_| {
+            let cache = cache.clone();
+            let url = url.clone();
+            let fetches = fetches.clone();
+            thread::spawn(move || {
+                cache.get_or_fetch(url.clone(), url, || {
+                    *fetches.lock().unwrap() += 1;
+                    thread::sleep(Duration::from_millis(50));
+                    Ok(Value::from(42))
+                }).unwrap()
+            })
+        }).collect();
+        for t in threads {
+            assert_eq!(t.join().unwrap(), Value::from(42));
+        }
+        assert_eq!(*fetches.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_response_cache_expired() {
+        let cache = ResponseCache::new(Duration::from_secs(0));
+        let url = String::from("http://127.0.0.1/servers");
+        let _ = cache.get_or_fetch(url.clone(), url.clone(),
+                                   || Ok(Value::Null)).unwrap();
+        let fetched = cache.get_or_fetch(url.clone(), url.clone(),
+                                         || Ok(Value::from(1))).unwrap();
+        assert_eq!(fetched, Value::from(1));
+    }
+
+    #[test]
+    fn test_is_path_prefix() {
+        assert!(is_path_prefix("/servers", "/servers"));
+        assert!(is_path_prefix("/servers", "/servers/1"));
+        assert!(is_path_prefix("/servers/", "/servers/1"));
+        assert!(is_path_prefix("/servers/1", "/servers/1/"));
+        assert!(! is_path_prefix("/servers/1", "/servers/12"));
+        assert!(! is_path_prefix("/servers/1", "/servers"));
+    }
 }
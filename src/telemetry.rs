@@ -0,0 +1,103 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Internal metrics.
+//!
+//! With the `metrics` feature enabled, metrics are reported via the facade
+//! of the `metrics` crate, so any recorder installed by the application
+//! (e.g. a Prometheus exporter) receives them. Without it all functions here
+//! are no-op.
+//!
+//! The following metrics are reported:
+//!
+//! * `openstack_requests_total` (counter) - HTTP requests by `service` and
+//!   `status`.
+//! * `openstack_request_retries_total` (counter) - retried HTTP requests by
+//!   `service` and `reason`.
+//! * `openstack_auth_refreshes_total` (counter) - requests for new tokens.
+//! * `openstack_waiter_duration_seconds` (histogram) - time spent waiting
+//!   by `waiter` and `outcome`.
+
+use std::time::Instant;
+
+
+/// Record an HTTP request.
+#[cfg(feature = "metrics")]
+pub fn record_request(service: &str, status: u16) {
+    counter!("openstack_requests_total", 1,
+             "service" => service.to_string(), "status" => status.to_string());
+}
+
+/// Record an HTTP request.
+#[cfg(not(feature = "metrics"))]
+#[inline]
+pub fn record_request(_service: &str, _status: u16) {}
+
+/// Record a retry of an HTTP request.
+#[cfg(feature = "metrics")]
+pub fn record_retry(service: &str, reason: &'static str) {
+    counter!("openstack_request_retries_total", 1,
+             "service" => service.to_string(), "reason" => reason);
+}
+
+/// Record a retry of an HTTP request.
+#[cfg(not(feature = "metrics"))]
+#[inline]
+pub fn record_retry(_service: &str, _reason: &'static str) {}
+
+/// Record a request for a new authentication token.
+#[cfg(feature = "metrics")]
+pub fn record_auth_refresh() {
+    counter!("openstack_auth_refreshes_total", 1);
+}
+
+/// Record a request for a new authentication token.
+#[cfg(not(feature = "metrics"))]
+#[inline]
+pub fn record_auth_refresh() {}
+
+/// Record the time spent by a waiter since `started`.
+#[cfg(feature = "metrics")]
+pub fn record_waiter(waiter: &'static str, started: Instant, success: bool) {
+    let elapsed = started.elapsed();
+    let seconds = elapsed.as_secs() as f64 +
+        elapsed.subsec_nanos() as f64 / 1_000_000_000f64;
+    let outcome = if success { "success" } else { "failure" };
+    histogram!("openstack_waiter_duration_seconds", seconds,
+               "waiter" => waiter, "outcome" => outcome);
+}
+
+/// Record the time spent by a waiter since `started`.
+#[cfg(not(feature = "metrics"))]
+#[inline]
+pub fn record_waiter(_waiter: &'static str, _started: Instant, _success: bool) {}
+
+
+#[cfg(test)]
+mod test {
+    use std::time::Instant;
+
+    use super::{record_auth_refresh, record_request, record_retry,
+                record_waiter};
+
+    // Without an installed recorder the calls are no-op, this mostly makes
+    // sure that the reporting code builds with and without the feature.
+    #[test]
+    fn test_record_without_recorder() {
+        record_request("compute", 200);
+        record_retry("compute", "rate_limit");
+        record_auth_refresh();
+        record_waiter("server_status", Instant::now(), true);
+    }
+}
@@ -0,0 +1,309 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for integration tests working with real clouds.
+//!
+//! Resources created through [Fixtures](struct.Fixtures.html) get unique
+//! names with a common prefix and are wrapped into
+//! [Ephemeral](struct.Ephemeral.html) guards, which delete them when dropped,
+//! including when a test panics.
+//!
+//! Requires the `testing` feature.
+
+use std::ops::{Deref, DerefMut};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use waiter::{Waiter, WaiterCurrentState};
+
+use super::{Cloud, Result};
+use super::common::{FlavorRef, NetworkRef};
+use super::compute::{KeyPair, NewServer, Server};
+use super::network::Port;
+
+
+static COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// A resource that can be deleted by an `Ephemeral` guard.
+pub trait Cleanup {
+    /// Delete the resource and wait for the deletion to finish.
+    fn cleanup(self) -> Result<()>;
+}
+
+/// A guard deleting the resource when dropped.
+///
+/// Dereferences to the resource. Errors during the deletion on drop are
+/// logged and otherwise ignored.
+#[derive(Debug)]
+pub struct Ephemeral<T: Cleanup> {
+    resource: Option<T>,
+}
+
+/// Factory of uniquely named ephemeral resources.
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    cloud: Cloud,
+    prefix: String,
+}
+
+impl Cleanup for KeyPair {
+    fn cleanup(self) -> Result<()> {
+        self.delete()
+    }
+}
+
+impl Cleanup for Port {
+    fn cleanup(self) -> Result<()> {
+        self.delete()?.wait()
+    }
+}
+
+impl Cleanup for Server {
+    fn cleanup(self) -> Result<()> {
+        self.delete()?.wait()
+    }
+}
+
+impl<T: Cleanup> Ephemeral<T> {
+    /// Wrap a resource into a guard.
+    pub fn new(resource: T) -> Ephemeral<T> {
+        Ephemeral {
+            resource: Some(resource)
+        }
+    }
+
+    /// Delete the resource now, reporting errors.
+    pub fn cleanup(mut self) -> Result<()> {
+        self.resource.take().unwrap().cleanup()
+    }
+
+    /// Release the resource from the guard, so that it is not deleted.
+    pub fn keep(mut self) -> T {
+        self.resource.take().unwrap()
+    }
+}
+
+impl<T: Cleanup> Deref for Ephemeral<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.resource.as_ref().unwrap()
+    }
+}
+
+impl<T: Cleanup> DerefMut for Ephemeral<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.resource.as_mut().unwrap()
+    }
+}
+
+impl<T: Cleanup> Drop for Ephemeral<T> {
+    fn drop(&mut self) {
+        if let Some(resource) = self.resource.take() {
+            if let Err(err) = resource.cleanup() {
+                warn!("Failed to clean up an ephemeral resource: {}", err);
+            }
+        }
+    }
+}
+
+impl Fixtures {
+    /// Create a factory using the given prefix for resource names.
+    pub fn new<S: Into<String>>(cloud: Cloud, prefix: S) -> Fixtures {
+        Fixtures {
+            cloud: cloud,
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Cloud used to create resources.
+    pub fn cloud(&self) -> &Cloud {
+        &self.cloud
+    }
+
+    /// Prefix of resource names.
+    pub fn prefix(&self) -> &String {
+        &self.prefix
+    }
+
+    /// Generate a unique name with the prefix.
+    ///
+    /// Names are unique between processes and between calls in one process.
+    pub fn unique_name(&self) -> String {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|value| value.as_secs()).unwrap_or(0);
+        format!("{}-{}-{}-{}", self.prefix, timestamp, process::id(),
+                COUNTER.fetch_add(1, Ordering::SeqCst))
+    }
+
+    /// Generate an ephemeral key pair.
+    ///
+    /// Returns the key pair and its private key.
+    pub fn keypair(&self) -> Result<(Ephemeral<KeyPair>, String)> {
+        let (keypair, private_key) = self.cloud
+            .new_keypair(self.unique_name()).generate()?;
+        Ok((Ephemeral::new(keypair), private_key))
+    }
+
+    /// Create an ephemeral port on the network.
+    pub fn port<N>(&self, network: N) -> Result<Ephemeral<Port>>
+            where N: Into<NetworkRef> {
+        let port = self.cloud.new_port(network)
+            .with_name(self.unique_name()).create()?;
+        Ok(Ephemeral::new(port))
+    }
+
+    /// Prepare a uniquely named server, use `server` to create it.
+    pub fn new_server<F>(&self, flavor: F) -> NewServer
+            where F: Into<FlavorRef> {
+        self.cloud.new_server(self.unique_name(), flavor)
+    }
+
+    /// Create an ephemeral server and wait for it to become active.
+    ///
+    /// If the server fails to become active, it is deleted.
+    pub fn server(&self, request: NewServer) -> Result<Ephemeral<Server>> {
+        let waiter = request.create()?;
+        let guard = Ephemeral::new(waiter.waiter_current_state().clone());
+        let server = waiter.wait()?;
+        let _ = guard.keep();
+        Ok(Ephemeral::new(server))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::collections::HashSet;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::process;
+    use std::rc::Rc;
+
+    use super::super::{Cloud, Error, ErrorKind, Result};
+    use super::super::utils::test::new_session;
+    use super::{Cleanup, Ephemeral, Fixtures};
+
+    /// A resource counting its deletions.
+    #[derive(Debug)]
+    struct Tracked {
+        deleted: Rc<Cell<usize>>,
+        fail: bool,
+    }
+
+    impl Tracked {
+        fn new(deleted: &Rc<Cell<usize>>) -> Tracked {
+            Tracked {
+                deleted: deleted.clone(),
+                fail: false,
+            }
+        }
+    }
+
+    impl Cleanup for Tracked {
+        fn cleanup(self) -> Result<()> {
+            self.deleted.set(self.deleted.get() + 1);
+            if self.fail {
+                Err(Error::new(ErrorKind::OperationFailed, "boom"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn fixtures() -> Fixtures {
+        let cloud = Cloud::from(new_session("http://127.0.0.1/"));
+        Fixtures::new(cloud, "rust-openstack-test")
+    }
+
+    #[test]
+    fn test_unique_name_format() {
+        let fixtures = fixtures();
+        assert_eq!(fixtures.prefix(), "rust-openstack-test");
+        let name = fixtures.unique_name();
+        let parts = name.rsplitn(4, '-').collect::<Vec<_>>();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[3], "rust-openstack-test");
+        assert!(parts[2].parse::<u64>().is_ok());
+        assert_eq!(parts[1], process::id().to_string());
+        assert!(parts[0].parse::<usize>().is_ok());
+    }
+
+    #[test]
+    fn test_unique_name_unique() {
+        let fixtures = fixtures();
+        let other = fixtures.clone();
+        let names = (0..50).map(|idx| if idx % 2 == 0 {
+            fixtures.unique_name()
+        } else {
+            other.unique_name()
+        }).collect::<HashSet<_>>();
+        assert_eq!(names.len(), 50);
+    }
+
+    #[test]
+    fn test_ephemeral_drop() {
+        let deleted = Rc::new(Cell::new(0));
+        {
+            let guard = Ephemeral::new(Tracked::new(&deleted));
+            assert!(!guard.fail);
+            assert_eq!(deleted.get(), 0);
+        }
+        assert_eq!(deleted.get(), 1);
+    }
+
+    #[test]
+    fn test_ephemeral_drop_on_panic() {
+        let deleted = Rc::new(Cell::new(0));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = Ephemeral::new(Tracked::new(&deleted));
+            panic!("test failure");
+        }));
+        assert!(result.is_err());
+        assert_eq!(deleted.get(), 1);
+    }
+
+    #[test]
+    fn test_ephemeral_cleanup() {
+        let deleted = Rc::new(Cell::new(0));
+        let guard = Ephemeral::new(Tracked::new(&deleted));
+        guard.cleanup().unwrap();
+        assert_eq!(deleted.get(), 1);
+
+        let mut failing = Tracked::new(&deleted);
+        failing.fail = true;
+        let err = Ephemeral::new(failing).cleanup().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::OperationFailed);
+        assert_eq!(deleted.get(), 2);
+    }
+
+    #[test]
+    fn test_ephemeral_drop_ignores_errors() {
+        let deleted = Rc::new(Cell::new(0));
+        {
+            let mut guard = Ephemeral::new(Tracked::new(&deleted));
+            guard.fail = true;
+        }
+        assert_eq!(deleted.get(), 1);
+    }
+
+    #[test]
+    fn test_ephemeral_keep() {
+        let deleted = Rc::new(Cell::new(0));
+        let resource = Ephemeral::new(Tracked::new(&deleted)).keep();
+        assert_eq!(deleted.get(), 0);
+        drop(resource);
+        assert_eq!(deleted.get(), 0);
+    }
+}
@@ -16,17 +16,22 @@
 
 #![allow(dead_code)] // various things are unused with --no-default-features
 
+use std::borrow::Cow;
 use std::cell::{Ref, RefCell};
 use std::collections::HashMap;
-use std::fmt;
+use std::fmt::{self, Write};
 use std::hash::Hash;
+use std::io::{self, Read};
+use std::sync::{Arc, Condvar, Mutex};
 
 use super::{Error, ErrorKind, Result};
 
 
 /// Type of query parameters.
+///
+/// Parameter names are almost always literals, so they are not copied.
 #[derive(Clone)]
-pub struct Query(pub Vec<(String, String)>);
+pub struct Query(pub Vec<(Cow<'static, str>, Cow<'static, str>)>);
 
 /// Cached clone-able value.
 #[derive(Debug, Clone)]
@@ -36,10 +41,44 @@ pub struct ValueCache<T: Clone>(RefCell<Option<T>>);
 #[derive(Debug, Clone)]
 pub struct MapCache<K: Hash + Eq, V: Clone>(RefCell<HashMap<K, V>>);
 
+/// Counting semaphore, shared between clones.
+#[derive(Debug, Clone)]
+pub struct Semaphore {
+    limit: usize,
+    state: Arc<(Mutex<usize>, Condvar)>,
+}
 
+/// A permit acquired from a semaphore, released on drop.
+#[derive(Debug)]
+pub struct SemaphoreGuard(Semaphore);
+
+/// A reader holding a semaphore permit until it is dropped.
+#[derive(Debug)]
+pub struct GuardedRead<R> {
+    inner: R,
+    _permit: Option<SemaphoreGuard>,
+}
+
+
+/// Encode a query component as `application/x-www-form-urlencoded`.
+fn form_encode(value: &str, result: &mut String) {
+    result.reserve(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'a' ... b'z' | b'A' ... b'Z' | b'0' ... b'9' |
+                b'*' | b'-' | b'.' | b'_' => result.push(byte as char),
+            b' ' => result.push('+'),
+            other => {
+                let _ = write!(result, "%{:02X}", other);
+            }
+        }
+    }
+}
+
+/// Renders the query string as it will be sent (without pagination).
 impl fmt::Debug for Query {
     fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
-        write!(f, "{:?}", self.0)
+        write!(f, "\"{}\"", self.to_query_string())
     }
 }
 
@@ -51,14 +90,42 @@ impl Query {
 
     /// Add an item to the query.
     pub fn push<K, V>(&mut self, param: K, value: V)
-            where K: Into<String>, V: ToString {
-        self.0.push((param.into(), value.to_string()))
+            where K: Into<Cow<'static, str>>, V: ToString {
+        self.0.push((param.into(), Cow::Owned(value.to_string())))
     }
 
     /// Add a strng item to the query.
     pub fn push_str<K, V>(&mut self, param: K, value: V)
-            where K: Into<String>, V: Into<String> {
-        self.0.push((param.into(), value.into()))
+            where K: Into<Cow<'static, str>>, V: Into<String> {
+        self.0.push((param.into(), Cow::Owned(value.into())))
+    }
+
+    /// Render the URL-encoded query string.
+    pub fn to_query_string(&self) -> String {
+        let mut result = String::new();
+        for &(ref key, ref value) in &self.0 {
+            if ! result.is_empty() {
+                result.push('&');
+            }
+            form_encode(key, &mut result);
+            result.push('=');
+            form_encode(value, &mut result);
+        }
+        result
+    }
+
+    /// Add a comma-separated list to the query.
+    pub fn push_list<K, I, S>(&mut self, param: K, values: I)
+            where K: Into<Cow<'static, str>>, I: IntoIterator<Item = S>,
+                  S: Into<String> {
+        let mut joined = String::new();
+        for (index, value) in values.into_iter().enumerate() {
+            if index > 0 {
+                joined.push(',');
+            }
+            joined.push_str(&value.into());
+        }
+        self.0.push((param.into(), Cow::Owned(joined)))
     }
 }
 
@@ -79,6 +146,11 @@ impl<T: Clone> ValueCache<T> {
         Ok(())
     }
 
+    /// Drop the cached value.
+    pub fn clear(&self) {
+        *self.0.borrow_mut() = None;
+    }
+
     /// Ensure that the cached value is valid.
     ///
     /// Returns `true` if the value exists and passes the check.
@@ -133,6 +205,21 @@ impl<K: Hash + Eq, V: Clone> MapCache<K, V> {
         Ok(())
     }
 
+    /// Remove the value from the cache, returning it.
+    pub fn remove(&self, key: &K) -> Option<V> {
+        self.0.borrow_mut().remove(key)
+    }
+
+    /// Remove all values with keys not matching the predicate.
+    pub fn retain<F>(&self, mut predicate: F) where F: FnMut(&K) -> bool {
+        self.0.borrow_mut().retain(|key, _| predicate(key))
+    }
+
+    /// Remove all values.
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear()
+    }
+
     /// Get a reference to the value.
     ///
     /// Borrows the inner RefCell.
@@ -147,14 +234,78 @@ impl<K: Hash + Eq, V: Clone> MapCache<K, V> {
 }
 
 
+impl Semaphore {
+    /// Create a semaphore with the given number of permits.
+    pub fn new(limit: usize) -> Semaphore {
+        Semaphore {
+            limit: limit,
+            state: Arc::new((Mutex::new(0), Condvar::new())),
+        }
+    }
+
+    /// Maximum number of permits.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Wait for a permit to become available and acquire it.
+    pub fn acquire(&self) -> SemaphoreGuard {
+        let &(ref lock, ref cvar) = &*self.state;
+        let mut in_use = lock.lock().unwrap_or_else(|e| e.into_inner());
+        while *in_use >= self.limit {
+            trace!("Waiting for one of {} request slots to free up", self.limit);
+            in_use = cvar.wait(in_use).unwrap_or_else(|e| e.into_inner());
+        }
+        *in_use += 1;
+        SemaphoreGuard(self.clone())
+    }
+}
+
+impl Drop for SemaphoreGuard {
+    fn drop(&mut self) {
+        let &(ref lock, ref cvar) = &*self.0.state;
+        let mut in_use = lock.lock().unwrap_or_else(|e| e.into_inner());
+        *in_use -= 1;
+        cvar.notify_one();
+    }
+}
+
+impl<R> GuardedRead<R> {
+    /// Wrap a reader, keeping the permit (if any) while it exists.
+    pub fn new(inner: R, permit: Option<SemaphoreGuard>) -> GuardedRead<R> {
+        GuardedRead {
+            inner: inner,
+            _permit: permit,
+        }
+    }
+}
+
+impl<R: Read> Read for GuardedRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+
 /// Extensions for Result type.
 pub trait ResultExt<T> {
     /// Process result if the error was ResourceNotFound.
     fn if_not_found_then<F>(self, f: F) -> Result<T>
         where F: FnOnce() -> Result<T>;
+
+    /// Convert ResourceNotFound into `None`.
+    fn not_found_as_none(self) -> Result<Option<T>>;
 }
 
 impl<T> ResultExt<T> for Result<T> {
+    fn not_found_as_none(self) -> Result<Option<T>> {
+        match self {
+            Ok(value) => Ok(Some(value)),
+            Err(ref err) if err.kind() == ErrorKind::ResourceNotFound => Ok(None),
+            Err(err) => Err(err)
+        }
+    }
+
     fn if_not_found_then<F>(self, f: F) -> Result<T>
             where F: FnOnce() -> Result<T> {
         self.or_else(|err| {
@@ -167,6 +318,22 @@ impl<T> ResultExt<T> for Result<T> {
     }
 }
 
+/// Whether the string looks like a UUID.
+///
+/// Accepts the canonical form with dashes as well as 32 hex digits without
+/// them, like the `is_uuid_like` check in OpenStack services.
+pub fn is_uuid(value: &str) -> bool {
+    let hex_only = value.len() == 32 &&
+        value.chars().all(|c| c.is_ascii_hexdigit());
+    let canonical = value.len() == 36 && value.char_indices().all(|(i, c)| {
+        match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit()
+        }
+    });
+    hex_only || canonical
+}
+
 /// Get one and only one item from an iterator.
 pub fn one<T, I, S>(collection: I, not_found_msg: S, too_many_msg: S)
         -> Result<T> where I: IntoIterator<Item = T>, S: Into<String> {
@@ -221,10 +388,130 @@ pub mod url {
 }
 
 
+#[cfg(test)]
+mod test_query {
+    use std::borrow::Cow;
+
+    use super::Query;
+
+    #[test]
+    fn test_query_debug() {
+        let mut query = Query::new();
+        query.push_str("name", "my server");
+        query.push_str("ip", "^10\\.0\\.0\\.1$");
+        query.push("limit", 2);
+        assert_eq!(query.to_query_string(),
+                   "name=my+server&ip=%5E10%5C.0%5C.0%5C.1%24&limit=2");
+        assert_eq!(format!("{:?}", query),
+                   "\"name=my+server&ip=%5E10%5C.0%5C.0%5C.1%24&limit=2\"");
+    }
+
+    #[test]
+    fn test_query_list() {
+        let mut query = Query::new();
+        query.push_list("tags", vec!["a", "b c"]);
+        match query.0[0].0 {
+            Cow::Borrowed(key) => assert_eq!(key, "tags"),
+            Cow::Owned(..) => panic!("Literal key was copied")
+        }
+        assert_eq!(query.to_query_string(), "tags=a%2Cb+c");
+    }
+
+    #[test]
+    fn test_query_list_empty_values() {
+        let mut query = Query::new();
+        query.push_list("tags", vec!["", "a", ""]);
+        assert_eq!(query.to_query_string(), "tags=%2Ca%2C");
+    }
+}
+
+
+#[cfg(test)]
+mod test_uuid {
+    use super::is_uuid;
+
+    #[test]
+    fn test_is_uuid() {
+        assert!(is_uuid("a86dba58-0043-4cc6-a1bb-69d5e86f3ca3"));
+        assert!(is_uuid("A86DBA5800434CC6A1BB69D5E86F3CA3"));
+        assert!(! is_uuid("example.org."));
+        assert!(! is_uuid("a86dba58-0043-4cc6-a1bb-69d5e86f3ca"));
+        assert!(! is_uuid("a86dba58-0043-4cc6-a1bb+69d5e86f3ca3"));
+        assert!(! is_uuid("g86dba58-0043-4cc6-a1bb-69d5e86f3ca3"));
+        assert!(! is_uuid(""));
+    }
+}
+
+#[cfg(test)]
+mod test_semaphore {
+    use std::io::Read;
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{GuardedRead, Semaphore};
+
+    #[test]
+    fn test_semaphore_limits_concurrency() {
+        let sem = Semaphore::new(2);
+        let stats = Arc::new(Mutex::new((0usize, 0usize)));
+        let threads: Vec<_> = (0..6).map(|_| {
+            let sem = sem.clone();
+            let stats = stats.clone();
+            thread::spawn(move || {
+                let _permit = sem.acquire();
+                {
+                    let mut s = stats.lock().unwrap();
+                    s.0 += 1;
+                    s.1 = ::std::cmp::max(s.0, s.1);
+                }
+                thread::sleep(Duration::from_millis(20));
+                stats.lock().unwrap().0 -= 1;
+            })
+        }).collect();
+
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        let s = stats.lock().unwrap();
+        assert_eq!(s.0, 0);
+        assert!(s.1 <= 2);
+    }
+
+    #[test]
+    fn test_guarded_read_holds_permit() {
+        let sem = Semaphore::new(1);
+        let mut reader = GuardedRead::new(&b"data"[..], Some(sem.acquire()));
+        let (tx, rx) = mpsc::channel();
+        let other = sem.clone();
+        let t = thread::spawn(move || {
+            let _permit = other.acquire();
+            tx.send(()).unwrap();
+        });
+
+        let mut data = Vec::new();
+        let _ = reader.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"data");
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+
+        drop(reader);
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        t.join().unwrap();
+    }
+}
+
+
 #[cfg(test)]
 pub mod test {
     //! Common primitives for testing.
 
+    use std::collections::{HashMap, VecDeque};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
     use reqwest::{IntoUrl, Url};
     use reqwest::header::Headers;
 
@@ -267,4 +554,137 @@ pub mod test {
             }
         }
     }
+
+    /// Canned responses by method and path.
+    type Responses = HashMap<(String, String), VecDeque<(u16, String)>>;
+
+    /// A local HTTP server answering with canned JSON responses.
+    ///
+    /// Requests are matched by their method and path (without the query
+    /// string), unknown requests receive HTTP 404. Several responses for the
+    /// same request are returned in order, the last one is repeated. The
+    /// string `{url}` in a response body is replaced with the server URL.
+    #[derive(Clone)]
+    pub struct FakeServer {
+        url: String,
+        responses: Arc<Mutex<Responses>>,
+        requests: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl FakeServer {
+        /// Start a server on a random local port.
+        pub fn new() -> FakeServer {
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .expect("Cannot start a fake server");
+            let server = FakeServer {
+                url: format!("http://{}", listener.local_addr().unwrap()),
+                responses: Arc::new(Mutex::new(HashMap::new())),
+                requests: Arc::new(Mutex::new(Vec::new())),
+            };
+            let handler = server.clone();
+            let _ = thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if let Ok(stream) = stream {
+                        handler.handle(stream);
+                    }
+                }
+            });
+            server
+        }
+
+        /// Start a server announcing a single API version at its root.
+        pub fn with_version(id: &str, version: &str) -> FakeServer {
+            let server = FakeServer::new();
+            server.respond("GET", "/", 200, &format!(
+                r#"{{"version": {{"id": "{}", "status": "CURRENT",
+                    "links": [{{"rel": "self", "href": "{{url}}/"}}],
+                    "version": "{}", "min_version": "{}"}}}}"#,
+                id, version, version));
+            server
+        }
+
+        /// Root URL of the server.
+        pub fn url(&self) -> String {
+            format!("{}/", self.url)
+        }
+
+        /// Create a session talking to this server.
+        pub fn session(&self) -> Session {
+            new_session(self.url().as_str())
+        }
+
+        /// Add a response to a request.
+        pub fn respond(&self, method: &str, path: &str, status: u16,
+                       body: &str) {
+            self.responses.lock().unwrap()
+                .entry((method.to_string(), path.to_string()))
+                .or_insert_with(VecDeque::new)
+                .push_back((status, body.to_string()));
+        }
+
+        /// Requests received so far as `METHOD /path?query`.
+        pub fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().iter()
+                .map(|item| item.0.clone()).collect()
+        }
+
+        /// Requests received so far with their bodies.
+        pub fn requests_with_bodies(&self) -> Vec<(String, String)> {
+            self.requests.lock().unwrap().clone()
+        }
+
+        fn handle(&self, mut stream: TcpStream) {
+            let (method, target) = {
+                let mut reader = BufReader::new(&stream);
+                let mut line = String::new();
+                if reader.read_line(&mut line).is_err() {
+                    return;
+                }
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or("").to_string();
+                let target = parts.next().unwrap_or("").to_string();
+
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    match reader.read_line(&mut header) {
+                        Ok(0) | Err(..) => break,
+                        Ok(..) if header.trim().is_empty() => break,
+                        Ok(..) => ()
+                    }
+                    let header = header.to_lowercase();
+                    if header.starts_with("content-length:") {
+                        length = header["content-length:".len()..].trim()
+                            .parse().unwrap_or(0);
+                    }
+                }
+                let mut body = vec![0u8; length];
+                let _ = reader.read_exact(&mut body);
+                self.requests.lock().unwrap().push(
+                    (format!("{} {}", method, target),
+                     String::from_utf8_lossy(&body).into_owned()));
+                (method, target)
+            };
+
+            let path = target.split('?').next().unwrap_or("").to_string();
+            let (status, body) = {
+                let mut responses = self.responses.lock().unwrap();
+                match responses.get_mut(&(method, path)) {
+                    Some(queue) => if queue.len() > 1 {
+                        queue.pop_front().unwrap()
+                    } else {
+                        queue[0].clone()
+                    },
+                    None => (404, String::from(
+                        r#"{"itemNotFound": {"code": 404, "message": "Not found"}}"#))
+                }
+            };
+            let body = body.replace("{url}", &self.url);
+            let _ = write!(stream, "HTTP/1.1 {} Fake\r\n\
+                                    Content-Type: application/json\r\n\
+                                    Content-Length: {}\r\n\
+                                    Connection: close\r\n\r\n{}",
+                           status, body.len(), body);
+        }
+    }
 }
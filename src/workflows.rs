@@ -0,0 +1,1045 @@
+// Copyright 2018 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Higher-level workflows spanning several API calls or clouds.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use waiter::Waiter;
+#[cfg(all(feature = "compute", feature = "image", feature = "network"))]
+use waiter::WaiterCurrentState;
+
+use super::{Error, ErrorKind, Result};
+#[cfg(feature = "image")]
+use super::Cloud;
+use super::common::{CancellationToken, DeletionWaiter, Refresh, ResourceId};
+#[cfg(all(feature = "compute", feature = "image", feature = "network"))]
+use super::common::PortRef;
+#[cfg(feature = "compute")]
+use super::compute::{NewServer, Server};
+#[cfg(feature = "image")]
+use super::image::{Image, ImageStatus};
+#[cfg(all(feature = "compute", feature = "image", feature = "network"))]
+use super::network::{Port, PortQuery};
+
+
+/// Outcome of a bulk operation.
+///
+/// Every item of the operation ends up in exactly one of the lists.
+#[derive(Debug, Default)]
+pub struct BulkReport {
+    /// IDs (or names) of items that were processed successfully.
+    pub succeeded: Vec<String>,
+    /// IDs (or names) of items that failed with the corresponding errors.
+    ///
+    /// Items interrupted by cancellation fail with `ErrorKind::Cancelled`.
+    pub failed: Vec<(String, Error)>,
+    /// IDs (or names) of items that were not started because of cancellation.
+    pub skipped: Vec<String>,
+}
+
+impl BulkReport {
+    /// Whether all items were processed successfully.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.skipped.is_empty()
+    }
+
+    fn record(&mut self, id: String, result: Result<()>) {
+        match result {
+            Ok(()) => self.succeeded.push(id),
+            Err(err) => {
+                warn!("Bulk operation on {} failed: {}", id, err);
+                self.failed.push((id, err))
+            }
+        }
+    }
+}
+
+/// Wait for the deletion of several resources.
+///
+/// Returned by `start_delete_all`. Waiting for it never fails: every item
+/// that is deleted, fails or times out (according to its own deletion
+/// waiter) is recorded in the resulting report.
+#[derive(Debug)]
+pub struct BulkDeletionWaiter<T> {
+    pending: Vec<(String, DeletionWaiter<T>)>,
+    report: BulkReport,
+    started: Instant,
+}
+
+impl<T> BulkDeletionWaiter<T> {
+    fn new() -> BulkDeletionWaiter<T> {
+        BulkDeletionWaiter {
+            pending: Vec::new(),
+            report: BulkReport::default(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Number of resources whose deletion is still being waited for.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Abort waiting for all resources once the token is cancelled.
+    ///
+    /// Resources that are still present fail with `ErrorKind::Cancelled`.
+    pub fn with_cancellation(mut self, token: CancellationToken)
+            -> BulkDeletionWaiter<T> {
+        self.pending = self.pending.into_iter().map(|(id, waiter)| {
+            (id, waiter.with_cancellation(token.clone()))
+        }).collect();
+        self
+    }
+}
+
+impl<T: Refresh + ResourceId> BulkDeletionWaiter<T> {
+    /// Wait for all resources and return the report.
+    pub fn wait_report(mut self) -> BulkReport {
+        loop {
+            if let Some(report) = self.poll_all() {
+                return report;
+            }
+            thread::sleep(self.default_delay());
+        }
+    }
+
+    /// Poll all pending resources, returning the report once none is left.
+    fn poll_all(&mut self) -> Option<BulkReport> {
+        let elapsed = self.started.elapsed();
+        for (id, mut waiter) in mem::replace(&mut self.pending, Vec::new()) {
+            let timed_out = waiter.default_wait_timeout()
+                .map(|timeout| elapsed >= timeout).unwrap_or(false);
+            let result = if timed_out {
+                Err(waiter.timeout_error())
+            } else {
+                waiter.poll()
+            };
+            match result {
+                Ok(None) => self.pending.push((id, waiter)),
+                Ok(Some(())) => self.report.record(id, Ok(())),
+                Err(err) => self.report.record(id, Err(err))
+            }
+        }
+
+        if self.pending.is_empty() {
+            Some(mem::replace(&mut self.report, BulkReport::default()))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Refresh + ResourceId> Waiter<BulkReport, Error> for BulkDeletionWaiter<T> {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        // Every resource is limited by the timeout of its own waiter.
+        None
+    }
+
+    fn default_delay(&self) -> Duration {
+        self.pending.iter().map(|&(_, ref waiter)| waiter.default_delay())
+            .min().unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    fn timeout_error(&self) -> Error {
+        Error::new(ErrorKind::OperationTimedOut,
+                   format!("Timeout waiting for {} resources to be deleted",
+                           self.pending.len()))
+    }
+
+    fn poll(&mut self) -> Result<Option<BulkReport>> {
+        Ok(self.poll_all())
+    }
+}
+
+/// Request deletion of resources without waiting for it.
+///
+/// Failures to request the deletion are recorded in the report produced by
+/// the returned waiter.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use openstack;
+///
+/// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+/// let servers = os.find_servers().with_name("^ci-").detailed()
+///     .expect("Unable to list servers");
+/// let report = openstack::workflows::start_delete_all(
+///     servers, openstack::compute::Server::delete).wait_report();
+/// println!("Deleted {:?}, failed {:?}", report.succeeded, report.failed);
+/// ```
+pub fn start_delete_all<T, F>(items: Vec<T>, delete: F)
+        -> BulkDeletionWaiter<T>
+        where T: Refresh + ResourceId,
+              F: FnMut(T) -> Result<DeletionWaiter<T>> {
+    request_deletion(items, delete, None)
+}
+
+/// Delete resources and wait for the deletion.
+///
+/// All deletions are requested first, then waited for. Once the token is
+/// cancelled, remaining items are skipped and waiting is aborted.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use openstack;
+///
+/// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+/// let token = openstack::common::CancellationToken::new();
+/// let servers = os.find_servers().with_name("^ci-").detailed()
+///     .expect("Unable to list servers");
+/// let report = openstack::workflows::delete_all(
+///     servers, openstack::compute::Server::delete, &token);
+/// println!("Deleted {:?}, failed {:?}", report.succeeded, report.failed);
+/// ```
+pub fn delete_all<T, F>(items: Vec<T>, delete: F,
+                        cancellation: &CancellationToken) -> BulkReport
+        where T: Refresh + ResourceId,
+              F: FnMut(T) -> Result<DeletionWaiter<T>> {
+    request_deletion(items, delete, Some(cancellation))
+        .with_cancellation(cancellation.clone())
+        .wait_report()
+}
+
+/// Request deletion of all items, skipping them once the token is cancelled.
+fn request_deletion<T, F>(items: Vec<T>, mut delete: F,
+                          cancellation: Option<&CancellationToken>)
+        -> BulkDeletionWaiter<T>
+        where T: Refresh + ResourceId,
+              F: FnMut(T) -> Result<DeletionWaiter<T>> {
+    let mut result = BulkDeletionWaiter::new();
+    for item in items {
+        let id = item.resource_id();
+        if cancellation.map(|token| token.is_cancelled()).unwrap_or(false) {
+            result.report.skipped.push(id);
+            continue;
+        }
+
+        match delete(item) {
+            Ok(waiter) => result.pending.push((id, waiter)),
+            Err(err) => result.report.record(id, Err(err))
+        }
+    }
+    result
+}
+
+/// Create servers and wait for them to become active.
+///
+/// All creations are requested first, then waited for. Returns the active
+/// servers and the report keyed by server names. Once the token is cancelled,
+/// remaining requests are skipped and waiting is aborted; servers that were
+/// already requested are not deleted.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use openstack;
+///
+/// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+/// let token = openstack::common::CancellationToken::new();
+/// let requests = (0..3).map(|idx| {
+///     os.new_server(format!("worker-{}", idx), "m1.small").with_image("centos7")
+/// }).collect();
+/// let (servers, report) = openstack::workflows::create_servers(requests, &token);
+/// ```
+#[cfg(feature = "compute")]
+pub fn create_servers(requests: Vec<NewServer>,
+                      cancellation: &CancellationToken)
+        -> (Vec<Server>, BulkReport) {
+    let mut report = BulkReport::default();
+    let mut waiters = Vec::with_capacity(requests.len());
+    for request in requests {
+        let name = request.name().clone();
+        if cancellation.is_cancelled() {
+            report.skipped.push(name);
+            continue;
+        }
+
+        match request.create() {
+            Ok(waiter) => waiters.push((name, waiter)),
+            Err(err) => report.record(name, Err(err))
+        }
+    }
+
+    let mut servers = Vec::with_capacity(waiters.len());
+    for (name, waiter) in waiters {
+        match waiter.with_cancellation(cancellation.clone()).wait() {
+            Ok(server) => {
+                servers.push(server);
+                report.record(name, Ok(()));
+            },
+            Err(err) => report.record(name, Err(err))
+        }
+    }
+    (servers, report)
+}
+
+/// Refresh resources, stopping once the token is cancelled.
+pub fn refresh_all<T>(items: &mut [T], cancellation: &CancellationToken)
+        -> BulkReport where T: Refresh + ResourceId {
+    let mut report = BulkReport::default();
+    for item in items {
+        let id = item.resource_id();
+        if cancellation.is_cancelled() {
+            report.skipped.push(id);
+        } else {
+            let result = item.refresh();
+            report.record(id, result);
+        }
+    }
+    report
+}
+
+/// A watch loop over resources returned by a query.
+///
+/// The controller periodically lists the resources and compares them to the
+/// previously seen ones. The handler is called with the old and the new
+/// version of every item that appeared (`None, Some`), disappeared
+/// (`Some, None`) or changed (`Some, Some`). Changes are detected using the
+/// function provided to `with_version`, without it only additions and
+/// removals are reported between resyncs. On a full resync the handler is
+/// called for all items, whether they changed or not.
+///
+/// Listing failures are retried with an exponential backoff.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use openstack;
+///
+/// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+/// let query = os.find_servers()
+///     .with_status(openstack::compute::ServerStatus::Error);
+/// let mut controller = openstack::workflows::Controller::new(
+///         query, openstack::compute::ServerQuery::detailed)
+///     .with_version(|server| format!("{:?}", server.updated_at()));
+/// controller.run(|old, new| {
+///     if let (None, Some(server)) = (old, new) {
+///         println!("Server {} failed", server.id());
+///     }
+///     Ok(())
+/// }).expect("Handler failed");
+/// ```
+pub struct Controller<Q, T, L> {
+    query: Q,
+    list: L,
+    version: Option<Box<Fn(&T) -> String>>,
+    period: Duration,
+    jitter: Duration,
+    resync_period: Duration,
+    max_backoff: Duration,
+    items: HashMap<String, T>,
+    last_resync: Option<Instant>,
+}
+
+impl<Q, T, L> Controller<Q, T, L>
+        where Q: Clone, T: ResourceId, L: FnMut(Q) -> Result<Vec<T>> {
+    /// Create a controller running `list` on clones of `query`.
+    ///
+    /// By default the resources are polled every 10 seconds with up to
+    /// 1 second of jitter and fully resynced every 5 minutes.
+    pub fn new(query: Q, list: L) -> Controller<Q, T, L> {
+        Controller {
+            query: query,
+            list: list,
+            version: None,
+            period: Duration::from_secs(10),
+            jitter: Duration::from_secs(1),
+            resync_period: Duration::from_secs(300),
+            max_backoff: Duration::from_secs(300),
+            items: HashMap::new(),
+            last_resync: None,
+        }
+    }
+
+    /// Items seen during the last successful poll.
+    pub fn items(&self) -> Vec<&T> {
+        self.items.values().collect()
+    }
+
+    /// Set the period between polls.
+    pub fn with_period(mut self, period: Duration) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Set the maximum random delay added to each period.
+    ///
+    /// Jitter prevents many controllers from polling the cloud at the
+    /// same moment.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set the maximum delay between retries of failed listings.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the period between full resyncs.
+    pub fn with_resync_period(mut self, resync_period: Duration) -> Self {
+        self.resync_period = resync_period;
+        self
+    }
+
+    /// Set a function returning the version of an item.
+    ///
+    /// Items with different versions are reported as changed. Using
+    /// the update time is usually enough.
+    pub fn with_version<F>(mut self, version: F) -> Self
+            where F: Fn(&T) -> String + 'static {
+        self.version = Some(Box::new(version));
+        self
+    }
+
+    /// List the resources once and call the handler for changed items.
+    ///
+    /// The first poll and polls after the resync period are full resyncs.
+    /// Errors from the handler are returned immediately, the remaining
+    /// changes are reported again on the next poll.
+    pub fn poll<H>(&mut self, mut handler: H) -> Result<()>
+            where H: FnMut(Option<&T>, Option<&T>) -> Result<()> {
+        let resync = match self.last_resync {
+            Some(last) => last.elapsed() >= self.resync_period,
+            None => true
+        };
+        let items = (self.list)(self.query.clone())?;
+        if resync {
+            trace!("Full resync of {} items", items.len());
+        }
+
+        let result = reconcile(&mut self.items, items, resync,
+                               self.version.as_ref().map(|f| &**f),
+                               &mut handler);
+        if resync && result.is_ok() {
+            self.last_resync = Some(Instant::now());
+        }
+        result
+    }
+
+    /// Poll the resources forever.
+    ///
+    /// Listing failures are logged and retried with an exponential backoff
+    /// starting at the period. Only returns if the handler fails.
+    pub fn run<H>(&mut self, mut handler: H) -> Result<()>
+            where H: FnMut(Option<&T>, Option<&T>) -> Result<()> {
+        let mut backoff = self.period;
+        loop {
+            let mut handled = false;
+            let result = self.poll(|old, new| {
+                handled = true;
+                handler(old, new)
+            });
+            let delay = match result {
+                Ok(()) => {
+                    backoff = self.period;
+                    self.period
+                },
+                Err(err) => if handled {
+                    return Err(err);
+                } else {
+                    warn!("Listing resources failed, retrying in {:?}: {}",
+                          backoff, err);
+                    let delay = backoff;
+                    backoff = ::std::cmp::min(backoff * 2, self.max_backoff);
+                    delay
+                }
+            };
+            thread::sleep(delay + jitter(self.jitter));
+        }
+    }
+}
+
+impl<Q, T, L> fmt::Debug for Controller<Q, T, L> where Q: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        f.debug_struct("Controller")
+            .field("query", &self.query)
+            .field("period", &self.period)
+            .field("jitter", &self.jitter)
+            .field("resync_period", &self.resync_period)
+            .field("max_backoff", &self.max_backoff)
+            .field("items", &self.items.len())
+            .finish()
+    }
+}
+
+/// Compare new items to the known ones, calling the handler for changes.
+fn reconcile<T, H>(known: &mut HashMap<String, T>, items: Vec<T>, resync: bool,
+                   version: Option<&Fn(&T) -> String>, handler: &mut H)
+        -> Result<()>
+        where T: ResourceId, H: FnMut(Option<&T>, Option<&T>) -> Result<()> {
+    let mut current = HashMap::with_capacity(items.len());
+    for item in items {
+        let _ = current.insert(item.resource_id(), item);
+    }
+
+    let removed: Vec<String> = known.keys()
+        .filter(|id| !current.contains_key(*id)).cloned().collect();
+    for id in removed {
+        handler(known.get(&id), None)?;
+        let _ = known.remove(&id);
+    }
+
+    for (id, item) in current {
+        let changed = match (known.get(&id), version) {
+            (Some(old), Some(version)) => version(old) != version(&item),
+            (Some(..), None) => false,
+            (None, _) => true
+        };
+        if changed || resync {
+            handler(known.get(&id), Some(&item))?;
+        }
+        let _ = known.insert(id, item);
+    }
+
+    Ok(())
+}
+
+/// A pseudo-random duration up to the given maximum.
+fn jitter(max: Duration) -> Duration {
+    let max_ms = max.as_secs() * 1000 + u64::from(max.subsec_nanos() / 1_000_000);
+    if max_ms == 0 {
+        return Duration::from_secs(0);
+    }
+
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|value| u64::from(value.subsec_nanos())).unwrap_or(0);
+    Duration::from_millis(seed % max_ms)
+}
+
+/// Copy an image to another cloud or region.
+///
+/// The image data is streamed from the source Image service to the target
+/// one without being stored locally. The name, formats, requirements, tags,
+/// visibility and custom properties are copied as well. Protection is only
+/// applied once the data has been verified.
+///
+/// The checksum of the copy is compared to the checksum of the source image.
+/// If the upload or the verification fails, the copy is deleted.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use openstack;
+///
+/// let source = openstack::Cloud::from_env()
+///     .expect("Unable to authenticate");
+/// let auth = openstack::auth::Identity::new_with_region(
+///         "https://cloud.local/identity", String::from("RegionTwo"))
+///     .expect("Invalid auth_url")
+///     .with_user("admin", "pa$$w0rd", "Default")
+///     .with_project_scope("admin", "Default")
+///     .create().expect("Unable to authenticate");
+/// let target = openstack::Cloud::new(auth);
+/// let image = source.get_image("cirros").expect("Unable to get the image");
+/// let copy = openstack::workflows::copy_image_between_regions(&image, &target)
+///     .expect("Unable to copy the image");
+/// ```
+#[cfg(feature = "image")]
+pub fn copy_image_between_regions(image: &Image, target: &Cloud)
+        -> Result<Image> {
+    if image.status() != ImageStatus::Active {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Image {} is not active, its data cannot be copied",
+                    image.id())));
+    }
+
+    let mut request = target.new_image(image.name().clone())
+        .with_minimum_required_disk(image.minimum_required_disk())
+        .with_minimum_required_ram(image.minimum_required_ram())
+        .with_visibility(image.visibility());
+    if let Some(ref architecture) = *image.architecture() {
+        request.set_architecture(architecture.clone());
+    }
+    if let Some(container_format) = image.container_format() {
+        request.set_container_format(container_format);
+    }
+    if let Some(disk_format) = image.disk_format() {
+        request.set_disk_format(disk_format);
+    }
+    for tag in image.tags() {
+        request.add_tag(tag.clone());
+    }
+    for (key, value) in image.properties() {
+        request.set_property(key.clone(), value.clone());
+    }
+
+    let mut copy = request.create()?;
+    debug!("Copying data of image {} into {}", image.id(), copy.id());
+    let data = image.download()?;
+    if let Err(err) = copy.upload(data).and_then(|_| verify_copy(image, &copy)) {
+        warn!("Copying image {} failed, deleting the copy {}: {}",
+              image.id(), copy.id(), err);
+        if let Err(del_err) = copy.delete() {
+            warn!("Failed to delete the image copy: {}", del_err);
+        }
+        return Err(err);
+    }
+
+    if image.protected() {
+        copy.set_protected(true);
+        copy.save()?;
+    }
+
+    debug!("Image {} was copied into {}", image.id(), copy.id());
+    Ok(copy)
+}
+
+/// Check that the copied image matches the source.
+#[cfg(feature = "image")]
+fn verify_copy(image: &Image, copy: &Image) -> Result<()> {
+    if image.size().is_some() && copy.size() != image.size() {
+        return Err(Error::new(
+            ErrorKind::OperationFailed,
+            format!("Size of the copy {:?} does not match the source {:?}",
+                    copy.size(), image.size())));
+    }
+
+    match (image.checksum(), copy.checksum()) {
+        (&Some(ref expected), &Some(ref actual)) if expected != actual =>
+            Err(Error::new(
+                ErrorKind::OperationFailed,
+                format!("Checksum of the copy {} does not match the source {}",
+                        actual, expected))),
+        _ => Ok(())
+    }
+}
+
+
+
+/// Replace a server with a new one booted from its snapshot.
+///
+/// The following steps are taken:
+///
+/// 1. A snapshot of the server is created with the provided name.
+/// 2. All ports are detached from the server.
+/// 3. A new server with the same name, flavor, key pair, metadata,
+///    availability zone and security groups is booted from the snapshot
+///    using the same ports. Since floating IPs are associated with ports,
+///    they move to the new server together with the fixed IPs.
+/// 4. The old server is deleted.
+///
+/// The Compute service deletes ports that it created itself (i.e. when
+/// booting with a network rather than a port) once they are detached. Such
+/// ports cannot be moved, so the replacement is refused with `InvalidInput`
+/// before anything is changed if any port may be owned by the Compute
+/// service. A port is considered owned by it if it was created after the
+/// server or if its creation time is unknown.
+///
+/// If detaching the ports or booting the new server fails, the new server
+/// is deleted and the detached ports are attached back to the old server.
+/// The snapshot is kept in any case.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use openstack;
+///
+/// let os = openstack::Cloud::from_env().expect("Unable to authenticate");
+/// let server = os.get_server("web-1").expect("Unable to get the server");
+/// let new_server = openstack::workflows::replace_server_from_snapshot(
+///     server, "web-1-snapshot").expect("Unable to replace the server");
+/// ```
+#[cfg(all(feature = "compute", feature = "image", feature = "network"))]
+pub fn replace_server_from_snapshot<S>(server: Server, snapshot_name: S)
+        -> Result<Server> where S: Into<String> {
+    let session = server.session().clone();
+    let ports = PortQuery::new(session.clone())
+        .with_device_id(server.id().clone()).all()?;
+    let owned = ports.iter()
+        .filter(|port| is_created_for(port, &server))
+        .map(|port| port.id().clone())
+        .collect::<Vec<_>>();
+    if ! owned.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Ports {} of server {} may be owned by the Compute \
+                     service and would be deleted when detached",
+                    owned.join(", "), server.id())));
+    }
+
+    let image_id = server.create_image(snapshot_name)?;
+    SnapshotWaiter {
+        image: Image::new(session, &image_id)?
+    }.wait()?;
+
+    let mut detached = Vec::with_capacity(ports.len());
+    let result = detach_ports(&server, ports, &mut detached)
+        .and_then(|()| boot_replacement(&server, image_id, &detached));
+
+    match result {
+        Ok(new_server) => {
+            server.delete()?.wait()?;
+            Ok(new_server)
+        },
+        Err(err) => {
+            warn!("Failed to boot a replacement for server {}, \
+                   re-attaching ports: {}", server.id(), err);
+            for port_id in &detached {
+                let attached = server.attach_port(
+                    PortRef::new_verified(port_id.clone()))
+                    .and_then(|waiter| waiter.wait());
+                if let Err(att_err) = attached {
+                    warn!("Failed to re-attach port {} to server {}: {}",
+                          port_id, server.id(), att_err);
+                }
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Whether the port was likely created by the Compute service for the server.
+#[cfg(all(feature = "compute", feature = "image", feature = "network"))]
+fn is_created_for(port: &Port, server: &Server) -> bool {
+    match port.created_at() {
+        Some(created_at) => created_at >= server.created_at(),
+        None => true
+    }
+}
+
+/// Detach ports, recording the IDs of the ones that were detached.
+#[cfg(all(feature = "compute", feature = "image", feature = "network"))]
+fn detach_ports(server: &Server, ports: Vec<Port>, detached: &mut Vec<String>)
+        -> Result<()> {
+    for port in ports {
+        let waiter = server.detach_interface(
+            PortRef::new_verified(port.id().clone()))?;
+        detached.push(port.id().clone());
+        let _ = waiter.wait()?;
+    }
+    Ok(())
+}
+
+/// Boot a replacement server, deleting it if it fails to become active.
+#[cfg(all(feature = "compute", feature = "image", feature = "network"))]
+fn boot_replacement(server: &Server, image_id: String, port_ids: &[String])
+        -> Result<Server> {
+    let mut new_server = server.prepare_replacement(image_id);
+    for port_id in port_ids {
+        new_server.add_port(PortRef::new_verified(port_id.clone()));
+    }
+
+    let waiter = new_server.create()?;
+    let created = waiter.waiter_current_state().clone();
+    waiter.wait().map_err(|err| {
+        let new_id = created.id().clone();
+        if let Err(del_err) = created.delete().and_then(|w| w.wait()) {
+            warn!("Failed to delete replacement server {}: {}",
+                  new_id, del_err);
+        }
+        err
+    })
+}
+
+/// Waiter for a server snapshot to become active.
+#[cfg(all(feature = "compute", feature = "image", feature = "network"))]
+#[derive(Debug)]
+struct SnapshotWaiter {
+    image: Image
+}
+
+#[cfg(all(feature = "compute", feature = "image", feature = "network"))]
+impl Waiter<(), Error> for SnapshotWaiter {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        Some(Duration::new(3600, 0))
+    }
+
+    fn default_delay(&self) -> Duration {
+        Duration::new(5, 0)
+    }
+
+    fn timeout_error(&self) -> Error {
+        Error::new(ErrorKind::OperationTimedOut,
+                   format!("Timeout waiting for image {} to become active",
+                           self.image.id()))
+    }
+
+    fn poll(&mut self) -> Result<Option<()>> {
+        self.image.refresh()?;
+        match self.image.status() {
+            ImageStatus::Active => {
+                debug!("Image {} is active", self.image.id());
+                Ok(Some(()))
+            },
+            ImageStatus::Queued | ImageStatus::Saving => {
+                trace!("Still waiting for image {} to become active, \
+                        current status is {}", self.image.id(),
+                       self.image.status());
+                Ok(None)
+            },
+            other => Err(Error::new(ErrorKind::OperationFailed,
+                                    format!("Image {} got into {} state",
+                                            self.image.id(), other)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use super::super::{Error, ErrorKind, Result};
+    use super::super::common::{CancellationToken, DeletionWaiter, Refresh,
+                               ResourceId};
+    #[cfg(feature = "compute")]
+    use super::super::Cloud;
+    #[cfg(feature = "compute")]
+    use super::super::utils;
+    use super::{delete_all, jitter, reconcile, refresh_all, start_delete_all,
+                BulkReport};
+    #[cfg(feature = "compute")]
+    use super::create_servers;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Item(&'static str, u32);
+
+    impl ResourceId for Item {
+        fn resource_id(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    /// A resource disappearing after several refreshes.
+    #[derive(Debug)]
+    struct Fake {
+        id: &'static str,
+        remaining: u32,
+        error: ErrorKind,
+    }
+
+    fn fake(id: &'static str, remaining: u32) -> Fake {
+        Fake {
+            id: id,
+            remaining: remaining,
+            error: ErrorKind::ResourceNotFound,
+        }
+    }
+
+    impl ResourceId for Fake {
+        fn resource_id(&self) -> String {
+            self.id.to_string()
+        }
+    }
+
+    impl Refresh for Fake {
+        fn refresh(&mut self) -> Result<()> {
+            if self.remaining > 0 {
+                self.remaining -= 1;
+                Ok(())
+            } else {
+                Err(Error::new(self.error, "Refresh failed"))
+            }
+        }
+    }
+
+    fn delete(item: Fake) -> Result<DeletionWaiter<Fake>> {
+        if item.id.starts_with("fail") {
+            Err(Error::new(ErrorKind::Conflict, "Cannot delete"))
+        } else {
+            Ok(DeletionWaiter::new(item, Duration::from_secs(5),
+                                   Duration::from_millis(1)))
+        }
+    }
+
+    fn failed_ids(failed: &[(String, Error)]) -> Vec<(&str, ErrorKind)> {
+        failed.iter().map(|&(ref id, ref err)| (id.as_str(), err.kind()))
+            .collect()
+    }
+
+    #[test]
+    fn test_start_delete_all() {
+        let broken = Fake { error: ErrorKind::AccessDenied, .. fake("d", 0) };
+        let items = vec![fake("a", 0), fake("b", 3), fake("fail-c", 0), broken];
+        let waiter = start_delete_all(items, delete);
+        assert_eq!(waiter.pending(), 3);
+
+        let report = waiter.wait_report();
+        assert_eq!(report.succeeded, vec!["a", "b"]);
+        assert_eq!(failed_ids(&report.failed),
+                   vec![("fail-c", ErrorKind::Conflict),
+                        ("d", ErrorKind::AccessDenied)]);
+        assert!(report.skipped.is_empty());
+        assert!(! report.is_success());
+    }
+
+    #[test]
+    fn test_start_delete_all_timeout() {
+        let report = start_delete_all(vec![fake("a", 0), fake("b", 1000)], |item| {
+            delete(item).map(|waiter| waiter.with_timeout(Duration::from_millis(20)))
+        }).wait_report();
+        assert_eq!(report.succeeded, vec!["a"]);
+        assert_eq!(failed_ids(&report.failed),
+                   vec![("b", ErrorKind::OperationTimedOut)]);
+    }
+
+    #[test]
+    fn test_start_delete_all_cancelled_while_waiting() {
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        let report = start_delete_all(vec![fake("a", 1000), fake("b", 1000)],
+                                      move |item| {
+            let cancel = cancel.clone();
+            delete(item).map(|waiter| waiter.with_progress(move |_| cancel.cancel()))
+        }).with_cancellation(token).wait_report();
+        assert!(report.succeeded.is_empty());
+        assert_eq!(failed_ids(&report.failed),
+                   vec![("b", ErrorKind::Cancelled), ("a", ErrorKind::Cancelled)]);
+    }
+
+    #[test]
+    fn test_delete_all_cancelled() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let report = delete_all(vec![fake("a", 0), fake("b", 0)], delete, &token);
+        assert!(report.succeeded.is_empty());
+        assert!(report.failed.is_empty());
+        assert_eq!(report.skipped, vec!["a", "b"]);
+        assert!(! report.is_success());
+    }
+
+    #[test]
+    fn test_delete_all() {
+        let items = vec![fake("fail-a", 0), fake("b", 2), fake("c", 0)];
+        let report = delete_all(items, delete, &CancellationToken::new());
+        assert_eq!(report.succeeded, vec!["c", "b"]);
+        assert_eq!(failed_ids(&report.failed),
+                   vec![("fail-a", ErrorKind::Conflict)]);
+        assert!(report.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_refresh_all() {
+        let mut items = vec![fake("a", 1), fake("b", 0), fake("c", 1)];
+        let report = refresh_all(&mut items, &CancellationToken::new());
+        assert_eq!(report.succeeded, vec!["a", "c"]);
+        assert_eq!(failed_ids(&report.failed),
+                   vec![("b", ErrorKind::ResourceNotFound)]);
+        assert_eq!(items[0].remaining, 0);
+    }
+
+    #[test]
+    fn test_refresh_all_cancelled() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut items = vec![fake("a", 1), fake("b", 1)];
+        let report = refresh_all(&mut items, &token);
+        assert_eq!(report.skipped, vec!["a", "b"]);
+        assert!(report.succeeded.is_empty());
+        assert_eq!(items[0].remaining, 1);
+    }
+
+    #[test]
+    fn test_bulk_report() {
+        let mut report = BulkReport::default();
+        assert!(report.is_success());
+        report.record(String::from("a"), Ok(()));
+        assert!(report.is_success());
+        report.record(String::from("b"),
+                      Err(Error::new(ErrorKind::Conflict, "Failed")));
+        assert!(! report.is_success());
+        assert_eq!(report.succeeded, vec!["a"]);
+        assert_eq!(failed_ids(&report.failed), vec![("b", ErrorKind::Conflict)]);
+
+        let skipped = BulkReport {
+            skipped: vec![String::from("c")],
+            .. BulkReport::default()
+        };
+        assert!(! skipped.is_success());
+    }
+
+    #[cfg(feature = "compute")]
+    #[test]
+    fn test_create_servers_invalid() {
+        let cloud = Cloud::from(utils::test::new_session(utils::test::URL));
+        // Neither an image nor a boot volume, validation fails before
+        // any request is sent.
+        let requests = vec![cloud.new_server("one", "m1.small"),
+                            cloud.new_server("two", "m1.small")];
+        let (servers, report) = create_servers(requests,
+                                               &CancellationToken::new());
+        assert!(servers.is_empty());
+        assert!(report.succeeded.is_empty());
+        assert_eq!(failed_ids(&report.failed),
+                   vec![("one", ErrorKind::InvalidInput),
+                        ("two", ErrorKind::InvalidInput)]);
+    }
+
+    #[cfg(feature = "compute")]
+    #[test]
+    fn test_create_servers_cancelled() {
+        let cloud = Cloud::from(utils::test::new_session(utils::test::URL));
+        let token = CancellationToken::new();
+        token.cancel();
+        let requests = vec![cloud.new_server("one", "m1.small")
+                                .with_image("cirros"),
+                            cloud.new_server("two", "m1.small")
+                                .with_image("cirros")];
+        let (servers, report) = create_servers(requests, &token);
+        assert!(servers.is_empty());
+        assert!(report.failed.is_empty());
+        assert_eq!(report.skipped, vec!["one", "two"]);
+    }
+
+    fn version(item: &Item) -> String {
+        item.1.to_string()
+    }
+
+    fn run(known: &mut HashMap<String, Item>, items: Vec<Item>, resync: bool)
+            -> Vec<(Option<Item>, Option<Item>)> {
+        let mut events = Vec::new();
+        let version: &Fn(&Item) -> String = &version;
+        reconcile(known, items, resync, Some(version), &mut |old, new| {
+            events.push((old.cloned(), new.cloned()));
+            Ok(())
+        }).unwrap();
+        events.sort_by_key(|event| {
+            event.0.as_ref().or(event.1.as_ref()).unwrap().0
+        });
+        events
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let mut known = HashMap::new();
+        assert_eq!(run(&mut known, vec![Item("a", 1), Item("b", 1)], true),
+                   vec![(None, Some(Item("a", 1))), (None, Some(Item("b", 1)))]);
+        assert_eq!(run(&mut known, vec![Item("a", 1), Item("b", 1)], false),
+                   vec![]);
+        assert_eq!(run(&mut known, vec![Item("b", 2), Item("c", 1)], false),
+                   vec![(Some(Item("a", 1)), None),
+                        (Some(Item("b", 1)), Some(Item("b", 2))),
+                        (None, Some(Item("c", 1)))]);
+        assert_eq!(run(&mut known, vec![Item("b", 2), Item("c", 1)], true),
+                   vec![(Some(Item("b", 2)), Some(Item("b", 2))),
+                        (Some(Item("c", 1)), Some(Item("c", 1)))]);
+        assert_eq!(known.len(), 2);
+    }
+
+    #[test]
+    fn test_jitter() {
+        assert_eq!(jitter(Duration::from_secs(0)), Duration::from_secs(0));
+        assert!(jitter(Duration::from_millis(500)) < Duration::from_millis(500));
+    }
+}
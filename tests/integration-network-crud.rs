@@ -77,3 +77,38 @@ fn test_port_create_update_delete() {
     os.get_port("rust-openstack-integration-2")
         .err().expect("Port is still present");
 }
+
+#[test]
+fn test_network_create_update_delete() {
+    let os = set_up();
+
+    let mut network = os.new_network()
+        .with_name("rust-openstack-integration-net")
+        .with_admin_state_up(false)
+        .create().expect("Could not create network");
+    assert_eq!(network.name(), "rust-openstack-integration-net");
+    assert!(!network.admin_state_up());
+    assert!(!network.is_dirty());
+
+    network.set_name("rust-openstack-integration-net-2");
+    network.set_admin_state_up(true);
+    network.set_description("integration test network");
+    assert!(network.is_dirty());
+
+    network.save().expect("Cannot update network");
+    assert_eq!(network.name(), "rust-openstack-integration-net-2");
+    assert!(network.admin_state_up());
+    assert_eq!(network.description().as_ref().unwrap(),
+               "integration test network");
+    assert!(!network.is_dirty());
+
+    network.refresh().expect("Cannot refresh network");
+    assert_eq!(network.name(), "rust-openstack-integration-net-2");
+    assert!(!network.is_dirty());
+
+    network.delete().expect("Cannot request network deletion")
+        .wait().expect("Network was not deleted");
+
+    os.get_network("rust-openstack-integration-net-2")
+        .err().expect("Network is still present");
+}